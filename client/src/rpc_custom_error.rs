@@ -24,6 +24,9 @@ pub const JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_LEN_MISMATCH: i64 = -32013
 pub const JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET: i64 = -32014;
 pub const JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION: i64 = -32015;
 pub const JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED: i64 = -32016;
+pub const JSON_RPC_SERVER_ERROR_ACCOUNT_HISTORY_NOT_AVAILABLE: i64 = -32017;
+pub const JSON_RPC_SERVER_ERROR_PARENT_BANK_NOT_AVAILABLE: i64 = -32018;
+pub const JSON_RPC_SERVER_ERROR_EPOCH_BOUNDARY_TRACE_NOT_SUPPORTED: i64 = -32019;
 
 #[derive(Error, Debug)]
 pub enum RpcCustomError {
@@ -65,6 +68,12 @@ pub enum RpcCustomError {
     UnsupportedTransactionVersion(u8),
     #[error("MinContextSlotNotReached")]
     MinContextSlotNotReached { context_slot: Slot },
+    #[error("AccountHistoryNotAvailable")]
+    AccountHistoryNotAvailable,
+    #[error("ParentBankNotAvailable")]
+    ParentBankNotAvailable { slot: Slot },
+    #[error("EpochBoundaryTraceNotSupported")]
+    EpochBoundaryTraceNotSupported { slot: Slot },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -210,6 +219,26 @@ impl From<RpcCustomError> for Error {
                     context_slot,
                 })),
             },
+            RpcCustomError::AccountHistoryNotAvailable => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_ACCOUNT_HISTORY_NOT_AVAILABLE),
+                message: "Account history is not available from this node".to_string(),
+                data: None,
+            },
+            RpcCustomError::ParentBankNotAvailable { slot } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_PARENT_BANK_NOT_AVAILABLE),
+                message: format!("Bank of the parent of slot {} is not available", slot),
+                data: None,
+            },
+            RpcCustomError::EpochBoundaryTraceNotSupported { slot } => Self {
+                code: ErrorCode::ServerError(
+                    JSON_RPC_SERVER_ERROR_EPOCH_BOUNDARY_TRACE_NOT_SUPPORTED,
+                ),
+                message: format!(
+                    "Transactions of slot {} cannot be traced, as it starts an epoch",
+                    slot
+                ),
+                data: None,
+            },
         }
     }
 }
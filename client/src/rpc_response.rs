@@ -7,11 +7,12 @@ use {
         fee_calculator::{FeeCalculator, FeeRateGovernor},
         hash::Hash,
         inflation::Inflation,
+        instruction::InstructionError,
         transaction::{Result, TransactionError},
     },
     solana_transaction_status::{
-        ConfirmedTransactionStatusWithSignature, TransactionConfirmationStatus, UiConfirmedBlock,
-        UiTransactionReturnData,
+        ConfirmedTransactionStatusWithSignature, EncodedTransaction, TransactionConfirmationStatus,
+        UiConfirmedBlock, UiTransactionReturnData,
     },
     std::{collections::HashMap, fmt, net::SocketAddr, str::FromStr},
     thiserror::Error,
@@ -305,6 +306,52 @@ pub struct RpcBlockProduction {
     pub range: RpcBlockProductionRange,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountProof {
+    /// The base58 state root the proof verifies against
+    pub state_root: String,
+    /// The base58 hash of the account in the state tree, all zeros if the account is not in the
+    /// state
+    pub value_hash: String,
+    /// The base64 compiled sparse merkle proof of the account
+    pub proof: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountVersion {
+    /// The slot the version was written in
+    pub slot: Slot,
+    pub write_version: u64,
+    /// The base58 signature of the transaction which wrote the version, if any
+    pub txn_signature: Option<String>,
+    pub account: UiAccount,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcEntry {
+    /// The number of hashes since the previous entry
+    pub num_hashes: u64,
+    /// The base58 PoH hash of the entry
+    pub hash: String,
+    pub transactions: Vec<EncodedTransaction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSlotEntries {
+    pub slot: Slot,
+    pub parent_slot: Option<Slot>,
+    /// The number of data shreds the entries were made of
+    pub num_shreds: u64,
+    pub entries: Vec<RpcEntry>,
+    /// The base64 payloads of the data shreds of the slot, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shreds: Option<Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct RpcVersionInfo {
@@ -402,6 +449,47 @@ pub struct RpcSimulateTransactionResult {
     pub return_data: Option<UiTransactionReturnData>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTraceTransactionResult {
+    /// The slot of the block the transaction was confirmed in
+    pub slot: Slot,
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: u64,
+    pub return_data: Option<UiTransactionReturnData>,
+    /// The instructions of the transaction message
+    pub instructions: Vec<RpcTracedInstruction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTracedInstruction {
+    pub program_id: String,
+    pub stack_height: usize,
+    pub accounts: Vec<String>,
+    /// The base58 instruction data
+    pub data: String,
+    /// `None` for the instructions which were not processed by a program, like precompiles
+    pub compute_units_consumed: Option<u64>,
+    pub err: Option<InstructionError>,
+    /// The accounts changed by the instruction and the instructions it invoked
+    pub account_diffs: Vec<RpcAccountDiff>,
+    /// The instructions invoked by this one
+    pub inner_instructions: Vec<RpcTracedInstruction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountDiff {
+    pub pubkey: String,
+    pub pre_lamports: u64,
+    pub post_lamports: u64,
+    /// The base64 account data before and after the instruction, only if it changed
+    pub pre_data: Option<String>,
+    pub post_data: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcStorageTurn {
@@ -0,0 +1,416 @@
+//! The `entry_publisher_service` publishes the entries of the slots of the
+//! finalized chain to the Geyser plugins, in slot order. A slot is published
+//! once it is rooted, or optimistically confirmed if configured so. The last
+//! slot the plugins acknowledged storing is kept in the ledger directory, so
+//! the slots published but not stored when the validator stopped are
+//! published again on restart. Slots which are not acknowledged in time, and
+//! the slots after them, are published again too.
+use {
+    solana_entry::entry::UntrustedEntry,
+    solana_geyser_plugin_manager::entry_notifier_interface::EntryNotifierLock,
+    solana_ledger::{
+        blockstore::{Blockstore, BlockstoreError},
+        blockstore_meta::SlotMeta,
+    },
+    solana_measure::measure::Measure,
+    solana_rpc::optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
+    solana_runtime::bank_forks::BankForks,
+    solana_sdk::clock::Slot,
+    std::{
+        collections::VecDeque,
+        fs, io,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, RwLock,
+        },
+        thread::{self, sleep, Builder, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+const PROGRESS_FILE: &str = "entry_publisher_slot";
+const LOOP_INTERVAL: Duration = Duration::from_millis(100);
+const ACKNOWLEDGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct EntryPublisherService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl EntryPublisherService {
+    pub fn new(
+        entry_notifier: EntryNotifierLock,
+        blockstore: Arc<Blockstore>,
+        bank_forks: Arc<RwLock<BankForks>>,
+        optimistically_confirmed_bank: Option<Arc<RwLock<OptimisticallyConfirmedBank>>>,
+        exit: &Arc<AtomicBool>,
+    ) -> Self {
+        let exit = exit.clone();
+        let thread_hdl = Builder::new()
+            .name("solEntryPublish".to_string())
+            .spawn(move || {
+                let progress_path = blockstore.ledger_path().join(PROGRESS_FILE);
+                let acknowledged_slot = match read_progress(&progress_path) {
+                    Ok(Some(slot)) => slot,
+                    Ok(None) => entry_notifier.read().unwrap().last_insert_entry(),
+                    Err(err) => {
+                        error!("Failed to read {:?}: {}", progress_path, err);
+                        entry_notifier.read().unwrap().last_insert_entry()
+                    }
+                };
+                info!("entry publisher starting after slot {}", acknowledged_slot);
+                let mut progress = PublishProgress::new(progress_path, acknowledged_slot);
+                loop {
+                    if exit.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    progress.acknowledge(&entry_notifier, Instant::now());
+                    let root = bank_forks.read().unwrap().root();
+                    let target_slot = optimistically_confirmed_bank
+                        .as_ref()
+                        .map(|bank| bank.read().unwrap().bank.slot().max(root))
+                        .unwrap_or(root);
+                    if target_slot > progress.last_published_slot() {
+                        progress.publish_slots(&entry_notifier, &blockstore, target_slot, &exit);
+                    }
+                    sleep(LOOP_INTERVAL);
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+/// The slots published to the plugins, and the last one they acknowledged
+struct PublishProgress {
+    progress_path: PathBuf,
+    /// Persisted in `progress_path`, the slots up to it are not published
+    /// again
+    acknowledged_slot: Slot,
+    /// The slots published after `acknowledged_slot` and not acknowledged
+    /// yet, in publishing order, with when they were published
+    pending_slots: VecDeque<(Slot, Instant)>,
+}
+
+impl PublishProgress {
+    fn new(progress_path: PathBuf, acknowledged_slot: Slot) -> Self {
+        Self {
+            progress_path,
+            acknowledged_slot,
+            pending_slots: VecDeque::new(),
+        }
+    }
+
+    fn last_published_slot(&self) -> Slot {
+        self.pending_slots
+            .back()
+            .map(|(slot, _)| *slot)
+            .unwrap_or(self.acknowledged_slot)
+    }
+
+    /// Advance the acknowledged slot over the pending slots the plugins
+    /// stored, in order. If the oldest pending slot is not stored by
+    /// `ACKNOWLEDGE_TIMEOUT` after it was published, it and the slots after
+    /// it are published again.
+    fn acknowledge(&mut self, entry_notifier: &EntryNotifierLock, now: Instant) {
+        let acknowledged_slot = self.acknowledged_slot;
+        while let Some((slot, published_at)) = self.pending_slots.front().copied() {
+            if entry_notifier.read().unwrap().entry_published(slot) {
+                self.acknowledged_slot = slot;
+                self.pending_slots.pop_front();
+            } else {
+                if now.duration_since(published_at) > ACKNOWLEDGE_TIMEOUT {
+                    warn!(
+                        "slot {} was not acknowledged in {:?}, publishing again after slot {}",
+                        slot, ACKNOWLEDGE_TIMEOUT, self.acknowledged_slot
+                    );
+                    self.pending_slots.clear();
+                }
+                break;
+            }
+        }
+        if self.acknowledged_slot != acknowledged_slot {
+            if let Err(err) = write_progress(&self.progress_path, self.acknowledged_slot) {
+                error!("Failed to write {:?}: {}", self.progress_path, err);
+            }
+        }
+    }
+
+    /// Publish the slots of the chain of `target_slot` after the last
+    /// published slot. Nothing is published while a slot of the chain is
+    /// missing from the blockstore, the chain is published once it is
+    /// complete.
+    fn publish_slots(
+        &mut self,
+        entry_notifier: &EntryNotifierLock,
+        blockstore: &Blockstore,
+        target_slot: Slot,
+        exit: &AtomicBool,
+    ) {
+        let last_published_slot = self.last_published_slot();
+        let (slots, chain_start) = chain_after(blockstore, last_published_slot, target_slot);
+        if chain_start < last_published_slot {
+            warn!(
+                "slot {} is not an ancestor of slot {}, publishing again after slot {}",
+                last_published_slot, target_slot, chain_start
+            );
+            self.pending_slots.retain(|(slot, _)| *slot <= chain_start);
+            if chain_start < self.acknowledged_slot {
+                self.acknowledged_slot = chain_start;
+                if let Err(err) = write_progress(&self.progress_path, chain_start) {
+                    error!("Failed to write {:?}: {}", self.progress_path, err);
+                }
+            }
+        } else if chain_start > last_published_slot {
+            if chain_start < blockstore.lowest_slot() {
+                error!(
+                    "slot {} was purged from the blockstore, skipping the slots after slot {}",
+                    chain_start, last_published_slot
+                );
+            } else {
+                debug!(
+                    "slot {} is missing from the blockstore, waiting to publish the slots after slot {}",
+                    chain_start, last_published_slot
+                );
+                return;
+            }
+        }
+        for slot in slots {
+            if exit.load(Ordering::Relaxed) {
+                break;
+            }
+            let mut publish_time = Measure::start("publish_entries");
+            let entry = match load_entry(blockstore, slot) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(err) => {
+                    error!("Failed to load the entries of slot {}: {:?}", slot, err);
+                    break;
+                }
+            };
+            entry_notifier.read().unwrap().notify_entry(&entry);
+            self.pending_slots.push_back((slot, Instant::now()));
+            publish_time.stop();
+            datapoint_info!(
+                "entry_publisher",
+                ("slot", slot, i64),
+                ("entries", entry.entries.len(), i64),
+                ("publish_us", publish_time.as_us(), i64)
+            );
+        }
+    }
+}
+
+/// The slots of the chain of `target_slot` after `last_slot`, in ascending
+/// order, with the slot the chain stops at. That is `last_slot` itself, a
+/// lower slot if `last_slot` is on another fork, or a higher slot if the
+/// chain is not in the blockstore down to `last_slot`.
+fn chain_after(blockstore: &Blockstore, last_slot: Slot, target_slot: Slot) -> (Vec<Slot>, Slot) {
+    let mut slots = vec![];
+    let mut slot = target_slot;
+    while slot > last_slot {
+        match blockstore.meta(slot) {
+            Ok(Some(SlotMeta {
+                parent_slot: Some(parent_slot),
+                ..
+            })) => {
+                slots.push(slot);
+                slot = parent_slot;
+            }
+            _ => break,
+        }
+    }
+    slots.reverse();
+    (slots, slot)
+}
+
+/// The entries of `slot` with the shreds they came from, or `None` if the
+/// slot is not full yet
+fn load_entry(
+    blockstore: &Blockstore,
+    slot: Slot,
+) -> Result<Option<UntrustedEntry>, BlockstoreError> {
+    let parent_slot = match blockstore.meta(slot)? {
+        Some(meta) if meta.is_full() => meta.parent_slot.unwrap_or_default(),
+        _ => return Ok(None),
+    };
+    let (entries, _, is_full_slot) = blockstore.get_slot_entries_with_shred_info(slot, 0, false)?;
+    // Pass the data shreds as signed by the leader along, so that they can be
+    // verified against the leader schedule downstream.
+    let shreds = blockstore
+        .get_data_shreds_for_slot(slot, 0)
+        .map_err(|err| {
+            let err = format!("Invalid stored shred: {}", err);
+            BlockstoreError::InvalidShredData(Box::new(bincode::ErrorKind::Custom(err)))
+        })?;
+    let shred_version = shreds
+        .first()
+        .map(|shred| shred.version())
+        .unwrap_or_default();
+    Ok(Some(UntrustedEntry {
+        entries,
+        slot,
+        parent_slot,
+        is_full_slot,
+        shreds: shreds
+            .into_iter()
+            .map(|shred| shred.into_payload())
+            .collect(),
+        shred_version,
+    }))
+}
+
+fn read_progress(path: &Path) -> io::Result<Option<Slot>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => contents
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn write_progress(path: &Path, slot: Slot) -> io::Result<()> {
+    let tmp_path = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&tmp_path, slot.to_string())?;
+    fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_geyser_plugin_manager::entry_notifier_interface::EntryNotifier,
+        solana_ledger::{blockstore::make_chaining_slot_entries, get_tmp_ledger_path_auto_delete},
+        std::{collections::HashSet, sync::Mutex},
+    };
+
+    #[derive(Default)]
+    struct TestEntryNotifier {
+        notified_slots: Mutex<Vec<Slot>>,
+        published_slots: Mutex<HashSet<Slot>>,
+    }
+
+    impl EntryNotifier for TestEntryNotifier {
+        fn notify_entry(&self, entry: &UntrustedEntry) {
+            self.notified_slots.lock().unwrap().push(entry.slot);
+        }
+
+        fn entry_published(&self, slot: u64) -> bool {
+            self.published_slots.lock().unwrap().contains(&slot)
+        }
+
+        fn last_insert_entry(&self) -> u64 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_chain_after() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        // Slots 3 and 5 are skipped, slot 4 is on another fork
+        for (shreds, _) in make_chaining_slot_entries(&[0, 1, 2, 4], 2)
+            .into_iter()
+            .chain(
+                make_chaining_slot_entries(&[2, 6, 7], 2)
+                    .into_iter()
+                    .skip(1),
+            )
+        {
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+
+        assert_eq!(chain_after(&blockstore, 0, 7), (vec![1, 2, 6, 7], 0));
+        assert_eq!(chain_after(&blockstore, 2, 7), (vec![6, 7], 2));
+        assert_eq!(chain_after(&blockstore, 7, 7), (vec![], 7));
+        // Slot 4 is not an ancestor of slot 7
+        assert_eq!(chain_after(&blockstore, 4, 7), (vec![6, 7], 2));
+        // The chain stops at the first slot missing from the blockstore
+        assert_eq!(chain_after(&blockstore, 0, 9), (vec![], 9));
+
+        let load = |slot| load_entry(&blockstore, slot).unwrap().unwrap();
+        assert_eq!(load(6).parent_slot, 2);
+        assert!(load(6).is_full_slot);
+        assert!(!load(6).shreds.is_empty());
+        assert!(load_entry(&blockstore, 9).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_publish_progress() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        // Slot 3 is skipped, slot 5 is missing from the blockstore
+        for (shreds, _) in make_chaining_slot_entries(&[0, 1, 2, 4], 2)
+            .into_iter()
+            .chain(make_chaining_slot_entries(&[5, 6], 2).into_iter().skip(1))
+        {
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        let test_notifier = Arc::new(RwLock::new(TestEntryNotifier::default()));
+        let entry_notifier: EntryNotifierLock = test_notifier.clone();
+        let notified_slots = || {
+            test_notifier
+                .read()
+                .unwrap()
+                .notified_slots
+                .lock()
+                .unwrap()
+                .clone()
+        };
+        let publish = |slots: &[Slot]| {
+            let test_notifier = test_notifier.read().unwrap();
+            test_notifier.published_slots.lock().unwrap().extend(slots)
+        };
+        let exit = AtomicBool::new(false);
+        let progress_path = ledger_path.path().join(PROGRESS_FILE);
+        let mut progress = PublishProgress::new(progress_path.clone(), 0);
+
+        progress.publish_slots(&entry_notifier, &blockstore, 4, &exit);
+        assert_eq!(notified_slots(), vec![1, 2, 4]);
+        assert_eq!(progress.last_published_slot(), 4);
+        // Only acknowledged slots are recorded, in order
+        publish(&[1, 4]);
+        let now = Instant::now();
+        progress.acknowledge(&entry_notifier, now);
+        assert_eq!(progress.acknowledged_slot, 1);
+        assert_eq!(read_progress(&progress_path).unwrap(), Some(1));
+        // Slots which are not acknowledged in time are published again
+        progress.acknowledge(&entry_notifier, now + ACKNOWLEDGE_TIMEOUT * 2);
+        assert_eq!(progress.last_published_slot(), 1);
+        progress.publish_slots(&entry_notifier, &blockstore, 4, &exit);
+        assert_eq!(notified_slots(), vec![1, 2, 4, 2, 4]);
+        publish(&[2]);
+        progress.acknowledge(&entry_notifier, Instant::now());
+        assert_eq!(progress.acknowledged_slot, 4);
+        assert_eq!(read_progress(&progress_path).unwrap(), Some(4));
+
+        // Slot 6 is not published until slot 5 is in the blockstore
+        progress.publish_slots(&entry_notifier, &blockstore, 6, &exit);
+        assert_eq!(progress.last_published_slot(), 4);
+        let (shreds, _) = make_chaining_slot_entries(&[4, 5], 2).pop().unwrap();
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        progress.publish_slots(&entry_notifier, &blockstore, 6, &exit);
+        assert_eq!(progress.last_published_slot(), 6);
+        assert_eq!(notified_slots(), vec![1, 2, 4, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_progress() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let path = ledger_path.path().join(PROGRESS_FILE);
+        assert_eq!(read_progress(&path).unwrap(), None);
+        write_progress(&path, 42).unwrap();
+        assert_eq!(read_progress(&path).unwrap(), Some(42));
+        write_progress(&path, 43).unwrap();
+        assert_eq!(read_progress(&path).unwrap(), Some(43));
+        fs::write(&path, "not a slot").unwrap();
+        assert!(read_progress(&path).is_err());
+    }
+}
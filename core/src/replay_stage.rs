@@ -32,7 +32,7 @@ use {
     lazy_static::lazy_static,
     rayon::{prelude::*, ThreadPool},
     solana_client::rpc_response::SlotUpdate,
-    solana_entry::entry::{VerifyRecyclers, UntrustedEntry},
+    solana_entry::entry::VerifyRecyclers,
     solana_geyser_plugin_manager::block_metadata_notifier_interface::BlockMetadataNotifierLock,
     solana_gossip::cluster_info::ClusterInfo,
     solana_ledger::{
@@ -57,6 +57,7 @@ use {
         bank::{Bank, NewBankOptions},
         bank_forks::{BankForks, MAX_ROOT_DISTANCE_FOR_VOTE_ONLY},
         commitment::BlockCommitmentCache,
+        slot_header::SlotHeader,
         vote_sender_types::ReplayVoteSender,
     },
     solana_sdk::{
@@ -82,7 +83,6 @@ use {
         time::{Duration, Instant},
     },
 };
-use solana_geyser_plugin_manager::entry_notifier_interface::EntryNotifierLock;
 
 pub const MAX_ENTRY_RECV_PER_ITER: usize = 512;
 pub const SUPERMINORITY_THRESHOLD: f64 = 1f64 / 3f64;
@@ -95,8 +95,6 @@ const MAX_VOTE_REFRESH_INTERVAL_MILLIS: usize = 5000;
 // to be able to replay all active forks at the same time in most cases.
 const MAX_CONCURRENT_FORKS_TO_REPLAY: usize = 4;
 
-static mut LAST_NOTIFIED_FULL_SLOT: u64 = 0;
-
 lazy_static! {
     static ref PAR_THREAD_POOL: ThreadPool = rayon::ThreadPoolBuilder::new()
         .num_threads(MAX_CONCURRENT_FORKS_TO_REPLAY)
@@ -400,7 +398,6 @@ impl ReplayStage {
         drop_bank_sender: Sender<Vec<Arc<Bank>>>,
         block_metadata_notifier: Option<BlockMetadataNotifierLock>,
         log_messages_bytes_limit: Option<usize>,
-        entry_notifier: Option<EntryNotifierLock>,
     ) -> Self {
         let mut tower = if let Some(process_blockstore) = maybe_process_blockstore {
             let tower = process_blockstore.process_to_create_tower();
@@ -511,6 +508,7 @@ impl ReplayStage {
                         &blockstore,
                         &bank_forks,
                         &my_pubkey,
+                        &identity_keypair,
                         &vote_account,
                         &mut progress,
                         transaction_status_sender.as_ref(),
@@ -533,7 +531,6 @@ impl ReplayStage {
                         block_metadata_notifier.clone(),
                         &mut replay_timing,
                         log_messages_bytes_limit,
-                        entry_notifier.clone(),
                     );
                     replay_active_banks_time.stop();
 
@@ -2400,6 +2397,7 @@ impl ReplayStage {
     fn process_replay_results(
         blockstore: &Blockstore,
         bank_forks: &RwLock<BankForks>,
+        identity_keypair: &Keypair,
         progress: &mut ProgressMap,
         transaction_status_sender: Option<&TransactionStatusSender>,
         cache_block_meta_sender: Option<&CacheBlockMetaSender>,
@@ -2418,7 +2416,6 @@ impl ReplayStage {
         ancestor_hashes_replay_update_sender: &AncestorHashesReplayUpdateSender,
         block_metadata_notifier: Option<BlockMetadataNotifierLock>,
         replay_result_vec: &[ReplaySlotFromBlockstore],
-        entry_notifier: Option<EntryNotifierLock>,
     ) -> bool {
         // TODO: See if processing of blockstore replay results and bank completion can be made thread safe.
         let mut did_complete_bank = false;
@@ -2542,75 +2539,20 @@ impl ReplayStage {
                 Self::record_rewards(bank, rewards_recorder_sender);
                 if let Some(ref block_metadata_notifier) = block_metadata_notifier {
                     let block_metadata_notifier = block_metadata_notifier.read().unwrap();
+                    let header = SlotHeader::new(bank);
                     block_metadata_notifier.notify_block_metadata(
                         bank.slot(),
-                        &bank.last_blockhash().to_string(),
+                        &header.blockhash.to_string(),
                         &bank.rewards,
                         Some(bank.clock().unix_timestamp),
                         Some(bank.block_height()),
+                        &header.state_root.to_string(),
+                        &header.transactions_state_root.to_string(),
+                        &header.bank_hash.to_string(),
+                        &header.sign(identity_keypair).to_string(),
                     )
                 }
 
-                // notify entries once current bank is completed
-                if let Some(ref entry_notifier) = entry_notifier {
-                    // unsafe {
-                    //     let entry_notifier = entry_notifier.read().unwrap();
-                    //     if LAST_NOTIFIED_FULL_SLOT == 0 {
-                    //         LAST_NOTIFIED_FULL_SLOT = entry_notifier.last_insert_entry();
-                    //     }
-                    //     let target_slot = LAST_NOTIFIED_FULL_SLOT + 1;
-                    //     // let is_full = blockstore.is_full(target_slot);
-                    //     // let is_dead = blockstore.is_dead(target_slot);
-                    //     // let is_skipped = blockstore.is_skipped(target_slot);
-                    //     let load_result = blockstore.get_slot_entries_with_shred_info(target_slot, 0, false).unwrap();
-                    //     if load_result.2 {
-                    //         let untrusted_entry = UntrustedEntry {
-                    //             entries: load_result.0.clone(),
-                    //             slot: target_slot,
-                    //             parent_slot: target_slot - 1,
-                    //             is_full_slot: load_result.2
-                    //         };
-
-                    //         entry_notifier.notify_entry(&untrusted_entry);
-                    //         LAST_NOTIFIED_FULL_SLOT += 1;
-                    //     } else {
-                    //         // target_slot is not full, let's try next one to see if it is valid
-                    //         let next_slot = target_slot + 1;
-                    //         if let Ok(Some(meta)) = blockstore.meta(next_slot) {
-                    //             if let Some(parent_slot) = meta.parent_slot {
-                    //                 if parent_slot == LAST_NOTIFIED_FULL_SLOT && blockstore.is_full(next_slot) {
-                    //                     LAST_NOTIFIED_FULL_SLOT += 1;
-                    //                 }
-                    //             }
-                    //         }
-                    //     }
-                    // }
-                    unsafe {
-                        let entry_notifier = entry_notifier.read().unwrap();
-                        if LAST_NOTIFIED_FULL_SLOT == 0 {
-                            LAST_NOTIFIED_FULL_SLOT = entry_notifier.last_insert_entry();
-                        }
-
-                        let target_slot = LAST_NOTIFIED_FULL_SLOT + 1;
-                        // target slot exists
-                        if let Ok(Some(slot_meta)) = blockstore.meta(target_slot) {
-                            // next slot exists, meaning target slot is final.
-                            if let Ok(Some(_)) = blockstore.meta(target_slot + 1) {
-                                let load_result = blockstore.get_slot_entries_with_shred_info(target_slot, 0, false).unwrap();
-                                let untrusted_entry = UntrustedEntry {
-                                    entries: load_result.0.clone(),
-                                    slot: target_slot,
-                                    parent_slot: slot_meta.parent_slot.unwrap(),
-                                    is_full_slot: load_result.2
-                                };
-
-                                entry_notifier.notify_entry(&untrusted_entry);
-                                LAST_NOTIFIED_FULL_SLOT += 1;
-                            }
-                        } 
-                    }
-                }
-
                 bank_complete_time.stop();
 
                 r_replay_stats.report_stats(
@@ -2647,6 +2589,7 @@ impl ReplayStage {
         blockstore: &Blockstore,
         bank_forks: &RwLock<BankForks>,
         my_pubkey: &Pubkey,
+        identity_keypair: &Keypair,
         vote_account: &Pubkey,
         progress: &mut ProgressMap,
         transaction_status_sender: Option<&TransactionStatusSender>,
@@ -2669,7 +2612,6 @@ impl ReplayStage {
         block_metadata_notifier: Option<BlockMetadataNotifierLock>,
         replay_timing: &mut ReplayTiming,
         log_messages_bytes_limit: Option<usize>,
-        entry_notifier: Option<EntryNotifierLock>,
     ) -> bool {
         let active_bank_slots = bank_forks.read().unwrap().active_bank_slots();
         let num_active_banks = active_bank_slots.len();
@@ -2739,6 +2681,7 @@ impl ReplayStage {
             Self::process_replay_results(
                 blockstore,
                 bank_forks,
+                identity_keypair,
                 progress,
                 transaction_status_sender,
                 cache_block_meta_sender,
@@ -2757,7 +2700,6 @@ impl ReplayStage {
                 ancestor_hashes_replay_update_sender,
                 block_metadata_notifier,
                 &replay_result_vec,
-                entry_notifier,
             )
         } else {
             false
@@ -52,7 +52,6 @@ use {
         sync::{atomic::AtomicBool, Arc, RwLock},
         thread::{self, JoinHandle},
     },
-    solana_geyser_plugin_manager::entry_notifier_interface::EntryNotifierLock,
 };
 
 pub struct Tvu {
@@ -130,7 +129,6 @@ impl Tvu {
         accounts_background_request_sender: AbsRequestSender,
         log_messages_bytes_limit: Option<usize>,
         connection_cache: &Arc<ConnectionCache>,
-        entry_notifier: Option<EntryNotifierLock>,
     ) -> Self {
         let TvuSockets {
             repair: repair_socket,
@@ -290,7 +288,6 @@ impl Tvu {
             drop_bank_sender,
             block_metadata_notifier,
             log_messages_bytes_limit,
-            entry_notifier,
         );
 
         let ledger_cleanup_service = tvu_config.max_ledger_shreds.map(|max_ledger_shreds| {
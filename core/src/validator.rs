@@ -9,6 +9,7 @@ use {
         cluster_info_vote_listener::VoteTracker,
         completed_data_sets_service::CompletedDataSetsService,
         consensus::{reconcile_blockstore_roots_with_external_source, ExternalRootSource, Tower},
+        entry_publisher_service::EntryPublisherService,
         ledger_metric_report_service::LedgerMetricReportService,
         poh_timing_report_service::PohTimingReportService,
         rewards_recorder_service::{RewardsRecorderSender, RewardsRecorderService},
@@ -27,7 +28,7 @@ use {
     rand::{thread_rng, Rng},
     solana_client::connection_cache::ConnectionCache,
     solana_entry::poh::compute_hash_time_ns,
-    solana_geyser_plugin_manager::geyser_plugin_service::GeyserPluginService,
+    solana_geyser_plugin_manager::geyser_plugin_service::{GeyserPluginAdmin, GeyserPluginService},
     solana_gossip::{
         cluster_info::{
             ClusterInfo, Node, DEFAULT_CONTACT_DEBUG_INTERVAL_MILLIS,
@@ -111,7 +112,6 @@ use {
         thread::{sleep, Builder, JoinHandle},
         time::{Duration, Instant},
     },
-    // solana_geyser_plugin_manager::entry_notifier_interface::EntryNotifierLock,
 };
 
 const MAX_COMPLETED_DATA_SETS_IN_CHANNEL: usize = 100_000;
@@ -127,6 +127,7 @@ pub struct ValidatorConfig {
     pub account_shrink_paths: Option<Vec<PathBuf>>,
     pub rpc_config: JsonRpcConfig,
     pub geyser_plugin_config_files: Option<Vec<PathBuf>>,
+    pub publish_confirmed_entries: bool,
     pub rpc_addrs: Option<(SocketAddr, SocketAddr)>, // (JsonRpc, JsonRpcPubSub)
     pub pubsub_config: PubSubConfig,
     pub snapshot_config: Option<SnapshotConfig>,
@@ -191,6 +192,7 @@ impl Default for ValidatorConfig {
             account_shrink_paths: None,
             rpc_config: JsonRpcConfig::default(),
             geyser_plugin_config_files: None,
+            publish_confirmed_entries: false,
             rpc_addrs: None,
             pubsub_config: PubSubConfig::default(),
             snapshot_config: None,
@@ -342,6 +344,7 @@ pub struct Validator {
     transaction_status_service: Option<TransactionStatusService>,
     rewards_recorder_service: Option<RewardsRecorderService>,
     cache_block_meta_service: Option<CacheBlockMetaService>,
+    entry_publisher_service: Option<EntryPublisherService>,
     system_monitor_service: Option<SystemMonitorService>,
     sample_performance_service: Option<SamplePerformanceService>,
     poh_timing_report_service: PohTimingReportService,
@@ -563,7 +566,6 @@ impl Validator {
             accounts_update_notifier,
             transaction_notifier,
             Some(poh_timing_point_sender.clone()),
-            // entry_notifier,
         );
 
         node.info.wallclock = timestamp();
@@ -742,6 +744,18 @@ impl Validator {
         let optimistically_confirmed_bank =
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
 
+        let entry_publisher_service = entry_notifier.map(|entry_notifier| {
+            EntryPublisherService::new(
+                entry_notifier,
+                blockstore.clone(),
+                bank_forks.clone(),
+                config
+                    .publish_confirmed_entries
+                    .then(|| optimistically_confirmed_bank.clone()),
+                &exit,
+            )
+        });
+
         let rpc_subscriptions = Arc::new(RpcSubscriptions::new_with_config(
             &exit,
             max_complete_transaction_status_slot.clone(),
@@ -1022,7 +1036,6 @@ impl Validator {
             accounts_background_request_sender,
             config.runtime_config.log_messages_bytes_limit,
             &connection_cache,
-            entry_notifier,
         );
 
         let tpu = Tpu::new(
@@ -1079,6 +1092,7 @@ impl Validator {
             transaction_status_service,
             rewards_recorder_service,
             cache_block_meta_service,
+            entry_publisher_service,
             system_monitor_service,
             sample_performance_service,
             poh_timing_report_service,
@@ -1100,6 +1114,13 @@ impl Validator {
         }
     }
 
+    /// Loads and unloads the geyser plugins, if the validator was started with any.
+    pub fn geyser_plugin_admin(&self) -> Option<GeyserPluginAdmin> {
+        self.geyser_plugin_service
+            .as_ref()
+            .map(GeyserPluginService::get_plugin_admin)
+    }
+
     // Used for notifying many nodes in parallel to exit
     pub fn exit(&mut self) {
         self.validator_exit.write().unwrap().exit();
@@ -1183,6 +1204,12 @@ impl Validator {
                 .expect("cache_block_meta_service");
         }
 
+        if let Some(entry_publisher_service) = self.entry_publisher_service {
+            entry_publisher_service
+                .join()
+                .expect("entry_publisher_service");
+        }
+
         if let Some(system_monitor_service) = self.system_monitor_service {
             system_monitor_service
                 .join()
@@ -1459,6 +1486,7 @@ fn load_blockstore(
         accounts_db_test_hash_calculation: config.accounts_db_test_hash_calculation,
         accounts_db_skip_shrink: config.accounts_db_skip_shrink,
         runtime_config: config.runtime_config.clone(),
+        persist_state_tree: true,
         ..blockstore_processor::ProcessOptions::default()
     };
 
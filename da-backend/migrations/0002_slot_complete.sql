@@ -0,0 +1,36 @@
+/**
+ * Migration 2: the leader-signed block header columns, one row per shred in
+ * the entry table and the slot_complete markers. Databases created by hand
+ * before the schema was versioned may already have some of these, so each
+ * step is skipped if it was applied.
+ */
+ALTER TABLE block ADD COLUMN IF NOT EXISTS bank_hash VARCHAR(44);
+ALTER TABLE block ADD COLUMN IF NOT EXISTS header_signature VARCHAR(88);
+
+DROP INDEX IF EXISTS entry_slot_index;
+
+DO $entry_pk$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_constraint
+                       WHERE conname = 'entry_pk' AND conrelid = 'entry'::regclass) THEN
+            -- Keep the last written copy of the shreds stored more than once
+            DELETE FROM entry a USING entry b
+                WHERE a.slot = b.slot AND a.entry_index = b.entry_index
+                AND a.updated_on < b.updated_on;
+            DELETE FROM entry a USING entry b
+                WHERE a.slot = b.slot AND a.entry_index = b.entry_index
+                AND a.ctid < b.ctid;
+            ALTER TABLE entry ADD CONSTRAINT entry_pk PRIMARY KEY (slot, entry_index);
+        END IF;
+    END;
+$entry_pk$;
+
+-- The slots whose shreds are all in the entry table, written in the same
+-- transaction as the shreds. Readers only consume the slots listed here.
+CREATE TABLE IF NOT EXISTS slot_complete (
+    slot BIGINT PRIMARY KEY,
+    parent_slot BIGINT NOT NULL,
+    shred_count BIGINT NOT NULL,
+    checksum BYTEA NOT NULL,
+    updated_on TIMESTAMP NOT NULL
+);
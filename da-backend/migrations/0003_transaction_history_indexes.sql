@@ -0,0 +1,14 @@
+/**
+ * Migration 3: the indexes the RPC ledger storage (solana-storage-postgres)
+ * reads the transaction history through, by signature and by the accounts a
+ * transaction loads. The expression of transaction_account_keys must match
+ * the one the queries use.
+ */
+CREATE INDEX IF NOT EXISTS transaction_signature ON transaction (signature);
+
+CREATE INDEX IF NOT EXISTS transaction_account_keys ON transaction USING GIN ((
+    (legacy_message).account_keys
+    || ((v0_loaded_message).message).account_keys
+    || ((v0_loaded_message).loaded_addresses).writable
+    || ((v0_loaded_message).loaded_addresses).readonly
+));
@@ -0,0 +1,5 @@
+/**
+ * Migration 4: the transactions state root of the block, which the signed
+ * slot header commits to and fraud proofs are checked against.
+ */
+ALTER TABLE block ADD COLUMN IF NOT EXISTS transactions_state_root VARCHAR(44);
@@ -0,0 +1,10 @@
+/**
+ * Migration 5: the DA blobs, published instead of the shreds in the entry
+ * table when the plugin is configured with "da_payload" : "blob".
+ */
+CREATE TABLE IF NOT EXISTS da_blob (
+    start_slot BIGINT PRIMARY KEY,
+    end_slot BIGINT NOT NULL,
+    blob BYTEA NOT NULL,
+    updated_on TIMESTAMP NOT NULL
+);
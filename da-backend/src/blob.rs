@@ -0,0 +1,322 @@
+//! The compressed blob format the entries of the rollup are posted to a DA
+//! layer in. Posting is paid per byte, so instead of the shreds, with their
+//! erasure coding and signature overhead, a blob carries the serialized
+//! entries of one or more consecutive slots of a fork, compressed with zstd.
+//!
+//! The header is left uncompressed, so the slot range of a blob and the
+//! commitment over its entries can be read without decompressing it. It is
+//! signed by the leader of the slots, so the replayers can check a blob was
+//! produced by the sequencer as they check the shreds.
+use {
+    crate::{DataAvailabilityError, Result},
+    serde_derive::{Deserialize, Serialize},
+    solana_entry::entry::Entry,
+    solana_merkle_tree::MerkleTree,
+    solana_sdk::{
+        clock::Slot,
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+    },
+};
+
+/// The version of the blob format written by `DaBlob::new`.
+pub const BLOB_VERSION: u8 = 2;
+/// Decompressing a blob fails beyond this size, so a malicious blob cannot
+/// exhaust the memory of the verification nodes.
+pub const MAX_BLOB_UNCOMPRESSED_SIZE: usize = 128 * 1024 * 1024;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The entries of one slot.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotEntries {
+    pub slot: Slot,
+    pub parent_slot: Slot,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobHeader {
+    pub version: u8,
+    pub start_slot: Slot,
+    pub end_slot: Slot,
+    /// The parent of `start_slot`.
+    pub parent_slot: Slot,
+    pub entry_count: u64,
+    /// The hash of the last entry, which the PoH of the next blob continues
+    /// from.
+    pub poh_hash: Hash,
+    /// The merkle root over the entries, each leaf being a serialized
+    /// `(slot, entry)`.
+    pub entries_root: Hash,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaBlob {
+    pub header: BlobHeader,
+    /// The signature of the serialized header by the leader of the slots.
+    pub signature: Signature,
+    /// The zstd compressed `Vec<SlotEntries>`.
+    compressed_entries: Vec<u8>,
+}
+
+impl DaBlob {
+    /// Pack `slots`, which must be consecutive slots of a fork in ascending
+    /// order, signing the header with `keypair`, the leader of the slots.
+    pub fn new(slots: &[SlotEntries], keypair: &Keypair) -> Result<Self> {
+        let header = BlobHeader::new(slots)?;
+        let signature = keypair.sign_message(&bincode::serialize(&header)?);
+        let compressed_entries =
+            zstd::bulk::compress(&bincode::serialize(slots)?, COMPRESSION_LEVEL)?;
+        Ok(Self {
+            header,
+            signature,
+            compressed_entries,
+        })
+    }
+
+    /// Whether the header was signed by `pubkey`. The header commits to the
+    /// entries, which `decode` checks.
+    pub fn verify(&self, pubkey: &Pubkey) -> bool {
+        bincode::serialize(&self.header)
+            .map(|header| self.signature.verify(pubkey.as_ref(), &header))
+            .unwrap_or(false)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let blob: Self = bincode::deserialize(bytes)?;
+        if blob.header.version != BLOB_VERSION {
+            return Err(invalid_blob(format!(
+                "unsupported version {}",
+                blob.header.version
+            )));
+        }
+        Ok(blob)
+    }
+
+    /// Decompress the entries, checking they match the header.
+    pub fn decode(&self) -> Result<Vec<SlotEntries>> {
+        let serialized_slots =
+            zstd::bulk::decompress(&self.compressed_entries, MAX_BLOB_UNCOMPRESSED_SIZE)?;
+        let slots: Vec<SlotEntries> = bincode::deserialize(&serialized_slots)?;
+        let header = BlobHeader::new(&slots)?;
+        if header != self.header {
+            return Err(invalid_blob(format!(
+                "the entries do not match the header, expected: {:?}, actual: {:?}",
+                self.header, header
+            )));
+        }
+        Ok(slots)
+    }
+}
+
+impl BlobHeader {
+    fn new(slots: &[SlotEntries]) -> Result<Self> {
+        let (first, last) = match (slots.first(), slots.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(invalid_blob("no slots".to_string())),
+        };
+        for slot_entries in slots {
+            if slot_entries.entries.is_empty() {
+                return Err(invalid_blob(format!(
+                    "slot {} has no entries",
+                    slot_entries.slot
+                )));
+            }
+            if slot_entries.parent_slot >= slot_entries.slot {
+                return Err(invalid_blob(format!(
+                    "slot {} has parent {}",
+                    slot_entries.slot, slot_entries.parent_slot
+                )));
+            }
+        }
+        for window in slots.windows(2) {
+            if window[1].parent_slot != window[0].slot {
+                return Err(invalid_blob(format!(
+                    "slot {} does not chain to slot {}",
+                    window[1].slot, window[0].slot
+                )));
+            }
+        }
+        Ok(Self {
+            version: BLOB_VERSION,
+            start_slot: first.slot,
+            end_slot: last.slot,
+            parent_slot: first.parent_slot,
+            entry_count: slots
+                .iter()
+                .map(|slot_entries| slot_entries.entries.len() as u64)
+                .sum(),
+            poh_hash: last.entries.last().unwrap().hash,
+            entries_root: entries_root(slots)?,
+        })
+    }
+}
+
+/// The merkle root over the entries of `slots`, each leaf being a serialized
+/// `(slot, entry)`.
+pub fn entries_root(slots: &[SlotEntries]) -> Result<Hash> {
+    let mut leaves = vec![];
+    for slot_entries in slots {
+        for entry in &slot_entries.entries {
+            leaves.push(bincode::serialize(&(slot_entries.slot, entry))?);
+        }
+    }
+    Ok(MerkleTree::new(&leaves)
+        .get_root()
+        .copied()
+        .unwrap_or_default())
+}
+
+fn invalid_blob(msg: String) -> DataAvailabilityError {
+    DataAvailabilityError::InvalidBlob { msg }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_entry::entry::create_ticks,
+        solana_sdk::{
+            hash::hash,
+            signature::{Keypair, Signer},
+            system_transaction,
+        },
+    };
+
+    fn slot_entries(slot: Slot, parent_slot: Slot, start_hash: Hash) -> SlotEntries {
+        let keypair = Keypair::new();
+        let transaction = system_transaction::transfer(&keypair, &keypair.pubkey(), 1, start_hash);
+        let mut entries = vec![Entry::new(&start_hash, 1, vec![transaction])];
+        entries.extend(create_ticks(8, 1, entries[0].hash));
+        SlotEntries {
+            slot,
+            parent_slot,
+            entries,
+        }
+    }
+
+    fn test_slots() -> Vec<SlotEntries> {
+        let first = slot_entries(4, 2, hash(&[1]));
+        let second = slot_entries(5, 4, first.entries.last().unwrap().hash);
+        vec![first, second]
+    }
+
+    #[test]
+    fn test_blob_roundtrip() {
+        let slots = test_slots();
+        let keypair = Keypair::new();
+        let blob = DaBlob::new(&slots, &keypair).unwrap();
+        assert_eq!(
+            blob.header,
+            BlobHeader {
+                version: BLOB_VERSION,
+                start_slot: 4,
+                end_slot: 5,
+                parent_slot: 2,
+                entry_count: 18,
+                poh_hash: slots[1].entries.last().unwrap().hash,
+                entries_root: entries_root(&slots).unwrap(),
+            }
+        );
+
+        let bytes = blob.to_bytes().unwrap();
+        assert!(bytes.len() < bincode::serialized_size(&slots).unwrap() as usize);
+        let blob = DaBlob::from_bytes(&bytes).unwrap();
+        assert!(blob.verify(&keypair.pubkey()));
+        assert_eq!(blob.decode().unwrap(), slots);
+    }
+
+    #[test]
+    fn test_blob_signature() {
+        let keypair = Keypair::new();
+        let blob = DaBlob::new(&test_slots(), &keypair).unwrap();
+        assert!(blob.verify(&keypair.pubkey()));
+        assert!(!blob.verify(&Pubkey::new_unique()));
+
+        // The signature covers every field of the header
+        let mut tampered = blob.clone();
+        tampered.header.end_slot += 1;
+        assert!(!tampered.verify(&keypair.pubkey()));
+        let mut tampered = blob.clone();
+        tampered.header.poh_hash = Hash::default();
+        assert!(!tampered.verify(&keypair.pubkey()));
+        let mut tampered = blob;
+        tampered.header.entries_root = Hash::default();
+        assert!(!tampered.verify(&keypair.pubkey()));
+    }
+
+    #[test]
+    fn test_blob_commitment() {
+        let slots = test_slots();
+        let keypair = Keypair::new();
+        let blob = DaBlob::new(&slots, &keypair).unwrap();
+
+        // Every entry is committed to, with its slot
+        let mut other_slots = slots.clone();
+        other_slots[1].entries.swap(1, 2);
+        assert_ne!(
+            entries_root(&other_slots).unwrap(),
+            blob.header.entries_root
+        );
+        let mut other_slots = slots;
+        other_slots[1].slot = 6;
+        assert_ne!(
+            entries_root(&other_slots).unwrap(),
+            blob.header.entries_root
+        );
+
+        // Entries which do not match the header are rejected
+        let other_blob = DaBlob::new(&other_slots, &keypair).unwrap();
+        let tampered = DaBlob {
+            header: blob.header.clone(),
+            signature: blob.signature,
+            compressed_entries: other_blob.compressed_entries,
+        };
+        assert!(matches!(
+            tampered.decode(),
+            Err(DataAvailabilityError::InvalidBlob { .. })
+        ));
+        let corrupted = DaBlob {
+            header: blob.header,
+            signature: blob.signature,
+            compressed_entries: vec![1, 2, 3],
+        };
+        assert!(corrupted.decode().is_err());
+    }
+
+    #[test]
+    fn test_blob_invalid() {
+        assert!(matches!(
+            DaBlob::new(&[], &Keypair::new()),
+            Err(DataAvailabilityError::InvalidBlob { .. })
+        ));
+
+        // The slots must chain
+        let mut slots = test_slots();
+        slots[1].parent_slot = 3;
+        assert!(matches!(
+            DaBlob::new(&slots, &Keypair::new()),
+            Err(DataAvailabilityError::InvalidBlob { .. })
+        ));
+
+        let mut slots = test_slots();
+        slots[0].entries.clear();
+        assert!(matches!(
+            DaBlob::new(&slots, &Keypair::new()),
+            Err(DataAvailabilityError::InvalidBlob { .. })
+        ));
+
+        // Unknown versions are rejected
+        let mut blob = DaBlob::new(&test_slots(), &Keypair::new()).unwrap();
+        blob.header.version = BLOB_VERSION + 1;
+        assert!(matches!(
+            DaBlob::from_bytes(&blob.to_bytes().unwrap()),
+            Err(DataAvailabilityError::InvalidBlob { .. })
+        ));
+    }
+}
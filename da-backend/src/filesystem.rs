@@ -0,0 +1,207 @@
+//! A DA backend storing one blob file per slot in a local directory.
+//! It is meant as a simulator of a real DA layer for local clusters and tests.
+//! A `DaBlob` is stored in a file named after the range of its slots.
+use {
+    crate::{blob::DaBlob, DataAvailabilityBackend, Result, SlotData},
+    log::*,
+    solana_sdk::clock::Slot,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+const SLOT_FILE_PREFIX: &str = "slot-";
+const SLOT_FILE_EXTENSION: &str = "bin";
+const BLOB_FILE_PREFIX: &str = "blob-";
+
+pub struct FilesystemBackend {
+    path: PathBuf,
+}
+
+impl FilesystemBackend {
+    pub fn new(path: &Path) -> Result<Self> {
+        fs::create_dir_all(path)?;
+        info!("Using filesystem DA backend at {:?}", path);
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn slot_file(&self, slot: Slot) -> PathBuf {
+        self.path
+            .join(format!("{}{:020}.{}", SLOT_FILE_PREFIX, slot, SLOT_FILE_EXTENSION))
+    }
+
+    fn blob_file(&self, blob: &DaBlob) -> PathBuf {
+        self.path.join(format!(
+            "{}{:020}-{:020}.{}",
+            BLOB_FILE_PREFIX, blob.header.start_slot, blob.header.end_slot, SLOT_FILE_EXTENSION
+        ))
+    }
+
+    fn slot_of_file(path: &Path) -> Option<Slot> {
+        if path.extension()? != SLOT_FILE_EXTENSION {
+            return None;
+        }
+        path.file_stem()?
+            .to_str()?
+            .strip_prefix(SLOT_FILE_PREFIX)?
+            .parse()
+            .ok()
+    }
+
+    /// The start and end slots of a blob file
+    fn slots_of_blob_file(path: &Path) -> Option<(Slot, Slot)> {
+        if path.extension()? != SLOT_FILE_EXTENSION {
+            return None;
+        }
+        let (start_slot, end_slot) = path
+            .file_stem()?
+            .to_str()?
+            .strip_prefix(BLOB_FILE_PREFIX)?
+            .split_once('-')?;
+        Some((start_slot.parse().ok()?, end_slot.parse().ok()?))
+    }
+
+    /// The blob files starting at `slot`
+    fn blob_files(&self, slot: Slot) -> Result<Vec<PathBuf>> {
+        let mut blob_files = vec![];
+        for dir_entry in fs::read_dir(&self.path)? {
+            let path = dir_entry?.path();
+            if matches!(Self::slots_of_blob_file(&path), Some((start_slot, _)) if start_slot == slot)
+            {
+                blob_files.push(path);
+            }
+        }
+        Ok(blob_files)
+    }
+}
+
+impl DataAvailabilityBackend for FilesystemBackend {
+    fn publish_slot(&mut self, slot_data: &SlotData) -> Result<()> {
+        let slot_file = self.slot_file(slot_data.slot);
+        // Write to a temporary file first so readers never observe a partial blob.
+        let tmp_file = slot_file.with_extension("tmp");
+        fs::write(&tmp_file, bincode::serialize(slot_data)?)?;
+        fs::rename(&tmp_file, &slot_file)?;
+        Ok(())
+    }
+
+    fn fetch_slot(&mut self, slot: Slot) -> Result<Option<SlotData>> {
+        let slot_file = self.slot_file(slot);
+        if !slot_file.exists() {
+            return Ok(None);
+        }
+        let blob = fs::read(&slot_file)?;
+        Ok(Some(bincode::deserialize(&blob)?))
+    }
+
+    fn publish_blob(&mut self, blob: &DaBlob) -> Result<()> {
+        let blob_file = self.blob_file(blob);
+        let tmp_file = blob_file.with_extension("tmp");
+        fs::write(&tmp_file, blob.to_bytes()?)?;
+        fs::rename(&tmp_file, &blob_file)?;
+        // Drop the blob published before starting at the same slot, if it
+        // spanned other slots
+        for other_blob_file in self.blob_files(blob.header.start_slot)? {
+            if other_blob_file != blob_file {
+                fs::remove_file(other_blob_file)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fetch_blob(&mut self, slot: Slot) -> Result<Option<DaBlob>> {
+        match self.blob_files(slot)?.first() {
+            Some(blob_file) => Ok(Some(DaBlob::from_bytes(&fs::read(blob_file)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn highest_slot(&mut self) -> Result<Option<Slot>> {
+        let mut highest_slot = None;
+        for dir_entry in fs::read_dir(&self.path)? {
+            let path = dir_entry?.path();
+            let slot = Self::slot_of_file(&path)
+                .or_else(|| Self::slots_of_blob_file(&path).map(|(_, end_slot)| end_slot));
+            highest_slot = highest_slot.max(slot);
+        }
+        Ok(highest_slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::blob::SlotEntries,
+        solana_entry::entry::create_ticks,
+        solana_sdk::{hash::Hash, signature::Keypair},
+    };
+
+    fn slot_data(slot: Slot) -> SlotData {
+        SlotData {
+            slot,
+            parent_slot: slot.saturating_sub(1),
+            is_full_slot: true,
+            shreds: vec![vec![slot as u8; 16], vec![1, 2, 3]],
+        }
+    }
+
+    #[test]
+    fn test_publish_and_fetch_slot() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut backend = FilesystemBackend::new(dir.path()).unwrap();
+        assert_eq!(backend.highest_slot().unwrap(), None);
+        assert_eq!(backend.fetch_slot(1).unwrap(), None);
+
+        backend.publish_slot(&slot_data(1)).unwrap();
+        backend.publish_slot(&slot_data(3)).unwrap();
+        assert_eq!(backend.fetch_slot(1).unwrap(), Some(slot_data(1)));
+        assert_eq!(backend.fetch_slot(2).unwrap(), None);
+        assert_eq!(backend.highest_slot().unwrap(), Some(3));
+
+        // publishing a slot again replaces the previous blob
+        let mut republished = slot_data(3);
+        republished.shreds.pop();
+        backend.publish_slot(&republished).unwrap();
+        assert_eq!(backend.fetch_slot(3).unwrap(), Some(republished));
+    }
+
+    #[test]
+    fn test_publish_and_fetch_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut backend = FilesystemBackend::new(dir.path()).unwrap();
+        let slot_entries = |slot, parent_slot| SlotEntries {
+            slot,
+            parent_slot,
+            entries: create_ticks(4, 1, Hash::default()),
+        };
+        let keypair = Keypair::new();
+        let blob = DaBlob::new(&[slot_entries(4, 2), slot_entries(5, 4)], &keypair).unwrap();
+        backend.publish_slot(&slot_data(3)).unwrap();
+        backend.publish_blob(&blob).unwrap();
+        assert_eq!(backend.fetch_blob(4).unwrap(), Some(blob));
+        assert_eq!(backend.fetch_blob(5).unwrap(), None);
+        assert_eq!(backend.highest_slot().unwrap(), Some(5));
+
+        // publishing a blob again replaces the previous one
+        let republished = DaBlob::new(&[slot_entries(4, 2)], &keypair).unwrap();
+        backend.publish_blob(&republished).unwrap();
+        assert_eq!(backend.fetch_blob(4).unwrap(), Some(republished));
+        assert_eq!(backend.highest_slot().unwrap(), Some(4));
+    }
+
+    #[test]
+    fn test_highest_slot_ignores_foreign_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut backend = FilesystemBackend::new(dir.path()).unwrap();
+        fs::write(dir.path().join("README"), b"not a slot").unwrap();
+        fs::write(dir.path().join("slot-00000000000000000009.tmp"), b"partial").unwrap();
+        assert_eq!(backend.highest_slot().unwrap(), None);
+
+        backend.publish_slot(&slot_data(7)).unwrap();
+        assert_eq!(backend.highest_slot().unwrap(), Some(7));
+    }
+}
@@ -0,0 +1,209 @@
+//! Data-availability (DA) backends for the rollup.
+//!
+//! The execution node publishes the shreds of every slot it produces to a DA
+//! backend, and the verification nodes (`shred_replay`, `smt-proof`) fetch
+//! them back to rebuild and replay the ledger. Which backend is used is chosen
+//! from the `da_backend` section of the shared JSON config file. With
+//! `"da_payload" : "blob"` the entries are published as compressed `DaBlob`s
+//! signed by the slot leader instead of shreds.
+//!
+//! The `schema` module holds the versioned migrations of the PostgreSQL
+//! database they share.
+pub mod blob;
+pub mod filesystem;
+pub mod postgres;
+pub mod schema;
+
+use {
+    crate::{blob::DaBlob, filesystem::FilesystemBackend, postgres::PostgresBackend},
+    serde_derive::{Deserialize, Serialize},
+    solana_sdk::{
+        clock::Slot,
+        hash::{Hash, Hasher},
+    },
+    std::{io, path::PathBuf},
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum DataAvailabilityError {
+    #[error("Error connecting to the DA backend. Error message: ({msg})")]
+    ConnectionError { msg: String },
+
+    #[error("Error publishing slot {slot} to the DA backend. Error message: ({msg})")]
+    PublishError { slot: Slot, msg: String },
+
+    #[error("Error fetching slot {slot} from the DA backend. Error message: ({msg})")]
+    FetchError { slot: Slot, msg: String },
+
+    #[error("Error querying the DA backend. Error message: ({msg})")]
+    QueryError { msg: String },
+
+    #[error("Invalid DA blob. Error message: ({msg})")]
+    InvalidBlob { msg: String },
+
+    #[error("Error migrating the database schema. Error message: ({msg})")]
+    SchemaMigrationError { msg: String },
+
+    #[error(
+        "The database schema version {version} is newer than the version {supported} of this binary"
+    )]
+    SchemaTooNew { version: i32, supported: i32 },
+
+    #[error("Io error: ({0})")]
+    Io(#[from] io::Error),
+
+    #[error("Serialization error: ({0})")]
+    Serialization(#[from] bincode::Error),
+}
+
+pub type Result<T> = std::result::Result<T, DataAvailabilityError>;
+
+/// The data of one slot as stored on the DA layer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotData {
+    pub slot: Slot,
+    pub parent_slot: Slot,
+    pub is_full_slot: bool,
+    /// Serialized data shred payloads, ordered by shred index.
+    pub shreds: Vec<Vec<u8>>,
+}
+
+impl SlotData {
+    /// The checksum of the shreds, to detect a slot which was not completely
+    /// persisted.
+    pub fn checksum(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        for shred in &self.shreds {
+            hasher.hash(&(shred.len() as u64).to_le_bytes());
+            hasher.hash(shred);
+        }
+        hasher.result()
+    }
+}
+
+/// A place the rollup block data is published to and fetched back from.
+pub trait DataAvailabilityBackend: Send {
+    /// Publish all shreds of `slot_data.slot`, replacing whatever was published
+    /// for the slot before. Either all of them are published or none.
+    fn publish_slot(&mut self, slot_data: &SlotData) -> Result<()>;
+
+    /// Fetch the data of `slot`, `None` if it was not completely published.
+    fn fetch_slot(&mut self, slot: Slot) -> Result<Option<SlotData>>;
+
+    /// Publish `blob`, replacing whatever blob was published starting at the
+    /// same slot before.
+    fn publish_blob(&mut self, blob: &DaBlob) -> Result<()>;
+
+    /// Fetch the blob starting at `slot`, `None` if there is none.
+    fn fetch_blob(&mut self, slot: Slot) -> Result<Option<DaBlob>>;
+
+    /// The highest slot completely published so far, with its shreds or in a
+    /// blob, `None` if there is none.
+    fn highest_slot(&mut self) -> Result<Option<Slot>>;
+}
+
+/// Selects what is published for each slot, e.g.
+/// "da_payload" : "blob"
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataAvailabilityPayload {
+    /// The data shreds as signed by the slot leader, which the replayers
+    /// verify against the leader schedule.
+    Shreds,
+    /// A `DaBlob` of the entries of the slot, a fraction of the size of the
+    /// shreds. Its header is signed by the slot leader and commits to the
+    /// entries, which the replayers verify.
+    Blob,
+}
+
+impl Default for DataAvailabilityPayload {
+    fn default() -> Self {
+        Self::Shreds
+    }
+}
+
+/// Selects the DA backend, e.g.
+/// "da_backend" : { "type" : "postgres" }
+/// or:
+/// "da_backend" : { "type" : "filesystem", "path" : "/tmp/da" }
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DataAvailabilityBackendConfig {
+    /// Store the shreds in the `entry` and `slot_complete` tables of the
+    /// PostgreSQL database the rest of the config file points at.
+    Postgres,
+    /// Store one blob per slot under `path`, a simulator for a real DA layer.
+    Filesystem { path: PathBuf },
+}
+
+impl Default for DataAvailabilityBackendConfig {
+    fn default() -> Self {
+        Self::Postgres
+    }
+}
+
+impl DataAvailabilityBackendConfig {
+    /// Create the configured backend. `connect` is only called for the
+    /// PostgreSQL backend so callers can reuse their own connection settings.
+    pub fn build<F>(&self, connect: F) -> Result<Box<dyn DataAvailabilityBackend>>
+    where
+        F: FnOnce() -> Result<::postgres::Client>,
+    {
+        match self {
+            Self::Postgres => Ok(Box::new(PostgresBackend::new(connect()?)?)),
+            Self::Filesystem { path } => Ok(Box::new(FilesystemBackend::new(path)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_data_checksum() {
+        let slot_data = SlotData {
+            slot: 1,
+            parent_slot: 0,
+            is_full_slot: true,
+            shreds: vec![vec![1, 2], vec![3]],
+        };
+        // Only the shreds are covered
+        let mut other = SlotData {
+            slot: 2,
+            ..slot_data.clone()
+        };
+        assert_eq!(slot_data.checksum(), other.checksum());
+        other.shreds = vec![vec![1], vec![2, 3]];
+        assert_ne!(slot_data.checksum(), other.checksum());
+        other.shreds = vec![vec![1, 2]];
+        assert_ne!(slot_data.checksum(), other.checksum());
+    }
+
+    #[test]
+    fn test_backend_config_from_json() {
+        let config: DataAvailabilityBackendConfig =
+            serde_json::from_str("{\"type\" : \"postgres\"}").unwrap();
+        assert_eq!(config, DataAvailabilityBackendConfig::Postgres);
+
+        let config: DataAvailabilityBackendConfig =
+            serde_json::from_str("{\"type\" : \"filesystem\", \"path\" : \"/tmp/da\"}").unwrap();
+        assert_eq!(
+            config,
+            DataAvailabilityBackendConfig::Filesystem {
+                path: PathBuf::from("/tmp/da")
+            }
+        );
+    }
+
+    #[test]
+    fn test_payload_from_json() {
+        assert_eq!(
+            DataAvailabilityPayload::default(),
+            DataAvailabilityPayload::Shreds
+        );
+        let payload: DataAvailabilityPayload = serde_json::from_str("\"blob\"").unwrap();
+        assert_eq!(payload, DataAvailabilityPayload::Blob);
+    }
+}
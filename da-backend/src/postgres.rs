@@ -0,0 +1,294 @@
+//! A DA backend storing the shreds in the PostgreSQL `entry` table, one row
+//! per shred, with a `slot_complete` row per slot once all its shreds are
+//! stored, and the blobs in the `da_blob` table.
+//! See `solana-accountsdb-plugin-postgres/scripts/create_schema.sql`.
+use {
+    crate::{blob::DaBlob, DataAvailabilityBackend, DataAvailabilityError, Result, SlotData},
+    chrono::Utc,
+    log::*,
+    postgres::{fallible_iterator::FallibleIterator, Client, IsolationLevel, Statement},
+    solana_sdk::clock::Slot,
+    std::time::Duration,
+};
+
+/// The channel notified with the slot number each time a slot is complete.
+pub const SLOT_COMPLETE_CHANNEL: &str = "slot_complete";
+
+pub struct PostgresBackend {
+    client: Client,
+    upsert_entry_stmt: Statement,
+    delete_entries_stmt: Statement,
+    upsert_slot_complete_stmt: Statement,
+    select_slot_complete_stmt: Statement,
+    select_entry_stmt: Statement,
+    upsert_blob_stmt: Statement,
+    select_blob_stmt: Statement,
+}
+
+impl PostgresBackend {
+    pub fn new(mut client: Client) -> Result<Self> {
+        let upsert_entry_stmt = Self::prepare(
+            &mut client,
+            "INSERT INTO entry (slot, parent_slot, entry_index, entry, is_full_slot, updated_on) \
+            VALUES ($1, $2, $3, $4, $5, $6) \
+            ON CONFLICT (slot, entry_index) DO UPDATE SET parent_slot=excluded.parent_slot, \
+            entry=excluded.entry, is_full_slot=excluded.is_full_slot, updated_on=excluded.updated_on",
+        )?;
+        let delete_entries_stmt = Self::prepare(
+            &mut client,
+            "DELETE FROM entry WHERE slot = $1 AND entry_index >= $2",
+        )?;
+        let upsert_slot_complete_stmt = Self::prepare(
+            &mut client,
+            "INSERT INTO slot_complete (slot, parent_slot, shred_count, checksum, updated_on) \
+            VALUES ($1, $2, $3, $4, $5) \
+            ON CONFLICT (slot) DO UPDATE SET parent_slot=excluded.parent_slot, \
+            shred_count=excluded.shred_count, checksum=excluded.checksum, \
+            updated_on=excluded.updated_on",
+        )?;
+        let select_slot_complete_stmt = Self::prepare(
+            &mut client,
+            "SELECT shred_count, checksum FROM slot_complete WHERE slot = $1",
+        )?;
+        let select_entry_stmt = Self::prepare(
+            &mut client,
+            "SELECT parent_slot, entry, is_full_slot FROM entry WHERE slot = $1 ORDER BY entry_index ASC",
+        )?;
+        let upsert_blob_stmt = Self::prepare(
+            &mut client,
+            "INSERT INTO da_blob (start_slot, end_slot, blob, updated_on) \
+            VALUES ($1, $2, $3, $4) \
+            ON CONFLICT (start_slot) DO UPDATE SET end_slot=excluded.end_slot, \
+            blob=excluded.blob, updated_on=excluded.updated_on",
+        )?;
+        let select_blob_stmt = Self::prepare(
+            &mut client,
+            "SELECT blob FROM da_blob WHERE start_slot = $1",
+        )?;
+        Ok(Self {
+            client,
+            upsert_entry_stmt,
+            delete_entries_stmt,
+            upsert_slot_complete_stmt,
+            select_slot_complete_stmt,
+            select_entry_stmt,
+            upsert_blob_stmt,
+            select_blob_stmt,
+        })
+    }
+
+    fn prepare(client: &mut Client, stmt: &str) -> Result<Statement> {
+        client
+            .prepare(stmt)
+            .map_err(|err| DataAvailabilityError::ConnectionError {
+                msg: format!("Error in preparing the statement {}: ({})", stmt, err),
+            })
+    }
+
+    /// Write the shreds of the slot and mark it complete, in one transaction
+    /// so a slot is never left partially written.
+    fn write_slot(&mut self, slot_data: &SlotData) -> std::result::Result<(), postgres::Error> {
+        let updated_on = Utc::now().naive_utc();
+        let slot = slot_data.slot as i64;
+        let parent_slot = slot_data.parent_slot as i64;
+        let shred_count = slot_data.shreds.len() as i64;
+        let mut transaction = self.client.transaction()?;
+        for (index, shred) in slot_data.shreds.iter().enumerate() {
+            transaction.execute(
+                &self.upsert_entry_stmt,
+                &[
+                    &slot,
+                    &parent_slot,
+                    &(index as i64),
+                    shred,
+                    &slot_data.is_full_slot,
+                    &updated_on,
+                ],
+            )?;
+        }
+        // Remove the shreds left over from a previous publish of the slot
+        transaction.execute(&self.delete_entries_stmt, &[&slot, &shred_count])?;
+        transaction.execute(
+            &self.upsert_slot_complete_stmt,
+            &[
+                &slot,
+                &parent_slot,
+                &shred_count,
+                &slot_data.checksum().as_ref(),
+                &updated_on,
+            ],
+        )?;
+        // Delivered to the listeners when the transaction commits
+        transaction.execute(
+            "SELECT pg_notify($1, $2)",
+            &[&SLOT_COMPLETE_CHANNEL, &slot_data.slot.to_string()],
+        )?;
+        transaction.commit()
+    }
+
+    fn write_blob(
+        &mut self,
+        blob: &DaBlob,
+        bytes: &[u8],
+    ) -> std::result::Result<(), postgres::Error> {
+        let mut transaction = self.client.transaction()?;
+        transaction.execute(
+            &self.upsert_blob_stmt,
+            &[
+                &(blob.header.start_slot as i64),
+                &(blob.header.end_slot as i64),
+                &bytes,
+                &Utc::now().naive_utc(),
+            ],
+        )?;
+        transaction.execute(
+            "SELECT pg_notify($1, $2)",
+            &[&SLOT_COMPLETE_CHANNEL, &blob.header.end_slot.to_string()],
+        )?;
+        transaction.commit()
+    }
+}
+
+impl DataAvailabilityBackend for PostgresBackend {
+    fn publish_slot(&mut self, slot_data: &SlotData) -> Result<()> {
+        self.write_slot(slot_data).map_err(|err| {
+            let msg = format!(
+                "Failed to persist entry/shred to the PostgreSQL database. Error: {:?}",
+                err
+            );
+            error!("{}", msg);
+            DataAvailabilityError::PublishError {
+                slot: slot_data.slot,
+                msg,
+            }
+        })
+    }
+
+    fn fetch_slot(&mut self, slot: Slot) -> Result<Option<SlotData>> {
+        let fetch_error = |err: postgres::Error| DataAvailabilityError::FetchError {
+            slot,
+            msg: err.to_string(),
+        };
+        // Read the marker and the shreds from the same snapshot of the database
+        let mut transaction = self
+            .client
+            .build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true)
+            .start()
+            .map_err(fetch_error)?;
+        let slot_complete = transaction
+            .query_opt(&self.select_slot_complete_stmt, &[&(slot as i64)])
+            .map_err(fetch_error)?;
+        let (shred_count, checksum) = match slot_complete {
+            Some(row) => (row.get::<_, i64>(0), row.get::<_, Vec<u8>>(1)),
+            None => return Ok(None),
+        };
+        let rows = transaction
+            .query(&self.select_entry_stmt, &[&(slot as i64)])
+            .map_err(fetch_error)?;
+        transaction.commit().map_err(fetch_error)?;
+
+        let slot_data = SlotData {
+            slot,
+            parent_slot: rows
+                .first()
+                .map(|row| row.get::<_, i64>(0) as Slot)
+                .unwrap_or_default(),
+            is_full_slot: rows.first().map(|row| row.get(2)).unwrap_or_default(),
+            shreds: rows.iter().map(|row| row.get(1)).collect(),
+        };
+        if slot_data.shreds.len() as i64 != shred_count
+            || slot_data.checksum().as_ref() != checksum.as_slice()
+        {
+            return Err(DataAvailabilityError::FetchError {
+                slot,
+                msg: format!(
+                    "the {} shreds stored do not match the {} shreds of the complete slot",
+                    slot_data.shreds.len(),
+                    shred_count
+                ),
+            });
+        }
+        Ok(Some(slot_data))
+    }
+
+    fn publish_blob(&mut self, blob: &DaBlob) -> Result<()> {
+        let bytes = blob.to_bytes()?;
+        self.write_blob(blob, &bytes).map_err(|err| {
+            let msg = format!(
+                "Failed to persist the blob of slots {}-{} to the PostgreSQL database. Error: {:?}",
+                blob.header.start_slot, blob.header.end_slot, err
+            );
+            error!("{}", msg);
+            DataAvailabilityError::PublishError {
+                slot: blob.header.start_slot,
+                msg,
+            }
+        })
+    }
+
+    fn fetch_blob(&mut self, slot: Slot) -> Result<Option<DaBlob>> {
+        let row = self
+            .client
+            .query_opt(&self.select_blob_stmt, &[&(slot as i64)])
+            .map_err(|err| DataAvailabilityError::FetchError {
+                slot,
+                msg: err.to_string(),
+            })?;
+        row.map(|row| DaBlob::from_bytes(row.get(0))).transpose()
+    }
+
+    fn highest_slot(&mut self) -> Result<Option<Slot>> {
+        // GREATEST ignores NULL, so it is NULL only if both tables are empty
+        let row = self
+            .client
+            .query_one(
+                "SELECT GREATEST((SELECT MAX(slot) FROM slot_complete), \
+                (SELECT MAX(end_slot) FROM da_blob))",
+                &[],
+            )
+            .map_err(|err| DataAvailabilityError::QueryError {
+                msg: format!("Failed to query the highest complete slot: {:?}", err),
+            })?;
+        let slot: Option<i64> = row.get(0);
+        Ok(slot.map(|slot| slot as Slot))
+    }
+}
+
+/// Listens for the slots completed in the `entry` table, so readers can
+/// follow the writer without polling.
+pub struct SlotCompleteListener {
+    client: Client,
+}
+
+impl SlotCompleteListener {
+    pub fn new(mut client: Client) -> Result<Self> {
+        client
+            .batch_execute(&format!("LISTEN {}", SLOT_COMPLETE_CHANNEL))
+            .map_err(|err| DataAvailabilityError::ConnectionError {
+                msg: format!("Failed to listen on {}: ({})", SLOT_COMPLETE_CHANNEL, err),
+            })?;
+        Ok(Self { client })
+    }
+
+    /// Wait up to `timeout` for slots to complete, returning the highest of
+    /// them or `None` on timeout.
+    pub fn wait(&mut self, timeout: Duration) -> Result<Option<Slot>> {
+        let query_error = |err: postgres::Error| DataAvailabilityError::QueryError {
+            msg: format!("Failed to wait for a notification: {}", err),
+        };
+        let mut notifications = self.client.notifications();
+        let first = notifications
+            .timeout_iter(timeout)
+            .next()
+            .map_err(query_error)?;
+        // Drain the notifications already received
+        let rest: Vec<_> = notifications.iter().collect().map_err(query_error)?;
+        Ok(first
+            .into_iter()
+            .chain(rest)
+            .filter_map(|notification| notification.payload().parse::<Slot>().ok())
+            .max())
+    }
+}
@@ -0,0 +1,372 @@
+//! Versioned migrations of the PostgreSQL schema shared by the plugin and the
+//! replayers.
+//!
+//! The version of a database is the highest one recorded in its
+//! `schema_version` table. A database with the plugin tables but no
+//! `schema_version` table was created by hand before the schema was versioned
+//! and is taken to be at version 1. The migrations are embedded in the
+//! binaries, which bring the database forward when they start and refuse to
+//! run against a database newer than them.
+use {
+    crate::{DataAvailabilityError, Result},
+    chrono::Utc,
+    log::*,
+    postgres::{Client, GenericClient},
+    std::fmt,
+};
+
+/// The version of the schema this binary reads and writes.
+pub const SCHEMA_VERSION: i32 = 5;
+
+/// Held while migrating, so the plugin and the replayers starting together
+/// apply each migration once.
+const SCHEMA_MIGRATION_LOCK: i64 = 0x736368656d61;
+
+const CREATE_SCHEMA_VERSION_TABLE: &str = "CREATE TABLE IF NOT EXISTS schema_version (
+    version INT PRIMARY KEY,
+    description VARCHAR(256) NOT NULL,
+    applied_on TIMESTAMP NOT NULL
+)";
+
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    sql: &'static str,
+}
+
+/// The migrations in version order, each bringing the schema from the
+/// previous version to its own.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: include_str!("../migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "block header columns, entry primary key and slot_complete",
+        sql: include_str!("../migrations/0002_slot_complete.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "transaction history indexes",
+        sql: include_str!("../migrations/0003_transaction_history_indexes.sql"),
+    },
+    Migration {
+        version: 4,
+        description: "block transactions state root",
+        sql: include_str!("../migrations/0004_transactions_state_root.sql"),
+    },
+    Migration {
+        version: 5,
+        description: "DA blobs",
+        sql: include_str!("../migrations/0005_da_blob.sql"),
+    },
+];
+
+impl fmt::Display for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.version, self.description)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchemaStatus {
+    /// The version of the database, 0 if it has no schema yet.
+    pub version: i32,
+    /// Whether the version is recorded in a `schema_version` table.
+    pub versioned: bool,
+}
+
+impl SchemaStatus {
+    /// The migrations to apply to bring the database to `SCHEMA_VERSION`.
+    pub fn pending_migrations(&self) -> impl Iterator<Item = &'static Migration> {
+        let version = self.version;
+        MIGRATIONS
+            .iter()
+            .filter(move |migration| migration.version > version)
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        self.version == SCHEMA_VERSION && self.versioned
+    }
+}
+
+impl fmt::Display for SchemaStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "database schema version: {}{}, binary schema version: {}",
+            self.version,
+            if self.versioned { "" } else { " (unversioned)" },
+            SCHEMA_VERSION
+        )?;
+        let pending: Vec<_> = self
+            .pending_migrations()
+            .map(|migration| migration.to_string())
+            .collect();
+        if !pending.is_empty() {
+            write!(f, ", pending migrations: {}", pending.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn schema_error(err: postgres::Error) -> DataAvailabilityError {
+    DataAvailabilityError::SchemaMigrationError {
+        msg: err.to_string(),
+    }
+}
+
+fn query_schema_status<C: GenericClient>(client: &mut C) -> Result<SchemaStatus> {
+    let row = client
+        .query_one(
+            "SELECT to_regclass('schema_version') IS NOT NULL, to_regclass('account') IS NOT NULL",
+            &[],
+        )
+        .map_err(schema_error)?;
+    let (versioned, has_tables): (bool, bool) = (row.get(0), row.get(1));
+    let version = if versioned {
+        client
+            .query_one("SELECT COALESCE(MAX(version), 0) FROM schema_version", &[])
+            .map_err(schema_error)?
+            .get(0)
+    } else if has_tables {
+        1
+    } else {
+        0
+    };
+    if version > SCHEMA_VERSION {
+        return Err(DataAvailabilityError::SchemaTooNew {
+            version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    Ok(SchemaStatus { version, versioned })
+}
+
+/// The version of the database and the migrations it lacks, without changing
+/// it. Fails if the database is newer than this binary.
+pub fn check_schema(client: &mut Client) -> Result<SchemaStatus> {
+    query_schema_status(client)
+}
+
+/// Apply the migrations the database lacks, all in one transaction, and
+/// return them. Fails if the database is newer than this binary.
+pub fn migrate_schema(client: &mut Client) -> Result<Vec<&'static Migration>> {
+    let mut transaction = client.transaction().map_err(schema_error)?;
+    transaction
+        .execute(
+            "SELECT pg_advisory_xact_lock($1)",
+            &[&SCHEMA_MIGRATION_LOCK],
+        )
+        .map_err(schema_error)?;
+    let status = query_schema_status(&mut transaction)?;
+    if status.is_up_to_date() {
+        return Ok(vec![]);
+    }
+    info!("Migrating the {}", status);
+
+    let updated_on = Utc::now().naive_utc();
+    transaction
+        .batch_execute(CREATE_SCHEMA_VERSION_TABLE)
+        .map_err(schema_error)?;
+    let insert_version = transaction
+        .prepare(
+            "INSERT INTO schema_version (version, description, applied_on) VALUES ($1, $2, $3)",
+        )
+        .map_err(schema_error)?;
+    // The migrations an unversioned database already has
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| !status.versioned && migration.version <= status.version)
+    {
+        transaction
+            .execute(
+                &insert_version,
+                &[&migration.version, &migration.description, &updated_on],
+            )
+            .map_err(schema_error)?;
+    }
+    let pending: Vec<_> = status.pending_migrations().collect();
+    for migration in &pending {
+        transaction.batch_execute(migration.sql).map_err(|err| {
+            DataAvailabilityError::SchemaMigrationError {
+                msg: format!("migration {} failed: {}", migration, err),
+            }
+        })?;
+        transaction
+            .execute(
+                &insert_version,
+                &[&migration.version, &migration.description, &updated_on],
+            )
+            .map_err(schema_error)?;
+    }
+    transaction.commit().map_err(schema_error)?;
+    for migration in &pending {
+        info!("Applied the schema migration {}", migration);
+    }
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, postgres::NoTls};
+
+    #[test]
+    fn test_migrations_in_order() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i32 + 1);
+        }
+        assert_eq!(MIGRATIONS.last().unwrap().version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_pending_migrations() {
+        let status = SchemaStatus {
+            version: 1,
+            versioned: false,
+        };
+        let pending: Vec<_> = status.pending_migrations().map(|m| m.version).collect();
+        assert_eq!(pending, vec![2, 3, 4, 5]);
+        assert!(!status.is_up_to_date());
+        let status = SchemaStatus {
+            version: SCHEMA_VERSION,
+            versioned: true,
+        };
+        assert_eq!(status.pending_migrations().count(), 0);
+        assert!(status.is_up_to_date());
+    }
+
+    /// The columns and indexes of the tables in the current schema.
+    fn describe_schema(client: &mut Client, schema: &str) -> Vec<String> {
+        client
+            .query(
+                "SELECT table_name || '.' || column_name || ' ' || data_type \
+                FROM information_schema.columns WHERE table_schema = $1 \
+                UNION SELECT tablename || ' ' || indexname FROM pg_indexes WHERE schemaname = $1 \
+                ORDER BY 1",
+                &[&schema],
+            )
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect()
+    }
+
+    #[test]
+    fn test_migrate_schema() {
+        let mut client = match Client::connect(
+            "host=localhost user=solana password=solana port=5432",
+            NoTls,
+        ) {
+            Ok(client) => client,
+            Err(err) => {
+                info!("Failed to connect to the PostgreSQL database. Please setup the database to run the integration tests. {:?}", err);
+                return;
+            }
+        };
+        // Separate schemas, as the database outlives the test
+        let suffix = format!(
+            "{}_{}",
+            std::process::id(),
+            Utc::now().timestamp_nanos() as u64
+        );
+        let migrated = format!("test_migrated_{}", suffix);
+        let scripted = format!("test_scripted_{}", suffix);
+
+        client
+            .batch_execute(&format!(
+                "CREATE SCHEMA {0}; SET search_path TO {0}",
+                migrated
+            ))
+            .unwrap();
+        assert_eq!(
+            check_schema(&mut client).unwrap(),
+            SchemaStatus {
+                version: 0,
+                versioned: false
+            }
+        );
+        assert_eq!(migrate_schema(&mut client).unwrap().len(), MIGRATIONS.len());
+        assert!(check_schema(&mut client).unwrap().is_up_to_date());
+        assert!(migrate_schema(&mut client).unwrap().is_empty());
+
+        // The create_schema.sql script ends up with the same schema
+        client
+            .batch_execute(&format!(
+                "CREATE SCHEMA {0}; SET search_path TO {0}",
+                scripted
+            ))
+            .unwrap();
+        client
+            .batch_execute(include_str!(
+                "../../solana-accountsdb-plugin-postgres/scripts/create_schema.sql"
+            ))
+            .unwrap();
+        assert!(check_schema(&mut client).unwrap().is_up_to_date());
+        assert_eq!(
+            describe_schema(&mut client, &migrated),
+            describe_schema(&mut client, &scripted)
+        );
+
+        // A database created by hand before versioning is brought forward
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA {0} CASCADE; CREATE SCHEMA {0}; SET search_path TO {0}",
+                scripted
+            ))
+            .unwrap();
+        client.batch_execute(MIGRATIONS[0].sql).unwrap();
+        client
+            .batch_execute(
+                "INSERT INTO entry VALUES (1, 0, 0, '\\x01', true, now()), \
+                (1, 0, 0, '\\x02', true, now() + interval '1 second')",
+            )
+            .unwrap();
+        assert_eq!(
+            check_schema(&mut client).unwrap(),
+            SchemaStatus {
+                version: 1,
+                versioned: false
+            }
+        );
+        let applied: Vec<_> = migrate_schema(&mut client)
+            .unwrap()
+            .iter()
+            .map(|migration| migration.version)
+            .collect();
+        assert_eq!(applied, (2..=SCHEMA_VERSION).collect::<Vec<_>>());
+        assert_eq!(
+            describe_schema(&mut client, &migrated),
+            describe_schema(&mut client, &scripted)
+        );
+        let entries: Vec<Vec<u8>> = client
+            .query("SELECT entry FROM entry", &[])
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(entries, vec![vec![2]]);
+
+        // A newer database is refused
+        client
+            .execute(
+                "INSERT INTO schema_version VALUES ($1, 'from the future', now())",
+                &[&(SCHEMA_VERSION + 1)],
+            )
+            .unwrap();
+        assert!(matches!(
+            migrate_schema(&mut client),
+            Err(DataAvailabilityError::SchemaTooNew { version, supported })
+                if version == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION
+        ));
+
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA {} CASCADE; DROP SCHEMA {} CASCADE",
+                migrated, scripted
+            ))
+            .unwrap();
+    }
+}
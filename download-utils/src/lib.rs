@@ -325,3 +325,39 @@ pub fn download_snapshot_archive<'a, 'b>(
         desired_snapshot_hash.0, rpc_addr
     ))
 }
+
+/// Get the file name of the latest full snapshot archive `rpc_addr` serves, or of its latest
+/// incremental snapshot archive if `incremental`.  Returns `None` if there is no such archive.
+pub fn get_latest_snapshot_archive_name(
+    rpc_addr: &SocketAddr,
+    incremental: bool,
+) -> Result<Option<String>, String> {
+    let url = format!(
+        "http://{}/{}",
+        rpc_addr,
+        if incremental {
+            "incremental-snapshot.tar.bz2"
+        } else {
+            "snapshot.tar.bz2"
+        }
+    );
+    // The RPC service redirects to the latest archive, whose name is all we need
+    let client = reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let response = client
+        .get(&url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| format!("Failed to query {}: {}", url, err))?;
+    if !response.status().is_redirection() {
+        return Ok(None);
+    }
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .ok_or_else(|| format!("Invalid redirect from {}", url))?;
+    Ok(Some(location.trim_start_matches('/').to_string()))
+}
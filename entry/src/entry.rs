@@ -55,7 +55,11 @@ pub struct UntrustedEntry {
     pub entries: Vec<Entry>,
     pub slot: u64,
     pub parent_slot: u64,
-    pub is_full_slot: bool
+    pub is_full_slot: bool,
+    /// The serialized data shreds of the slot, as signed by the slot leader.
+    pub shreds: Vec<Vec<u8>>,
+    /// The shred version the shreds were produced with.
+    pub shred_version: u16,
 }
 
 pub type EntrySender = Sender<UntrustedEntry>;
@@ -134,8 +134,41 @@ pub struct ReplicaBlockInfo<'a> {
     pub block_height: Option<u64>,
 }
 
+/// Extending ReplicaBlockInfo by sending the state root of the block.
+#[derive(Clone, Debug)]
+pub struct ReplicaBlockInfoV2<'a> {
+    pub slot: u64,
+    pub blockhash: &'a str,
+    pub rewards: &'a [Reward],
+    pub block_time: Option<UnixTimestamp>,
+    pub block_height: Option<u64>,
+    /// The root of the sparse merkle tree of the accounts state at the block
+    pub state_root: &'a str,
+}
+
+/// Extending ReplicaBlockInfoV2 by sending the bank hash of the block and the
+/// signature of its header by the node.
+#[derive(Clone, Debug)]
+pub struct ReplicaBlockInfoV3<'a> {
+    pub slot: u64,
+    pub blockhash: &'a str,
+    pub rewards: &'a [Reward],
+    pub block_time: Option<UnixTimestamp>,
+    pub block_height: Option<u64>,
+    /// The root of the sparse merkle tree of the accounts state at the block
+    pub state_root: &'a str,
+    /// The root of the state of the parent block with the accounts written by
+    /// the transactions of the block, which fraud proofs are checked against
+    pub transactions_state_root: &'a str,
+    pub bank_hash: &'a str,
+    /// The signature of the `SlotHeader` of the block by the node identity
+    pub header_signature: &'a str,
+}
+
 pub enum ReplicaBlockInfoVersions<'a> {
     V0_0_1(&'a ReplicaBlockInfo<'a>),
+    V0_0_2(&'a ReplicaBlockInfoV2<'a>),
+    V0_0_3(&'a ReplicaBlockInfoV3<'a>),
 }
 
 /// Errors returned by plugin calls
@@ -276,6 +309,15 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         Ok(())
     }
 
+    /// Check if the entries of `slot` passed to `notify_entry` are durably
+    /// stored. Slots which are not are notified again, also after a restart.
+    /// Default is true -- plugins storing the entries asynchronously should
+    /// only return true once they are stored.
+    #[allow(unused_variables)]
+    fn entry_published(&self, slot: u64) -> Result<bool> {
+        Ok(true)
+    }
+
     /// Check if the plugin is interested in account data
     /// Default is true -- if the plugin is not interested in
     /// account data, please return false.
@@ -5,7 +5,7 @@ use {
     },
     log::*,
     solana_geyser_plugin_interface::geyser_plugin_interface::{
-        ReplicaBlockInfo, ReplicaBlockInfoVersions,
+        ReplicaBlockInfoV3, ReplicaBlockInfoVersions,
     },
     solana_measure::measure::Measure,
     solana_metrics::*,
@@ -21,6 +21,7 @@ pub(crate) struct BlockMetadataNotifierImpl {
 
 impl BlockMetadataNotifier for BlockMetadataNotifierImpl {
     /// Notify the block metadata
+    #[allow(clippy::too_many_arguments)]
     fn notify_block_metadata(
         &self,
         slot: u64,
@@ -28,6 +29,10 @@ impl BlockMetadataNotifier for BlockMetadataNotifierImpl {
         rewards: &RwLock<Vec<(Pubkey, RewardInfo)>>,
         block_time: Option<UnixTimestamp>,
         block_height: Option<u64>,
+        state_root: &str,
+        transactions_state_root: &str,
+        bank_hash: &str,
+        header_signature: &str,
     ) {
         let mut plugin_manager = self.plugin_manager.write().unwrap();
         if plugin_manager.plugins.is_empty() {
@@ -37,9 +42,18 @@ impl BlockMetadataNotifier for BlockMetadataNotifierImpl {
 
         for plugin in plugin_manager.plugins.iter_mut() {
             let mut measure = Measure::start("geyser-plugin-update-slot");
-            let block_info =
-                Self::build_replica_block_info(slot, blockhash, &rewards, block_time, block_height);
-            let block_info = ReplicaBlockInfoVersions::V0_0_1(&block_info);
+            let block_info = Self::build_replica_block_info(
+                slot,
+                blockhash,
+                &rewards,
+                block_time,
+                block_height,
+                state_root,
+                transactions_state_root,
+                bank_hash,
+                header_signature,
+            );
+            let block_info = ReplicaBlockInfoVersions::V0_0_3(&block_info);
             match plugin.notify_block_metadata(block_info) {
                 Err(err) => {
                     error!(
@@ -83,19 +97,28 @@ impl BlockMetadataNotifierImpl {
             .collect()
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn build_replica_block_info<'a>(
         slot: u64,
         blockhash: &'a str,
         rewards: &'a [Reward],
         block_time: Option<UnixTimestamp>,
         block_height: Option<u64>,
-    ) -> ReplicaBlockInfo<'a> {
-        ReplicaBlockInfo {
+        state_root: &'a str,
+        transactions_state_root: &'a str,
+        bank_hash: &'a str,
+        header_signature: &'a str,
+    ) -> ReplicaBlockInfoV3<'a> {
+        ReplicaBlockInfoV3 {
             slot,
             blockhash,
             rewards,
             block_time,
             block_height,
+            state_root,
+            transactions_state_root,
+            bank_hash,
+            header_signature,
         }
     }
 
@@ -7,6 +7,7 @@ use {
 /// Interface for notifying block metadata changes
 pub trait BlockMetadataNotifier {
     /// Notify the block metadata
+    #[allow(clippy::too_many_arguments)]
     fn notify_block_metadata(
         &self,
         slot: u64,
@@ -14,6 +15,10 @@ pub trait BlockMetadataNotifier {
         rewards: &RwLock<Vec<(Pubkey, RewardInfo)>>,
         block_time: Option<UnixTimestamp>,
         block_height: Option<u64>,
+        state_root: &str,
+        transactions_state_root: &str,
+        bank_hash: &str,
+        header_signature: &str,
     );
 }
 
@@ -42,6 +42,26 @@ impl EntryNotifier for EntryNotifierImpl {
         }
     }
 
+    fn entry_published(&self, slot: u64) -> bool {
+        let plugin_manager = self.plugin_manager.read().unwrap();
+        plugin_manager
+            .plugins
+            .iter()
+            .filter(|plugin| plugin.entry_notifications_enabled())
+            .all(|plugin| match plugin.entry_published(slot) {
+                Ok(published) => published,
+                Err(err) => {
+                    error!(
+                        "Failed to check the entries of slot {} are published: {:?} to plugin {}",
+                        slot,
+                        err,
+                        plugin.name()
+                    );
+                    false
+                }
+            })
+    }
+
     fn last_insert_entry(&self) -> u64 {
         let mut plugin_manager = self.plugin_manager.write().unwrap();
         if plugin_manager.plugins.is_empty() {
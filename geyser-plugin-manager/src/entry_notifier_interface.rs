@@ -8,6 +8,9 @@ pub trait EntryNotifier {
     /// Notify the entry
     fn notify_entry(&self, entry: &UntrustedEntry);
 
+    /// Whether every plugin stored the entries of `slot`
+    fn entry_published(&self, slot: u64) -> bool;
+
     /// Query last entry slot
     fn last_insert_entry(&self) -> u64;
 }
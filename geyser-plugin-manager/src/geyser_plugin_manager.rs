@@ -3,13 +3,65 @@ use {
     libloading::{Library, Symbol},
     log::*,
     solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin,
-    std::error::Error,
+    std::{
+        error::Error,
+        path::{Path, PathBuf},
+    },
 };
 
 #[derive(Default, Debug)]
 pub struct GeyserPluginManager {
     pub plugins: Vec<Box<dyn GeyserPlugin>>,
     libs: Vec<Library>,
+    config_files: Vec<PathBuf>,
+}
+
+/// A plugin together with the library its code lives in, which is not managed
+/// by a `GeyserPluginManager`.
+#[derive(Debug)]
+pub struct LoadedGeyserPlugin {
+    // Declared before `lib` so it is dropped first
+    plugin: Box<dyn GeyserPlugin>,
+    lib: Library,
+    config_file: PathBuf,
+}
+
+impl LoadedGeyserPlugin {
+    /// # Safety
+    ///
+    /// This function loads the dynamically linked library specified in the path. The library
+    /// must do necessary initializations.
+    pub unsafe fn load(libpath: &str, config_file: &str) -> Result<Self, Box<dyn Error>> {
+        type PluginConstructor = unsafe fn() -> *mut dyn GeyserPlugin;
+        let lib = Library::new(libpath)?;
+        let constructor: Symbol<PluginConstructor> = lib.get(b"_create_plugin")?;
+        let plugin_raw = constructor();
+        let mut plugin = Box::from_raw(plugin_raw);
+        plugin.on_load(config_file)?;
+        Ok(Self {
+            plugin,
+            lib,
+            config_file: PathBuf::from(config_file),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        self.plugin.name()
+    }
+
+    pub fn config_file(&self) -> &Path {
+        &self.config_file
+    }
+
+    pub fn plugin_mut(&mut self) -> &mut dyn GeyserPlugin {
+        self.plugin.as_mut()
+    }
+
+    /// Fire the `on_unload()` method of the plugin, then drop it and its library.
+    pub fn unload(mut self) {
+        info!("Unloading plugin for {:?}", self.plugin.name());
+        self.plugin.on_unload();
+    }
 }
 
 impl GeyserPluginManager {
@@ -17,6 +69,7 @@ impl GeyserPluginManager {
         GeyserPluginManager {
             plugins: Vec::default(),
             libs: Vec::default(),
+            config_files: Vec::default(),
         }
     }
 
@@ -29,17 +82,81 @@ impl GeyserPluginManager {
         libpath: &str,
         config_file: &str,
     ) -> Result<(), Box<dyn Error>> {
-        type PluginConstructor = unsafe fn() -> *mut dyn GeyserPlugin;
-        let lib = Library::new(libpath)?;
-        let constructor: Symbol<PluginConstructor> = lib.get(b"_create_plugin")?;
-        let plugin_raw = constructor();
-        let mut plugin = Box::from_raw(plugin_raw);
-        plugin.on_load(config_file)?;
+        let plugin = LoadedGeyserPlugin::load(libpath, config_file)?;
+        self.add_plugin(plugin).map_err(|plugin| {
+            let msg = format!("A plugin named {:?} is already loaded", plugin.name());
+            plugin.unload();
+            msg.into()
+        })
+    }
+
+    /// Manage `plugin`, unless one with the same name already is, in which
+    /// case it is handed back.
+    pub fn add_plugin(&mut self, plugin: LoadedGeyserPlugin) -> Result<(), LoadedGeyserPlugin> {
+        if self.position(plugin.name()).is_some() {
+            return Err(plugin);
+        }
+        let LoadedGeyserPlugin {
+            plugin,
+            lib,
+            config_file,
+        } = plugin;
         self.plugins.push(plugin);
         self.libs.push(lib);
+        self.config_files.push(config_file);
         Ok(())
     }
 
+    /// Stop managing the plugin named `name` and return it, without unloading it.
+    pub fn remove_plugin(&mut self, name: &str) -> Option<LoadedGeyserPlugin> {
+        let index = self.position(name)?;
+        Some(LoadedGeyserPlugin {
+            plugin: self.plugins.remove(index),
+            lib: self.libs.remove(index),
+            config_file: self.config_files.remove(index),
+        })
+    }
+
+    /// Manage `plugin` in place of the plugin named `name`, which is returned,
+    /// so no notification is missed in between. `plugin` is handed back if
+    /// there is no plugin named `name`, or if another one has its name.
+    pub fn replace_plugin(
+        &mut self,
+        name: &str,
+        plugin: LoadedGeyserPlugin,
+    ) -> Result<LoadedGeyserPlugin, LoadedGeyserPlugin> {
+        let index = match self.position(name) {
+            Some(index) => index,
+            None => return Err(plugin),
+        };
+        if matches!(self.position(plugin.name()), Some(other) if other != index) {
+            return Err(plugin);
+        }
+        let LoadedGeyserPlugin {
+            plugin,
+            lib,
+            config_file,
+        } = plugin;
+        Ok(LoadedGeyserPlugin {
+            plugin: std::mem::replace(&mut self.plugins[index], plugin),
+            lib: std::mem::replace(&mut self.libs[index], lib),
+            config_file: std::mem::replace(&mut self.config_files[index], config_file),
+        })
+    }
+
+    /// The names of the plugins and the config files they were loaded with.
+    pub fn list_plugins(&self) -> Vec<(String, PathBuf)> {
+        self.plugins
+            .iter()
+            .zip(&self.config_files)
+            .map(|(plugin, config_file)| (plugin.name().to_string(), config_file.clone()))
+            .collect()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.plugins.iter().position(|plugin| plugin.name() == name)
+    }
+
     /// Unload all plugins and loaded plugin libraries, making sure to fire
     /// their `on_plugin_unload()` methods so they can do any necessary cleanup.
     pub fn unload(&mut self) {
@@ -51,6 +168,7 @@ impl GeyserPluginManager {
         for lib in self.libs.drain(..) {
             drop(lib);
         }
+        self.config_files.clear();
     }
 
     /// Check if there is any plugin interested in account data
@@ -83,3 +201,174 @@ impl GeyserPluginManager {
         false
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::*,
+        std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    /// A plugin living in the test binary, which records whether it was unloaded.
+    #[derive(Debug, Default)]
+    pub(crate) struct TestPlugin {
+        pub name: &'static str,
+        pub transaction_notifications: bool,
+        pub entry_notifications: bool,
+        pub unloaded: Arc<AtomicBool>,
+    }
+
+    impl GeyserPlugin for TestPlugin {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn on_unload(&mut self) {
+            self.unloaded.store(true, Ordering::Relaxed);
+        }
+
+        fn transaction_notifications_enabled(&self) -> bool {
+            self.transaction_notifications
+        }
+
+        fn entry_notifications_enabled(&self) -> bool {
+            self.entry_notifications
+        }
+    }
+
+    /// Wrap `plugin` as if it was loaded from a library, the test binary
+    /// standing in for the library.
+    pub(crate) fn load_test_plugin(plugin: TestPlugin, config_file: &str) -> LoadedGeyserPlugin {
+        #[cfg(unix)]
+        let lib = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let lib = libloading::os::windows::Library::this().unwrap();
+        LoadedGeyserPlugin {
+            plugin: Box::new(plugin),
+            lib: Library::from(lib),
+            config_file: PathBuf::from(config_file),
+        }
+    }
+
+    fn test_plugin(name: &'static str) -> TestPlugin {
+        TestPlugin {
+            name,
+            ..TestPlugin::default()
+        }
+    }
+
+    fn plugin_names(manager: &GeyserPluginManager) -> Vec<String> {
+        manager
+            .list_plugins()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_add_plugin() {
+        let mut manager = GeyserPluginManager::new();
+        manager
+            .add_plugin(load_test_plugin(test_plugin("a"), "a.json"))
+            .unwrap();
+        manager
+            .add_plugin(load_test_plugin(test_plugin("b"), "b.json"))
+            .unwrap();
+        assert_eq!(
+            manager.list_plugins(),
+            vec![
+                ("a".to_string(), PathBuf::from("a.json")),
+                ("b".to_string(), PathBuf::from("b.json"))
+            ]
+        );
+
+        // A plugin with the name of a managed one is handed back, still loaded
+        let duplicate = test_plugin("a");
+        let unloaded = duplicate.unloaded.clone();
+        let duplicate = manager
+            .add_plugin(load_test_plugin(duplicate, "other.json"))
+            .unwrap_err();
+        assert_eq!(duplicate.name(), "a");
+        assert_eq!(duplicate.config_file(), Path::new("other.json"));
+        assert!(!unloaded.load(Ordering::Relaxed));
+        assert_eq!(plugin_names(&manager), vec!["a", "b"]);
+
+        manager.unload();
+        assert!(manager.list_plugins().is_empty());
+    }
+
+    #[test]
+    fn test_remove_plugin() {
+        let mut manager = GeyserPluginManager::new();
+        let plugin = test_plugin("a");
+        let unloaded = plugin.unloaded.clone();
+        manager
+            .add_plugin(load_test_plugin(plugin, "a.json"))
+            .unwrap();
+        manager
+            .add_plugin(load_test_plugin(test_plugin("b"), "b.json"))
+            .unwrap();
+
+        assert!(manager.remove_plugin("c").is_none());
+        assert_eq!(plugin_names(&manager), vec!["a", "b"]);
+
+        // The removed plugin is left to the caller to unload
+        let removed = manager.remove_plugin("a").unwrap();
+        assert_eq!(removed.config_file(), Path::new("a.json"));
+        assert!(!unloaded.load(Ordering::Relaxed));
+        assert_eq!(plugin_names(&manager), vec!["b"]);
+        removed.unload();
+        assert!(unloaded.load(Ordering::Relaxed));
+        assert!(manager.remove_plugin("a").is_none());
+    }
+
+    #[test]
+    fn test_replace_plugin() {
+        let mut manager = GeyserPluginManager::new();
+        manager
+            .add_plugin(load_test_plugin(test_plugin("a"), "a.json"))
+            .unwrap();
+        manager
+            .add_plugin(load_test_plugin(test_plugin("b"), "b.json"))
+            .unwrap();
+
+        // Reloaded under the same name
+        let old = manager
+            .replace_plugin("a", load_test_plugin(test_plugin("a"), "a2.json"))
+            .unwrap();
+        assert_eq!(old.config_file(), Path::new("a.json"));
+        assert_eq!(
+            manager.list_plugins()[0],
+            ("a".to_string(), PathBuf::from("a2.json"))
+        );
+
+        // Reloaded under a new name, in the same place
+        let old = manager
+            .replace_plugin("a", load_test_plugin(test_plugin("c"), "c.json"))
+            .unwrap();
+        assert_eq!(old.name(), "a");
+        assert_eq!(plugin_names(&manager), vec!["c", "b"]);
+
+        // Nothing to replace
+        let plugin = manager
+            .replace_plugin("a", load_test_plugin(test_plugin("d"), "d.json"))
+            .unwrap_err();
+        assert_eq!(plugin.name(), "d");
+
+        // The new name is taken by another plugin
+        let plugin = manager
+            .replace_plugin("c", load_test_plugin(test_plugin("b"), "b2.json"))
+            .unwrap_err();
+        assert_eq!(plugin.config_file(), Path::new("b2.json"));
+        assert_eq!(
+            manager.list_plugins(),
+            vec![
+                ("c".to_string(), PathBuf::from("c.json")),
+                ("b".to_string(), PathBuf::from("b.json"))
+            ]
+        );
+    }
+}
@@ -3,9 +3,12 @@ use {
         accounts_update_notifier::AccountsUpdateNotifierImpl,
         block_metadata_notifier::BlockMetadataNotifierImpl,
         block_metadata_notifier_interface::BlockMetadataNotifierLock,
-        entry_notifier::EntryNotifierImpl, entry_notifier_interface::EntryNotifierLock,
-        geyser_plugin_manager::GeyserPluginManager, slot_status_notifier::SlotStatusNotifierImpl,
-        slot_status_observer::SlotStatusObserver, transaction_notifier::TransactionNotifierImpl,
+        entry_notifier::EntryNotifierImpl,
+        entry_notifier_interface::EntryNotifierLock,
+        geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
+        slot_status_notifier::SlotStatusNotifierImpl,
+        slot_status_observer::SlotStatusObserver,
+        transaction_notifier::TransactionNotifierImpl,
     },
     crossbeam_channel::Receiver,
     log::*,
@@ -43,6 +46,15 @@ pub enum GeyserPluginServiceError {
 
     #[error("Cannot load plugin shared library")]
     PluginLoadError(String),
+
+    #[error("A plugin named {0} is already loaded")]
+    PluginAlreadyLoaded(String),
+
+    #[error("No plugin named {0} is loaded")]
+    PluginNotLoaded(String),
+
+    #[error("The plugin {0} requires notifications that were not enabled at startup")]
+    NotificationsNotEnabled(String),
 }
 
 /// The service managing the Geyser plugin workflow.
@@ -53,6 +65,149 @@ pub struct GeyserPluginService {
     transaction_notifier: Option<TransactionNotifierLock>,
     block_metadata_notifier: Option<BlockMetadataNotifierLock>,
     entry_notifier: Option<EntryNotifierLock>,
+    account_data_notifications_enabled: bool,
+    transaction_notifications_enabled: bool,
+    entry_notifications_enabled: bool,
+}
+
+/// Loads, unloads and reloads the plugins of a running `GeyserPluginService`.
+///
+/// The notifiers only exist for the kinds of notifications some plugin enabled
+/// at startup, so a plugin loaded later can only receive those.
+#[derive(Clone)]
+pub struct GeyserPluginAdmin {
+    plugin_manager: Arc<RwLock<GeyserPluginManager>>,
+    account_data_notifications_enabled: bool,
+    transaction_notifications_enabled: bool,
+    entry_notifications_enabled: bool,
+}
+
+impl GeyserPluginAdmin {
+    /// The names of the loaded plugins and the config files they were loaded with.
+    pub fn list_plugins(&self) -> Vec<(String, PathBuf)> {
+        self.plugin_manager.read().unwrap().list_plugins()
+    }
+
+    /// Load the plugin configured by `geyser_plugin_config_file` and return its name.
+    pub fn load_plugin(
+        &self,
+        geyser_plugin_config_file: &Path,
+    ) -> Result<String, GeyserPluginServiceError> {
+        let plugin = GeyserPluginService::load_plugin_from_config(geyser_plugin_config_file)?;
+        self.add_plugin(plugin)
+    }
+
+    fn add_plugin(&self, plugin: LoadedGeyserPlugin) -> Result<String, GeyserPluginServiceError> {
+        let plugin = self.start_runtime_plugin(plugin)?;
+        let name = plugin.name().to_string();
+        let config_file = plugin.config_file().to_path_buf();
+        // Bind the result so the lock is released before unloading
+        let result = self.plugin_manager.write().unwrap().add_plugin(plugin);
+        match result {
+            Ok(()) => {
+                info!("Loaded plugin {:?} from {:?}", name, config_file);
+                Ok(name)
+            }
+            Err(plugin) => {
+                plugin.unload();
+                Err(GeyserPluginServiceError::PluginAlreadyLoaded(name))
+            }
+        }
+    }
+
+    /// Unload the plugin named `name`.
+    pub fn unload_plugin(&self, name: &str) -> Result<(), GeyserPluginServiceError> {
+        let plugin = self.plugin_manager.write().unwrap().remove_plugin(name);
+        match plugin {
+            Some(plugin) => {
+                plugin.unload();
+                Ok(())
+            }
+            None => Err(GeyserPluginServiceError::PluginNotLoaded(name.to_string())),
+        }
+    }
+
+    /// Replace the plugin named `name` by the plugin configured by
+    /// `geyser_plugin_config_file`, which is loaded before the old one is
+    /// swapped out so no notification is missed, and return its name.
+    pub fn reload_plugin(
+        &self,
+        name: &str,
+        geyser_plugin_config_file: &Path,
+    ) -> Result<String, GeyserPluginServiceError> {
+        if !self
+            .list_plugins()
+            .iter()
+            .any(|(loaded_name, _)| loaded_name == name)
+        {
+            return Err(GeyserPluginServiceError::PluginNotLoaded(name.to_string()));
+        }
+        let plugin = GeyserPluginService::load_plugin_from_config(geyser_plugin_config_file)?;
+        self.replace_plugin(name, plugin)
+    }
+
+    fn replace_plugin(
+        &self,
+        name: &str,
+        plugin: LoadedGeyserPlugin,
+    ) -> Result<String, GeyserPluginServiceError> {
+        let plugin = self.start_runtime_plugin(plugin)?;
+        let new_name = plugin.name().to_string();
+        let config_file = plugin.config_file().to_path_buf();
+        let result = self
+            .plugin_manager
+            .write()
+            .unwrap()
+            .replace_plugin(name, plugin);
+        match result {
+            Ok(old_plugin) => {
+                old_plugin.unload();
+                info!(
+                    "Reloaded plugin {:?} as {:?} from {:?}",
+                    name, new_name, config_file
+                );
+                Ok(new_name)
+            }
+            Err(plugin) => {
+                plugin.unload();
+                // Either unloaded meanwhile, or the new plugin has the name of another one
+                Err(if new_name == name {
+                    GeyserPluginServiceError::PluginNotLoaded(name.to_string())
+                } else {
+                    GeyserPluginServiceError::PluginAlreadyLoaded(new_name)
+                })
+            }
+        }
+    }
+
+    /// Check the notifications a plugin loaded outside of the lock wants are
+    /// delivered. Startup is over, so it is told so right away.
+    fn start_runtime_plugin(
+        &self,
+        mut plugin: LoadedGeyserPlugin,
+    ) -> Result<LoadedGeyserPlugin, GeyserPluginServiceError> {
+        let plugin_mut = plugin.plugin_mut();
+        if (plugin_mut.account_data_notifications_enabled()
+            && !self.account_data_notifications_enabled)
+            || (plugin_mut.transaction_notifications_enabled()
+                && !self.transaction_notifications_enabled)
+            || (plugin_mut.entry_notifications_enabled() && !self.entry_notifications_enabled)
+        {
+            let name = plugin.name().to_string();
+            plugin.unload();
+            return Err(GeyserPluginServiceError::NotificationsNotEnabled(name));
+        }
+        if let Err(err) = plugin.plugin_mut().notify_end_of_startup() {
+            let msg = format!(
+                "Failed to notify the end of startup to the plugin {:?}, error: {:?}",
+                plugin.name(),
+                err
+            );
+            plugin.unload();
+            return Err(GeyserPluginServiceError::PluginLoadError(msg));
+        }
+        Ok(plugin)
+    }
 }
 
 impl GeyserPluginService {
@@ -136,6 +291,9 @@ impl GeyserPluginService {
         Ok(GeyserPluginService {
             slot_status_observer,
             plugin_manager,
+            account_data_notifications_enabled,
+            transaction_notifications_enabled,
+            entry_notifications_enabled,
             accounts_update_notifier,
             transaction_notifier,
             block_metadata_notifier,
@@ -147,6 +305,17 @@ impl GeyserPluginService {
         plugin_manager: &mut GeyserPluginManager,
         geyser_plugin_config_file: &Path,
     ) -> Result<(), GeyserPluginServiceError> {
+        let plugin = Self::load_plugin_from_config(geyser_plugin_config_file)?;
+        plugin_manager.add_plugin(plugin).map_err(|plugin| {
+            let name = plugin.name().to_string();
+            plugin.unload();
+            GeyserPluginServiceError::PluginAlreadyLoaded(name)
+        })
+    }
+
+    fn load_plugin_from_config(
+        geyser_plugin_config_file: &Path,
+    ) -> Result<LoadedGeyserPlugin, GeyserPluginServiceError> {
         let mut file = match File::open(geyser_plugin_config_file) {
             Ok(file) => file,
             Err(err) => {
@@ -194,17 +363,13 @@ impl GeyserPluginService {
             .to_str()
             .ok_or(GeyserPluginServiceError::InvalidPluginPath)?;
 
-        unsafe {
-            let result = plugin_manager.load_plugin(libpath.to_str().unwrap(), config_file);
-            if let Err(err) = result {
-                let msg = format!(
-                    "Failed to load the plugin library: {:?}, error: {:?}",
-                    libpath, err
-                );
-                return Err(GeyserPluginServiceError::PluginLoadError(msg));
-            }
-        }
-        Ok(())
+        unsafe { LoadedGeyserPlugin::load(libpath.to_str().unwrap(), config_file) }.map_err(|err| {
+            let msg = format!(
+                "Failed to load the plugin library: {:?}, error: {:?}",
+                libpath, err
+            );
+            GeyserPluginServiceError::PluginLoadError(msg)
+        })
     }
 
     pub fn get_accounts_update_notifier(&self) -> Option<AccountsUpdateNotifier> {
@@ -223,6 +388,15 @@ impl GeyserPluginService {
         self.entry_notifier.clone()
     }
 
+    pub fn get_plugin_admin(&self) -> GeyserPluginAdmin {
+        GeyserPluginAdmin {
+            plugin_manager: self.plugin_manager.clone(),
+            account_data_notifications_enabled: self.account_data_notifications_enabled,
+            transaction_notifications_enabled: self.transaction_notifications_enabled,
+            entry_notifications_enabled: self.entry_notifications_enabled,
+        }
+    }
+
     pub fn join(self) -> thread::Result<()> {
         if let Some(mut slot_status_observer) = self.slot_status_observer {
             slot_status_observer.join()?;
@@ -231,3 +405,187 @@ impl GeyserPluginService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::geyser_plugin_manager::tests::{load_test_plugin, TestPlugin},
+        std::sync::atomic::Ordering,
+    };
+
+    /// The admin of a service whose plugins at startup only enabled the
+    /// account data notifications.
+    fn test_plugin_admin() -> GeyserPluginAdmin {
+        GeyserPluginAdmin {
+            plugin_manager: Arc::new(RwLock::new(GeyserPluginManager::new())),
+            account_data_notifications_enabled: true,
+            transaction_notifications_enabled: false,
+            entry_notifications_enabled: false,
+        }
+    }
+
+    fn test_plugin(name: &'static str) -> TestPlugin {
+        TestPlugin {
+            name,
+            ..TestPlugin::default()
+        }
+    }
+
+    fn plugin_names(admin: &GeyserPluginAdmin) -> Vec<String> {
+        admin
+            .list_plugins()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_load_plugin() {
+        let admin = test_plugin_admin();
+        let plugin = test_plugin("a");
+        let unloaded = plugin.unloaded.clone();
+        assert_eq!(
+            admin
+                .add_plugin(load_test_plugin(plugin, "a.json"))
+                .unwrap(),
+            "a"
+        );
+
+        // The duplicate is unloaded, the loaded plugin is kept
+        let duplicate = test_plugin("a");
+        let duplicate_unloaded = duplicate.unloaded.clone();
+        assert!(matches!(
+            admin.add_plugin(load_test_plugin(duplicate, "other.json")),
+            Err(GeyserPluginServiceError::PluginAlreadyLoaded(name)) if name == "a"
+        ));
+        assert!(duplicate_unloaded.load(Ordering::Relaxed));
+        assert!(!unloaded.load(Ordering::Relaxed));
+        assert_eq!(
+            admin.list_plugins(),
+            vec![("a".to_string(), PathBuf::from("a.json"))]
+        );
+    }
+
+    #[test]
+    fn test_load_plugin_notifications_not_enabled() {
+        let admin = test_plugin_admin();
+        for plugin in [
+            TestPlugin {
+                transaction_notifications: true,
+                ..test_plugin("transactions")
+            },
+            TestPlugin {
+                entry_notifications: true,
+                ..test_plugin("entries")
+            },
+        ] {
+            let name = plugin.name;
+            let unloaded = plugin.unloaded.clone();
+            assert!(matches!(
+                admin.add_plugin(load_test_plugin(plugin, "plugin.json")),
+                Err(GeyserPluginServiceError::NotificationsNotEnabled(rejected)) if rejected == name
+            ));
+            assert!(unloaded.load(Ordering::Relaxed));
+        }
+        assert!(admin.list_plugins().is_empty());
+
+        // Neither when reloading a plugin
+        admin
+            .add_plugin(load_test_plugin(test_plugin("a"), "a.json"))
+            .unwrap();
+        let plugin = TestPlugin {
+            transaction_notifications: true,
+            ..test_plugin("a")
+        };
+        assert!(matches!(
+            admin.replace_plugin("a", load_test_plugin(plugin, "a2.json")),
+            Err(GeyserPluginServiceError::NotificationsNotEnabled(_))
+        ));
+        assert_eq!(
+            admin.list_plugins(),
+            vec![("a".to_string(), PathBuf::from("a.json"))]
+        );
+    }
+
+    #[test]
+    fn test_unload_plugin() {
+        let admin = test_plugin_admin();
+        let plugin = test_plugin("a");
+        let unloaded = plugin.unloaded.clone();
+        admin
+            .add_plugin(load_test_plugin(plugin, "a.json"))
+            .unwrap();
+
+        assert!(matches!(
+            admin.unload_plugin("b"),
+            Err(GeyserPluginServiceError::PluginNotLoaded(name)) if name == "b"
+        ));
+        assert!(!unloaded.load(Ordering::Relaxed));
+
+        admin.unload_plugin("a").unwrap();
+        assert!(unloaded.load(Ordering::Relaxed));
+        assert!(admin.list_plugins().is_empty());
+        assert!(matches!(
+            admin.unload_plugin("a"),
+            Err(GeyserPluginServiceError::PluginNotLoaded(_))
+        ));
+    }
+
+    #[test]
+    fn test_reload_plugin() {
+        let admin = test_plugin_admin();
+        let plugin = test_plugin("a");
+        let unloaded = plugin.unloaded.clone();
+        admin
+            .add_plugin(load_test_plugin(plugin, "a.json"))
+            .unwrap();
+        admin
+            .add_plugin(load_test_plugin(test_plugin("b"), "b.json"))
+            .unwrap();
+
+        // The config file is not read when there is no plugin to reload
+        assert!(matches!(
+            admin.reload_plugin("c", Path::new("missing.json")),
+            Err(GeyserPluginServiceError::PluginNotLoaded(name)) if name == "c"
+        ));
+
+        // Reloaded under a new name, the old plugin is unloaded
+        let plugin = test_plugin("c");
+        let new_unloaded = plugin.unloaded.clone();
+        assert_eq!(
+            admin
+                .replace_plugin("a", load_test_plugin(plugin, "c.json"))
+                .unwrap(),
+            "c"
+        );
+        assert!(unloaded.load(Ordering::Relaxed));
+        assert!(!new_unloaded.load(Ordering::Relaxed));
+        assert_eq!(plugin_names(&admin), vec!["c", "b"]);
+
+        // Reloaded under the name of another plugin
+        let plugin = test_plugin("b");
+        let duplicate_unloaded = plugin.unloaded.clone();
+        assert!(matches!(
+            admin.replace_plugin("c", load_test_plugin(plugin, "b2.json")),
+            Err(GeyserPluginServiceError::PluginAlreadyLoaded(name)) if name == "b"
+        ));
+        assert!(duplicate_unloaded.load(Ordering::Relaxed));
+        assert!(!new_unloaded.load(Ordering::Relaxed));
+        assert_eq!(
+            admin.list_plugins(),
+            vec![
+                ("c".to_string(), PathBuf::from("c.json")),
+                ("b".to_string(), PathBuf::from("b.json"))
+            ]
+        );
+
+        // Unloaded meanwhile
+        admin.unload_plugin("c").unwrap();
+        assert!(matches!(
+            admin.replace_plugin("c", load_test_plugin(test_plugin("c"), "c.json")),
+            Err(GeyserPluginServiceError::PluginNotLoaded(name)) if name == "c"
+        ));
+        assert_eq!(plugin_names(&admin), vec!["b"]);
+    }
+}
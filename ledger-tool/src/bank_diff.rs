@@ -0,0 +1,505 @@
+//! The `bank-diff` subcommand: locate the first slot two ledgers diverge at,
+//! then replay it transaction by transaction to explain the difference.
+use {
+    crate::{
+        hardforks_of, ledger_path::canonicalize_ledger_path, load_bank_forks_with_frozen_banks,
+        open_blockstore, open_genesis_config_by,
+        DEFAULT_LEDGER_TOOL_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES,
+    },
+    clap::{value_t, ArgMatches},
+    crossbeam_channel::unbounded,
+    log::*,
+    solana_ledger::{
+        blockstore::Blockstore,
+        blockstore_options::{AccessType, BlockstoreRecoveryMode, ShredStorageType},
+        blockstore_processor::ProcessOptions,
+        leader_schedule_cache::LeaderScheduleCache,
+    },
+    solana_runtime::{bank::Bank, bank_forks::BankForks},
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        clock::Slot,
+        genesis_config::GenesisConfig,
+        hash::Hash,
+        pubkey::Pubkey,
+        transaction::VersionedTransaction,
+    },
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        path::Path,
+        process::exit,
+        sync::{Arc, RwLock},
+        thread,
+    },
+};
+
+/// One of the two ledgers compared.
+struct DiffLedger {
+    name: &'static str,
+    blockstore: Blockstore,
+    genesis_config: GenesisConfig,
+}
+
+impl DiffLedger {
+    fn open(
+        name: &'static str,
+        ledger_path: &Path,
+        arg_matches: &ArgMatches<'_>,
+        wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+    ) -> Self {
+        let ledger_path = canonicalize_ledger_path(ledger_path);
+        let shred_storage_type = ShredStorageType::from_ledger_path(
+            &ledger_path,
+            DEFAULT_LEDGER_TOOL_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES,
+        )
+        .unwrap_or(ShredStorageType::RocksLevel);
+        Self {
+            name,
+            blockstore: open_blockstore(
+                &ledger_path,
+                AccessType::Secondary,
+                wal_recovery_mode,
+                &shred_storage_type,
+            ),
+            genesis_config: open_genesis_config_by(&ledger_path, arg_matches),
+        }
+    }
+
+    /// Replay the ledger up to `halt_slot`, returning the bank hash of every
+    /// replayed slot along with the bank forks.
+    fn replay(
+        &self,
+        arg_matches: &ArgMatches<'_>,
+        halt_slot: Slot,
+    ) -> (Arc<RwLock<BankForks>>, BTreeMap<Slot, Hash>) {
+        let process_options = ProcessOptions {
+            new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+            halt_at_slot: Some(halt_slot),
+            poh_verify: false,
+            ..ProcessOptions::default()
+        };
+        // The banks are dropped as soon as they are received, so the replayed
+        // slots are not all kept in memory.
+        let (frozen_bank_sender, frozen_bank_receiver) = unbounded();
+        let bank_hashes = thread::spawn(move || {
+            frozen_bank_receiver
+                .iter()
+                .map(|bank: Arc<Bank>| (bank.slot(), bank.hash()))
+                .collect()
+        });
+        let bank_forks = load_bank_forks_with_frozen_banks(
+            arg_matches,
+            &self.genesis_config,
+            &self.blockstore,
+            process_options,
+            None,
+            None,
+            Some(&frozen_bank_sender),
+        )
+        .map(|(bank_forks, ..)| bank_forks)
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to load ledger {}: {:?}", self.name, err);
+            exit(1);
+        });
+        drop(frozen_bank_sender);
+        (bank_forks, bank_hashes.join().unwrap())
+    }
+
+    /// The bank of `slot` on top of the replayed bank of its parent, with the
+    /// transactions and tick hashes of its entries.
+    fn child_bank(
+        &self,
+        arg_matches: &ArgMatches<'_>,
+        slot: Slot,
+        parent_slot: Slot,
+    ) -> (Arc<Bank>, Vec<VersionedTransaction>, Vec<Hash>) {
+        let (bank_forks, _) = self.replay(arg_matches, parent_slot);
+        let parent = bank_forks
+            .read()
+            .unwrap()
+            .get(parent_slot)
+            .unwrap_or_else(|| {
+                eprintln!(
+                    "Ledger {} could not be replayed to slot {}",
+                    self.name, parent_slot
+                );
+                exit(1);
+            });
+        let leader = LeaderScheduleCache::new_from_bank(&parent)
+            .slot_leader_at(slot, Some(&parent))
+            .unwrap_or_default();
+        let bank = Arc::new(Bank::new_from_parent(&parent, &leader, slot));
+
+        let entries = self
+            .blockstore
+            .get_slot_entries(slot, 0)
+            .unwrap_or_else(|err| {
+                eprintln!(
+                    "Failed to read the entries of slot {} in ledger {}: {:?}",
+                    slot, self.name, err
+                );
+                exit(1);
+            });
+        let mut transactions = vec![];
+        let mut tick_hashes = vec![];
+        for entry in entries {
+            if entry.is_tick() {
+                tick_hashes.push(entry.hash);
+            } else {
+                transactions.extend(entry.transactions);
+            }
+        }
+        (bank, transactions, tick_hashes)
+    }
+
+    fn parent_slot(&self, slot: Slot) -> Option<Slot> {
+        self.blockstore
+            .meta(slot)
+            .ok()
+            .flatten()
+            .and_then(|slot_meta| slot_meta.parent_slot)
+    }
+}
+
+/// The first slot at or before `ending_slot` whose bank hashes stored in both
+/// blockstores differ. The bank hashes are only stored by the validators which
+/// produced the ledgers, not by the verification nodes.
+fn first_divergent_stored_slot(
+    ledger_a: &DiffLedger,
+    ledger_b: &DiffLedger,
+    ending_slot: Slot,
+) -> Option<(Slot, Hash, Hash)> {
+    let bank_hashes: Vec<_> = ledger_a
+        .blockstore
+        .slot_meta_iterator(0)
+        .unwrap()
+        .map(|(slot, _)| slot)
+        .take_while(|slot| *slot <= ending_slot)
+        .filter_map(|slot| {
+            Some((
+                slot,
+                ledger_a.blockstore.get_bank_hash(slot)?,
+                ledger_b.blockstore.get_bank_hash(slot)?,
+            ))
+        })
+        .collect();
+    info!(
+        "{} slots have a stored bank hash in both ledgers",
+        bank_hashes.len()
+    );
+    // The slots of every fork are walked, so slots after the first divergent
+    // one may match again
+    bank_hashes
+        .into_iter()
+        .find(|(_, hash_a, hash_b)| hash_a != hash_b)
+}
+
+/// The first slot replayed in either ledger with different bank hashes, or
+/// replayed in only one of them.
+fn first_divergent_replayed_slot(
+    bank_hashes_a: &BTreeMap<Slot, Hash>,
+    bank_hashes_b: &BTreeMap<Slot, Hash>,
+) -> Option<(Slot, Option<Hash>, Option<Hash>)> {
+    let slots: BTreeSet<_> = bank_hashes_a.keys().chain(bank_hashes_b.keys()).collect();
+    slots.into_iter().find_map(|slot| {
+        let hash_a = bank_hashes_a.get(slot).copied();
+        let hash_b = bank_hashes_b.get(slot).copied();
+        if hash_a != hash_b {
+            Some((*slot, hash_a, hash_b))
+        } else {
+            None
+        }
+    })
+}
+
+/// The differences of the account in ledger a and b, empty if the same.
+fn account_diff(
+    account_a: Option<&AccountSharedData>,
+    account_b: Option<&AccountSharedData>,
+) -> Vec<String> {
+    let (account_a, account_b) = match (account_a, account_b) {
+        (Some(account_a), Some(account_b)) => (account_a, account_b),
+        (None, None) => return vec![],
+        (None, Some(_)) => return vec!["missing in ledger a".to_string()],
+        (Some(_), None) => return vec!["missing in ledger b".to_string()],
+    };
+    let mut diff = vec![];
+    if account_a.lamports() != account_b.lamports() {
+        diff.push(format!(
+            "lamports: {} -> {}",
+            account_a.lamports(),
+            account_b.lamports()
+        ));
+    }
+    if account_a.owner() != account_b.owner() {
+        diff.push(format!(
+            "owner: {} -> {}",
+            account_a.owner(),
+            account_b.owner()
+        ));
+    }
+    if account_a.executable() != account_b.executable() {
+        diff.push(format!(
+            "executable: {} -> {}",
+            account_a.executable(),
+            account_b.executable()
+        ));
+    }
+    if account_a.rent_epoch() != account_b.rent_epoch() {
+        diff.push(format!(
+            "rent epoch: {} -> {}",
+            account_a.rent_epoch(),
+            account_b.rent_epoch()
+        ));
+    }
+    let (data_a, data_b) = (account_a.data(), account_b.data());
+    if data_a != data_b {
+        let differing_offsets: Vec<_> = data_a
+            .iter()
+            .zip(data_b)
+            .enumerate()
+            .filter(|(_, (byte_a, byte_b))| byte_a != byte_b)
+            .map(|(offset, _)| offset)
+            .collect();
+        let mut data_diff = format!("data: {} -> {} bytes", data_a.len(), data_b.len());
+        if let Some(first_offset) = differing_offsets.first() {
+            data_diff.push_str(&format!(
+                ", {} bytes differ from offset {}",
+                differing_offsets.len(),
+                first_offset
+            ));
+        }
+        diff.push(data_diff);
+    }
+    diff
+}
+
+fn print_account_diffs<'a>(
+    bank_a: &Bank,
+    bank_b: &Bank,
+    pubkeys: impl IntoIterator<Item = &'a Pubkey>,
+) -> usize {
+    let mut num_diffs = 0;
+    for pubkey in pubkeys {
+        let diff = account_diff(
+            bank_a.get_account(pubkey).as_ref(),
+            bank_b.get_account(pubkey).as_ref(),
+        );
+        if !diff.is_empty() {
+            println!("  {}:", pubkey);
+            for line in diff {
+                println!("    {}", line);
+            }
+            num_diffs += 1;
+        }
+    }
+    num_diffs
+}
+
+/// Replay `slot` in both ledgers transaction by transaction, printing the
+/// first transaction after which the accounts it touches differ, then all
+/// the accounts which differ once the banks are frozen.
+fn explain_divergent_slot(
+    ledger_a: &DiffLedger,
+    ledger_b: &DiffLedger,
+    arg_matches: &ArgMatches<'_>,
+    slot: Slot,
+) {
+    let (parent_a, parent_b) = (ledger_a.parent_slot(slot), ledger_b.parent_slot(slot));
+    let parent_slot = match (parent_a, parent_b) {
+        (Some(parent_a), Some(parent_b)) if parent_a == parent_b => parent_a,
+        _ => {
+            println!(
+                "Slot {} has parent {:?} in ledger a and {:?} in ledger b, not replaying it",
+                slot, parent_a, parent_b
+            );
+            return;
+        }
+    };
+
+    println!("Replaying slot {} on top of slot {}", slot, parent_slot);
+    let (bank_a, transactions_a, tick_hashes_a) =
+        ledger_a.child_bank(arg_matches, slot, parent_slot);
+    let (bank_b, transactions_b, tick_hashes_b) =
+        ledger_b.child_bank(arg_matches, slot, parent_slot);
+    println!(
+        "Slot {} has {} transactions in ledger a and {} in ledger b",
+        slot,
+        transactions_a.len(),
+        transactions_b.len()
+    );
+
+    let mut first_difference = None;
+    for index in 0..transactions_a.len().max(transactions_b.len()) {
+        let transaction_a = transactions_a.get(index);
+        let transaction_b = transactions_b.get(index);
+        let process = |bank: &Bank, transaction: Option<&VersionedTransaction>| {
+            transaction.map(|transaction| {
+                bank.try_process_entry_transactions(vec![transaction.clone()])
+                    .map(|mut results| results.remove(0))
+                    .and_then(|result| result)
+            })
+        };
+        let result_a = process(&bank_a, transaction_a);
+        let result_b = process(&bank_b, transaction_b);
+        if first_difference.is_some() {
+            continue;
+        }
+
+        let signature_a = transaction_a.map(|transaction| transaction.signatures[0]);
+        let signature_b = transaction_b.map(|transaction| transaction.signatures[0]);
+        let pubkeys: BTreeSet<_> = transaction_a
+            .into_iter()
+            .chain(transaction_b)
+            .flat_map(|transaction| transaction.message.static_account_keys())
+            .collect();
+        if signature_a != signature_b || result_a != result_b {
+            println!(
+                "Transaction {} differs:\n  ledger a: {:?} {:?}\n  ledger b: {:?} {:?}",
+                index, signature_a, result_a, signature_b, result_b
+            );
+        }
+        let num_diffs = print_account_diffs(&bank_a, &bank_b, pubkeys);
+        if num_diffs > 0 || signature_a != signature_b {
+            println!(
+                "Transaction {} ({:?}) first produced the difference, in {} accounts",
+                index,
+                signature_a.or(signature_b),
+                num_diffs
+            );
+            first_difference = Some(index);
+        }
+    }
+    if first_difference.is_none() {
+        println!(
+            "The transactions of slot {} produced the same accounts",
+            slot
+        );
+    }
+
+    for (bank, tick_hashes) in [(&bank_a, tick_hashes_a), (&bank_b, tick_hashes_b)] {
+        for tick_hash in &tick_hashes {
+            bank.register_tick(tick_hash);
+        }
+        bank.freeze();
+    }
+    let pubkeys: BTreeSet<_> = bank_a
+        .get_all_accounts_modified_since_parent()
+        .into_iter()
+        .chain(bank_b.get_all_accounts_modified_since_parent())
+        .map(|(pubkey, _)| pubkey)
+        .collect();
+    println!("Accounts which differ once slot {} is frozen:", slot);
+    let num_diffs = print_account_diffs(&bank_a, &bank_b, &pubkeys);
+    println!(
+        "{} of the {} accounts modified in slot {} differ, bank hash: {} -> {}",
+        num_diffs,
+        pubkeys.len(),
+        slot,
+        bank_a.hash(),
+        bank_b.hash()
+    );
+}
+
+pub fn bank_diff_process_command(
+    arg_matches: &ArgMatches<'_>,
+    wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+) {
+    let ledger_a = DiffLedger::open(
+        "a",
+        Path::new(arg_matches.value_of("ledger_a").unwrap()),
+        arg_matches,
+        wal_recovery_mode.clone(),
+    );
+    let ledger_b = DiffLedger::open(
+        "b",
+        Path::new(arg_matches.value_of("ledger_b").unwrap()),
+        arg_matches,
+        wal_recovery_mode,
+    );
+    let ending_slot = value_t!(arg_matches, "ending_slot", Slot).unwrap_or_else(|_| {
+        let highest_slot = |ledger: &DiffLedger| {
+            ledger
+                .blockstore
+                .highest_slot()
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+        highest_slot(&ledger_a).min(highest_slot(&ledger_b))
+    });
+
+    let divergent_slot = if let Some((slot, hash_a, hash_b)) =
+        first_divergent_stored_slot(&ledger_a, &ledger_b, ending_slot)
+    {
+        println!(
+            "First divergent slot by the stored bank hashes: {}, bank hash: {} -> {}",
+            slot, hash_a, hash_b
+        );
+        Some(slot)
+    } else {
+        println!("Replaying both ledgers up to slot {}", ending_slot);
+        let (_, bank_hashes_a) = ledger_a.replay(arg_matches, ending_slot);
+        let (_, bank_hashes_b) = ledger_b.replay(arg_matches, ending_slot);
+        first_divergent_replayed_slot(&bank_hashes_a, &bank_hashes_b).map(
+            |(slot, hash_a, hash_b)| {
+                println!(
+                    "First divergent slot by replaying: {}, bank hash: {:?} -> {:?}",
+                    slot, hash_a, hash_b
+                );
+                slot
+            },
+        )
+    };
+
+    match divergent_slot {
+        Some(slot) => explain_divergent_slot(&ledger_a, &ledger_b, arg_matches, slot),
+        None => println!("The ledgers do not diverge up to slot {}", ending_slot),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::account::WritableAccount};
+
+    #[test]
+    fn test_account_diff() {
+        let account = AccountSharedData::new(10, 4, &Pubkey::new_unique());
+        assert!(account_diff(Some(&account), Some(&account)).is_empty());
+        assert!(account_diff(None, None).is_empty());
+        assert_eq!(
+            account_diff(None, Some(&account)),
+            vec!["missing in ledger a".to_string()]
+        );
+
+        let mut other = account.clone();
+        other.set_lamports(11);
+        other.set_owner(Pubkey::new_unique());
+        other.data_as_mut_slice()[1] = 1;
+        other.data_as_mut_slice()[3] = 1;
+        let diff = account_diff(Some(&account), Some(&other));
+        assert_eq!(diff.len(), 3);
+        assert_eq!(diff[0], "lamports: 10 -> 11");
+        assert_eq!(diff[2], "data: 4 -> 4 bytes, 2 bytes differ from offset 1");
+    }
+
+    #[test]
+    fn test_first_divergent_replayed_slot() {
+        let (hash, other_hash) = (Hash::new_unique(), Hash::new_unique());
+        let bank_hashes_a = BTreeMap::from([(0, hash), (1, hash), (2, hash)]);
+        assert_eq!(
+            first_divergent_replayed_slot(&bank_hashes_a, &bank_hashes_a),
+            None
+        );
+        let bank_hashes_b = BTreeMap::from([(0, hash), (1, other_hash), (2, other_hash)]);
+        assert_eq!(
+            first_divergent_replayed_slot(&bank_hashes_a, &bank_hashes_b),
+            Some((1, Some(hash), Some(other_hash)))
+        );
+        let bank_hashes_b = BTreeMap::from([(0, hash), (2, hash)]);
+        assert_eq!(
+            first_divergent_replayed_slot(&bank_hashes_a, &bank_hashes_b),
+            Some((1, Some(hash), None))
+        );
+    }
+}
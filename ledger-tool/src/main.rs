@@ -1,6 +1,6 @@
 #![allow(clippy::integer_arithmetic)]
 use {
-    crate::{bigtable::*, ledger_path::*},
+    crate::{bank_diff::*, bigtable::*, ledger_path::*},
     chrono::{DateTime, Utc},
     clap::{
         crate_description, crate_name, value_t, value_t_or_exit, values_t_or_exit, App,
@@ -29,7 +29,9 @@ use {
             AccessType, BlockstoreOptions, BlockstoreRecoveryMode, LedgerColumnOptions,
             ShredStorageType,
         },
-        blockstore_processor::{self, BlockstoreProcessorError, ProcessOptions},
+        blockstore_processor::{
+            self, BlockstoreProcessorError, CacheBlockMetaSender, ProcessOptions,
+        },
         shred::Shred,
     },
     solana_measure::{measure, measure::Measure},
@@ -93,6 +95,7 @@ use {
     },
 };
 
+mod bank_diff;
 mod bigtable;
 mod ledger_path;
 
@@ -800,6 +803,9 @@ fn analyze_storage(database: &Database) {
     analyze_column::<BlockHeight>(database, "BlockHeight");
     analyze_column::<ProgramCosts>(database, "ProgramCosts");
     analyze_column::<OptimisticSlots>(database, "OptimisticSlots");
+    analyze_column::<StateTreeBranches>(database, "StateTreeBranches");
+    analyze_column::<StateTreeLeaves>(database, "StateTreeLeaves");
+    analyze_column::<StateTreeRoots>(database, "StateTreeRoots");
 }
 
 fn open_blockstore(
@@ -844,6 +850,28 @@ fn load_bank_forks(
     process_options: ProcessOptions,
     snapshot_archive_path: Option<PathBuf>,
     incremental_snapshot_archive_path: Option<PathBuf>,
+) -> Result<(Arc<RwLock<BankForks>>, Option<StartingSnapshotHashes>), BlockstoreProcessorError> {
+    load_bank_forks_with_frozen_banks(
+        arg_matches,
+        genesis_config,
+        blockstore,
+        process_options,
+        snapshot_archive_path,
+        incremental_snapshot_archive_path,
+        None,
+    )
+}
+
+/// `load_bank_forks`, also sending every bank frozen while processing the
+/// blockstore to `frozen_bank_sender`.
+fn load_bank_forks_with_frozen_banks(
+    arg_matches: &ArgMatches,
+    genesis_config: &GenesisConfig,
+    blockstore: &Blockstore,
+    process_options: ProcessOptions,
+    snapshot_archive_path: Option<PathBuf>,
+    incremental_snapshot_archive_path: Option<PathBuf>,
+    frozen_bank_sender: Option<&CacheBlockMetaSender>,
 ) -> Result<(Arc<RwLock<BankForks>>, Option<StartingSnapshotHashes>), BlockstoreProcessorError> {
     let bank_snapshots_dir = blockstore
         .ledger_path()
@@ -960,7 +988,7 @@ fn load_bank_forks(
         &leader_schedule_cache,
         &process_options,
         None,
-        None,
+        frozen_bank_sender,
         &AbsRequestSender::default(),
     )
     .map(|_| (bank_forks, starting_snapshot_hashes));
@@ -1515,6 +1543,41 @@ fn main() {
             .about("Prints the hash of the working bank after reading the ledger")
             .arg(&max_genesis_archive_unpacked_size_arg)
         )
+        .subcommand(
+            SubCommand::with_name("bank-diff")
+            .about("Find the first slot two ledgers diverge at and explain the difference. \
+                    The bank hashes stored in both ledgers are searched first; if none \
+                    differ, both ledgers are replayed. The divergent slot is then replayed \
+                    transaction by transaction, printing the accounts which differ")
+            .arg(
+                Arg::with_name("ledger_a")
+                    .long("ledger-a")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .required(true)
+                    .help("The first ledger"),
+            )
+            .arg(
+                Arg::with_name("ledger_b")
+                    .long("ledger-b")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .required(true)
+                    .help("The second ledger"),
+            )
+            .arg(
+                Arg::with_name("ending_slot")
+                    .long("ending-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .help("Compare the ledgers up to this slot \
+                           [default: the lowest of the highest slots of the ledgers]"),
+            )
+            .arg(&no_snapshot_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(&hard_forks_arg)
+        )
         .subcommand(
             SubCommand::with_name("bounds")
             .about("Print lowest and highest non-empty slots. \
@@ -2025,6 +2088,8 @@ fn main() {
 
     if let ("bigtable", Some(arg_matches)) = matches.subcommand() {
         bigtable_process_command(&ledger_path, arg_matches, &shred_storage_type)
+    } else if let ("bank-diff", Some(arg_matches)) = matches.subcommand() {
+        bank_diff_process_command(arg_matches, wal_recovery_mode)
     } else {
         let ledger_path = canonicalize_ledger_path(&ledger_path);
 
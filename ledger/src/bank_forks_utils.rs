@@ -16,6 +16,7 @@ use {
         snapshot_config::SnapshotConfig,
         snapshot_hash::{FullSnapshotHash, IncrementalSnapshotHash, StartingSnapshotHashes},
         snapshot_utils,
+        state_tree::StateTreeStore,
     },
     solana_sdk::genesis_config::GenesisConfig,
     std::{
@@ -116,6 +117,10 @@ pub fn load_bank_forks(
         false
     };
 
+    let state_tree_store = process_options
+        .persist_state_tree
+        .then(|| Arc::new(blockstore.state_tree_store()) as Arc<dyn StateTreeStore>);
+
     let (bank_forks, starting_snapshot_hashes) = if snapshot_present {
         bank_forks_from_snapshot(
             genesis_config,
@@ -124,6 +129,7 @@ pub fn load_bank_forks(
             snapshot_config.as_ref().unwrap(),
             process_options,
             accounts_update_notifier,
+            state_tree_store,
         )
     } else {
         let maybe_filler_accounts = process_options
@@ -149,6 +155,21 @@ pub fn load_bank_forks(
             .unwrap()
             .root_bank()
             .set_startup_verification_complete();
+        // Replaying from genesis recomputes every root, a store committed at
+        // a later slot is stale for it and is rewritten from slot 0
+        if let Some(state_tree_store) = state_tree_store {
+            if let Err(err) = bank_forks
+                .read()
+                .unwrap()
+                .root_bank()
+                .set_state_tree_store(state_tree_store)
+            {
+                warn!(
+                    "failed to copy the state tree to its store: {:?}, keeping it in memory",
+                    err
+                );
+            }
+        }
 
         (bank_forks, None)
     };
@@ -186,6 +207,7 @@ fn bank_forks_from_snapshot(
     snapshot_config: &SnapshotConfig,
     process_options: &ProcessOptions,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    state_tree_store: Option<Arc<dyn StateTreeStore>>,
 ) -> (Arc<RwLock<BankForks>>, Option<StartingSnapshotHashes>) {
     // Fail hard here if snapshot fails to load, don't silently continue
     if account_paths.is_empty() {
@@ -213,6 +235,7 @@ fn bank_forks_from_snapshot(
             process_options.verify_index,
             process_options.accounts_db_config.clone(),
             accounts_update_notifier,
+            state_tree_store,
         )
         .expect("Load from snapshot failed");
 
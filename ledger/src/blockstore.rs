@@ -21,6 +21,7 @@ use {
             Shred, ShredData, ShredId, ShredType, Shredder,
         },
         slot_stats::{ShredSource, SlotsStats},
+        state_tree_store::BlockstoreStateTreeStore,
     },
     assert_matches::debug_assert_matches,
     bincode::deserialize,
@@ -2743,6 +2744,11 @@ impl Blockstore {
             .collect())
     }
 
+    /// Returns the store persisting the rooted state tree in this blockstore
+    pub fn state_tree_store(&self) -> BlockstoreStateTreeStore {
+        BlockstoreStateTreeStore::new(self.db.clone())
+    }
+
     pub fn write_program_cost(&self, key: &Pubkey, value: &u64) -> Result<()> {
         self.program_costs_cf
             .put(*key, &ProgramCost { cost: *value })
@@ -3273,6 +3279,12 @@ impl Blockstore {
         self.db.is_primary_access()
     }
 
+    /// Makes a blockstore opened with secondary access see what the primary
+    /// wrote since it was opened or last caught up
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()
+    }
+
     pub fn scan_and_fix_roots(&self, exit: &AtomicBool) -> Result<()> {
         let ancestor_iterator = AncestorIterator::new(self.last_root(), self)
             .take_while(|&slot| slot >= self.lowest_cleanup_slot());
@@ -27,6 +27,7 @@ use {
     solana_runtime::hardened_unpack::UnpackError,
     solana_sdk::{
         clock::{Slot, UnixTimestamp},
+        hash::Hash,
         pubkey::Pubkey,
         signature::Signature,
     },
@@ -93,6 +94,12 @@ const BLOCK_HEIGHT_CF: &str = "block_height";
 const PROGRAM_COSTS_CF: &str = "program_costs";
 /// Column family for optimistic slots
 const OPTIMISTIC_SLOTS_CF: &str = "optimistic_slots";
+/// Column family for the branches of the state tree
+const STATE_TREE_BRANCHES_CF: &str = "state_tree_branches";
+/// Column family for the leaves of the state tree
+const STATE_TREE_LEAVES_CF: &str = "state_tree_leaves";
+/// Column family for the committed root of the state tree
+const STATE_TREE_ROOTS_CF: &str = "state_tree_roots";
 
 // 1 day is chosen for the same reasoning of DEFAULT_COMPACTION_SLOT_INTERVAL
 const PERIODIC_COMPACTION_SECONDS: u64 = 60 * 60 * 24;
@@ -215,6 +222,18 @@ pub mod columns {
     /// The optimistic slot column
     pub struct OptimisticSlots;
 
+    #[derive(Debug)]
+    /// The state tree branches column
+    pub struct StateTreeBranches;
+
+    #[derive(Debug)]
+    /// The state tree leaves column
+    pub struct StateTreeLeaves;
+
+    #[derive(Debug)]
+    /// The state tree roots column
+    pub struct StateTreeRoots;
+
     // When adding a new column ...
     // - Add struct below and implement `Column` and `ColumnName` traits
     // - Add descriptor in Rocks::cf_descriptors() and name in Rocks::columns()
@@ -398,6 +417,9 @@ impl Rocks {
             new_cf_descriptor::<BlockHeight>(options, oldest_slot),
             new_cf_descriptor::<ProgramCosts>(options, oldest_slot),
             new_cf_descriptor::<OptimisticSlots>(options, oldest_slot),
+            new_cf_descriptor::<StateTreeBranches>(options, oldest_slot),
+            new_cf_descriptor::<StateTreeLeaves>(options, oldest_slot),
+            new_cf_descriptor::<StateTreeRoots>(options, oldest_slot),
         ]
     }
 
@@ -425,6 +447,9 @@ impl Rocks {
             BlockHeight::NAME,
             ProgramCosts::NAME,
             OptimisticSlots::NAME,
+            StateTreeBranches::NAME,
+            StateTreeLeaves::NAME,
+            StateTreeRoots::NAME,
         ]
     }
 
@@ -514,6 +539,12 @@ impl Rocks {
             || self.access_type == AccessType::PrimaryForMaintenance
     }
 
+    fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db
+            .try_catch_up_with_primary()
+            .map_err(BlockstoreError::RocksDb)
+    }
+
     /// Retrieves the specified RocksDB integer property of the current
     /// column family.
     ///
@@ -949,6 +980,77 @@ impl TypedColumn for columns::OptimisticSlots {
     type Type = blockstore_meta::OptimisticSlotMetaVersioned;
 }
 
+impl Column for columns::StateTreeBranches {
+    type Index = (u8, [u8; 32]);
+
+    fn key((height, node_key): (u8, [u8; 32])) -> Vec<u8> {
+        let mut key = vec![0; 1 + 32]; // size_of u8 + size_of node key
+        key[0] = height;
+        key[1..33].clone_from_slice(&node_key);
+        key
+    }
+
+    fn index(key: &[u8]) -> (u8, [u8; 32]) {
+        let mut node_key = [0; 32];
+        node_key.clone_from_slice(&key[1..33]);
+        (key[0], node_key)
+    }
+
+    fn primary_index(_index: Self::Index) -> u64 {
+        unimplemented!()
+    }
+
+    fn slot(_index: Self::Index) -> Slot {
+        unimplemented!()
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn as_index(_index: u64) -> Self::Index {
+        (0, [0; 32])
+    }
+}
+impl ColumnName for columns::StateTreeBranches {
+    const NAME: &'static str = STATE_TREE_BRANCHES_CF;
+}
+
+impl Column for columns::StateTreeLeaves {
+    type Index = [u8; 32];
+
+    fn key(leaf_key: [u8; 32]) -> Vec<u8> {
+        leaf_key.to_vec()
+    }
+
+    fn index(key: &[u8]) -> [u8; 32] {
+        let mut leaf_key = [0; 32];
+        leaf_key.clone_from_slice(&key[0..32]);
+        leaf_key
+    }
+
+    fn primary_index(_index: Self::Index) -> u64 {
+        unimplemented!()
+    }
+
+    fn slot(_index: Self::Index) -> Slot {
+        unimplemented!()
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn as_index(_index: u64) -> Self::Index {
+        [0; 32]
+    }
+}
+impl ColumnName for columns::StateTreeLeaves {
+    const NAME: &'static str = STATE_TREE_LEAVES_CF;
+}
+
+impl SlotColumn for columns::StateTreeRoots {}
+impl ColumnName for columns::StateTreeRoots {
+    const NAME: &'static str = STATE_TREE_ROOTS_CF;
+}
+impl TypedColumn for columns::StateTreeRoots {
+    type Type = Hash;
+}
+
 #[derive(Debug)]
 pub struct Database {
     backend: Arc<Rocks>,
@@ -1145,6 +1247,10 @@ impl Database {
         self.backend.is_primary_access()
     }
 
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.backend.try_catch_up_with_primary()
+    }
+
     pub fn set_oldest_slot(&self, oldest_slot: Slot) {
         self.backend.oldest_slot.set(oldest_slot);
     }
@@ -1692,6 +1798,9 @@ fn should_exclude_from_compaction(cf_name: &str) -> bool {
         columns::TransactionStatusIndex::NAME,
         columns::ProgramCosts::NAME,
         columns::TransactionMemos::NAME,
+        columns::StateTreeBranches::NAME,
+        columns::StateTreeLeaves::NAME,
+        columns::StateTreeRoots::NAME,
     ]
     .into_iter()
     .collect();
@@ -1780,7 +1889,7 @@ pub mod tests {
 
     #[test]
     fn test_should_exclude_from_compaction() {
-        // currently there are three CFs excluded from compaction:
+        // currently there are six CFs excluded from compaction:
         assert!(should_exclude_from_compaction(
             columns::TransactionStatusIndex::NAME
         ));
@@ -1788,6 +1897,15 @@ pub mod tests {
         assert!(should_exclude_from_compaction(
             columns::TransactionMemos::NAME
         ));
+        assert!(should_exclude_from_compaction(
+            columns::StateTreeBranches::NAME
+        ));
+        assert!(should_exclude_from_compaction(
+            columns::StateTreeLeaves::NAME
+        ));
+        assert!(should_exclude_from_compaction(
+            columns::StateTreeRoots::NAME
+        ));
         assert!(!should_exclude_from_compaction("something else"));
     }
 }
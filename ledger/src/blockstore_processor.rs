@@ -712,6 +712,8 @@ pub struct ProcessOptions {
     /// true if after processing the contents of the blockstore at startup, we should run an accounts hash calc
     /// This is useful for debugging.
     pub run_final_accounts_hash_calc: bool,
+    /// true to keep the state tree of the rooted bank in the blockstore, resuming it on restart
+    pub persist_state_tree: bool,
 }
 
 pub fn test_process_blockstore(
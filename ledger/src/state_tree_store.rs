@@ -0,0 +1,221 @@
+//! The `state_tree_store` module persists the rooted state tree of the banks
+//! in blockstore columns, so a restarted validator can resume it instead of
+//! rebuilding it from the accounts.
+use {
+    crate::blockstore_db::{columns as cf, BlockstoreError, Database, IteratorMode, LedgerColumn},
+    bincode::deserialize,
+    solana_runtime::state_tree::{
+        self, hash_to_root, root_to_hash, StateTreeChanges, StateTreeStore,
+    },
+    solana_sdk::{clock::Slot, hash::Hash},
+    sparse_merkle_tree::{error::Error, traits::StoreReadOps, BranchKey, BranchNode, H256},
+    std::{fmt, sync::Arc},
+};
+
+fn store_error(err: BlockstoreError) -> Error {
+    Error::Store(format!("{:?}", err))
+}
+
+/// Keeps the nodes of the rooted state tree in the blockstore, with the slot
+/// and root of the last committed tree
+pub struct BlockstoreStateTreeStore {
+    db: Arc<Database>,
+    branches_cf: LedgerColumn<cf::StateTreeBranches>,
+    leaves_cf: LedgerColumn<cf::StateTreeLeaves>,
+}
+
+impl fmt::Debug for BlockstoreStateTreeStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockstoreStateTreeStore").finish()
+    }
+}
+
+impl BlockstoreStateTreeStore {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            branches_cf: db.column(),
+            leaves_cf: db.column(),
+            db,
+        }
+    }
+}
+
+impl StoreReadOps<H256> for BlockstoreStateTreeStore {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, Error> {
+        self.branches_cf
+            .get_bytes((branch_key.height, branch_key.node_key.into()))
+            .map_err(store_error)?
+            .map(|bytes| state_tree::deserialize_branch(&bytes))
+            .transpose()
+    }
+
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<H256>, Error> {
+        Ok(self
+            .leaves_cf
+            .get_bytes((*leaf_key).into())
+            .map_err(store_error)?
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+            .map(H256::from))
+    }
+}
+
+impl StateTreeStore for BlockstoreStateTreeStore {
+    fn committed(&self) -> Result<Option<(Slot, H256)>, Error> {
+        self.db
+            .iter::<cf::StateTreeRoots>(IteratorMode::End)
+            .map_err(store_error)?
+            .next()
+            .map(|(slot, bytes)| {
+                let root: Hash = deserialize(&bytes)
+                    .map_err(|err| store_error(BlockstoreError::Serialize(err)))?;
+                Ok((slot, hash_to_root(&root)))
+            })
+            .transpose()
+    }
+
+    fn commit(&self, slot: Slot, root: H256, changes: &StateTreeChanges) -> Result<(), Error> {
+        let mut batch = self.db.batch().map_err(store_error)?;
+        for (branch_key, branch) in &changes.branches {
+            let key = (branch_key.height, branch_key.node_key.into());
+            match branch {
+                Some(branch) => batch
+                    .put_bytes::<cf::StateTreeBranches>(key, &state_tree::serialize_branch(branch)),
+                None => batch.delete::<cf::StateTreeBranches>(key),
+            }
+            .map_err(store_error)?;
+        }
+        for (leaf_key, leaf) in &changes.leaves {
+            let key = (*leaf_key).into();
+            match leaf {
+                Some(leaf) => batch.put_bytes::<cf::StateTreeLeaves>(key, leaf.as_slice()),
+                None => batch.delete::<cf::StateTreeLeaves>(key),
+            }
+            .map_err(store_error)?;
+        }
+        // Only the last committed root is kept
+        for (committed_slot, _) in self
+            .db
+            .iter::<cf::StateTreeRoots>(IteratorMode::Start)
+            .map_err(store_error)?
+        {
+            batch
+                .delete::<cf::StateTreeRoots>(committed_slot)
+                .map_err(store_error)?;
+        }
+        batch
+            .put::<cf::StateTreeRoots>(slot, &root_to_hash(&root))
+            .map_err(store_error)?;
+        self.db.write(batch).map_err(store_error)
+    }
+
+    fn nodes(&self) -> Result<StateTreeChanges, Error> {
+        let mut nodes = StateTreeChanges::default();
+        for ((height, node_key), bytes) in self
+            .db
+            .iter::<cf::StateTreeBranches>(IteratorMode::Start)
+            .map_err(store_error)?
+        {
+            nodes.branches.insert(
+                BranchKey::new(height, H256::from(node_key)),
+                Some(state_tree::deserialize_branch(&bytes)?),
+            );
+        }
+        for (leaf_key, bytes) in self
+            .db
+            .iter::<cf::StateTreeLeaves>(IteratorMode::Start)
+            .map_err(store_error)?
+        {
+            let leaf = <[u8; 32]>::try_from(&*bytes)
+                .map_err(|_| Error::Store("invalid state tree leaf".to_string()))?;
+            nodes
+                .leaves
+                .insert(H256::from(leaf_key), Some(H256::from(leaf)));
+        }
+        Ok(nodes)
+    }
+
+    fn clear(&self) -> Result<(), Error> {
+        let mut batch = self.db.batch().map_err(store_error)?;
+        // The end of the ranges is exclusive, delete the last keys on their own
+        let last_branch_key = (u8::MAX, [u8::MAX; 32]);
+        batch
+            .delete_range_cf::<cf::StateTreeBranches>(
+                self.db.cf_handle::<cf::StateTreeBranches>(),
+                (0, [0; 32]),
+                last_branch_key,
+            )
+            .and_then(|_| batch.delete::<cf::StateTreeBranches>(last_branch_key))
+            .map_err(store_error)?;
+        let last_leaf_key = [u8::MAX; 32];
+        batch
+            .delete_range_cf::<cf::StateTreeLeaves>(
+                self.db.cf_handle::<cf::StateTreeLeaves>(),
+                [0; 32],
+                last_leaf_key,
+            )
+            .and_then(|_| batch.delete::<cf::StateTreeLeaves>(last_leaf_key))
+            .map_err(store_error)?;
+        batch
+            .delete_range_cf::<cf::StateTreeRoots>(
+                self.db.cf_handle::<cf::StateTreeRoots>(),
+                0,
+                Slot::MAX,
+            )
+            .and_then(|_| batch.delete::<cf::StateTreeRoots>(Slot::MAX))
+            .map_err(store_error)?;
+        self.db.write(batch).map_err(store_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{blockstore::Blockstore, get_tmp_ledger_path_auto_delete},
+        solana_runtime::state_tree::StateTree,
+    };
+
+    #[test]
+    fn test_state_tree_store() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let store = Arc::new(blockstore.state_tree_store());
+        assert_eq!(store.committed().unwrap(), None);
+
+        let leaves: Vec<_> = (0..10u8)
+            .map(|i| (H256::from([i; 32]), H256::from([i + 1; 32])))
+            .collect();
+        let state_tree = StateTree::new_from_leaves(1, leaves.clone()).unwrap();
+        assert!(!state_tree.set_store(store.clone()).unwrap());
+        let root = hash_to_root(&state_tree.rooted_root());
+        assert_eq!(store.committed().unwrap(), Some((1, root)));
+
+        // Remove a leaf and add another one
+        let slot2 = vec![
+            (H256::from([0; 32]), H256::zero()),
+            (H256::from([20; 32]), H256::from([21; 32])),
+        ];
+        let (root2, changes2) = state_tree
+            .compute_root(None, vec![], slot2.clone())
+            .unwrap();
+        state_tree.root_slots(vec![(2, root2, &changes2)]).unwrap();
+        assert_eq!(store.committed().unwrap(), Some((2, hash_to_root(&root2))));
+        assert_eq!(store.get_leaf(&H256::from([0; 32])).unwrap(), None);
+
+        // A restarted tree resumes from the store...
+        let resumed_tree =
+            StateTree::new_from_leaves(2, [&leaves[1..], &slot2[1..]].concat()).unwrap();
+        assert!(resumed_tree.set_store(store.clone()).unwrap());
+
+        // ...and the store of an older tree is replaced
+        let older_tree = StateTree::new_from_leaves(1, leaves).unwrap();
+        assert!(!older_tree.set_store(store.clone()).unwrap());
+        assert_eq!(store.committed().unwrap(), Some((1, root)));
+        assert_eq!(store.get_leaf(&H256::from([20; 32])).unwrap(), None);
+
+        store.clear().unwrap();
+        assert_eq!(store.committed().unwrap(), None);
+        assert!(store.nodes().unwrap().branches.is_empty());
+        assert!(store.nodes().unwrap().leaves.is_empty());
+    }
+}
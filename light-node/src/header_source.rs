@@ -0,0 +1,159 @@
+//! Where the light node fetches the signed slot headers from.
+//!
+//! Only `PostgresHeaderSource` is implemented: the headers are read from the
+//! PostgreSQL database the execution node writes the `slot`, `block` and
+//! `merkle_tree_proof` tables of through the geyser plugin. Reading them from
+//! the DA backend is out of scope. Whether it publishes shreds or signed blobs,
+//! the DA backend carries the entries of the slots and not their headers; the
+//! bank hash and state root only come out of replaying them, and the execution
+//! node does not post its header signatures there.
+use {
+    crate::{LightNodeError, Result, SignedSlotHeader},
+    hex::FromHex,
+    log::*,
+    postgres::{Client, NoTls, Row},
+    serde_derive::{Deserialize, Serialize},
+    solana_runtime::slot_header::SlotHeader,
+    solana_sdk::{clock::Slot, hash::Hash, signature::Signature},
+    std::str::FromStr,
+};
+
+pub trait HeaderSource: Send {
+    /// Fetch up to `limit` headers of the rooted slots after `after_slot`, or
+    /// from the first one if `None`, in ascending order of slot.
+    fn fetch_headers(
+        &mut self,
+        after_slot: Option<Slot>,
+        limit: usize,
+    ) -> Result<Vec<SignedSlotHeader>>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightNodePostgresConfig {
+    /// The host name or IP of the PostgreSQL server
+    pub host: Option<String>,
+    /// The user name of the PostgreSQL server.
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub dbname: Option<String>,
+    /// The port number of the PostgreSQL database, the default is 5432
+    pub port: Option<u16>,
+}
+
+impl LightNodePostgresConfig {
+    fn connection_str(&self) -> String {
+        format!(
+            "host={} user={} password={} dbname={} port={}",
+            self.host.as_deref().unwrap_or("localhost"),
+            self.user.as_deref().unwrap_or_default(),
+            self.password.as_deref().unwrap_or_default(),
+            self.dbname.as_deref().unwrap_or_default(),
+            self.port.unwrap_or(5432),
+        )
+    }
+}
+
+pub struct PostgresHeaderSource {
+    client: Client,
+}
+
+impl PostgresHeaderSource {
+    pub fn new(config: &LightNodePostgresConfig) -> Result<Self> {
+        let connection_str = config.connection_str();
+        let client = Client::connect(&connection_str, NoTls).map_err(|err| {
+            LightNodeError::DbConnectError {
+                msg: format!("the config is {}, error: {}", connection_str, err),
+            }
+        })?;
+        Ok(Self { client })
+    }
+
+    /// The header of a row of the query in `fetch_headers`, `None` if the
+    /// execution node has not written all of it yet.
+    fn header_of_row(row: &Row) -> Result<Option<SignedSlotHeader>> {
+        let slot = row.get::<_, i64>(0) as Slot;
+        let columns = || {
+            Some((
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        };
+        let (
+            parent_slot,
+            blockhash,
+            bank_hash,
+            header_signature,
+            root_hash,
+            transactions_state_root,
+        ) = match columns() {
+            Some(columns) => columns,
+            None => return Ok(None),
+        };
+
+        let invalid_header = |msg: String| LightNodeError::InvalidHeader { slot, msg };
+        let parse_hash = |name: &str, hash: &str| {
+            Hash::from_str(hash)
+                .map_err(|err| invalid_header(format!("{} {}: {}", name, hash, err)))
+        };
+        let header = SlotHeader {
+            slot,
+            parent_slot: parent_slot as Slot,
+            blockhash: parse_hash("blockhash", &blockhash)?,
+            bank_hash: parse_hash("bank hash", &bank_hash)?,
+            state_root: <[u8; 32]>::from_hex(&root_hash)
+                .map(Hash::new_from_array)
+                .map_err(|err| invalid_header(format!("state root {}: {}", root_hash, err)))?,
+            transactions_state_root: parse_hash(
+                "transactions state root",
+                &transactions_state_root,
+            )?,
+        };
+        let signature = Signature::from_str(&header_signature)
+            .map_err(|err| invalid_header(format!("signature {}: {}", header_signature, err)))?;
+        Ok(Some(SignedSlotHeader { header, signature }))
+    }
+}
+
+impl HeaderSource for PostgresHeaderSource {
+    fn fetch_headers(
+        &mut self,
+        after_slot: Option<Slot>,
+        limit: usize,
+    ) -> Result<Vec<SignedSlotHeader>> {
+        let after_slot = after_slot.map(|slot| slot as i64).unwrap_or(-1);
+        let rows = self
+            .client
+            .query(
+                "SELECT s.slot, s.parent, b.blockhash, b.bank_hash, b.header_signature, \
+                (SELECT m.root_hash FROM merkle_tree_proof m WHERE m.slot = s.slot \
+                ORDER BY m.updated_on DESC LIMIT 1), b.transactions_state_root \
+                FROM slot s LEFT JOIN block b ON b.slot = s.slot \
+                WHERE s.slot > $1 AND s.status = 'rooted' ORDER BY s.slot LIMIT $2",
+                &[&after_slot, &(limit as i64)],
+            )
+            .map_err(|err| LightNodeError::FetchError {
+                msg: err.to_string(),
+            })?;
+
+        let mut headers = Vec::with_capacity(rows.len());
+        for row in &rows {
+            match Self::header_of_row(row)? {
+                Some(header) => headers.push(header),
+                None => {
+                    // Stop at the first incomplete slot, so the headers stay
+                    // contiguous; it is fetched again on the next call.
+                    debug!(
+                        "The header of slot {} is not complete yet",
+                        row.get::<_, i64>(0)
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(headers)
+    }
+}
@@ -0,0 +1,107 @@
+//! The local store of the verified slot headers, one file per slot in a
+//! directory, plus a file with the slot of the tip of the chain.
+use {
+    crate::{Result, SignedSlotHeader},
+    solana_sdk::clock::Slot,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+const HEADER_FILE_PREFIX: &str = "header-";
+const HEADER_FILE_EXTENSION: &str = "bin";
+const TIP_FILE: &str = "tip";
+
+/// Several `HeaderStore`s may be opened on the same directory, e.g. by the
+/// sync loop and the RPC service, as long as only one of them inserts.
+#[derive(Clone, Debug)]
+pub struct HeaderStore {
+    path: PathBuf,
+}
+
+impl HeaderStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        fs::create_dir_all(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn header_file(&self, slot: Slot) -> PathBuf {
+        self.path.join(format!(
+            "{}{:020}.{}",
+            HEADER_FILE_PREFIX, slot, HEADER_FILE_EXTENSION
+        ))
+    }
+
+    /// Write `contents` to a temporary file first so readers never observe a
+    /// partial file.
+    fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+        let tmp_file = path.with_extension("tmp");
+        fs::write(&tmp_file, contents)?;
+        fs::rename(&tmp_file, path)?;
+        Ok(())
+    }
+
+    /// Store `signed_header` and make it the tip of the chain. The caller
+    /// checks it extends the chain.
+    pub fn insert(&self, signed_header: &SignedSlotHeader) -> Result<()> {
+        let slot = signed_header.header.slot;
+        Self::write_file(&self.header_file(slot), &bincode::serialize(signed_header)?)?;
+        Self::write_file(&self.path.join(TIP_FILE), &bincode::serialize(&slot)?)
+    }
+
+    pub fn get(&self, slot: Slot) -> Result<Option<SignedSlotHeader>> {
+        let header_file = self.header_file(slot);
+        if !header_file.exists() {
+            return Ok(None);
+        }
+        Ok(Some(bincode::deserialize(&fs::read(&header_file)?)?))
+    }
+
+    pub fn tip_slot(&self) -> Result<Option<Slot>> {
+        let tip_file = self.path.join(TIP_FILE);
+        if !tip_file.exists() {
+            return Ok(None);
+        }
+        Ok(Some(bincode::deserialize(&fs::read(&tip_file)?)?))
+    }
+
+    /// The header at the tip of the chain, `None` if the store is empty.
+    pub fn tip(&self) -> Result<Option<SignedSlotHeader>> {
+        match self.tip_slot()? {
+            Some(slot) => self.get(slot),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::tests::signed_header, solana_sdk::signature::Keypair, tempfile::TempDir,
+    };
+
+    #[test]
+    fn test_header_store() {
+        let store_dir = TempDir::new().unwrap();
+        let header_store = HeaderStore::open(store_dir.path()).unwrap();
+        assert_eq!(header_store.tip().unwrap(), None);
+        assert_eq!(header_store.get(1).unwrap(), None);
+
+        let sequencer = Keypair::new();
+        let first = signed_header(&sequencer, 1, 0);
+        let second = signed_header(&sequencer, 3, 1);
+        header_store.insert(&first).unwrap();
+        header_store.insert(&second).unwrap();
+        assert_eq!(header_store.get(1).unwrap(), Some(first));
+        assert_eq!(header_store.get(2).unwrap(), None);
+        assert_eq!(header_store.tip().unwrap(), Some(second));
+
+        // The headers persist
+        let header_store = HeaderStore::open(store_dir.path()).unwrap();
+        assert_eq!(header_store.tip_slot().unwrap(), Some(3));
+        assert_eq!(header_store.get(1).unwrap(), Some(first));
+    }
+}
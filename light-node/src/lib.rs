@@ -0,0 +1,281 @@
+//! A settlement-layer light node.
+//!
+//! Instead of replaying the rollup, the light node follows the chain of slot
+//! headers the sequencer signs when it freezes the bank of each slot. Every
+//! header is checked to be signed by the sequencer and to extend the local
+//! chain, and is kept in a local `HeaderStore`. The state roots of the headers
+//! let other tools check the `getAccountProof` proofs of the execution node
+//! without trusting it.
+pub mod header_source;
+pub mod header_store;
+pub mod rpc;
+
+use {
+    crate::{header_source::HeaderSource, header_store::HeaderStore},
+    log::*,
+    serde_derive::{Deserialize, Serialize},
+    solana_runtime::slot_header::SlotHeader,
+    solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
+    std::{
+        io,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::sleep,
+        time::Duration,
+    },
+    thiserror::Error,
+};
+
+/// The default interval the header source is polled at for new headers.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The most headers fetched from the header source at once.
+pub const MAX_HEADERS_PER_FETCH: usize = 1000;
+
+#[derive(Error, Debug)]
+pub enum LightNodeError {
+    #[error("Error connecting postgresdb Error message: ({msg})")]
+    DbConnectError { msg: String },
+
+    #[error("Error fetching the slot headers. Error message: ({msg})")]
+    FetchError { msg: String },
+
+    #[error("Invalid slot header at slot {slot}. Error message: ({msg})")]
+    InvalidHeader { slot: Slot, msg: String },
+
+    #[error("The header of slot {slot} is not signed by the sequencer {sequencer}")]
+    InvalidSignature { slot: Slot, sequencer: Pubkey },
+
+    #[error("The header of slot {slot} has parent {parent_slot}, the chain is at slot {tip_slot}")]
+    BrokenChain {
+        slot: Slot,
+        parent_slot: Slot,
+        tip_slot: Slot,
+    },
+
+    #[error("Io error: ({0})")]
+    Io(#[from] io::Error),
+
+    #[error("Serialization error: ({0})")]
+    Serialization(#[from] bincode::Error),
+}
+
+pub type Result<T> = std::result::Result<T, LightNodeError>;
+
+/// A slot header with the signature of the sequencer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedSlotHeader {
+    pub header: SlotHeader,
+    pub signature: Signature,
+}
+
+impl SignedSlotHeader {
+    pub fn verify(&self, sequencer: &Pubkey) -> bool {
+        self.header.verify(sequencer, &self.signature)
+    }
+}
+
+pub struct LightNode {
+    header_source: Box<dyn HeaderSource>,
+    header_store: HeaderStore,
+    sequencer: Pubkey,
+}
+
+impl LightNode {
+    pub fn new(
+        header_source: Box<dyn HeaderSource>,
+        header_store: HeaderStore,
+        sequencer: Pubkey,
+    ) -> Self {
+        Self {
+            header_source,
+            header_store,
+            sequencer,
+        }
+    }
+
+    /// Fetch the headers after the tip of the store, appending them to it.
+    /// Returns the number of headers appended.
+    ///
+    /// The first header of an empty store is trusted as the start of the
+    /// chain, as long as it is signed by the sequencer.
+    pub fn sync(&mut self) -> Result<usize> {
+        let tip = self.header_store.tip()?;
+        let headers = self
+            .header_source
+            .fetch_headers(tip.map(|tip| tip.header.slot), MAX_HEADERS_PER_FETCH)?;
+        let mut tip_slot = tip.map(|tip| tip.header.slot);
+        for signed_header in &headers {
+            check_header(signed_header, &self.sequencer, tip_slot)?;
+            self.header_store.insert(signed_header)?;
+            tip_slot = Some(signed_header.header.slot);
+        }
+        Ok(headers.len())
+    }
+
+    /// Sync the headers every `poll_interval` until `exit` is set.
+    pub fn run(&mut self, poll_interval: Duration, exit: &Arc<AtomicBool>) -> Result<()> {
+        while !exit.load(Ordering::Relaxed) {
+            let synced = self.sync()?;
+            if synced > 0 {
+                info!(
+                    "Synced {} slot headers, the chain is at slot {:?}",
+                    synced,
+                    self.header_store.tip_slot()?
+                );
+            }
+            if synced < MAX_HEADERS_PER_FETCH {
+                sleep(poll_interval);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check `signed_header` is signed by `sequencer` and extends the chain at
+/// `tip_slot`.
+pub fn check_header(
+    signed_header: &SignedSlotHeader,
+    sequencer: &Pubkey,
+    tip_slot: Option<Slot>,
+) -> Result<()> {
+    let header = &signed_header.header;
+    if !signed_header.verify(sequencer) {
+        return Err(LightNodeError::InvalidSignature {
+            slot: header.slot,
+            sequencer: *sequencer,
+        });
+    }
+    if header.parent_slot >= header.slot && header.slot != 0 {
+        return Err(LightNodeError::InvalidHeader {
+            slot: header.slot,
+            msg: format!("parent slot {} is not before the slot", header.parent_slot),
+        });
+    }
+    match tip_slot {
+        Some(tip_slot) if header.parent_slot != tip_slot => Err(LightNodeError::BrokenChain {
+            slot: header.slot,
+            parent_slot: header.parent_slot,
+            tip_slot,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            hash::{hash, Hash},
+            signature::{Keypair, Signer},
+        },
+        std::{collections::VecDeque, sync::Mutex},
+        tempfile::TempDir,
+    };
+
+    pub(crate) fn signed_header(
+        keypair: &Keypair,
+        slot: Slot,
+        parent_slot: Slot,
+    ) -> SignedSlotHeader {
+        let header = SlotHeader {
+            slot,
+            parent_slot,
+            blockhash: hash(&slot.to_le_bytes()),
+            bank_hash: Hash::new_unique(),
+            state_root: Hash::new_unique(),
+            transactions_state_root: Hash::new_unique(),
+        };
+        SignedSlotHeader {
+            header,
+            signature: header.sign(keypair),
+        }
+    }
+
+    /// Serves batches of headers in order.
+    struct TestHeaderSource(Arc<Mutex<VecDeque<Vec<SignedSlotHeader>>>>);
+
+    impl HeaderSource for TestHeaderSource {
+        fn fetch_headers(
+            &mut self,
+            _after_slot: Option<Slot>,
+            _limit: usize,
+        ) -> Result<Vec<SignedSlotHeader>> {
+            Ok(self.0.lock().unwrap().pop_front().unwrap_or_default())
+        }
+    }
+
+    #[test]
+    fn test_check_header() {
+        let sequencer = Keypair::new();
+        let signed_header = signed_header(&sequencer, 4, 2);
+        assert!(check_header(&signed_header, &sequencer.pubkey(), None).is_ok());
+        assert!(check_header(&signed_header, &sequencer.pubkey(), Some(2)).is_ok());
+        assert!(matches!(
+            check_header(&signed_header, &sequencer.pubkey(), Some(3)),
+            Err(LightNodeError::BrokenChain {
+                slot: 4,
+                parent_slot: 2,
+                tip_slot: 3
+            })
+        ));
+        assert!(matches!(
+            check_header(&signed_header, &Pubkey::new_unique(), Some(2)),
+            Err(LightNodeError::InvalidSignature { slot: 4, .. })
+        ));
+
+        // The signature covers the whole header
+        let mut tampered = signed_header;
+        tampered.header.state_root = Hash::new_unique();
+        assert!(matches!(
+            check_header(&tampered, &sequencer.pubkey(), Some(2)),
+            Err(LightNodeError::InvalidSignature { slot: 4, .. })
+        ));
+
+        let invalid = self::signed_header(&sequencer, 4, 4);
+        assert!(matches!(
+            check_header(&invalid, &sequencer.pubkey(), None),
+            Err(LightNodeError::InvalidHeader { slot: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_light_node_sync() {
+        let sequencer = Keypair::new();
+        let batches = Arc::new(Mutex::new(VecDeque::from(vec![
+            vec![
+                signed_header(&sequencer, 1, 0),
+                signed_header(&sequencer, 2, 1),
+            ],
+            vec![signed_header(&sequencer, 4, 2)],
+            // Does not extend the chain at slot 4
+            vec![signed_header(&sequencer, 6, 5)],
+            // Not signed by the sequencer
+            vec![signed_header(&Keypair::new(), 5, 4)],
+        ])));
+        let store_dir = TempDir::new().unwrap();
+        let mut light_node = LightNode::new(
+            Box::new(TestHeaderSource(batches)),
+            HeaderStore::open(store_dir.path()).unwrap(),
+            sequencer.pubkey(),
+        );
+        assert_eq!(light_node.sync().unwrap(), 2);
+        assert_eq!(light_node.sync().unwrap(), 1);
+        assert!(matches!(
+            light_node.sync(),
+            Err(LightNodeError::BrokenChain { slot: 6, .. })
+        ));
+        assert!(matches!(
+            light_node.sync(),
+            Err(LightNodeError::InvalidSignature { slot: 5, .. })
+        ));
+        assert_eq!(light_node.sync().unwrap(), 0);
+
+        let header_store = HeaderStore::open(store_dir.path()).unwrap();
+        assert_eq!(header_store.tip_slot().unwrap(), Some(4));
+        assert!(header_store.get(2).unwrap().is_some());
+        assert!(header_store.get(3).unwrap().is_none());
+    }
+}
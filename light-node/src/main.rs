@@ -0,0 +1,111 @@
+use {
+    clap::{crate_description, crate_name, value_t_or_exit, App, Arg},
+    light_node::{
+        header_source::{LightNodePostgresConfig, PostgresHeaderSource},
+        header_store::HeaderStore,
+        rpc::start_rpc_service,
+        LightNode, DEFAULT_POLL_INTERVAL,
+    },
+    solana_clap_utils::input_validators::{is_parsable, is_pubkey},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        fs::File,
+        io::Read,
+        net::SocketAddr,
+        path::PathBuf,
+        process::exit,
+        sync::{atomic::AtomicBool, Arc},
+        time::Duration,
+    },
+};
+
+fn main() {
+    solana_logger::setup_with_default("solana=info,light_node=info");
+    let default_poll_interval_secs = DEFAULT_POLL_INTERVAL.as_secs().to_string();
+
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version("0.1")
+        .arg(
+            Arg::with_name("config_file")
+                .short("c")
+                .long("config")
+                .value_name("CONFIG")
+                .takes_value(true)
+                .required(true)
+                .default_value("config.json")
+                .help("Configuration file with the PostgreSQL database to sync the headers from"),
+        )
+        .arg(
+            Arg::with_name("sequencer")
+                .long("sequencer")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .validator(is_pubkey)
+                .help("The identity of the sequencer the headers must be signed by"),
+        )
+        .arg(
+            Arg::with_name("store_path")
+                .short("s")
+                .long("store")
+                .value_name("DIR")
+                .takes_value(true)
+                .required(true)
+                .default_value("light-node-headers")
+                .help("Keep the verified headers in DIR"),
+        )
+        .arg(
+            Arg::with_name("rpc_addr")
+                .long("rpc-addr")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .validator(is_parsable::<SocketAddr>)
+                .default_value("127.0.0.1:8999")
+                .help("Serve the JSON RPC API at this address"),
+        )
+        .arg(
+            Arg::with_name("poll_interval_secs")
+                .long("poll-interval-secs")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(&default_poll_interval_secs)
+                .help("Poll for new headers every this many seconds"),
+        )
+        .get_matches();
+
+    let config_file = value_t_or_exit!(matches, "config_file", PathBuf);
+    let sequencer = value_t_or_exit!(matches, "sequencer", Pubkey);
+    let store_path = value_t_or_exit!(matches, "store_path", PathBuf);
+    let rpc_addr = value_t_or_exit!(matches, "rpc_addr", SocketAddr);
+    let poll_interval = Duration::from_secs(value_t_or_exit!(matches, "poll_interval_secs", u64));
+
+    let mut file = File::open(config_file.as_path()).unwrap_or_else(|err| {
+        eprintln!("Unable to open config file {:?}: {}", config_file, err);
+        exit(1);
+    });
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap_or_else(|err| {
+        eprintln!("Unable to read config file {:?}: {}", config_file, err);
+        exit(1);
+    });
+    let config: LightNodePostgresConfig = serde_json::from_str(&contents).unwrap_or_else(|err| {
+        eprintln!(
+            "The config file is not in the JSON format expected: {:?}",
+            err
+        );
+        exit(1);
+    });
+
+    let result = HeaderStore::open(&store_path).and_then(|header_store| {
+        let _rpc_server = start_rpc_service(&rpc_addr, header_store.clone())?;
+        let header_source = PostgresHeaderSource::new(&config)?;
+        let exit = Arc::new(AtomicBool::new(false));
+        LightNode::new(Box::new(header_source), header_store, sequencer).run(poll_interval, &exit)
+    });
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        exit(1);
+    }
+}
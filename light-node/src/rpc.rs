@@ -0,0 +1,302 @@
+//! The JSON RPC service of the light node, serving the verified slot headers
+//! and checking account proofs against their state roots.
+use {
+    crate::{header_store::HeaderStore, Result as LightNodeResult, SignedSlotHeader},
+    jsonrpc_core::{Error, MetaIoHandler, Metadata, Result},
+    jsonrpc_derive::rpc,
+    jsonrpc_http_server::{hyper, Server, ServerBuilder},
+    log::*,
+    serde_derive::{Deserialize, Serialize},
+    smt_proof::verify_account_proof,
+    solana_sdk::{clock::Slot, hash::Hash, pubkey::Pubkey},
+    std::{net::SocketAddr, str::FromStr},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSlotHeader {
+    pub slot: Slot,
+    pub parent_slot: Slot,
+    pub blockhash: String,
+    pub bank_hash: String,
+    pub state_root: String,
+    /// The root fraud proofs of the transactions of the slot are checked
+    /// against
+    pub transactions_state_root: String,
+    /// The signature of the header by the sequencer
+    pub signature: String,
+}
+
+impl From<SignedSlotHeader> for RpcSlotHeader {
+    fn from(signed_header: SignedSlotHeader) -> Self {
+        let header = signed_header.header;
+        Self {
+            slot: header.slot,
+            parent_slot: header.parent_slot,
+            blockhash: header.blockhash.to_string(),
+            bank_hash: header.bank_hash.to_string(),
+            state_root: header.state_root.to_string(),
+            transactions_state_root: header.transactions_state_root.to_string(),
+            signature: signed_header.signature.to_string(),
+        }
+    }
+}
+
+/// The value of a `getAccountProof` response of the execution node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountProof {
+    pub state_root: String,
+    pub value_hash: String,
+    /// The base64 encoded compiled merkle proof
+    pub proof: String,
+}
+
+#[derive(Clone)]
+pub struct JsonRpcRequestProcessor {
+    header_store: HeaderStore,
+}
+
+impl Metadata for JsonRpcRequestProcessor {}
+
+impl JsonRpcRequestProcessor {
+    pub fn new(header_store: HeaderStore) -> Self {
+        Self { header_store }
+    }
+
+    fn get_slot_header(&self, slot: Slot) -> Result<Option<RpcSlotHeader>> {
+        Ok(self
+            .header_store
+            .get(slot)
+            .map_err(internal_error)?
+            .map(RpcSlotHeader::from))
+    }
+
+    fn get_latest_slot_header(&self) -> Result<Option<RpcSlotHeader>> {
+        Ok(self
+            .header_store
+            .tip()
+            .map_err(internal_error)?
+            .map(RpcSlotHeader::from))
+    }
+
+    /// Whether `account_proof` proves the value of `pubkey` at `slot`. The
+    /// state root of the proof must be the one of the verified header of the
+    /// slot.
+    fn verify_account_proof(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+        account_proof: &RpcAccountProof,
+    ) -> Result<bool> {
+        let signed_header = self
+            .header_store
+            .get(slot)
+            .map_err(internal_error)?
+            .ok_or_else(|| {
+                Error::invalid_params(format!("No verified header for slot {}", slot))
+            })?;
+        let state_root = parse_hash("state root", &account_proof.state_root)?;
+        if state_root != signed_header.header.state_root {
+            debug!(
+                "The state root {} of the proof does not match the header of slot {}",
+                state_root, slot
+            );
+            return Ok(false);
+        }
+        let value_hash = parse_hash("value hash", &account_proof.value_hash)?;
+        let proof = base64::decode(&account_proof.proof)
+            .map_err(|err| Error::invalid_params(format!("Invalid proof: {}", err)))?;
+        Ok(verify_account_proof(
+            pubkey,
+            &value_hash,
+            &proof,
+            &state_root,
+        ))
+    }
+}
+
+fn internal_error(err: crate::LightNodeError) -> Error {
+    error!("Error reading the header store: {}", err);
+    Error::internal_error()
+}
+
+fn parse_hash(name: &str, hash: &str) -> Result<Hash> {
+    Hash::from_str(hash).map_err(|err| Error::invalid_params(format!("Invalid {}: {}", name, err)))
+}
+
+fn verify_pubkey(input: &str) -> Result<Pubkey> {
+    input
+        .parse()
+        .map_err(|err| Error::invalid_params(format!("Invalid param: {:?}", err)))
+}
+
+pub mod rpc_slot_headers {
+    use super::*;
+    #[rpc]
+    pub trait SlotHeaders {
+        type Metadata;
+
+        #[rpc(meta, name = "getSlotHeader")]
+        fn get_slot_header(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> Result<Option<RpcSlotHeader>>;
+
+        #[rpc(meta, name = "getLatestSlotHeader")]
+        fn get_latest_slot_header(&self, meta: Self::Metadata) -> Result<Option<RpcSlotHeader>>;
+
+        #[rpc(meta, name = "verifyAccountProof")]
+        fn verify_account_proof(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            slot: Slot,
+            account_proof: RpcAccountProof,
+        ) -> Result<bool>;
+    }
+
+    pub struct SlotHeadersImpl;
+    impl SlotHeaders for SlotHeadersImpl {
+        type Metadata = JsonRpcRequestProcessor;
+
+        fn get_slot_header(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> Result<Option<RpcSlotHeader>> {
+            debug!("get_slot_header rpc request received: {:?}", slot);
+            meta.get_slot_header(slot)
+        }
+
+        fn get_latest_slot_header(&self, meta: Self::Metadata) -> Result<Option<RpcSlotHeader>> {
+            debug!("get_latest_slot_header rpc request received");
+            meta.get_latest_slot_header()
+        }
+
+        fn verify_account_proof(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            slot: Slot,
+            account_proof: RpcAccountProof,
+        ) -> Result<bool> {
+            debug!(
+                "verify_account_proof rpc request received: {:?} {:?}",
+                pubkey_str, slot
+            );
+            let pubkey = verify_pubkey(&pubkey_str)?;
+            meta.verify_account_proof(&pubkey, slot, &account_proof)
+        }
+    }
+}
+
+/// Start serving the headers of `header_store` at `rpc_addr`. The service
+/// stops when the returned server is closed or dropped.
+pub fn start_rpc_service(
+    rpc_addr: &SocketAddr,
+    header_store: HeaderStore,
+) -> LightNodeResult<Server> {
+    use rpc_slot_headers::*;
+    let mut io = MetaIoHandler::default();
+    io.extend_with(SlotHeadersImpl.to_delegate());
+    let request_processor = JsonRpcRequestProcessor::new(header_store);
+    let server =
+        ServerBuilder::with_meta_extractor(io, move |_req: &hyper::Request<hyper::Body>| {
+            request_processor.clone()
+        })
+        .threads(1)
+        .start_http(rpc_addr)?;
+    info!("Light node JSON RPC service listening on {}", rpc_addr);
+    Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{rpc_slot_headers::*, *},
+        crate::tests::signed_header,
+        jsonrpc_core::{Output, Response},
+        solana_runtime::state_tree::{leaf_key, root_to_hash, StateTree},
+        solana_sdk::signature::Keypair,
+        sparse_merkle_tree::H256,
+        tempfile::TempDir,
+    };
+
+    fn rpc_call(
+        io: &MetaIoHandler<JsonRpcRequestProcessor>,
+        meta: &JsonRpcRequestProcessor,
+        request: &str,
+    ) -> serde_json::Value {
+        let response = io.handle_request_sync(request, meta.clone()).unwrap();
+        match serde_json::from_str(&response).unwrap() {
+            Response::Single(Output::Success(success)) => success.result,
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn test_rpc_verify_account_proof() {
+        let pubkey = Pubkey::new_unique();
+        let value = H256::from([1; 32]);
+        let state_tree = StateTree::new_from_leaves(0, vec![(leaf_key(&pubkey), value)]).unwrap();
+        let state_root = state_tree.rooted_root();
+        let (_, proof) = state_tree
+            .merkle_proof(0, state_root, vec![], vec![leaf_key(&pubkey)])
+            .unwrap()
+            .unwrap();
+
+        let store_dir = TempDir::new().unwrap();
+        let header_store = HeaderStore::open(store_dir.path()).unwrap();
+        let sequencer = Keypair::new();
+        let mut signed_header = signed_header(&sequencer, 2, 1);
+        signed_header.header.state_root = state_root;
+        signed_header.signature = signed_header.header.sign(&sequencer);
+        header_store.insert(&signed_header).unwrap();
+
+        let mut io = MetaIoHandler::default();
+        io.extend_with(SlotHeadersImpl.to_delegate());
+        let meta = JsonRpcRequestProcessor::new(header_store);
+
+        let result = rpc_call(
+            &io,
+            &meta,
+            r#"{"jsonrpc":"2.0","id":1,"method":"getLatestSlotHeader"}"#,
+        );
+        let rpc_header: RpcSlotHeader = serde_json::from_value(result).unwrap();
+        assert_eq!(rpc_header, RpcSlotHeader::from(signed_header));
+
+        let verify_request = |slot: Slot, state_root: &Hash, value_hash: &Hash| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"verifyAccountProof","params":["{}",{},{{"stateRoot":"{}","valueHash":"{}","proof":"{}"}}]}}"#,
+                pubkey,
+                slot,
+                state_root,
+                value_hash,
+                base64::encode(&proof.0)
+            )
+        };
+        let value_hash = root_to_hash(&value);
+        let result = rpc_call(&io, &meta, &verify_request(2, &state_root, &value_hash));
+        assert_eq!(result, serde_json::json!(true));
+        let result = rpc_call(
+            &io,
+            &meta,
+            &verify_request(2, &state_root, &Hash::default()),
+        );
+        assert_eq!(result, serde_json::json!(false));
+        // The proof must be against the state root of the header
+        let result = rpc_call(
+            &io,
+            &meta,
+            &verify_request(2, &Hash::default(), &value_hash),
+        );
+        assert_eq!(result, serde_json::json!(false));
+
+        let response = io
+            .handle_request_sync(&verify_request(3, &state_root, &value_hash), meta)
+            .unwrap();
+        assert!(response.contains("No verified header for slot 3"));
+    }
+}
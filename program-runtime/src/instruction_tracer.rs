@@ -0,0 +1,66 @@
+//! Records what the instruction trace of a `TransactionContext` leaves out:
+//! the compute units, the result and the accounts of each instruction an
+//! `InvokeContext` processes.
+use {
+    solana_sdk::{
+        account::AccountSharedData,
+        instruction::InstructionError,
+        transaction_context::{InstructionTrace, TransactionContext},
+    },
+    std::{cell::RefCell, collections::HashMap, rc::Rc},
+};
+
+/// The position of an instruction in the `InstructionTrace`: the index of its
+/// top-level instruction, and its index among the instructions of that one.
+pub type IndexInTrace = (usize, usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstructionRecord {
+    /// The compute units of the instruction and the instructions it invoked
+    pub compute_units_consumed: u64,
+    pub result: Result<(), InstructionError>,
+    /// The accounts of the instruction by index in the transaction, without
+    /// duplicates, before it was processed
+    pub pre_accounts: Vec<(usize, AccountSharedData)>,
+    /// The same accounts after the instruction was processed
+    pub post_accounts: Vec<(usize, AccountSharedData)>,
+}
+
+#[derive(Debug, Default)]
+pub struct InstructionTracer {
+    records: HashMap<IndexInTrace, InstructionRecord>,
+    instruction_trace: InstructionTrace,
+}
+
+impl InstructionTracer {
+    pub fn new_ref() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    /// The position of the instruction last pushed onto `transaction_context`
+    pub fn last_index_in_trace(transaction_context: &TransactionContext) -> Option<IndexInTrace> {
+        let instruction_trace = transaction_context.get_instruction_trace();
+        let top_level_index = instruction_trace.len().checked_sub(1)?;
+        let index = instruction_trace.last()?.len().checked_sub(1)?;
+        Some((top_level_index, index))
+    }
+
+    pub fn record(&mut self, index_in_trace: IndexInTrace, record: InstructionRecord) {
+        self.records.insert(index_in_trace, record);
+    }
+
+    /// The record of an instruction, `None` for the instructions which were
+    /// not processed by a program, like precompiles
+    pub fn get_record(&self, index_in_trace: IndexInTrace) -> Option<&InstructionRecord> {
+        self.records.get(&index_in_trace)
+    }
+
+    /// Keep the instruction trace of the transaction once it was processed
+    pub fn set_instruction_trace(&mut self, instruction_trace: InstructionTrace) {
+        self.instruction_trace = instruction_trace;
+    }
+
+    pub fn get_instruction_trace(&self) -> &InstructionTrace {
+        &self.instruction_trace
+    }
+}
@@ -6,6 +6,7 @@ use {
         compute_budget::ComputeBudget,
         executor_cache::{Executor, Executors, TransactionExecutor},
         ic_logger_msg, ic_msg,
+        instruction_tracer::{InstructionRecord, InstructionTracer},
         log_collector::LogCollector,
         pre_account::PreAccount,
         stable_log,
@@ -153,6 +154,7 @@ pub struct InvokeContext<'a> {
     builtin_programs: &'a [BuiltinProgram],
     pub sysvar_cache: Cow<'a, SysvarCache>,
     log_collector: Option<Rc<RefCell<LogCollector>>>,
+    instruction_tracer: Option<Rc<RefCell<InstructionTracer>>>,
     compute_budget: ComputeBudget,
     current_compute_budget: ComputeBudget,
     compute_meter: Rc<RefCell<ComputeMeter>>,
@@ -173,6 +175,7 @@ impl<'a> InvokeContext<'a> {
         builtin_programs: &'a [BuiltinProgram],
         sysvar_cache: Cow<'a, SysvarCache>,
         log_collector: Option<Rc<RefCell<LogCollector>>>,
+        instruction_tracer: Option<Rc<RefCell<InstructionTracer>>>,
         compute_budget: ComputeBudget,
         executors: Rc<RefCell<Executors>>,
         feature_set: Arc<FeatureSet>,
@@ -188,6 +191,7 @@ impl<'a> InvokeContext<'a> {
             builtin_programs,
             sysvar_cache,
             log_collector,
+            instruction_tracer,
             current_compute_budget: compute_budget,
             compute_budget,
             compute_meter: ComputeMeter::new_ref(compute_budget.compute_unit_limit),
@@ -229,6 +233,7 @@ impl<'a> InvokeContext<'a> {
             builtin_programs,
             Cow::Owned(sysvar_cache),
             Some(LogCollector::new_ref()),
+            None,
             ComputeBudget::default(),
             Rc::new(RefCell::new(Executors::default())),
             Arc::new(FeatureSet::all_enabled()),
@@ -762,8 +767,14 @@ impl<'a> InvokeContext<'a> {
             verify_caller_result?;
         }
 
+        let pre_accounts = self
+            .instruction_tracer
+            .as_ref()
+            .map(|_| self.get_instruction_accounts_for_tracer(instruction_accounts));
         self.push(instruction_accounts, program_indices, instruction_data)?;
-        self.process_executable_chain(compute_units_consumed, timings)
+        let index_in_trace = InstructionTracer::last_index_in_trace(self.transaction_context);
+        let result = self
+            .process_executable_chain(compute_units_consumed, timings)
             .and_then(|_| {
                 if self
                     .feature_set
@@ -791,7 +802,47 @@ impl<'a> InvokeContext<'a> {
             })
             // MUST pop if and only if `push` succeeded, independent of `result`.
             // Thus, the `.and()` instead of an `.and_then()`.
-            .and(self.pop())
+            .and(self.pop());
+
+        if let (Some(instruction_tracer), Some(pre_accounts), Some(index_in_trace)) =
+            (&self.instruction_tracer, pre_accounts, index_in_trace)
+        {
+            let post_accounts = self.get_instruction_accounts_for_tracer(instruction_accounts);
+            instruction_tracer.borrow_mut().record(
+                index_in_trace,
+                InstructionRecord {
+                    compute_units_consumed: *compute_units_consumed,
+                    result: result.clone(),
+                    pre_accounts,
+                    post_accounts,
+                },
+            );
+        }
+        result
+    }
+
+    /// The current state of the accounts of an instruction, without duplicates
+    fn get_instruction_accounts_for_tracer(
+        &self,
+        instruction_accounts: &[InstructionAccount],
+    ) -> Vec<(usize, AccountSharedData)> {
+        instruction_accounts
+            .iter()
+            .enumerate()
+            .filter(|(index_in_instruction, instruction_account)| {
+                *index_in_instruction == instruction_account.index_in_callee
+            })
+            .filter_map(|(_, instruction_account)| {
+                let account = self
+                    .transaction_context
+                    .get_account_at_index(instruction_account.index_in_transaction)
+                    .ok()?
+                    .try_borrow()
+                    .ok()?
+                    .clone();
+                Some((instruction_account.index_in_transaction, account))
+            })
+            .collect()
     }
 
     /// Calls the instruction's program entrypoint method
@@ -873,6 +924,11 @@ impl<'a> InvokeContext<'a> {
         self.log_collector.clone()
     }
 
+    /// Get this invocation's InstructionTracer
+    pub fn get_instruction_tracer(&self) -> Option<Rc<RefCell<InstructionTracer>>> {
+        self.instruction_tracer.clone()
+    }
+
     /// Get this invocation's ComputeMeter
     pub fn get_compute_meter(&self) -> Rc<RefCell<ComputeMeter>> {
         self.compute_meter.clone()
@@ -12,6 +12,7 @@ extern crate solana_metrics;
 pub mod accounts_data_meter;
 pub mod compute_budget;
 pub mod executor_cache;
+pub mod instruction_tracer;
 pub mod invoke_context;
 pub mod log_collector;
 pub mod pre_account;
@@ -1,19 +1,14 @@
 use {
-    clap::{value_t_or_exit, App, Arg, SubCommand},
-    solana_client::rpc_config::RpcContextConfig,
-    solana_client::{connection_cache::ConnectionCache, rpc_cache::LargestAccountsCache},
-    solana_gossip::{
-        cluster_info::ClusterInfo,
-        contact_info::ContactInfo,
-        crds::GossipRoute,
-        crds_value::{CrdsData, CrdsValue, SnapshotHashes},
-    },
+    clap::{value_t, value_t_or_exit, App, Arg},
+    solana_client::connection_cache::ConnectionCache,
+    solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
     solana_ledger::{
         bank_forks_utils,
-        blockstore::{Blockstore, BlockstoreSignals},
+        blockstore::Blockstore,
         blockstore_options::{AccessType, BlockstoreOptions},
-        blockstore_processor::{process_blockstore_from_root, ProcessOptions},
-        genesis_utils::{create_genesis_config, GenesisConfigInfo},
+        blockstore_processor::{
+            process_blockstore_from_root, BlockstoreProcessorError, ProcessOptions,
+        },
         leader_schedule_cache::LeaderScheduleCache,
     },
     solana_rpc::{
@@ -23,34 +18,32 @@ use {
         rpc_service::JsonRpcService,
     },
     solana_runtime::{
-        accounts_background_service::AbsRequestSender,
+        accounts_background_service::{
+            AbsRequestHandler, AbsRequestSender, AccountsBackgroundService,
+        },
         bank::Bank,
         bank_forks::BankForks,
-        commitment::BlockCommitmentCache,
+        commitment::{BlockCommitmentCache, VOTE_THRESHOLD_SIZE},
         hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
-        snapshot_archive_info::SnapshotArchiveInfoGetter,
         snapshot_config::SnapshotConfig,
         snapshot_utils,
     },
     solana_sdk::{
-        clock::Slot,
-        genesis_config::{ClusterType, DEFAULT_GENESIS_ARCHIVE},
+        clock::{BankId, Slot},
         hash::Hash,
-        signature::Signer,
         signer::keypair::Keypair,
     },
-    solana_send_transaction_service::send_transaction_service::{self, SendTransactionService},
+    solana_send_transaction_service::send_transaction_service,
     solana_streamer::socket::SocketAddrSpace,
     std::{
-        collections::HashSet,
         fs,
-        net::{IpAddr, Ipv4Addr, SocketAddr},
-        path::{Path, PathBuf},
+        net::{IpAddr, SocketAddr},
+        path::PathBuf,
         sync::{
             atomic::{AtomicBool, AtomicU64, Ordering},
             Arc, RwLock,
         },
-        thread::{self, Builder, JoinHandle},
+        thread,
         time::{Duration, Instant},
     },
 };
@@ -69,152 +62,361 @@ fn main() {
                 .default_value("ledger")
                 .help("Use DIR as ledger location"),
         )
+        .arg(
+            Arg::with_name("rpc_bind_address")
+                .long("rpc-bind-address")
+                .value_name("HOST")
+                .takes_value(true)
+                .default_value("127.0.0.1")
+                .help("IP address to bind the RPC port"),
+        )
+        .arg(
+            Arg::with_name("rpc_port")
+                .long("rpc-port")
+                .value_name("PORT")
+                .takes_value(true)
+                .default_value("9988")
+                .help("Port for the RPC service"),
+        )
+        .arg(
+            Arg::with_name("snapshots")
+                .long("snapshots")
+                .value_name("DIR")
+                .takes_value(true)
+                .help(
+                    "Use DIR as the full snapshot archives location \
+                     [default: --ledger value]",
+                ),
+        )
+        .arg(
+            Arg::with_name("incremental_snapshot_archive_path")
+                .long("incremental-snapshot-archive-path")
+                .value_name("DIR")
+                .takes_value(true)
+                .help(
+                    "Use DIR as the incremental snapshot archives location \
+                     [default: --snapshots value]",
+                ),
+        )
+        .arg(
+            Arg::with_name("accounts")
+                .long("accounts")
+                .value_name("DIR")
+                .takes_value(true)
+                .help(
+                    "Use DIR as the accounts location, which is cleared on start \
+                     [default: --ledger value/srpc/accounts]",
+                ),
+        )
+        .arg(
+            Arg::with_name("poll_interval_ms")
+                .long("poll-interval-ms")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .default_value("1000")
+                .help("How often to catch up with the ledger and replay its new slots"),
+        )
         .get_matches();
 
     let ledger_path = value_t_or_exit!(matches, "ledger_path", PathBuf);
-    run_rpc_with_elapse(&ledger_path, 5);
-}
+    let rpc_addr = SocketAddr::new(
+        value_t_or_exit!(matches, "rpc_bind_address", IpAddr),
+        value_t_or_exit!(matches, "rpc_port", u16),
+    );
+    let full_snapshot_archives_dir =
+        value_t!(matches, "snapshots", PathBuf).unwrap_or_else(|_| ledger_path.clone());
+    let incremental_snapshot_archives_dir =
+        value_t!(matches, "incremental_snapshot_archive_path", PathBuf)
+            .unwrap_or_else(|_| full_snapshot_archives_dir.clone());
+    let accounts_path = value_t!(matches, "accounts", PathBuf)
+        .unwrap_or_else(|_| ledger_path.join("srpc").join("accounts"));
+    let poll_interval = Duration::from_millis(value_t_or_exit!(matches, "poll_interval_ms", u64));
+
+    // The bank snapshots of the loaded archive are kept apart from the ones of
+    // the validator writing the ledger.
+    let snapshot_config = SnapshotConfig {
+        full_snapshot_archive_interval_slots:
+            snapshot_utils::DEFAULT_FULL_SNAPSHOT_ARCHIVE_INTERVAL_SLOTS,
+        incremental_snapshot_archive_interval_slots:
+            snapshot_utils::DEFAULT_INCREMENTAL_SNAPSHOT_ARCHIVE_INTERVAL_SLOTS,
+        bank_snapshots_dir: ledger_path.join("srpc").join("snapshot"),
+        full_snapshot_archives_dir,
+        incremental_snapshot_archives_dir,
+        ..SnapshotConfig::default()
+    };
+    for dir in [&accounts_path, &snapshot_config.bank_snapshots_dir] {
+        let _ = fs::remove_dir_all(dir);
+        if let Err(err) = fs::create_dir_all(dir) {
+            eprintln!("Unable to create {}: {}", dir.display(), err);
+            std::process::exit(1);
+        }
+    }
 
-fn run_rpc_with_elapse(ledger_path: &Path, secs: u64) {
-    let full_snapshot_archives_dir = ledger_path.join("srpc").to_path_buf();
-    fs::remove_dir_all(full_snapshot_archives_dir.as_path());
-    fs::create_dir(full_snapshot_archives_dir.as_path());
-
-    let full_snapshot_archive_info =
-        snapshot_utils::get_highest_full_snapshot_archive_info(&ledger_path);
-    
-    let full_snapshot_archive_infos = snapshot_utils::get_full_snapshot_archives(&ledger_path);
-    full_snapshot_archive_infos.iter().for_each(|full_snapshot_archive_info| {
-        let file_name = full_snapshot_archive_info.path().file_name().unwrap();
-        let dst_path = full_snapshot_archives_dir.as_path().join(file_name);
-        fs::copy(full_snapshot_archive_info.path(), dst_path);
-    });
-    
-    loop {
+    let start = Instant::now();
+    let follower = LedgerFollower::new(ledger_path.clone(), accounts_path, &snapshot_config);
+    let exit = Arc::new(AtomicBool::new(false));
+    let cluster_info = Arc::new(ClusterInfo::new(
+        ContactInfo::default(),
+        Arc::new(Keypair::new()),
+        SocketAddrSpace::Unspecified,
+    ));
+    let mut rpc_service = JsonRpcService::new(
+        rpc_addr,
+        JsonRpcConfig {
+            enable_rpc_transaction_history: true,
+            full_api: true,
+            ..JsonRpcConfig::default()
+        },
+        Some(snapshot_config),
+        follower.bank_forks.clone(),
+        follower.block_commitment_cache.clone(),
+        follower.blockstore.clone(),
+        cluster_info,
+        None,
+        follower.genesis_hash,
+        &ledger_path,
+        create_validator_exit(&exit),
+        None,
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(AtomicBool::new(true)),
+        follower.optimistically_confirmed_bank.clone(),
+        send_transaction_service::Config {
+            retry_rate_ms: 1000,
+            leader_forward_count: 1,
+            ..send_transaction_service::Config::default()
+        },
+        follower.max_slots.clone(),
+        follower.leader_schedule_cache.clone(),
+        Arc::new(ConnectionCache::default()),
+        Arc::new(AtomicU64::default()),
+    );
+    println!(
+        "rpc: {}, root: {}, it costs {:?} to start.",
+        rpc_addr,
+        follower.root(),
+        start.elapsed()
+    );
+
+    while !exit.load(Ordering::Relaxed) {
+        thread::sleep(poll_interval);
         let start = Instant::now();
-        
+        let root = follower.root();
+        match follower.catch_up() {
+            Ok(slot) if slot > root => println!(
+                "replayed slots {}..={}, root: {}, it costs {:?}.",
+                root + 1,
+                slot,
+                follower.root(),
+                start.elapsed()
+            ),
+            Ok(_) => {}
+            Err(err) => eprintln!("Failed to replay the slots after {}: {}", root, err),
+        }
+    }
+    rpc_service.exit();
+    rpc_service.join().unwrap();
+}
+
+/// The banks replayed from a ledger another process writes, kept warm so each
+/// catch up only replays the slots after the root. The banks are only rooted at
+/// the roots the writing process recorded in the ledger, and the processed and
+/// confirmed commitment levels follow the replayed banks and their votes.
+struct LedgerFollower {
+    blockstore: Arc<Blockstore>,
+    genesis_hash: Hash,
+    bank_forks: Arc<RwLock<BankForks>>,
+    leader_schedule_cache: Arc<LeaderScheduleCache>,
+    block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+    optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
+    max_slots: Arc<MaxSlots>,
+    process_options: ProcessOptions,
+    exit: Arc<AtomicBool>,
+    accounts_background_service: Option<AccountsBackgroundService>,
+}
 
+impl LedgerFollower {
+    /// Load the banks from the highest snapshot archive, then replay the
+    /// slots of the ledger after it.
+    fn new(ledger_path: PathBuf, accounts_path: PathBuf, snapshot_config: &SnapshotConfig) -> Self {
         let genesis_config = open_genesis_config(&ledger_path, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE);
-        let blockstore = Arc::new(
-            Blockstore::open_with_options(
-                &ledger_path,
-                BlockstoreOptions {
-                    access_type: AccessType::Secondary,
-                    ..BlockstoreOptions::default()
-                },
-            )
-            .unwrap(),
-        );
-        let non_primary_accounts_path = blockstore.ledger_path().join("accounts");
-        let account_paths = vec![non_primary_accounts_path];
-        let process_options = ProcessOptions::default();
-        let snapshot_config = SnapshotConfig {
-            full_snapshot_archive_interval_slots:
-                snapshot_utils::DEFAULT_FULL_SNAPSHOT_ARCHIVE_INTERVAL_SLOTS,
-            incremental_snapshot_archive_interval_slots:
-                snapshot_utils::DEFAULT_INCREMENTAL_SNAPSHOT_ARCHIVE_INTERVAL_SLOTS,
-            bank_snapshots_dir: ledger_path.join("snapshot"),
-            full_snapshot_archives_dir: full_snapshot_archives_dir.clone(),
-            incremental_snapshot_archives_dir: full_snapshot_archives_dir.clone(),
-            ..SnapshotConfig::default()
+        let blockstore = Blockstore::open_with_options(
+            &ledger_path,
+            BlockstoreOptions {
+                access_type: AccessType::Secondary,
+                ..BlockstoreOptions::default()
+            },
+        )
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to open the ledger {}: {}",
+                ledger_path.display(),
+                err
+            );
+            std::process::exit(1);
+        });
+        let process_options = ProcessOptions {
+            accounts_db_caching_enabled: true,
+            ..ProcessOptions::default()
         };
-
         let (bank_forks, leader_schedule_cache, ..) = bank_forks_utils::load_bank_forks(
             &genesis_config,
             &blockstore,
-            account_paths,
+            vec![accounts_path],
             None,
-            Some(&snapshot_config),
+            Some(snapshot_config),
             &process_options,
             None,
             None,
         );
-        process_blockstore_from_root(
-            &blockstore,
-            &bank_forks,
-            &leader_schedule_cache,
-            &process_options,
-            None,
-            None,
-            &AbsRequestSender::default(),
-        )
-        .unwrap();
-
-        let exit = Arc::new(AtomicBool::new(false));
-        let validator_exit = create_validator_exit(&exit);
-        let cluster_info = Arc::new(ClusterInfo::new(
-            ContactInfo::default(),
-            Arc::new(Keypair::new()),
-            SocketAddrSpace::Unspecified,
-        ));
-        let ip_addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
-        let rpc_addr = SocketAddr::new(ip_addr, 9988);
 
+        let root_bank = bank_forks.read().unwrap().root_bank();
         let mut block_commitment_cache = BlockCommitmentCache::default();
-        let bank_forks_guard = bank_forks.read().unwrap();
-        block_commitment_cache.set_all_slots(
-            bank_forks_guard.working_bank().slot(),
-            bank_forks_guard.root(),
-        );
-        let block_commitment_cache = Arc::new(RwLock::new(block_commitment_cache));
-
-        let optimistically_confirmed_bank =
-            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
-        let connection_cache = Arc::new(ConnectionCache::default());
-        let mut rpc_service = JsonRpcService::new(
-            rpc_addr,
-            JsonRpcConfig {
-                enable_rpc_transaction_history: true,
-                full_api: true,
-                ..JsonRpcConfig::default()
-            },
-            Some(snapshot_config),
+        block_commitment_cache.set_all_slots(root_bank.slot(), root_bank.slot());
+        let exit = Arc::new(AtomicBool::new(false));
+        let pruned_banks_receiver =
+            AccountsBackgroundService::setup_bank_drop_callback(bank_forks.clone());
+        let accounts_background_service = AccountsBackgroundService::new(
             bank_forks.clone(),
-            block_commitment_cache,
-            blockstore,
-            cluster_info,
-            None,
-            genesis_config.hash(),
-            &ledger_path,
-            validator_exit,
-            None,
-            Arc::new(AtomicBool::new(false)),
-            Arc::new(AtomicBool::new(true)),
-            optimistically_confirmed_bank,
-            send_transaction_service::Config {
-                retry_rate_ms: 1000,
-                leader_forward_count: 1,
-                ..send_transaction_service::Config::default()
+            &exit,
+            AbsRequestHandler {
+                snapshot_request_handler: None,
+                pruned_banks_receiver,
             },
-            Arc::new(MaxSlots::default()),
-            Arc::new(LeaderScheduleCache::default()),
-            connection_cache,
-            Arc::new(AtomicU64::default()),
+            process_options.accounts_db_caching_enabled,
+            false,
+            None,
         );
+        let follower = Self {
+            blockstore: Arc::new(blockstore),
+            genesis_hash: genesis_config.hash(),
+            optimistically_confirmed_bank: OptimisticallyConfirmedBank::locked_from_bank_forks_root(
+                &bank_forks,
+            ),
+            bank_forks,
+            leader_schedule_cache: Arc::new(leader_schedule_cache),
+            block_commitment_cache: Arc::new(RwLock::new(block_commitment_cache)),
+            max_slots: Arc::new(MaxSlots::default()),
+            process_options,
+            exit,
+            accounts_background_service: Some(accounts_background_service),
+        };
+        if let Err(err) = follower.replay_new_slots() {
+            eprintln!("Failed to replay the ledger: {}", err);
+            std::process::exit(1);
+        }
+        follower
+    }
 
-        println!(
-            "rpc: {}, slot: {}, root: {}, it costs {:?} to start.",
-            rpc_addr,
-            bank_forks_guard.working_bank().slot(),
-            bank_forks_guard.root(),
-            start.elapsed()
+    fn root(&self) -> Slot {
+        self.bank_forks.read().unwrap().root()
+    }
+
+    /// Catch up with the process writing the ledger and replay the slots it
+    /// completed since. Returns the new processed slot.
+    fn catch_up(&self) -> Result<Slot, BlockstoreProcessorError> {
+        self.blockstore.try_catch_up_with_primary()?;
+        self.replay_new_slots()
+    }
+
+    /// Replay the slots after the root. The replay starts from the root bank
+    /// alone, so the banks replayed after it by the previous catch up are
+    /// removed first, with their accounts, and the commitment levels reset to
+    /// the root meanwhile. The replay roots the slots the writing process
+    /// rooted, then the highest replayed bank is processed and the highest slot
+    /// a supermajority voted for in it is confirmed. Returns the processed slot.
+    fn replay_new_slots(&self) -> Result<Slot, BlockstoreProcessorError> {
+        let root_bank = self.bank_forks.read().unwrap().root_bank();
+        let root = root_bank.slot();
+        self.block_commitment_cache
+            .write()
+            .unwrap()
+            .set_all_slots(root, root);
+        self.optimistically_confirmed_bank.write().unwrap().bank = root_bank.clone();
+        let (slots_to_purge, removed_banks): (Vec<(Slot, BankId)>, Vec<Arc<Bank>>) = {
+            let mut bank_forks = self.bank_forks.write().unwrap();
+            let slots: Vec<Slot> = bank_forks
+                .banks()
+                .into_keys()
+                .filter(|slot| *slot != root)
+                .collect();
+            slots
+                .into_iter()
+                .filter_map(|slot| bank_forks.remove(slot))
+                .map(|bank| ((bank.slot(), bank.bank_id()), bank))
+                .unzip()
+        };
+        root_bank.remove_unrooted_slots(&slots_to_purge);
+        drop(removed_banks);
+
+        let result = process_blockstore_from_root(
+            &self.blockstore,
+            &self.bank_forks,
+            &self.leader_schedule_cache,
+            &self.process_options,
+            None,
+            None,
+            &AbsRequestSender::default(),
         );
 
-        let full_snapshot_archives_dir = ledger_path.join("srpc").to_path_buf();
-        fs::remove_dir_all(full_snapshot_archives_dir.as_path());
-        fs::create_dir(full_snapshot_archives_dir.as_path());
-
-        let full_snapshot_archive_info =
-            snapshot_utils::get_highest_full_snapshot_archive_info(&ledger_path);
-        
-        let full_snapshot_archive_infos = snapshot_utils::get_full_snapshot_archives(&ledger_path);
-        full_snapshot_archive_infos.iter().for_each(|full_snapshot_archive_info| {
-            let file_name = full_snapshot_archive_info.path().file_name().unwrap();
-            let dst_path = full_snapshot_archives_dir.as_path().join(file_name);
-            fs::copy(full_snapshot_archive_info.path(), dst_path);
-        });
+        // Follow the banks replayed before a later slot failed too
+        let bank_forks = self.bank_forks.read().unwrap();
+        let root = bank_forks.root();
+        let processed_bank = bank_forks
+            .frozen_banks()
+            .into_values()
+            .max_by_key(|bank| bank.slot())
+            .unwrap_or_else(|| bank_forks.root_bank());
+        let confirmed_bank = supermajority_voted_slot(&processed_bank)
+            .filter(|slot| *slot >= root)
+            .and_then(|slot| bank_forks.get(slot))
+            .unwrap_or_else(|| bank_forks.root_bank());
+        drop(bank_forks);
+        {
+            let mut block_commitment_cache = self.block_commitment_cache.write().unwrap();
+            block_commitment_cache.set_all_slots(processed_bank.slot(), root);
+            block_commitment_cache.set_highest_confirmed_slot(confirmed_bank.slot());
+        }
+        self.optimistically_confirmed_bank.write().unwrap().bank = confirmed_bank;
+
+        if let Ok(Some(highest_slot)) = self.blockstore.highest_slot() {
+            self.max_slots
+                .shred_insert
+                .store(highest_slot, Ordering::Relaxed);
+        }
+        result.map(|()| processed_bank.slot())
+    }
+}
+
+/// The highest slot a supermajority of the stake voted for in `bank`, which
+/// is confirmed on the fork of `bank`.
+fn supermajority_voted_slot(bank: &Bank) -> Option<Slot> {
+    let mut voted_slots: Vec<(Slot, u64)> = bank
+        .vote_accounts()
+        .values()
+        .filter(|(stake, _)| *stake > 0)
+        .filter_map(|(stake, account)| {
+            let last_voted_slot = account.vote_state().as_ref().ok()?.last_voted_slot()?;
+            Some((last_voted_slot, *stake))
+        })
+        .collect();
+    // A vote for a slot is a vote for its ancestors too
+    voted_slots.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+    let total_stake = bank.total_epoch_stake();
+    let mut voted_stake = 0;
+    for (slot, stake) in voted_slots {
+        voted_stake += stake;
+        if voted_stake as f64 / total_stake as f64 > VOTE_THRESHOLD_SIZE {
+            return Some(slot);
+        }
+    }
+    None
+}
 
-        std::thread::sleep(std::time::Duration::from_secs(secs));
-        rpc_service.exit();
+impl Drop for LedgerFollower {
+    fn drop(&mut self) {
+        self.exit.store(true, Ordering::Relaxed);
+        if let Some(accounts_background_service) = self.accounts_background_service.take() {
+            accounts_background_service.join().unwrap();
+        }
     }
 }
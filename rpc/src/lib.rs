@@ -1,8 +1,10 @@
 #![allow(clippy::integer_arithmetic)]
 mod cluster_tpu_info;
+pub mod long_term_storage;
 pub mod max_slots;
 pub mod optimistically_confirmed_bank_tracker;
 pub mod parsed_token_accounts;
+mod rebuilt_banks;
 pub mod rpc;
 pub mod rpc_completed_slots_service;
 pub mod rpc_health;
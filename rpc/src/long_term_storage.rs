@@ -0,0 +1,118 @@
+//! The long-term storage the RPC falls back to for the blocks and transactions
+//! the blockstore no longer has: a BigTable instance, or the PostgreSQL
+//! database of the geyser plugin.
+use {
+    solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
+    solana_transaction_status::{
+        ConfirmedBlock, ConfirmedTransactionStatusWithSignature,
+        ConfirmedTransactionWithStatusMeta, TransactionStatus,
+    },
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum LongTermStorageError {
+    #[error(transparent)]
+    BigTable(#[from] solana_storage_bigtable::Error),
+
+    #[error(transparent)]
+    Postgres(#[from] solana_storage_postgres::Error),
+}
+
+impl LongTermStorageError {
+    /// The slot of the block which was not found, if that is the error.
+    pub fn block_not_found(&self) -> Option<Slot> {
+        match self {
+            Self::BigTable(solana_storage_bigtable::Error::BlockNotFound(slot))
+            | Self::Postgres(solana_storage_postgres::Error::BlockNotFound(slot)) => Some(*slot),
+            _ => None,
+        }
+    }
+
+    pub fn is_signature_not_found(&self) -> bool {
+        matches!(
+            self,
+            Self::BigTable(solana_storage_bigtable::Error::SignatureNotFound)
+                | Self::Postgres(solana_storage_postgres::Error::SignatureNotFound)
+        )
+    }
+}
+
+pub type Result<T> = std::result::Result<T, LongTermStorageError>;
+
+#[derive(Clone)]
+pub enum LongTermStorage {
+    BigTable(solana_storage_bigtable::LedgerStorage),
+    Postgres(solana_storage_postgres::LedgerStorage),
+}
+
+impl LongTermStorage {
+    pub async fn get_first_available_block(&self) -> Result<Option<Slot>> {
+        Ok(match self {
+            Self::BigTable(storage) => storage.get_first_available_block().await?,
+            Self::Postgres(storage) => storage.get_first_available_block().await?,
+        })
+    }
+
+    pub async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
+        Ok(match self {
+            Self::BigTable(storage) => storage.get_confirmed_blocks(start_slot, limit).await?,
+            Self::Postgres(storage) => storage.get_confirmed_blocks(start_slot, limit).await?,
+        })
+    }
+
+    pub async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
+        Ok(match self {
+            Self::BigTable(storage) => storage.get_confirmed_block(slot).await?,
+            Self::Postgres(storage) => storage.get_confirmed_block(slot).await?,
+        })
+    }
+
+    pub async fn get_signature_status(&self, signature: &Signature) -> Result<TransactionStatus> {
+        Ok(match self {
+            Self::BigTable(storage) => storage.get_signature_status(signature).await?,
+            Self::Postgres(storage) => storage.get_signature_status(signature).await?,
+        })
+    }
+
+    pub async fn get_confirmed_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>> {
+        Ok(match self {
+            Self::BigTable(storage) => storage.get_confirmed_transaction(signature).await?,
+            Self::Postgres(storage) => storage.get_confirmed_transaction(signature).await?,
+        })
+    }
+
+    pub async fn get_confirmed_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before_signature: Option<&Signature>,
+        until_signature: Option<&Signature>,
+        limit: usize,
+    ) -> Result<Vec<(ConfirmedTransactionStatusWithSignature, u32)>> {
+        Ok(match self {
+            Self::BigTable(storage) => {
+                storage
+                    .get_confirmed_signatures_for_address(
+                        address,
+                        before_signature,
+                        until_signature,
+                        limit,
+                    )
+                    .await?
+            }
+            Self::Postgres(storage) => {
+                storage
+                    .get_confirmed_signatures_for_address(
+                        address,
+                        before_signature,
+                        until_signature,
+                        limit,
+                    )
+                    .await?
+            }
+        })
+    }
+}
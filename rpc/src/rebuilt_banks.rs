@@ -0,0 +1,279 @@
+//! Rebuild of the banks of a block whose parent bank has been pruned from the
+//! bank forks, to trace the transactions of rooted blocks. The parent bank is
+//! loaded from the highest snapshot archives at or below its slot, then the
+//! blocks of the ledger after them are replayed up to it. The banks have an
+//! accounts db of their own, in a directory of the ledger removed once they are
+//! dropped, so rebuilding them changes nothing in the state of the node.
+use {
+    solana_ledger::{
+        blockstore::{Blockstore, BlockstoreError},
+        blockstore_options::{
+            AccessType, BlockstoreOptions, LedgerColumnOptions, ShredStorageType,
+            DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES,
+        },
+        blockstore_processor::{
+            process_blockstore_from_root, BlockstoreProcessorError, ProcessOptions,
+        },
+        leader_schedule_cache::LeaderScheduleCache,
+    },
+    solana_runtime::{
+        accounts_background_service::AbsRequestSender,
+        accounts_db::AccountShrinkThreshold,
+        accounts_index::AccountSecondaryIndexes,
+        bank::Bank,
+        bank_forks::BankForks,
+        snapshot_archive_info::SnapshotArchiveInfoGetter,
+        snapshot_config::SnapshotConfig,
+        snapshot_utils::{self, SnapshotError},
+    },
+    solana_sdk::{clock::Slot, genesis_config::GenesisConfig},
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+        sync::{Arc, RwLock},
+    },
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum RebuildBanksError {
+    #[error("no snapshot archive at or below slot {0}")]
+    NoSnapshotArchive(Slot),
+
+    #[error("slot {0} could not be replayed")]
+    SlotNotReplayed(Slot),
+
+    #[error("failed to load the genesis config: {0}")]
+    GenesisConfig(io::Error),
+
+    #[error("rebuild directory error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Blockstore(#[from] BlockstoreError),
+
+    #[error(transparent)]
+    BlockstoreProcessor(#[from] BlockstoreProcessorError),
+
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+}
+
+/// The rebuilt bank of the parent of a block, and the bank of the block built
+/// on it
+pub struct RebuiltBanks {
+    pub parent: Arc<Bank>,
+    pub bank: Arc<Bank>,
+    // Declared last so that it is removed after the banks are dropped
+    _rebuild_dir: RebuildDir,
+}
+
+/// Rebuild the bank of `parent_slot`, and the bank of its child `slot` on top
+/// of it. This unpacks a snapshot and replays the ledger after it, so it takes
+/// as long as a node restarting from that snapshot.
+pub fn rebuild_banks(
+    blockstore: &Blockstore,
+    snapshot_config: &SnapshotConfig,
+    parent_slot: Slot,
+    slot: Slot,
+) -> Result<RebuiltBanks, RebuildBanksError> {
+    let full_snapshot_archive_info =
+        snapshot_utils::get_full_snapshot_archives(&snapshot_config.full_snapshot_archives_dir)
+            .into_iter()
+            .filter(|archive_info| archive_info.slot() <= parent_slot)
+            .max_by_key(|archive_info| archive_info.slot())
+            .ok_or(RebuildBanksError::NoSnapshotArchive(parent_slot))?;
+    let incremental_snapshot_archive_info = snapshot_utils::get_incremental_snapshot_archives(
+        &snapshot_config.incremental_snapshot_archives_dir,
+    )
+    .into_iter()
+    .filter(|archive_info| {
+        archive_info.base_slot() == full_snapshot_archive_info.slot()
+            && archive_info.slot() <= parent_slot
+    })
+    .max_by_key(|archive_info| archive_info.slot());
+
+    let ledger_path = blockstore.ledger_path();
+    let genesis_config =
+        GenesisConfig::load(ledger_path).map_err(RebuildBanksError::GenesisConfig)?;
+    let rebuild_dir = RebuildDir::new(ledger_path)?;
+    let (root_bank, _) = snapshot_utils::bank_from_snapshot_archives(
+        &[rebuild_dir.accounts_path()],
+        rebuild_dir.bank_snapshots_path(),
+        &full_snapshot_archive_info,
+        incremental_snapshot_archive_info.as_ref(),
+        &genesis_config,
+        None,
+        None,
+        AccountSecondaryIndexes::default(),
+        true,
+        None,
+        AccountShrinkThreshold::default(),
+        false,
+        true,
+        false,
+        None,
+        None,
+        None,
+    )?;
+
+    // Replaying from the ledger of the node with primary access would mark the
+    // replayed slots as rooted or dead in it, so it is read as a secondary
+    let secondary_blockstore;
+    let blockstore = if blockstore.is_primary_access() {
+        secondary_blockstore = Blockstore::open_with_options(
+            ledger_path,
+            BlockstoreOptions {
+                access_type: AccessType::Secondary,
+                column_options: LedgerColumnOptions {
+                    shred_storage_type: ShredStorageType::from_ledger_path(
+                        ledger_path,
+                        DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES,
+                    )
+                    .unwrap_or_default(),
+                    ..LedgerColumnOptions::default()
+                },
+                ..BlockstoreOptions::default()
+            },
+        )?;
+        &secondary_blockstore
+    } else {
+        blockstore
+    };
+
+    let bank_forks = Arc::new(RwLock::new(BankForks::new(root_bank)));
+    let leader_schedule_cache =
+        LeaderScheduleCache::new_from_bank(&bank_forks.read().unwrap().root_bank());
+    let process_options = ProcessOptions {
+        accounts_db_caching_enabled: true,
+        halt_at_slot: Some(parent_slot),
+        poh_verify: false,
+        ..ProcessOptions::default()
+    };
+    process_blockstore_from_root(
+        blockstore,
+        &bank_forks,
+        &leader_schedule_cache,
+        &process_options,
+        None,
+        None,
+        &AbsRequestSender::default(),
+    )?;
+    let parent = bank_forks
+        .read()
+        .unwrap()
+        .get(parent_slot)
+        .filter(|bank| bank.is_frozen())
+        .ok_or(RebuildBanksError::SlotNotReplayed(parent_slot))?;
+
+    let leader = leader_schedule_cache
+        .slot_leader_at(slot, Some(&parent))
+        .unwrap_or_default();
+    let bank = Arc::new(Bank::new_from_parent(&parent, &leader, slot));
+    Ok(RebuiltBanks {
+        parent,
+        bank,
+        _rebuild_dir: rebuild_dir,
+    })
+}
+
+/// The directory the accounts of the rebuilt banks are stored in
+struct RebuildDir {
+    path: PathBuf,
+}
+
+impl RebuildDir {
+    const REBUILD_DIR: &'static str = "trace.rebuild";
+
+    /// Create the directory under `ledger_path`, dropping the one left there by
+    /// a rebuild which did not finish
+    fn new(ledger_path: &Path) -> io::Result<Self> {
+        let path = ledger_path.join(Self::REBUILD_DIR);
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        let rebuild_dir = Self { path };
+        fs::create_dir_all(rebuild_dir.accounts_path())?;
+        fs::create_dir_all(rebuild_dir.bank_snapshots_path())?;
+        Ok(rebuild_dir)
+    }
+
+    fn accounts_path(&self) -> PathBuf {
+        self.path.join("accounts")
+    }
+
+    fn bank_snapshots_path(&self) -> PathBuf {
+        self.path.join("snapshot")
+    }
+}
+
+impl Drop for RebuildDir {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.path) {
+            warn!("Failed to remove {:?}: {}", self.path, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_ledger::{create_new_tmp_ledger_auto_delete, genesis_utils::create_genesis_config},
+        solana_runtime::snapshot_utils::ArchiveFormat,
+        solana_sdk::{hash::Hash, signature::Signer},
+    };
+
+    #[test]
+    fn test_rebuild_banks() {
+        let genesis_config_info = create_genesis_config(10_000);
+        let (ledger_path, _) =
+            create_new_tmp_ledger_auto_delete!(&genesis_config_info.genesis_config);
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let snapshot_config = SnapshotConfig {
+            full_snapshot_archives_dir: ledger_path.path().join("snapshots"),
+            incremental_snapshot_archives_dir: ledger_path.path().join("snapshots"),
+            bank_snapshots_dir: ledger_path.path().join("bank_snapshots"),
+            ..SnapshotConfig::default()
+        };
+
+        // Without a snapshot archive, nothing can be rebuilt
+        assert!(matches!(
+            rebuild_banks(&blockstore, &snapshot_config, 0, 1),
+            Err(RebuildBanksError::NoSnapshotArchive(0))
+        ));
+
+        let bank0 = Bank::new_for_tests(&genesis_config_info.genesis_config);
+        while !bank0.is_complete() {
+            bank0.register_tick(&Hash::new_unique());
+        }
+        fs::create_dir_all(&snapshot_config.bank_snapshots_dir).unwrap();
+        snapshot_utils::bank_to_full_snapshot_archive(
+            &snapshot_config.bank_snapshots_dir,
+            &bank0,
+            None,
+            &snapshot_config.full_snapshot_archives_dir,
+            &snapshot_config.incremental_snapshot_archives_dir,
+            ArchiveFormat::Tar,
+            1,
+            1,
+        )
+        .unwrap();
+
+        let rebuilt_banks = rebuild_banks(&blockstore, &snapshot_config, 0, 1).unwrap();
+        let mint_pubkey = genesis_config_info.mint_keypair.pubkey();
+        assert_eq!(rebuilt_banks.parent.slot(), 0);
+        assert_eq!(
+            rebuilt_banks.parent.get_balance(&mint_pubkey),
+            bank0.get_balance(&mint_pubkey)
+        );
+        assert_eq!(rebuilt_banks.bank.parent_slot(), 0);
+        assert_eq!(rebuilt_banks.bank.clock().slot, 1);
+
+        // The accounts of the rebuilt banks are removed with them
+        let rebuild_dir = ledger_path.path().join(RebuildDir::REBUILD_DIR);
+        assert!(rebuild_dir.exists());
+        drop(rebuilt_banks);
+        assert!(!rebuild_dir.exists());
+    }
+}
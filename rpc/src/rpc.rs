@@ -2,8 +2,12 @@
 
 use {
     crate::{
-        max_slots::MaxSlots, optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
-        parsed_token_accounts::*, rpc_health::*,
+        long_term_storage::{LongTermStorage, LongTermStorageError},
+        max_slots::MaxSlots,
+        optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
+        parsed_token_accounts::*,
+        rebuilt_banks::rebuild_banks,
+        rpc_health::*,
     },
     bincode::{config::Options, serialize},
     crossbeam_channel::{unbounded, Receiver, Sender},
@@ -22,7 +26,7 @@ use {
         rpc_deprecated_config::*,
         rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
         rpc_request::{
-            TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE,
+            TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE, MAX_GET_ACCOUNT_HISTORY_LIMIT,
             MAX_GET_CONFIRMED_BLOCKS_RANGE, MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
             MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE, MAX_GET_PROGRAM_ACCOUNT_FILTERS,
             MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS,
@@ -44,7 +48,10 @@ use {
     solana_runtime::{
         accounts::AccountAddressFilter,
         accounts_index::{AccountIndex, AccountSecondaryIndexes, IndexKey, ScanConfig},
-        bank::{Bank, TransactionSimulationResult},
+        bank::{
+            Bank, TracedAccount, TracedInstruction, TransactionSimulationResult,
+            TransactionTraceResult,
+        },
         bank_forks::BankForks,
         commitment::{BlockCommitmentArray, BlockCommitmentCache, CommitmentSlots},
         inline_spl_token::{SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
@@ -81,13 +88,13 @@ use {
         tpu_info::NullTpuInfo,
     },
     solana_stake_program,
-    solana_storage_bigtable::Error as StorageError,
+    solana_storage_postgres::AccountVersion,
     solana_streamer::socket::SocketAddrSpace,
     solana_transaction_status::{
         BlockEncodingOptions, ConfirmedBlock, ConfirmedTransactionStatusWithSignature,
-        ConfirmedTransactionWithStatusMeta, EncodedConfirmedTransactionWithStatusMeta, Reward,
-        RewardType, TransactionBinaryEncoding, TransactionConfirmationStatus, TransactionStatus,
-        UiConfirmedBlock, UiTransactionEncoding,
+        ConfirmedTransactionWithStatusMeta, Encodable, EncodedConfirmedTransactionWithStatusMeta,
+        Reward, RewardType, TransactionBinaryEncoding, TransactionConfirmationStatus,
+        TransactionStatus, UiConfirmedBlock, UiTransactionEncoding,
     },
     solana_vote_program::vote_state::{VoteState, MAX_LOCKOUT_HISTORY},
     spl_token_2022::{
@@ -153,6 +160,7 @@ pub struct JsonRpcConfig {
     pub faucet_addr: Option<SocketAddr>,
     pub health_check_slot_distance: u64,
     pub rpc_bigtable_config: Option<RpcBigtableConfig>,
+    pub rpc_postgres_config: Option<RpcPostgresConfig>,
     pub max_multiple_accounts: Option<usize>,
     pub account_indexes: AccountSecondaryIndexes,
     pub rpc_threads: usize,
@@ -192,6 +200,14 @@ impl Default for RpcBigtableConfig {
     }
 }
 
+/// Serve the history the blockstore no longer has from the PostgreSQL
+/// database of the geyser plugin, instead of BigTable.
+#[derive(Debug, Default, Clone)]
+pub struct RpcPostgresConfig {
+    /// e.g. "host=localhost user=solana password=solana port=5432"
+    pub connection_str: String,
+}
+
 #[derive(Clone)]
 pub struct JsonRpcRequestProcessor {
     bank_forks: Arc<RwLock<BankForks>>,
@@ -205,12 +221,13 @@ pub struct JsonRpcRequestProcessor {
     cluster_info: Arc<ClusterInfo>,
     genesis_hash: Hash,
     transaction_sender: Arc<Mutex<Sender<TransactionInfo>>>,
-    bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
+    long_term_storage: Option<LongTermStorage>,
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
     max_slots: Arc<MaxSlots>,
     leader_schedule_cache: Arc<LeaderScheduleCache>,
     max_complete_transaction_status_slot: Arc<AtomicU64>,
+    rebuild_banks_lock: Arc<Mutex<()>>,
 }
 impl Metadata for JsonRpcRequestProcessor {}
 
@@ -310,7 +327,7 @@ impl JsonRpcRequestProcessor {
         health: Arc<RpcHealth>,
         cluster_info: Arc<ClusterInfo>,
         genesis_hash: Hash,
-        bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
+        long_term_storage: Option<LongTermStorage>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
         max_slots: Arc<MaxSlots>,
@@ -330,12 +347,13 @@ impl JsonRpcRequestProcessor {
                 cluster_info,
                 genesis_hash,
                 transaction_sender: Arc::new(Mutex::new(sender)),
-                bigtable_ledger_storage,
+                long_term_storage,
                 optimistically_confirmed_bank,
                 largest_accounts_cache,
                 max_slots,
                 leader_schedule_cache,
                 max_complete_transaction_status_slot,
+                rebuild_banks_lock: Arc::new(Mutex::new(())),
             },
             receiver,
         )
@@ -392,7 +410,7 @@ impl JsonRpcRequestProcessor {
             cluster_info,
             genesis_hash,
             transaction_sender: Arc::new(Mutex::new(sender)),
-            bigtable_ledger_storage: None,
+            long_term_storage: None,
             optimistically_confirmed_bank: Arc::new(RwLock::new(OptimisticallyConfirmedBank {
                 bank: bank.clone(),
             })),
@@ -400,6 +418,7 @@ impl JsonRpcRequestProcessor {
             max_slots: Arc::new(MaxSlots::default()),
             leader_schedule_cache: Arc::new(LeaderScheduleCache::new_from_bank(bank)),
             max_complete_transaction_status_slot: Arc::new(AtomicU64::default()),
+            rebuild_banks_lock: Arc::new(Mutex::new(())),
         }
     }
 
@@ -425,6 +444,41 @@ impl JsonRpcRequestProcessor {
         Ok(new_response(&bank, response))
     }
 
+    pub fn get_account_proof(
+        &self,
+        pubkey: &Pubkey,
+        slot: Option<Slot>,
+    ) -> Result<RpcResponse<RpcAccountProof>> {
+        let bank = match slot {
+            Some(slot) => self
+                .bank_forks
+                .read()
+                .unwrap()
+                .get(slot)
+                .ok_or(RpcCustomError::BlockNotAvailable { slot })?,
+            None => self.bank(None),
+        };
+        let (value_hash, proof) = bank
+            .get_account_proof(pubkey)
+            .map_err(|err| {
+                warn!(
+                    "get_account_proof failed for slot {}: {:?}",
+                    bank.slot(),
+                    err
+                );
+                Error::internal_error()
+            })?
+            .ok_or(RpcCustomError::BlockNotAvailable { slot: bank.slot() })?;
+        Ok(new_response(
+            &bank,
+            RpcAccountProof {
+                state_root: bank.state_root().to_string(),
+                value_hash: value_hash.to_string(),
+                proof: base64::encode(proof),
+            },
+        ))
+    }
+
     pub fn get_multiple_accounts(
         &self,
         pubkeys: Vec<Pubkey>,
@@ -530,7 +584,7 @@ impl JsonRpcRequestProcessor {
         // Rewards for this epoch are found in the first confirmed block of the next epoch
         let first_slot_in_epoch = epoch_schedule.get_first_slot_in_epoch(epoch.saturating_add(1));
         if first_slot_in_epoch < first_available_block {
-            if self.bigtable_ledger_storage.is_some() {
+            if self.long_term_storage.is_some() {
                 return Err(RpcCustomError::LongTermStorageSlotSkipped {
                     slot: first_slot_in_epoch,
                 }
@@ -742,6 +796,16 @@ impl JsonRpcRequestProcessor {
         Ok(bank.block_height())
     }
 
+    fn get_state_root(&self, config: RpcContextConfig) -> Result<RpcResponse<String>> {
+        let bank = self.get_bank_with_config(config)?;
+        // The state root is only known once the bank is frozen
+        let bank = match bank.parent() {
+            Some(parent) if !bank.is_frozen() => parent,
+            _ => bank,
+        };
+        Ok(new_response(&bank, bank.state_root().to_string()))
+    }
+
     fn get_max_retransmit_slot(&self) -> Slot {
         self.max_slots.retransmit.load(Ordering::Relaxed)
     }
@@ -1044,12 +1108,12 @@ impl JsonRpcRequestProcessor {
         Ok(())
     }
 
-    fn check_bigtable_result<T>(
+    fn check_long_term_storage_result<T>(
         &self,
-        result: &std::result::Result<T, solana_storage_bigtable::Error>,
+        result: &std::result::Result<T, LongTermStorageError>,
     ) -> Result<()> {
-        if let Err(solana_storage_bigtable::Error::BlockNotFound(slot)) = result {
-            return Err(RpcCustomError::LongTermStorageSlotSkipped { slot: *slot }.into());
+        if let Some(slot) = result.as_ref().err().and_then(|err| err.block_not_found()) {
+            return Err(RpcCustomError::LongTermStorageSlotSkipped { slot }.into());
         }
         Ok(())
     }
@@ -1106,11 +1170,10 @@ impl JsonRpcRequestProcessor {
                     Ok(encoded_block)
                 };
                 if result.is_err() {
-                    if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                        let bigtable_result =
-                            bigtable_ledger_storage.get_confirmed_block(slot).await;
-                        self.check_bigtable_result(&bigtable_result)?;
-                        return bigtable_result.ok().map(encode_block).transpose();
+                    if let Some(long_term_storage) = &self.long_term_storage {
+                        let storage_result = long_term_storage.get_confirmed_block(slot).await;
+                        self.check_long_term_storage_result(&storage_result)?;
+                        return storage_result.ok().map(encode_block).transpose();
                     }
                 }
                 self.check_slot_cleaned_up(&result, slot)?;
@@ -1157,6 +1220,47 @@ impl JsonRpcRequestProcessor {
         Err(RpcCustomError::BlockNotAvailable { slot }.into())
     }
 
+    pub fn get_slot_entries(
+        &self,
+        slot: Slot,
+        config: Option<RpcSlotEntriesConfig>,
+    ) -> Result<RpcSlotEntries> {
+        let config = config.unwrap_or_default();
+        let commitment = config.commitment.unwrap_or_default();
+        check_is_at_least_confirmed(commitment)?;
+
+        // Only serve the slots of the finalized chain, or of the confirmed
+        // chain if requested, never the ones of dead or abandoned forks
+        let is_rooted = slot
+            <= self
+                .block_commitment_cache
+                .read()
+                .unwrap()
+                .highest_confirmed_root()
+            && self.blockstore.is_root(slot);
+        let is_confirmed = || {
+            commitment.is_confirmed()
+                && self
+                    .bank(Some(CommitmentConfig::confirmed()))
+                    .status_cache_ancestors()
+                    .contains(&slot)
+        };
+        if !(is_rooted || is_confirmed()) {
+            return Err(RpcCustomError::BlockNotAvailable { slot }.into());
+        }
+        let result = get_slot_entries(
+            &self.blockstore,
+            slot,
+            config.encoding.unwrap_or(UiTransactionEncoding::Base64),
+            config.include_shreds.unwrap_or_default(),
+        );
+        self.check_slot_cleaned_up(&result, slot)?;
+        result
+            .ok()
+            .flatten()
+            .ok_or_else(|| RpcCustomError::BlockNotAvailable { slot }.into())
+    }
+
     pub async fn get_blocks(
         &self,
         start_slot: Slot,
@@ -1196,19 +1300,19 @@ impl JsonRpcRequestProcessor {
             .unwrap_or_default();
         if start_slot < lowest_blockstore_slot {
             // If the starting slot is lower than what's available in blockstore assume the entire
-            // [start_slot..end_slot] can be fetched from BigTable. This range should not ever run
+            // [start_slot..end_slot] can be fetched from long-term storage. This range should not ever run
             // into unfinalized confirmed blocks due to MAX_GET_CONFIRMED_BLOCKS_RANGE
-            if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                return bigtable_ledger_storage
+            if let Some(long_term_storage) = &self.long_term_storage {
+                return long_term_storage
                     .get_confirmed_blocks(start_slot, (end_slot - start_slot) as usize + 1) // increment limit by 1 to ensure returned range is inclusive of both start_slot and end_slot
                     .await
-                    .map(|mut bigtable_blocks| {
-                        bigtable_blocks.retain(|&slot| slot <= end_slot);
-                        bigtable_blocks
+                    .map(|mut storage_blocks| {
+                        storage_blocks.retain(|&slot| slot <= end_slot);
+                        storage_blocks
                     })
                     .map_err(|_| {
                         Error::invalid_params(
-                            "BigTable query failed (maybe timeout due to too large range?)"
+                            "Long-term storage query failed (maybe timeout due to too large range?)"
                                 .to_string(),
                         )
                     });
@@ -1264,10 +1368,10 @@ impl JsonRpcRequestProcessor {
 
         if start_slot < lowest_blockstore_slot {
             // If the starting slot is lower than what's available in blockstore assume the entire
-            // range can be fetched from BigTable. This range should not ever run into unfinalized
+            // range can be fetched from long-term storage. This range should not ever run into unfinalized
             // confirmed blocks due to MAX_GET_CONFIRMED_BLOCKS_RANGE
-            if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                return Ok(bigtable_ledger_storage
+            if let Some(long_term_storage) = &self.long_term_storage {
+                return Ok(long_term_storage
                     .get_confirmed_blocks(start_slot, limit)
                     .await
                     .unwrap_or_default());
@@ -1322,10 +1426,10 @@ impl JsonRpcRequestProcessor {
             let result = self.blockstore.get_block_time(slot);
             self.check_blockstore_root(&result, slot)?;
             if result.is_err() || matches!(result, Ok(None)) {
-                if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                    let bigtable_result = bigtable_ledger_storage.get_confirmed_block(slot).await;
-                    self.check_bigtable_result(&bigtable_result)?;
-                    return Ok(bigtable_result
+                if let Some(long_term_storage) = &self.long_term_storage {
+                    let storage_result = long_term_storage.get_confirmed_block(slot).await;
+                    self.check_long_term_storage_result(&storage_result)?;
+                    return Ok(storage_result
                         .ok()
                         .and_then(|confirmed_block| confirmed_block.block_time));
                 }
@@ -1415,8 +1519,8 @@ impl JsonRpcRequestProcessor {
                     })
                 {
                     Some(status)
-                } else if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                    bigtable_ledger_storage
+                } else if let Some(long_term_storage) = &self.long_term_storage {
+                    long_term_storage
                         .get_signature_status(&signature)
                         .await
                         .map(Some)
@@ -1524,8 +1628,8 @@ impl JsonRpcRequestProcessor {
                     }
                 }
                 None => {
-                    if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                        return bigtable_ledger_storage
+                    if let Some(long_term_storage) = &self.long_term_storage {
+                        return long_term_storage
                             .get_confirmed_transaction(&signature)
                             .await
                             .unwrap_or(None)
@@ -1540,6 +1644,115 @@ impl JsonRpcRequestProcessor {
         Ok(None)
     }
 
+    pub fn trace_transaction(
+        &self,
+        signature: Signature,
+        config: Option<RpcTraceTransactionConfig>,
+    ) -> Result<Option<RpcTraceTransactionResult>> {
+        let commitment = config
+            .and_then(|config| config.commitment)
+            .unwrap_or_else(CommitmentConfig::confirmed);
+        check_is_at_least_confirmed(commitment)?;
+        if !self.config.enable_rpc_transaction_history {
+            return Err(RpcCustomError::TransactionHistoryNotAvailable.into());
+        }
+
+        let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
+        let confirmed_transaction = if commitment.is_confirmed() {
+            self.blockstore
+                .get_complete_transaction(signature, confirmed_bank.slot())
+        } else {
+            self.blockstore.get_rooted_transaction(signature)
+        };
+        let slot = match confirmed_transaction.unwrap_or(None) {
+            Some(confirmed_transaction) => confirmed_transaction.slot,
+            None => return Ok(None),
+        };
+        let is_confirmed = (commitment.is_confirmed()
+            && confirmed_bank.status_cache_ancestors().contains(&slot))
+            || slot
+                <= self
+                    .block_commitment_cache
+                    .read()
+                    .unwrap()
+                    .highest_confirmed_root();
+        if !is_confirmed {
+            return Ok(None);
+        }
+
+        let block = self
+            .blockstore
+            .get_complete_block(slot, false)
+            .map_err(|_| RpcCustomError::BlockNotAvailable { slot })?;
+        let epoch_schedule = confirmed_bank.epoch_schedule();
+        if epoch_schedule.get_epoch(block.parent_slot) != epoch_schedule.get_epoch(slot) {
+            return Err(RpcCustomError::EpochBoundaryTraceNotSupported { slot }.into());
+        }
+        // The transaction is re-executed against the bank its block was built on, with the
+        // sysvars of the bank of the block. Once these banks have been pruned from the bank
+        // forks, as happens when the block is rooted, they are rebuilt from a snapshot archive.
+        let banks = {
+            let bank_forks = self.bank_forks.read().unwrap();
+            bank_forks
+                .get(block.parent_slot)
+                .filter(|bank| bank.is_frozen())
+                .zip(bank_forks.get(slot))
+        };
+        // Declared first so that the rebuilt banks are dropped last, with their accounts
+        let rebuilt_banks;
+        let (parent_bank, bank) = match banks {
+            Some(banks) => banks,
+            None => {
+                let snapshot_config = self
+                    .snapshot_config
+                    .as_ref()
+                    .ok_or(RpcCustomError::ParentBankNotAvailable { slot })?;
+                // Each rebuild takes as long as loading a snapshot, and they share a directory
+                let _rebuild_banks_lock = self.rebuild_banks_lock.lock().unwrap();
+                rebuilt_banks =
+                    rebuild_banks(&self.blockstore, snapshot_config, block.parent_slot, slot)
+                        .map_err(|err| {
+                            warn!(
+                                "Failed to rebuild the bank of slot {}: {}",
+                                block.parent_slot, err
+                            );
+                            RpcCustomError::ParentBankNotAvailable { slot }
+                        })?;
+                (rebuilt_banks.parent.clone(), rebuilt_banks.bank.clone())
+            }
+        };
+        let index = match block.transactions.iter().position(|tx_with_meta| {
+            tx_with_meta.transaction.signatures.first() == Some(&signature)
+        }) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let mut transactions = block
+            .transactions
+            .into_iter()
+            .take(index.saturating_add(1))
+            .map(|tx_with_meta| sanitize_transaction(tx_with_meta.transaction, &*parent_bank))
+            .collect::<Result<Vec<_>>>()?;
+        let transaction = transactions.pop().unwrap();
+
+        let TransactionTraceResult {
+            result,
+            logs,
+            units_consumed,
+            return_data,
+            instructions,
+        } = parent_bank.trace_transaction(&bank, &transactions, transaction);
+
+        Ok(Some(RpcTraceTransactionResult {
+            slot,
+            err: result.err(),
+            logs,
+            units_consumed,
+            return_data: return_data.map(|return_data| return_data.into()),
+            instructions: nest_traced_instructions(instructions),
+        }))
+    }
+
     pub fn get_confirmed_signatures_for_address(
         &self,
         pubkey: Pubkey,
@@ -1547,7 +1760,7 @@ impl JsonRpcRequestProcessor {
         end_slot: Slot,
     ) -> Vec<Signature> {
         if self.config.enable_rpc_transaction_history {
-            // TODO: Add bigtable_ledger_storage support as a part of
+            // TODO: Add long_term_storage support as a part of
             // https://github.com/solana-labs/solana/pull/10928
             let end_slot = min(
                 end_slot,
@@ -1627,23 +1840,23 @@ impl JsonRpcRequestProcessor {
             };
 
             if results.len() < limit {
-                if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                    let mut bigtable_before = before;
+                if let Some(long_term_storage) = &self.long_term_storage {
+                    let mut storage_before = before;
                     if !results.is_empty() {
                         limit -= results.len();
-                        bigtable_before = results.last().map(|x| x.signature);
+                        storage_before = results.last().map(|x| x.signature);
                     }
 
                     // If the oldest address-signature found in Blockstore has not yet been
                     // uploaded to long-term storage, modify the storage query to return all latest
                     // signatures to prevent erroring on RowNotFound. This can race with upload.
-                    if found_before && bigtable_before.is_some() {
-                        match bigtable_ledger_storage
-                            .get_signature_status(&bigtable_before.unwrap())
+                    if found_before && storage_before.is_some() {
+                        match long_term_storage
+                            .get_signature_status(&storage_before.unwrap())
                             .await
                         {
-                            Err(StorageError::SignatureNotFound) => {
-                                bigtable_before = None;
+                            Err(err) if err.is_signature_not_found() => {
+                                storage_before = None;
                             }
                             Err(err) => {
                                 warn!("{:?}", err);
@@ -1653,26 +1866,26 @@ impl JsonRpcRequestProcessor {
                         }
                     }
 
-                    let bigtable_results = bigtable_ledger_storage
+                    let storage_results = long_term_storage
                         .get_confirmed_signatures_for_address(
                             &address,
-                            bigtable_before.as_ref(),
+                            storage_before.as_ref(),
                             until.as_ref(),
                             limit,
                         )
                         .await;
-                    match bigtable_results {
-                        Ok(bigtable_results) => {
+                    match storage_results {
+                        Ok(storage_results) => {
                             let results_set: HashSet<_> =
                                 results.iter().map(|result| result.signature).collect();
-                            for (bigtable_result, _) in bigtable_results {
+                            for (storage_result, _) in storage_results {
                                 // In the upload race condition, latest address-signatures in
                                 // long-term storage may include original `before` signature...
-                                if before != Some(bigtable_result.signature)
+                                if before != Some(storage_result.signature)
                                     // ...or earlier Blockstore signatures
-                                    && !results_set.contains(&bigtable_result.signature)
+                                    && !results_set.contains(&storage_result.signature)
                                 {
-                                    results.push(bigtable_result);
+                                    results.push(storage_result);
                                 }
                             }
                         }
@@ -1695,20 +1908,94 @@ impl JsonRpcRequestProcessor {
             .get_first_available_block()
             .unwrap_or_default();
 
-        if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-            let bigtable_slot = bigtable_ledger_storage
+        if let Some(long_term_storage) = &self.long_term_storage {
+            let storage_slot = long_term_storage
                 .get_first_available_block()
                 .await
                 .unwrap_or(None)
                 .unwrap_or(slot);
 
-            if bigtable_slot < slot {
-                return bigtable_slot;
+            if storage_slot < slot {
+                return storage_slot;
             }
         }
         slot
     }
 
+    /// The long-term storage which keeps the past versions of accounts
+    fn account_history_storage(&self) -> Result<&solana_storage_postgres::LedgerStorage> {
+        match &self.long_term_storage {
+            Some(LongTermStorage::Postgres(storage)) => Ok(storage),
+            _ => Err(RpcCustomError::AccountHistoryNotAvailable.into()),
+        }
+    }
+
+    fn encode_account_version(
+        &self,
+        pubkey: &Pubkey,
+        version: AccountVersion,
+        encoding: UiAccountEncoding,
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<RpcAccountVersion> {
+        // Token amounts are parsed with the decimals of the mint in the current bank
+        let account = encode_bank_account(
+            &self.bank(None),
+            pubkey,
+            AccountSharedData::from(version.account),
+            encoding,
+            data_slice,
+        )?;
+        Ok(RpcAccountVersion {
+            slot: version.slot,
+            write_version: version.write_version,
+            txn_signature: version.txn_signature.map(|signature| signature.to_string()),
+            account,
+        })
+    }
+
+    pub async fn get_account_info_at_slot(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+        config: Option<RpcAccountAtSlotConfig>,
+    ) -> Result<Option<RpcAccountVersion>> {
+        let RpcAccountAtSlotConfig {
+            encoding,
+            data_slice,
+        } = config.unwrap_or_default();
+        let encoding = encoding.unwrap_or(UiAccountEncoding::Binary);
+        check_slice_and_encoding(&encoding, data_slice.is_some())?;
+
+        self.account_history_storage()?
+            .get_account_at_slot(pubkey, slot)
+            .await
+            .map_err(account_history_error)?
+            // Like a bank, a closed account is not found
+            .filter(|version| version.account.lamports != 0)
+            .map(|version| self.encode_account_version(pubkey, version, encoding, data_slice))
+            .transpose()
+    }
+
+    pub async fn get_account_history(
+        &self,
+        pubkey: &Pubkey,
+        before: Option<Slot>,
+        until: Option<Slot>,
+        limit: usize,
+        encoding: UiAccountEncoding,
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<RpcAccountVersion>> {
+        check_slice_and_encoding(&encoding, data_slice.is_some())?;
+
+        self.account_history_storage()?
+            .get_account_history(pubkey, before, until, limit)
+            .await
+            .map_err(account_history_error)?
+            .into_iter()
+            .map(|version| self.encode_account_version(pubkey, version, encoding, data_slice))
+            .collect()
+    }
+
     pub fn get_stake_activation(
         &self,
         pubkey: &Pubkey,
@@ -2264,6 +2551,70 @@ fn verify_and_parse_signatures_for_address_params(
     Ok((address, before, until, limit))
 }
 
+fn account_history_error(err: solana_storage_postgres::Error) -> Error {
+    Error::invalid_params(format!("Account history query failed: {}", err))
+}
+
+fn verify_and_parse_account_history_params(
+    pubkey: String,
+    limit: Option<usize>,
+) -> Result<(Pubkey, usize)> {
+    let pubkey = verify_pubkey(&pubkey)?;
+    let limit = limit.unwrap_or(MAX_GET_ACCOUNT_HISTORY_LIMIT);
+
+    if limit == 0 || limit > MAX_GET_ACCOUNT_HISTORY_LIMIT {
+        return Err(Error::invalid_params(format!(
+            "Invalid limit; max {}",
+            MAX_GET_ACCOUNT_HISTORY_LIMIT
+        )));
+    }
+    Ok((pubkey, limit))
+}
+
+/// The entries of `slot` with their transactions in `encoding`, and the data
+/// shreds they were made of if `include_shreds`, or `None` until the slot is
+/// complete in the blockstore and for dead slots.
+pub(crate) fn get_slot_entries(
+    blockstore: &Blockstore,
+    slot: Slot,
+    encoding: UiTransactionEncoding,
+    include_shreds: bool,
+) -> std::result::Result<Option<RpcSlotEntries>, BlockstoreError> {
+    let slot_meta = match blockstore.meta(slot)? {
+        Some(slot_meta) if slot_meta.is_full() && !blockstore.is_dead(slot) => slot_meta,
+        _ => return Ok(None),
+    };
+    let (entries, num_shreds, _) = blockstore.get_slot_entries_with_shred_info(slot, 0, false)?;
+    let shreds = if include_shreds {
+        Some(
+            blockstore
+                .slot_data_iterator(slot, 0)?
+                .map(|(_, payload)| base64::encode(payload))
+                .collect(),
+        )
+    } else {
+        None
+    };
+    Ok(Some(RpcSlotEntries {
+        slot,
+        parent_slot: slot_meta.parent_slot,
+        num_shreds,
+        entries: entries
+            .iter()
+            .map(|entry| RpcEntry {
+                num_hashes: entry.num_hashes,
+                hash: entry.hash.to_string(),
+                transactions: entry
+                    .transactions
+                    .iter()
+                    .map(|transaction| transaction.encode(encoding))
+                    .collect(),
+            })
+            .collect(),
+        shreds,
+    }))
+}
+
 pub(crate) fn check_is_at_least_confirmed(commitment: CommitmentConfig) -> Result<()> {
     if !commitment.is_at_least_confirmed() {
         return Err(Error::invalid_params(
@@ -2302,18 +2653,23 @@ fn get_encoded_account(
     encoding: UiAccountEncoding,
     data_slice: Option<UiDataSliceConfig>,
 ) -> Result<Option<UiAccount>> {
-    match bank.get_account(pubkey) {
-        Some(account) => {
-            let response = if is_known_spl_token_id(account.owner())
-                && encoding == UiAccountEncoding::JsonParsed
-            {
-                get_parsed_token_account(bank.clone(), pubkey, account)
-            } else {
-                encode_account(&account, pubkey, encoding, data_slice)?
-            };
-            Ok(Some(response))
-        }
-        None => Ok(None),
+    bank.get_account(pubkey)
+        .map(|account| encode_bank_account(bank, pubkey, account, encoding, data_slice))
+        .transpose()
+}
+
+/// Encode `account`, parsing token accounts with the mint decimals in `bank`
+fn encode_bank_account(
+    bank: &Arc<Bank>,
+    pubkey: &Pubkey,
+    account: AccountSharedData,
+    encoding: UiAccountEncoding,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<UiAccount> {
+    if is_known_spl_token_id(account.owner()) && encoding == UiAccountEncoding::JsonParsed {
+        Ok(get_parsed_token_account(bank.clone(), pubkey, account))
+    } else {
+        encode_account(&account, pubkey, encoding, data_slice)
     }
 }
 
@@ -2786,6 +3142,13 @@ pub mod rpc_bank {
             limit: u64,
         ) -> Result<Vec<String>>;
 
+        #[rpc(meta, name = "getStateRoot")]
+        fn get_state_root(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> Result<RpcResponse<String>>;
+
         #[rpc(meta, name = "getBlockProduction")]
         fn get_block_production(
             &self,
@@ -2842,6 +3205,15 @@ pub mod rpc_bank {
             meta.get_slot_leader(config.unwrap_or_default())
         }
 
+        fn get_state_root(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> Result<RpcResponse<String>> {
+            debug!("get_state_root rpc request received");
+            meta.get_state_root(config.unwrap_or_default())
+        }
+
         fn get_slot_leaders(
             &self,
             meta: Self::Metadata,
@@ -2976,6 +3348,14 @@ pub mod rpc_accounts {
             config: Option<RpcAccountInfoConfig>,
         ) -> Result<RpcResponse<Option<UiAccount>>>;
 
+        #[rpc(meta, name = "getAccountProof")]
+        fn get_account_proof(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            slot: Option<Slot>,
+        ) -> Result<RpcResponse<RpcAccountProof>>;
+
         #[rpc(meta, name = "getMultipleAccounts")]
         fn get_multiple_accounts(
             &self,
@@ -3083,6 +3463,17 @@ pub mod rpc_accounts {
             meta.get_account_info(&pubkey, config)
         }
 
+        fn get_account_proof(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            slot: Option<Slot>,
+        ) -> Result<RpcResponse<RpcAccountProof>> {
+            debug!("get_account_proof rpc request received: {:?}", pubkey_str);
+            let pubkey = verify_pubkey(&pubkey_str)?;
+            meta.get_account_proof(&pubkey, slot)
+        }
+
         fn get_multiple_accounts(
             &self,
             meta: Self::Metadata,
@@ -3336,6 +3727,14 @@ pub mod rpc_full {
             config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
         ) -> BoxFuture<Result<Option<UiConfirmedBlock>>>;
 
+        #[rpc(meta, name = "getSlotEntries")]
+        fn get_slot_entries(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            config: Option<RpcSlotEntriesConfig>,
+        ) -> Result<RpcSlotEntries>;
+
         #[rpc(meta, name = "getBlockTime")]
         fn get_block_time(
             &self,
@@ -3369,6 +3768,14 @@ pub mod rpc_full {
             config: Option<RpcEncodingConfigWrapper<RpcTransactionConfig>>,
         ) -> BoxFuture<Result<Option<EncodedConfirmedTransactionWithStatusMeta>>>;
 
+        #[rpc(meta, name = "traceTransaction")]
+        fn trace_transaction(
+            &self,
+            meta: Self::Metadata,
+            signature_str: String,
+            config: Option<RpcTraceTransactionConfig>,
+        ) -> Result<Option<RpcTraceTransactionResult>>;
+
         #[rpc(meta, name = "getSignaturesForAddress")]
         fn get_signatures_for_address(
             &self,
@@ -3380,6 +3787,23 @@ pub mod rpc_full {
         #[rpc(meta, name = "getFirstAvailableBlock")]
         fn get_first_available_block(&self, meta: Self::Metadata) -> BoxFuture<Result<Slot>>;
 
+        #[rpc(meta, name = "getAccountInfoAtSlot")]
+        fn get_account_info_at_slot(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            slot: Slot,
+            config: Option<RpcAccountAtSlotConfig>,
+        ) -> BoxFuture<Result<Option<RpcAccountVersion>>>;
+
+        #[rpc(meta, name = "getAccountHistory")]
+        fn get_account_history(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            config: Option<RpcAccountHistoryConfig>,
+        ) -> BoxFuture<Result<Vec<RpcAccountVersion>>>;
+
         #[rpc(meta, name = "getLatestBlockhash")]
         fn get_latest_blockhash(
             &self,
@@ -3823,6 +4247,16 @@ pub mod rpc_full {
             Box::pin(async move { meta.get_block(slot, config).await })
         }
 
+        fn get_slot_entries(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            config: Option<RpcSlotEntriesConfig>,
+        ) -> Result<RpcSlotEntries> {
+            debug!("get_slot_entries rpc request received: {:?}", slot);
+            meta.get_slot_entries(slot, config)
+        }
+
         fn get_blocks(
             &self,
             meta: Self::Metadata,
@@ -3881,6 +4315,20 @@ pub mod rpc_full {
             Box::pin(async move { meta.get_transaction(signature.unwrap(), config).await })
         }
 
+        fn trace_transaction(
+            &self,
+            meta: Self::Metadata,
+            signature_str: String,
+            config: Option<RpcTraceTransactionConfig>,
+        ) -> Result<Option<RpcTraceTransactionResult>> {
+            debug!(
+                "trace_transaction rpc request received: {:?}",
+                signature_str
+            );
+            let signature = verify_signature(&signature_str)?;
+            meta.trace_transaction(signature, config)
+        }
+
         fn get_signatures_for_address(
             &self,
             meta: Self::Metadata,
@@ -3920,6 +4368,56 @@ pub mod rpc_full {
             Box::pin(async move { Ok(meta.get_first_available_block().await) })
         }
 
+        fn get_account_info_at_slot(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            slot: Slot,
+            config: Option<RpcAccountAtSlotConfig>,
+        ) -> BoxFuture<Result<Option<RpcAccountVersion>>> {
+            debug!(
+                "get_account_info_at_slot rpc request received: {:?} {:?}",
+                pubkey_str, slot
+            );
+            let pubkey = match verify_pubkey(&pubkey_str) {
+                Ok(pubkey) => pubkey,
+                Err(err) => return Box::pin(future::err(err)),
+            };
+            Box::pin(async move { meta.get_account_info_at_slot(&pubkey, slot, config).await })
+        }
+
+        fn get_account_history(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+            config: Option<RpcAccountHistoryConfig>,
+        ) -> BoxFuture<Result<Vec<RpcAccountVersion>>> {
+            debug!("get_account_history rpc request received: {:?}", pubkey_str);
+            let RpcAccountHistoryConfig {
+                before,
+                until,
+                limit,
+                encoding,
+                data_slice,
+            } = config.unwrap_or_default();
+            let verification = verify_and_parse_account_history_params(pubkey_str, limit);
+
+            match verification {
+                Err(err) => Box::pin(future::err(err)),
+                Ok((pubkey, limit)) => Box::pin(async move {
+                    meta.get_account_history(
+                        &pubkey,
+                        before,
+                        until,
+                        limit,
+                        encoding.unwrap_or(UiAccountEncoding::Binary),
+                        data_slice,
+                    )
+                    .await
+                }),
+            }
+        }
+
         fn get_inflation_reward(
             &self,
             meta: Self::Metadata,
@@ -4472,6 +4970,70 @@ fn sanitize_transaction(
     .map_err(|err| Error::invalid_params(format!("invalid transaction: {}", err)))
 }
 
+/// Nest the traced instructions of a transaction under the instructions which invoked them
+fn nest_traced_instructions(instructions: Vec<TracedInstruction>) -> Vec<RpcTracedInstruction> {
+    fn close_last(
+        stack: &mut Vec<RpcTracedInstruction>,
+        top_level_instructions: &mut Vec<RpcTracedInstruction>,
+    ) {
+        if let Some(instruction) = stack.pop() {
+            match stack.last_mut() {
+                Some(caller) => caller.inner_instructions.push(instruction),
+                None => top_level_instructions.push(instruction),
+            }
+        }
+    }
+
+    let mut top_level_instructions = vec![];
+    let mut stack: Vec<RpcTracedInstruction> = vec![];
+    for instruction in instructions {
+        // Instructions deeper than or as deep as this one have returned
+        while stack.len() >= instruction.stack_height.max(1) {
+            close_last(&mut stack, &mut top_level_instructions);
+        }
+        stack.push(RpcTracedInstruction {
+            program_id: instruction.program_id.to_string(),
+            stack_height: instruction.stack_height,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| account.pubkey.to_string())
+                .collect(),
+            data: bs58::encode(&instruction.data).into_string(),
+            compute_units_consumed: instruction.compute_units_consumed,
+            err: instruction.result.and_then(|result| result.err()),
+            account_diffs: account_diffs(&instruction.accounts),
+            inner_instructions: vec![],
+        });
+    }
+    while !stack.is_empty() {
+        close_last(&mut stack, &mut top_level_instructions);
+    }
+    top_level_instructions
+}
+
+/// The accounts whose lamports or data were changed by an instruction
+fn account_diffs(accounts: &[TracedAccount]) -> Vec<RpcAccountDiff> {
+    accounts
+        .iter()
+        .filter_map(|account| {
+            let pre = account.pre.as_ref()?;
+            let post = account.post.as_ref()?;
+            let data_changed = pre.data() != post.data();
+            if pre.lamports() == post.lamports() && !data_changed {
+                return None;
+            }
+            Some(RpcAccountDiff {
+                pubkey: account.pubkey.to_string(),
+                pre_lamports: pre.lamports(),
+                post_lamports: post.lamports(),
+                pre_data: data_changed.then(|| base64::encode(pre.data())),
+                post_data: data_changed.then(|| base64::encode(post.data())),
+            })
+        })
+        .collect()
+}
+
 pub fn create_validator_exit(exit: &Arc<AtomicBool>) -> Arc<RwLock<Exit>> {
     let mut validator_exit = Exit::default();
     let exit_ = exit.clone();
@@ -4584,6 +5146,7 @@ pub mod tests {
         solana_address_lookup_table_program::state::{AddressLookupTable, LookupTableMeta},
         solana_client::{
             rpc_custom_error::{
+                JSON_RPC_SERVER_ERROR_ACCOUNT_HISTORY_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION,
@@ -5121,6 +5684,61 @@ pub mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_rpc_get_state_root() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        bank.freeze();
+        assert_ne!(bank.state_root(), Hash::default());
+
+        let request =
+            create_test_request("getStateRoot", Some(json!([{"commitment": "processed"}])));
+        let result: RpcResponse<String> = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.value, bank.state_root().to_string());
+    }
+
+    #[test]
+    fn test_rpc_get_account_proof() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        bank.freeze();
+        let mint = rpc.mint_keypair.pubkey();
+
+        let request = create_test_request(
+            "getAccountProof",
+            Some(json!([mint.to_string(), bank.slot()])),
+        );
+        let result: RpcResponse<RpcAccountProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.context.slot, bank.slot());
+        assert_eq!(result.value.state_root, bank.state_root().to_string());
+        let (value_hash, proof) = bank.get_account_proof(&mint).unwrap().unwrap();
+        assert_ne!(value_hash, Hash::default());
+        assert_eq!(result.value.value_hash, value_hash.to_string());
+        assert_eq!(result.value.proof, base64::encode(proof));
+
+        // Accounts not in the state get a non-membership proof
+        let request = create_test_request(
+            "getAccountProof",
+            Some(json!([Pubkey::new_unique().to_string(), bank.slot()])),
+        );
+        let result: RpcResponse<RpcAccountProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.value.value_hash, Hash::default().to_string());
+
+        // Slots which are not in the bank forks can not be proven
+        let request = create_test_request(
+            "getAccountProof",
+            Some(json!([mint.to_string(), bank.slot() + 100])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+            format!("Block not available for slot {}", bank.slot() + 100),
+        );
+        assert_eq!(response, expected);
+    }
+
     #[test]
     fn test_rpc_get_slot() {
         let rpc = RpcHandler::start();
@@ -6714,6 +7332,189 @@ pub mod tests {
         assert_eq!(response, expected);
     }
 
+    #[test]
+    fn test_get_account_history_not_available() {
+        let rpc = RpcHandler::start();
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let expected = (
+            JSON_RPC_SERVER_ERROR_ACCOUNT_HISTORY_NOT_AVAILABLE,
+            String::from("Account history is not available from this node"),
+        );
+
+        // Without the PostgreSQL long-term storage, there is no account history
+        let request = create_test_request(
+            "getAccountInfoAtSlot",
+            Some(json!([pubkey.to_string(), 0u64])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(response, expected);
+
+        let request = create_test_request(
+            "getAccountHistory",
+            Some(json!([pubkey.to_string(), {"limit": 10}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(response, expected);
+
+        let request = create_test_request(
+            "getAccountHistory",
+            Some(json!([pubkey.to_string(), {"limit": 10_000}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            String::from("Invalid limit; max 1000"),
+        );
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn test_trace_transaction() {
+        let rpc = RpcHandler::start();
+
+        // A transaction which is not in the ledger is not traced
+        let signature = Signature::new(&[7u8; 64]);
+        let request = create_test_request("traceTransaction", Some(json!([signature.to_string()])));
+        let result: Option<RpcTraceTransactionResult> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, None);
+
+        let request = create_test_request(
+            "traceTransaction",
+            Some(json!([signature.to_string(), {"commitment": "processed"}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(
+            response,
+            (
+                ErrorCode::InvalidParams.code(),
+                String::from("Method does not support commitment below `confirmed`"),
+            )
+        );
+
+        // A transaction of a confirmed block is traced against the bank of its parent
+        let bank0 = rpc.working_bank();
+        let blockhash = bank0.last_blockhash();
+        let bank1 = rpc.advance_bank_to_confirmed_slot(1);
+        rpc.meta.optimistically_confirmed_bank.write().unwrap().bank = bank1.clone();
+        let recipient = Pubkey::new_unique();
+        let rent_exempt_amount = bank1.get_minimum_balance_for_rent_exemption(0);
+        let transfer_tx = system_transaction::transfer(
+            &rpc.mint_keypair,
+            &recipient,
+            rent_exempt_amount,
+            blockhash,
+        );
+        let signature = transfer_tx.signatures[0];
+        let entry = solana_entry::entry::next_entry(&blockhash, 1, vec![transfer_tx]);
+        populate_blockstore_for_tests(
+            vec![entry],
+            bank1.clone(),
+            rpc.blockstore.clone(),
+            rpc.max_complete_transaction_status_slot.clone(),
+        );
+
+        let request = create_test_request("traceTransaction", Some(json!([signature.to_string()])));
+        let result: Option<RpcTraceTransactionResult> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let result = result.unwrap();
+        assert_eq!(result.slot, 1);
+        assert_eq!(result.err, None);
+        assert_eq!(result.instructions.len(), 1);
+        let instruction = &result.instructions[0];
+        assert_eq!(instruction.program_id, system_program::id().to_string());
+        assert_eq!(instruction.stack_height, 1);
+        assert_eq!(instruction.err, None);
+        assert!(instruction.inner_instructions.is_empty());
+        let recipient_diff = instruction
+            .account_diffs
+            .iter()
+            .find(|account_diff| account_diff.pubkey == recipient.to_string())
+            .unwrap();
+        assert_eq!(recipient_diff.pre_lamports, 0);
+        assert_eq!(recipient_diff.post_lamports, rent_exempt_amount);
+
+        // Nothing was committed by the trace
+        assert_eq!(bank0.get_balance(&recipient), 0);
+        assert_eq!(bank1.get_balance(&recipient), rent_exempt_amount);
+    }
+
+    #[test]
+    fn test_get_slot_entries() {
+        let rpc = RpcHandler::start();
+        let signatures = rpc.create_test_transactions_and_populate_blockstore();
+        let (entries, num_shreds, _) = rpc
+            .blockstore
+            .get_slot_entries_with_shred_info(0, 0, false)
+            .unwrap();
+
+        let request = create_test_request("getSlotEntries", Some(json!([0u64])));
+        let result: RpcSlotEntries = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.slot, 0);
+        assert_eq!(result.num_shreds, num_shreds);
+        assert_eq!(result.shreds, None);
+        assert_eq!(result.entries.len(), entries.len());
+        for (rpc_entry, entry) in result.entries.iter().zip(&entries) {
+            assert_eq!(rpc_entry.num_hashes, entry.num_hashes);
+            assert_eq!(rpc_entry.hash, entry.hash.to_string());
+            assert_eq!(rpc_entry.transactions.len(), entry.transactions.len());
+            for (encoded, transaction) in rpc_entry.transactions.iter().zip(&entry.transactions) {
+                let decoded = encoded.decode().unwrap();
+                assert_eq!(&decoded, transaction);
+            }
+        }
+
+        let request = create_test_request(
+            "getSlotEntries",
+            Some(json!([0u64, {"encoding": "json", "includeShreds": true}])),
+        );
+        let result: RpcSlotEntries = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.shreds.unwrap().len() as u64, num_shreds);
+        let json_signatures: Vec<_> = result
+            .entries
+            .iter()
+            .flat_map(|entry| &entry.transactions)
+            .map(|transaction| match transaction {
+                EncodedTransaction::Json(transaction) => transaction.signatures[0].clone(),
+                _ => panic!("expected a json encoded transaction"),
+            })
+            .collect();
+        for signature in &signatures {
+            assert!(json_signatures.contains(&signature.to_string()));
+        }
+
+        // Slots which are not complete in the blockstore have no entries
+        let request = create_test_request("getSlotEntries", Some(json!([12345u64])));
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+            String::from("Block not available for slot 12345"),
+        );
+        assert_eq!(response, expected);
+
+        // Processed slots are not served
+        let request = create_test_request(
+            "getSlotEntries",
+            Some(json!([0u64, {"commitment": "processed"}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            String::from("Method does not support commitment below `confirmed`"),
+        );
+        assert_eq!(response, expected);
+
+        // Neither are dead slots
+        rpc.blockstore.set_dead_slot(0).unwrap();
+        let request = create_test_request("getSlotEntries", Some(json!([0u64])));
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+            String::from("Block not available for slot 0"),
+        );
+        assert_eq!(response, expected);
+    }
+
     #[test]
     fn test_get_block() {
         let mut rpc = RpcHandler::start();
@@ -6,9 +6,9 @@ use {
         rpc_pubsub_service::PubSubConfig,
         rpc_subscription_tracker::{
             AccountSubscriptionParams, BlockSubscriptionKind, BlockSubscriptionParams,
-            LogsSubscriptionKind, LogsSubscriptionParams, ProgramSubscriptionParams,
-            SignatureSubscriptionParams, SubscriptionControl, SubscriptionId, SubscriptionParams,
-            SubscriptionToken,
+            EntrySubscriptionParams, LogsSubscriptionKind, LogsSubscriptionParams,
+            ProgramSubscriptionParams, SignatureSubscriptionParams, SubscriptionControl,
+            SubscriptionId, SubscriptionParams, SubscriptionToken,
         },
     },
     dashmap::DashMap,
@@ -19,12 +19,12 @@ use {
     solana_client::{
         rpc_config::{
             RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
-            RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
-            RpcTransactionLogsFilter,
+            RpcEntrySubscribeConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
+            RpcTransactionLogsConfig, RpcTransactionLogsFilter,
         },
         rpc_response::{
             Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
-            RpcSignatureResult, RpcVersionInfo, RpcVote, SlotInfo, SlotUpdate,
+            RpcSignatureResult, RpcSlotEntries, RpcVersionInfo, RpcVote, SlotInfo, SlotUpdate,
         },
     },
     solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
@@ -213,6 +213,27 @@ pub trait RpcSolPubSub {
         id: PubSubSubscriptionId,
     ) -> Result<bool>;
 
+    // Subscribe to the entries of each new slot
+    #[pubsub(subscription = "entryNotification", subscribe, name = "entrySubscribe")]
+    fn entry_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<Arc<RpcSlotEntries>>,
+        config: Option<RpcEntrySubscribeConfig>,
+    );
+
+    // Unsubscribe from entry notification subscription.
+    #[pubsub(
+        subscription = "entryNotification",
+        unsubscribe,
+        name = "entryUnsubscribe"
+    )]
+    fn entry_unsubscribe(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: PubSubSubscriptionId,
+    ) -> Result<bool>;
+
     // Get notification when vote is encountered
     #[pubsub(subscription = "voteNotification", subscribe, name = "voteSubscribe")]
     fn vote_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<RpcVote>);
@@ -333,6 +354,17 @@ mod internal {
         #[rpc(name = "blockUnsubscribe")]
         fn block_unsubscribe(&self, id: SubscriptionId) -> Result<bool>;
 
+        // Subscribe to the entries of each new slot
+        #[rpc(name = "entrySubscribe")]
+        fn entry_subscribe(
+            &self,
+            config: Option<RpcEntrySubscribeConfig>,
+        ) -> Result<SubscriptionId>;
+
+        // Unsubscribe from entry notification subscription.
+        #[rpc(name = "entryUnsubscribe")]
+        fn entry_unsubscribe(&self, id: SubscriptionId) -> Result<bool>;
+
         // Get notification when vote is encountered
         #[rpc(name = "voteSubscribe")]
         fn vote_subscribe(&self) -> Result<SubscriptionId>;
@@ -559,6 +591,28 @@ impl RpcSolPubSubInternal for RpcSolPubSubImpl {
         self.unsubscribe(id)
     }
 
+    fn entry_subscribe(&self, config: Option<RpcEntrySubscribeConfig>) -> Result<SubscriptionId> {
+        if !self.config.enable_entry_subscription {
+            return Err(Error::new(jsonrpc_core::ErrorCode::MethodNotFound));
+        }
+        let config = config.unwrap_or_default();
+        let commitment = config.commitment.unwrap_or_default();
+        check_is_at_least_confirmed(commitment)?;
+        let params = EntrySubscriptionParams {
+            commitment,
+            encoding: config.encoding.unwrap_or(UiTransactionEncoding::Base64),
+            include_shreds: config.include_shreds.unwrap_or_default(),
+        };
+        self.subscribe(SubscriptionParams::Entry(params))
+    }
+
+    fn entry_unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
+        if !self.config.enable_entry_subscription {
+            return Err(Error::new(jsonrpc_core::ErrorCode::MethodNotFound));
+        }
+        self.unsubscribe(id)
+    }
+
     fn vote_subscribe(&self) -> Result<SubscriptionId> {
         if !self.config.enable_vote_subscription {
             return Err(Error::new(jsonrpc_core::ErrorCode::MethodNotFound));
@@ -34,6 +34,7 @@ pub const DEFAULT_WORKER_THREADS: usize = 1;
 #[derive(Debug, Clone)]
 pub struct PubSubConfig {
     pub enable_block_subscription: bool,
+    pub enable_entry_subscription: bool,
     pub enable_vote_subscription: bool,
     pub max_active_subscriptions: usize,
     pub queue_capacity_items: usize,
@@ -46,6 +47,7 @@ impl Default for PubSubConfig {
     fn default() -> Self {
         Self {
             enable_block_subscription: false,
+            enable_entry_subscription: false,
             enable_vote_subscription: false,
             max_active_subscriptions: MAX_ACTIVE_SUBSCRIPTIONS,
             queue_capacity_items: DEFAULT_QUEUE_CAPACITY_ITEMS,
@@ -60,6 +62,7 @@ impl PubSubConfig {
     pub fn default_for_tests() -> Self {
         Self {
             enable_block_subscription: false,
+            enable_entry_subscription: false,
             enable_vote_subscription: false,
             max_active_subscriptions: MAX_ACTIVE_SUBSCRIPTIONS,
             queue_capacity_items: DEFAULT_TEST_QUEUE_CAPACITY_ITEMS,
@@ -148,6 +151,9 @@ fn count_final(params: &SubscriptionParams) {
         SubscriptionParams::Block(_) => {
             inc_new_counter_info!("rpc-pubsub-final-slot-txs", 1);
         }
+        SubscriptionParams::Entry(_) => {
+            inc_new_counter_info!("rpc-pubsub-final-slot-entries", 1);
+        }
     }
 }
 
@@ -236,6 +242,7 @@ pub fn test_connection(
     let rpc_impl = RpcSolPubSubImpl::new(
         PubSubConfig {
             enable_block_subscription: true,
+            enable_entry_subscription: true,
             enable_vote_subscription: true,
             queue_capacity_items: 100,
             ..PubSubConfig::default()
@@ -3,6 +3,7 @@
 use {
     crate::{
         cluster_tpu_info::ClusterTpuInfo,
+        long_term_storage::LongTermStorage,
         max_slots::MaxSlots,
         optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
         rpc::{
@@ -393,8 +394,22 @@ impl JsonRpcService {
 
         let exit_bigtable_ledger_upload_service = Arc::new(AtomicBool::new(false));
 
-        let (bigtable_ledger_storage, _bigtable_ledger_upload_service) =
-            if let Some(RpcBigtableConfig {
+        let (long_term_storage, _bigtable_ledger_upload_service) =
+            if let Some(RpcPostgresConfig { ref connection_str }) = config.rpc_postgres_config {
+                runtime
+                    .block_on(solana_storage_postgres::LedgerStorage::new(connection_str))
+                    .map(|postgres_ledger_storage| {
+                        info!("PostgreSQL ledger storage initialized");
+                        (
+                            Some(LongTermStorage::Postgres(postgres_ledger_storage)),
+                            None,
+                        )
+                    })
+                    .unwrap_or_else(|err| {
+                        error!("Failed to initialize PostgreSQL ledger storage: {:?}", err);
+                        (None, None)
+                    })
+            } else if let Some(RpcBigtableConfig {
                 enable_bigtable_ledger_upload,
                 ref bigtable_instance_name,
                 ref bigtable_app_profile_id,
@@ -430,7 +445,7 @@ impl JsonRpcService {
                         };
 
                         (
-                            Some(bigtable_ledger_storage),
+                            Some(LongTermStorage::BigTable(bigtable_ledger_storage)),
                             bigtable_ledger_upload_service,
                         )
                     })
@@ -454,7 +469,7 @@ impl JsonRpcService {
             health.clone(),
             cluster_info.clone(),
             genesis_hash,
-            bigtable_ledger_storage,
+            long_term_storage,
             optimistically_confirmed_bank,
             largest_accounts_cache,
             max_slots,
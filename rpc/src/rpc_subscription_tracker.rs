@@ -43,6 +43,7 @@ impl From<SubscriptionId> for u64 {
 pub enum SubscriptionParams {
     Account(AccountSubscriptionParams),
     Block(BlockSubscriptionParams),
+    Entry(EntrySubscriptionParams),
     Logs(LogsSubscriptionParams),
     Program(ProgramSubscriptionParams),
     Signature(SignatureSubscriptionParams),
@@ -62,6 +63,7 @@ impl SubscriptionParams {
             SubscriptionParams::Slot => "slotNotification",
             SubscriptionParams::SlotsUpdates => "slotsUpdatesNotification",
             SubscriptionParams::Block(_) => "blockNotification",
+            SubscriptionParams::Entry(_) => "entryNotification",
             SubscriptionParams::Root => "rootNotification",
             SubscriptionParams::Vote => "voteNotification",
         }
@@ -74,6 +76,7 @@ impl SubscriptionParams {
             SubscriptionParams::Program(params) => Some(params.commitment),
             SubscriptionParams::Signature(params) => Some(params.commitment),
             SubscriptionParams::Block(params) => Some(params.commitment),
+            SubscriptionParams::Entry(params) => Some(params.commitment),
             SubscriptionParams::Slot
             | SubscriptionParams::SlotsUpdates
             | SubscriptionParams::Root
@@ -85,6 +88,7 @@ impl SubscriptionParams {
         let commitment = match self {
             SubscriptionParams::Account(params) => &params.commitment,
             SubscriptionParams::Block(params) => &params.commitment,
+            SubscriptionParams::Entry(params) => &params.commitment,
             SubscriptionParams::Logs(params) => &params.commitment,
             SubscriptionParams::Program(params) => &params.commitment,
             SubscriptionParams::Signature(params) => &params.commitment,
@@ -100,6 +104,7 @@ impl SubscriptionParams {
         let commitment = match self {
             SubscriptionParams::Account(params) => &params.commitment,
             SubscriptionParams::Block(params) => &params.commitment,
+            SubscriptionParams::Entry(params) => &params.commitment,
             SubscriptionParams::Logs(params) => &params.commitment,
             SubscriptionParams::Program(params) => &params.commitment,
             SubscriptionParams::Signature(params) => &params.commitment,
@@ -146,6 +151,13 @@ pub enum BlockSubscriptionKind {
     MentionsAccountOrProgram(Pubkey),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntrySubscriptionParams {
+    pub commitment: CommitmentConfig,
+    pub encoding: UiTransactionEncoding,
+    pub include_shreds: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct LogsSubscriptionParams {
     pub kind: LogsSubscriptionKind,
@@ -4,6 +4,7 @@ use {
     crate::{
         optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
         parsed_token_accounts::{get_parsed_token_account, get_parsed_token_accounts},
+        rpc::get_slot_entries,
         rpc_pubsub_service::PubSubConfig,
         rpc_subscription_tracker::{
             AccountSubscriptionParams, BlockSubscriptionKind, BlockSubscriptionParams,
@@ -949,6 +950,9 @@ impl RpcSubscriptions {
         let num_blocks_found = AtomicUsize::new(0);
         let num_blocks_notified = AtomicUsize::new(0);
 
+        let num_entries_found = AtomicUsize::new(0);
+        let num_entries_notified = AtomicUsize::new(0);
+
         let num_logs_found = AtomicUsize::new(0);
         let num_logs_notified = AtomicUsize::new(0);
 
@@ -1080,6 +1084,52 @@ impl RpcSubscriptions {
                         }
                     }
                 }
+                SubscriptionParams::Entry(params) => {
+                    num_entries_found.fetch_add(1, Ordering::Relaxed);
+                    if let Some(slot) = slot {
+                        let bank = bank_forks.read().unwrap().get(slot);
+                        if let Some(bank) = bank {
+                            // As for blocks, the ancestors of `slot` missed since
+                            // the last notification are notified first.
+                            let mut w_last_unnotified_slot =
+                                subscription.last_notified_slot.write().unwrap();
+                            if *w_last_unnotified_slot == 0 {
+                                *w_last_unnotified_slot = slot;
+                            }
+                            let ancestors = bank.proper_ancestors_set();
+                            let slots_to_notify: Vec<_> = (*w_last_unnotified_slot..=slot)
+                                .filter(|s| *s == slot || ancestors.contains(s))
+                                .collect();
+                            for s in slots_to_notify {
+                                match get_slot_entries(
+                                    blockstore,
+                                    s,
+                                    params.encoding,
+                                    params.include_shreds,
+                                ) {
+                                    Ok(Some(slot_entries)) => {
+                                        notifier.notify(
+                                            RpcResponse::from(RpcNotificationResponse {
+                                                context: RpcNotificationContext { slot: s },
+                                                value: slot_entries,
+                                            }),
+                                            subscription,
+                                            false,
+                                        );
+                                        num_entries_notified.fetch_add(1, Ordering::Relaxed);
+                                        *w_last_unnotified_slot = s + 1;
+                                    }
+                                    // Retried on the next notification
+                                    Ok(None) => break,
+                                    Err(err) => {
+                                        error!("get_slot_entries error: {}", err);
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
                 SubscriptionParams::Logs(params) => {
                     num_logs_found.fetch_add(1, Ordering::Relaxed);
                     if let Some(slot) = slot {
@@ -1260,12 +1310,13 @@ pub(crate) mod tests {
             rpc::{create_test_transaction_entries, populate_blockstore_for_tests},
             rpc_pubsub::RpcSolPubSubInternal,
             rpc_pubsub_service,
+            rpc_subscription_tracker::EntrySubscriptionParams,
         },
         serial_test::serial,
         solana_client::rpc_config::{
             RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
-            RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
-            RpcTransactionLogsFilter,
+            RpcEntrySubscribeConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
+            RpcTransactionLogsConfig, RpcTransactionLogsFilter,
         },
         solana_runtime::{
             commitment::BlockCommitment,
@@ -1773,6 +1824,97 @@ pub(crate) mod tests {
             .assert_unsubscribed(&SubscriptionParams::Block(params));
     }
 
+    #[test]
+    #[serial]
+    fn test_check_entry_subscribe() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let rent_exempt_amount = bank.get_minimum_balance_for_rent_exemption(0);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let optimistically_confirmed_bank =
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let max_complete_transaction_status_slot = Arc::new(AtomicU64::default());
+        let subscriptions = Arc::new(RpcSubscriptions::new_for_tests_with_blockstore(
+            &exit,
+            max_complete_transaction_status_slot.clone(),
+            blockstore.clone(),
+            bank_forks.clone(),
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            optimistically_confirmed_bank,
+        ));
+        let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&subscriptions);
+        let config = RpcEntrySubscribeConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            include_shreds: Some(true),
+        };
+        let params = EntrySubscriptionParams {
+            commitment: config.commitment.unwrap(),
+            encoding: config.encoding.unwrap(),
+            include_shreds: true,
+        };
+        let sub_id = rpc.entry_subscribe(Some(config)).unwrap();
+        subscriptions
+            .control
+            .assert_subscribed(&SubscriptionParams::Entry(params.clone()));
+
+        let bank = bank_forks.read().unwrap().working_bank();
+        let keypair1 = Keypair::new();
+        let keypair2 = Keypair::new();
+        let keypair3 = Keypair::new();
+        bank.transfer(rent_exempt_amount, &mint_keypair, &keypair2.pubkey())
+            .unwrap();
+        populate_blockstore_for_tests(
+            create_test_transaction_entries(
+                vec![&mint_keypair, &keypair1, &keypair2, &keypair3],
+                bank.clone(),
+            )
+            .0,
+            bank,
+            blockstore.clone(),
+            max_complete_transaction_status_slot,
+        );
+
+        let slot = 0;
+        subscriptions.notify_gossip_subscribers(slot);
+        let actual_resp = receiver.recv();
+        let actual_resp = serde_json::from_str::<serde_json::Value>(&actual_resp).unwrap();
+
+        let slot_entries = get_slot_entries(&blockstore, slot, params.encoding, true)
+            .unwrap()
+            .unwrap();
+        assert!(!slot_entries.entries.is_empty());
+        let expected_resp = json!({
+           "jsonrpc": "2.0",
+           "method": "entryNotification",
+           "params": {
+               "result": {
+                   "context": { "slot": slot },
+                   "value": slot_entries,
+               },
+               "subscription": 0,
+           }
+        });
+        assert_eq!(expected_resp, actual_resp);
+
+        // The entries of a slot are only notified once
+        subscriptions.notify_gossip_subscribers(slot);
+        let should_err = receiver.recv_timeout(Duration::from_millis(300));
+        assert!(should_err.is_err());
+
+        rpc.entry_unsubscribe(sub_id).unwrap();
+        subscriptions
+            .control
+            .assert_unsubscribed(&SubscriptionParams::Entry(params));
+    }
+
     #[test]
     #[serial]
     fn test_check_program_subscribe() {
@@ -64,6 +64,9 @@ use {
             MAX_ALLOWABLE_DRIFT_PERCENTAGE_FAST, MAX_ALLOWABLE_DRIFT_PERCENTAGE_SLOW_V2,
         },
         stakes::{InvalidCacheEntryReason, Stakes, StakesCache, StakesEnum},
+        state_tree::{
+            self, StateTree, StateTreeChanges, StateTreeError, StateTreeLeaf, StateTreeStore,
+        },
         status_cache::{SlotDelta, StatusCache},
         storable_accounts::StorableAccounts,
         system_instruction_processor::{get_system_account_kind, SystemAccountKind},
@@ -86,6 +89,7 @@ use {
         accounts_data_meter::MAX_ACCOUNTS_DATA_LEN,
         compute_budget::{self, ComputeBudget},
         executor_cache::{CachedExecutors, Executors, TransactionExecutor, MAX_CACHED_EXECUTORS},
+        instruction_tracer::InstructionTracer,
         invoke_context::{BuiltinProgram, ProcessInstructionWithContext},
         log_collector::LogCollector,
         sysvar_cache::SysvarCache,
@@ -118,7 +122,7 @@ use {
         hash::{extend_and_hash, hashv, Hash},
         incinerator,
         inflation::Inflation,
-        instruction::CompiledInstruction,
+        instruction::{CompiledInstruction, InstructionError},
         lamports::LamportsError,
         message::{AccountKeys, SanitizedMessage},
         native_loader,
@@ -202,6 +206,9 @@ pub const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
 
 pub const MAX_LEADER_SCHEDULE_STAKES: Epoch = 5;
 
+/// How long to wait before retrying a state tree update the store failed
+const STATE_TREE_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
 pub type Rewrites = RwLock<HashMap<Pubkey, Hash>>;
 
 #[derive(Default)]
@@ -307,6 +314,8 @@ pub struct SquashTiming {
     pub squash_accounts_index_ms: u64,
     pub squash_accounts_store_ms: u64,
 
+    pub squash_state_tree_ms: u64,
+
     pub squash_cache_ms: u64,
 }
 
@@ -470,6 +479,39 @@ pub struct TransactionSimulationResult {
     pub units_consumed: u64,
     pub return_data: Option<TransactionReturnData>,
 }
+
+pub struct TransactionTraceResult {
+    pub result: Result<()>,
+    pub logs: TransactionLogMessages,
+    pub units_consumed: u64,
+    pub return_data: Option<TransactionReturnData>,
+    /// The instructions of the transaction and the ones they invoked, in invocation order
+    pub instructions: Vec<TracedInstruction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedInstruction {
+    /// 1 for the instructions of the message, plus one for each level of invocation
+    pub stack_height: usize,
+    pub program_id: Pubkey,
+    pub accounts: Vec<TracedAccount>,
+    pub data: Vec<u8>,
+    /// `None` for the instructions which were not processed by a program, like precompiles
+    pub compute_units_consumed: Option<u64>,
+    pub result: Option<std::result::Result<(), InstructionError>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    /// The account before and after the instruction, `None` for the duplicates of an account
+    /// earlier in the instruction and for the instructions which were not traced
+    pub pre: Option<AccountSharedData>,
+    pub post: Option<AccountSharedData>,
+}
+
 pub struct TransactionBalancesSet {
     pub pre_balances: TransactionBalances,
     pub post_balances: TransactionBalances,
@@ -530,6 +572,90 @@ pub fn inner_instructions_list_from_instruction_trace(
         .collect()
 }
 
+/// Convert from the InstructionTrace kept by an InstructionTracer to a list of TracedInstruction
+pub fn traced_instructions_from_instruction_tracer(
+    instruction_tracer: &InstructionTracer,
+    transaction_accounts: &[TransactionAccount],
+) -> Vec<TracedInstruction> {
+    fn find_account(
+        accounts: &[(usize, AccountSharedData)],
+        index_in_transaction: usize,
+    ) -> Option<AccountSharedData> {
+        accounts
+            .iter()
+            .find(|(index, _)| *index == index_in_transaction)
+            .map(|(_, account)| account.clone())
+    }
+    let get_key = |index_in_transaction: usize| {
+        transaction_accounts
+            .get(index_in_transaction)
+            .map(|(pubkey, _)| *pubkey)
+            .unwrap_or_default()
+    };
+
+    instruction_tracer
+        .get_instruction_trace()
+        .iter()
+        .enumerate()
+        .flat_map(|(top_level_index, inner_instructions_trace)| {
+            inner_instructions_trace
+                .iter()
+                .enumerate()
+                .map(move |(index, instruction_context)| {
+                    let record = instruction_tracer.get_record((top_level_index, index));
+                    let accounts = (0..instruction_context.get_number_of_instruction_accounts())
+                        .filter_map(|instruction_account_index| {
+                            let index_in_transaction = instruction_context
+                                .get_index_of_instruction_account_in_transaction(
+                                    instruction_account_index,
+                                )
+                                .ok()?;
+                            let record = record.filter(|_| {
+                                matches!(
+                                    instruction_context.is_instruction_account_duplicate(
+                                        instruction_account_index
+                                    ),
+                                    Ok(None)
+                                )
+                            });
+                            Some(TracedAccount {
+                                pubkey: get_key(index_in_transaction),
+                                is_signer: instruction_context
+                                    .is_instruction_account_signer(instruction_account_index)
+                                    .ok()?,
+                                is_writable: instruction_context
+                                    .is_instruction_account_writable(instruction_account_index)
+                                    .ok()?,
+                                pre: record.and_then(|record| {
+                                    find_account(&record.pre_accounts, index_in_transaction)
+                                }),
+                                post: record.and_then(|record| {
+                                    find_account(&record.post_accounts, index_in_transaction)
+                                }),
+                            })
+                        })
+                        .collect();
+                    TracedInstruction {
+                        stack_height: instruction_context.get_stack_height(),
+                        program_id: get_key(
+                            instruction_context
+                                .get_index_of_program_account_in_transaction(
+                                    instruction_context
+                                        .get_number_of_program_accounts()
+                                        .saturating_sub(1),
+                                )
+                                .unwrap_or_default(),
+                        ),
+                        accounts,
+                        data: instruction_context.get_instruction_data().to_vec(),
+                        compute_units_consumed: record.map(|record| record.compute_units_consumed),
+                        result: record.map(|record| record.result.clone()),
+                    }
+                })
+        })
+        .collect()
+}
+
 /// A list of log messages emitted during a transaction
 pub type TransactionLogMessages = Vec<String>;
 
@@ -837,6 +963,10 @@ impl PartialEq for Bank {
             accounts_data_size_delta_off_chain: _,
             fee_structure: _,
             incremental_snapshot_persistence: _,
+            state_tree: _,
+            state_tree_changes: _,
+            state_root,
+            transactions_state_root: _,
             // Ignore new fields explicitly if they do not impact PartialEq.
             // Adding ".." will remove compile-time checks that if a new field
             // is added to the struct, this ParitalEq is accordingly updated.
@@ -844,6 +974,7 @@ impl PartialEq for Bank {
         *blockhash_queue.read().unwrap() == *other.blockhash_queue.read().unwrap()
             && ancestors == &other.ancestors
             && *hash.read().unwrap() == *other.hash.read().unwrap()
+            && *state_root.read().unwrap() == *other.state_root.read().unwrap()
             && parent_hash == &other.parent_hash
             && parent_slot == &other.parent_slot
             && *hard_forks.read().unwrap() == *other.hard_forks.read().unwrap()
@@ -1091,6 +1222,20 @@ pub struct Bank {
     pub fee_structure: FeeStructure,
 
     pub incremental_snapshot_persistence: Option<BankIncrementalSnapshotPersistence>,
+
+    /// The state tree of the rooted bank, shared by all the banks descending from it
+    state_tree: Arc<StateTree>,
+
+    /// The state tree nodes changed by the accounts written in this slot, set
+    /// at freeze
+    state_tree_changes: RwLock<Arc<StateTreeChanges>>,
+
+    /// The root of the state tree at this slot, set at freeze
+    state_root: RwLock<Hash>,
+
+    /// The root of the state tree of the parent with the accounts written by
+    /// the transactions of this slot, set at freeze
+    transactions_state_root: RwLock<Hash>,
 }
 
 struct VoteWithStakeDelegations {
@@ -1279,6 +1424,10 @@ impl Bank {
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
             accounts_data_size_delta_off_chain: AtomicI64::new(0),
             fee_structure: FeeStructure::default(),
+            state_tree: Arc::<StateTree>::default(),
+            state_tree_changes: RwLock::<Arc<StateTreeChanges>>::default(),
+            state_root: RwLock::<Hash>::default(),
+            transactions_state_root: RwLock::<Hash>::default(),
         };
 
         let accounts_data_size_initial = bank.get_total_accounts_stats().unwrap().data_len as u64;
@@ -1605,6 +1754,10 @@ impl Bank {
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
             accounts_data_size_delta_off_chain: AtomicI64::new(0),
             fee_structure: parent.fee_structure.clone(),
+            state_tree: parent.state_tree.clone(),
+            state_tree_changes: RwLock::default(),
+            state_root: RwLock::new(Hash::default()),
+            transactions_state_root: RwLock::new(Hash::default()),
         };
 
         let (_, ancestors_time) = measure!(
@@ -1865,7 +2018,8 @@ impl Bank {
         additional_builtins: Option<&Builtins>,
         debug_do_not_add_builtins: bool,
         accounts_data_size_initial: u64,
-    ) -> Self {
+        state_tree_store: Option<Arc<dyn StateTreeStore>>,
+    ) -> std::result::Result<Self, StateTreeError> {
         let now = Instant::now();
         let ancestors = Ancestors::from(&fields.ancestors);
         // For backward compatibility, we can only serialize and deserialize
@@ -1948,12 +2102,17 @@ impl Bank {
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
             accounts_data_size_delta_off_chain: AtomicI64::new(0),
             fee_structure: FeeStructure::default(),
+            state_tree: new(),
+            state_tree_changes: new(),
+            state_root: new(),
+            transactions_state_root: new(),
         };
         bank.finish_init(
             genesis_config,
             additional_builtins,
             debug_do_not_add_builtins,
         );
+        bank.load_state_tree(state_tree_store)?;
 
         // Sanity assertions between bank snapshot and genesis config
         // Consider removing from serializable bank state
@@ -2008,7 +2167,7 @@ impl Bank {
                 i64
             ),
         );
-        bank
+        Ok(bank)
     }
 
     /// Return subset of bank fields representing serializable state
@@ -2083,6 +2242,88 @@ impl Bank {
         *self.hash.read().unwrap()
     }
 
+    /// Return the root of the sparse merkle tree of the accounts state at this
+    /// bank, `Hash::default()` until the bank is frozen
+    pub fn state_root(&self) -> Hash {
+        *self.state_root.read().unwrap()
+    }
+
+    /// Return the root of the state tree of the parent with the accounts
+    /// written by the transactions of this slot, which fraud proofs re-execute
+    /// the transactions against. Unlike `state_root()` it leaves out the
+    /// changes the bank makes at freeze, like the fee distribution and the
+    /// eager rent collection, and the sysvars. `Hash::default()` until the
+    /// bank is frozen, and for banks loaded from a snapshot.
+    pub fn transactions_state_root(&self) -> Hash {
+        *self.transactions_state_root.read().unwrap()
+    }
+
+    /// Return the state tree leaf value of `pubkey` in this bank's state, zero
+    /// if the account is not in the state, and the compiled merkle proof of it
+    /// against `state_root()`. Returns `None` if the bank is not frozen or is
+    /// older than the rooted state tree, and an error if the state tree store
+    /// failed to read the proven nodes.
+    pub fn get_account_proof(
+        &self,
+        pubkey: &Pubkey,
+    ) -> std::result::Result<Option<(Hash, Vec<u8>)>, StateTreeError> {
+        Ok(self
+            .get_accounts_proof(&[*pubkey])?
+            .map(|(mut values, proof)| (values.remove(0), proof)))
+    }
+
+    /// Like `get_account_proof`, with a single proof of all of `pubkeys`,
+    /// which must not repeat
+    pub fn get_accounts_proof(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> std::result::Result<Option<(Vec<Hash>, Vec<u8>)>, StateTreeError> {
+        if !self.is_frozen() {
+            return Ok(None);
+        }
+        let changes = self.state_tree_changes.read().unwrap().clone();
+        let ancestors: Vec<_> = self
+            .parents()
+            .iter()
+            .map(|bank| (bank.slot(), bank.state_tree_changes.read().unwrap().clone()))
+            .collect();
+        self.state_tree
+            .merkle_proof(
+                self.slot(),
+                self.state_root(),
+                std::iter::once((self.slot(), changes.as_ref())).chain(
+                    ancestors
+                        .iter()
+                        .map(|(slot, changes)| (*slot, changes.as_ref())),
+                ),
+                pubkeys.iter().map(state_tree::leaf_key).collect(),
+            )
+            .map(|proof| {
+                proof.map(|(values, proof)| {
+                    (
+                        values.iter().map(state_tree::root_to_hash).collect(),
+                        proof.into(),
+                    )
+                })
+            })
+    }
+
+    /// Keep the rooted state tree in `store`, resuming it from the store if it
+    /// committed the tree of the rooted slot. If the store fails, the tree is
+    /// kept in the store it was in.
+    pub fn set_state_tree_store(
+        &self,
+        store: Arc<dyn StateTreeStore>,
+    ) -> std::result::Result<(), StateTreeError> {
+        let resumed = self.state_tree.set_store(store)?;
+        info!(
+            "state tree {} store at slot {:?}",
+            if resumed { "resumed from" } else { "copied to" },
+            self.state_tree.rooted_slot(),
+        );
+        Ok(())
+    }
+
     pub fn is_frozen(&self) -> bool {
         *self.hash.read().unwrap() != Hash::default()
     }
@@ -3128,6 +3369,10 @@ impl Bank {
         // committed before this write lock can be obtained here.
         let mut hash = self.hash.write().unwrap();
         if *hash == Hash::default() {
+            // before the deferred changes below, only the transactions wrote
+            // to the accounts of the slot
+            self.update_transactions_state_root();
+
             // finish up any deferred changes to account state
             self.collect_rent_eagerly(false);
             self.collect_fees();
@@ -3137,6 +3382,7 @@ impl Bank {
 
             // freeze is a one-way trip, idempotent
             self.freeze_started.store(true, Relaxed);
+            self.update_state_root();
             *hash = self.hash_internal_state();
             self.rc.accounts.accounts_db.mark_slot_frozen(self.slot());
         }
@@ -3174,6 +3420,30 @@ impl Bank {
         }
         squash_accounts_time.stop();
 
+        let mut squash_state_tree_time = Measure::start("squash_state_tree_time");
+        let mut rooted_banks = self.parents();
+        rooted_banks.reverse();
+        let rooted_slots: Vec<_> = rooted_banks
+            .iter()
+            .map(|bank| bank.as_ref())
+            .chain([self])
+            .map(|bank| {
+                (
+                    bank.slot(),
+                    bank.state_root(),
+                    bank.state_tree_changes.read().unwrap().clone(),
+                )
+            })
+            .collect();
+        retry_state_tree("commit the rooted state tree", || {
+            self.state_tree.root_slots(
+                rooted_slots
+                    .iter()
+                    .map(|(slot, root, changes)| (*slot, *root, changes.as_ref())),
+            )
+        });
+        squash_state_tree_time.stop();
+
         *self.rc.parent.write().unwrap() = None;
 
         let mut squash_cache_time = Measure::start("squash_cache_time");
@@ -3188,6 +3458,8 @@ impl Bank {
             squash_accounts_cache_ms: total_cache_us / 1000,
             squash_accounts_store_ms: total_store_us / 1000,
 
+            squash_state_tree_ms: squash_state_tree_time.as_ms(),
+
             squash_cache_ms: squash_cache_time.as_ms(),
         }
     }
@@ -3568,6 +3840,19 @@ impl Bank {
         self.update_recent_blockhashes_locked(&w_blockhash_queue);
     }
 
+    /// Like `register_recent_blockhash`, with `lamports_per_signature` as the fee of the
+    /// transactions using `blockhash` instead of the bank's one. Used to restore the recent
+    /// blockhashes of another bank.
+    pub fn register_recent_blockhash_with_fee(
+        &self,
+        blockhash: &Hash,
+        lamports_per_signature: u64,
+    ) {
+        let mut w_blockhash_queue = self.blockhash_queue.write().unwrap();
+        w_blockhash_queue.register_hash(blockhash, lamports_per_signature);
+        self.update_recent_blockhashes_locked(&w_blockhash_queue);
+    }
+
     /// Tell the bank which Entry IDs exist on the ledger. This function assumes subsequent calls
     /// correspond to later entries, and will boot the oldest ones once its internal cache is full.
     /// Once boot, the bank will reject transactions using that `hash`.
@@ -3786,6 +4071,206 @@ impl Bank {
         }
     }
 
+    /// Re-execute a transaction of the block of `block_bank` against this frozen bank, the bank
+    /// the block was built on, without committing the results, and trace the instructions it
+    /// invoked. The transactions preceding it in its block are executed first, their results
+    /// being kept in account overrides.
+    ///
+    /// The transactions see the sysvars of the block: the Clock, Fees and SlotHashes stored by
+    /// `block_bank` when it was created are served through the account overrides and a sysvar
+    /// cache of the trace, and the other sysvars the block saw are the ones of this bank. Nothing
+    /// is stored, neither in this bank nor in the bank of the block. Blocks starting an epoch are
+    /// not supported, as their bank changes accounts other than the sysvars when it is created.
+    pub fn trace_transaction(
+        &self,
+        block_bank: &Bank,
+        preceding_transactions: &[SanitizedTransaction],
+        transaction: SanitizedTransaction,
+    ) -> TransactionTraceResult {
+        assert!(self.is_frozen(), "trace bank must be frozen");
+        assert_eq!(block_bank.parent_slot(), self.slot());
+        assert_eq!(
+            block_bank.epoch(),
+            self.epoch(),
+            "trace bank must be in the epoch of the block"
+        );
+
+        let mut account_overrides = AccountOverrides::default();
+        #[allow(deprecated)]
+        for pubkey in [
+            sysvar::clock::id(),
+            sysvar::fees::id(),
+            sysvar::slot_hashes::id(),
+        ] {
+            account_overrides.set_account(&pubkey, block_bank.get_account_with_fixed_root(&pubkey));
+        }
+        let mut sysvar_cache = SysvarCache::default();
+        sysvar_cache.fill_missing_entries(|pubkey, callback| {
+            if let Some(account) = account_overrides
+                .get(pubkey)
+                .cloned()
+                .or_else(|| self.get_account_with_fixed_root(pubkey))
+            {
+                callback(account.data());
+            }
+        });
+
+        for preceding_transaction in preceding_transactions {
+            self.execute_transaction_into_overrides(
+                preceding_transaction.clone(),
+                &mut account_overrides,
+                &sysvar_cache,
+            );
+        }
+
+        let instruction_tracer = InstructionTracer::new_ref();
+        let batch = self.prepare_simulation_batch(transaction);
+        let mut timings = ExecuteTimings::default();
+
+        let LoadAndExecuteTransactionsOutput {
+            loaded_transactions,
+            mut execution_results,
+            ..
+        } = self.load_and_execute_transactions_with_tracer(
+            &batch,
+            MAX_PROCESSING_AGE,
+            false,
+            true,
+            true,
+            &mut timings,
+            Some(&account_overrides),
+            None,
+            Some(&instruction_tracer),
+            Some(&sysvar_cache),
+        );
+
+        let transaction_accounts = loaded_transactions
+            .into_iter()
+            .next()
+            .unwrap()
+            .0
+            .map(|loaded_transaction| loaded_transaction.accounts)
+            .unwrap_or_default();
+
+        let units_consumed = timings
+            .details
+            .per_program_timings
+            .iter()
+            .fold(0, |acc: u64, (_, program_timing)| {
+                acc.saturating_add(program_timing.accumulated_units)
+            });
+
+        debug!("trace_transaction: {:?}", timings);
+
+        let execution_result = execution_results.pop().unwrap();
+        let flattened_result = execution_result.flattened_result();
+        let (logs, return_data) = match execution_result {
+            TransactionExecutionResult::Executed { details, .. } => {
+                (details.log_messages, details.return_data)
+            }
+            TransactionExecutionResult::NotExecuted(_) => (None, None),
+        };
+        let logs = logs.unwrap_or_default();
+        let instructions = traced_instructions_from_instruction_tracer(
+            &instruction_tracer.borrow(),
+            &transaction_accounts,
+        );
+
+        TransactionTraceResult {
+            result: flattened_result,
+            logs,
+            units_consumed,
+            return_data,
+            instructions,
+        }
+    }
+
+    /// Execute a transaction without committing the results, and keep the accounts it would
+    /// have stored in `account_overrides` for the transactions executed after it
+    fn execute_transaction_into_overrides(
+        &self,
+        transaction: SanitizedTransaction,
+        account_overrides: &mut AccountOverrides,
+        sysvar_cache: &SysvarCache,
+    ) {
+        let batch = self.prepare_simulation_batch(transaction);
+        let mut timings = ExecuteTimings::default();
+
+        let LoadAndExecuteTransactionsOutput {
+            mut loaded_transactions,
+            execution_results,
+            ..
+        } = self.load_and_execute_transactions_with_tracer(
+            &batch,
+            MAX_PROCESSING_AGE,
+            false,
+            false,
+            false,
+            &mut timings,
+            Some(&*account_overrides),
+            None,
+            None,
+            Some(sysvar_cache),
+        );
+
+        let (last_blockhash, lamports_per_signature) =
+            self.last_blockhash_and_lamports_per_signature();
+        let durable_nonce = DurableNonce::from_blockhash(&last_blockhash);
+        let (accounts_to_store, _) = self.rc.accounts.collect_accounts_to_store(
+            batch.sanitized_transactions(),
+            &execution_results,
+            &mut loaded_transactions,
+            &self.rent_collector,
+            &durable_nonce,
+            lamports_per_signature,
+            self.preserve_rent_epoch_for_rent_exempt_accounts(),
+        );
+        for (pubkey, account) in accounts_to_store {
+            // Accounts left without lamports are purged when stored
+            let account = if account.lamports() == 0 {
+                AccountSharedData::default()
+            } else {
+                account.clone()
+            };
+            account_overrides.set_account(pubkey, Some(account));
+        }
+
+        // Fees for failed transactions which don't use durable nonces are
+        // withdrawn in Bank::filter_program_errors_and_collect_fee
+        for (tx, execution_result) in batch
+            .sanitized_transactions()
+            .iter()
+            .zip(&execution_results)
+        {
+            if let Some(TransactionExecutionDetails {
+                status: Err(_),
+                durable_nonce_fee: None,
+                ..
+            }) = execution_result.details()
+            {
+                let message = tx.message();
+                let fee = self
+                    .get_lamports_per_signature_for_blockhash(message.recent_blockhash())
+                    .map(|lamports_per_signature| {
+                        self.get_fee_for_message_with_lamports_per_signature(
+                            message,
+                            lamports_per_signature,
+                        )
+                    })
+                    .unwrap_or_default();
+                let fee_payer = message.fee_payer();
+                let mut account = account_overrides
+                    .get(fee_payer)
+                    .cloned()
+                    .or_else(|| self.get_account_with_fixed_root(fee_payer))
+                    .unwrap_or_default();
+                if account.checked_sub_lamports(fee).is_ok() {
+                    account_overrides.set_account(fee_payer, Some(account));
+                }
+            }
+        }
+    }
+
     fn get_account_overrides_for_simulation(&self, account_keys: &AccountKeys) -> AccountOverrides {
         let mut account_overrides = AccountOverrides::default();
         let slot_history_id = sysvar::slot_history::id();
@@ -4037,6 +4522,8 @@ impl Bank {
         timings: &mut ExecuteTimings,
         error_counters: &mut TransactionErrorMetrics,
         log_messages_bytes_limit: Option<usize>,
+        instruction_tracer: Option<Rc<RefCell<InstructionTracer>>>,
+        sysvar_cache: Option<&SysvarCache>,
     ) -> TransactionExecutionResult {
         let mut get_executors_time = Measure::start("get_executors_time");
         let executors = self.get_executors(&loaded_transaction.accounts);
@@ -4077,6 +4564,8 @@ impl Bank {
         };
 
         let (blockhash, lamports_per_signature) = self.last_blockhash_and_lamports_per_signature();
+        let bank_sysvar_cache = self.sysvar_cache.read().unwrap();
+        let sysvar_cache = sysvar_cache.unwrap_or(&bank_sysvar_cache);
 
         let mut executed_units = 0u64;
 
@@ -4088,17 +4577,19 @@ impl Bank {
             &mut transaction_context,
             self.rent_collector.rent,
             log_collector.clone(),
+            instruction_tracer.clone(),
             executors.clone(),
             self.feature_set.clone(),
             compute_budget,
             timings,
-            &*self.sysvar_cache.read().unwrap(),
+            sysvar_cache,
             blockhash,
             lamports_per_signature,
             prev_accounts_data_len,
             &mut executed_units,
         );
         process_message_time.stop();
+        drop(bank_sysvar_cache);
 
         saturating_add_assign!(
             timings.execute_accessories.process_message_us,
@@ -4161,6 +4652,11 @@ impl Bank {
             accounts_resize_delta,
         } = transaction_context.into();
         loaded_transaction.accounts = accounts;
+        if let Some(instruction_tracer) = instruction_tracer {
+            instruction_tracer
+                .borrow_mut()
+                .set_instruction_trace(instruction_trace.clone());
+        }
         if self
             .feature_set
             .is_active(&enable_early_verification_of_account_modifications::id())
@@ -4226,6 +4722,34 @@ impl Bank {
         timings: &mut ExecuteTimings,
         account_overrides: Option<&AccountOverrides>,
         log_messages_bytes_limit: Option<usize>,
+    ) -> LoadAndExecuteTransactionsOutput {
+        self.load_and_execute_transactions_with_tracer(
+            batch,
+            max_age,
+            enable_cpi_recording,
+            enable_log_recording,
+            enable_return_data_recording,
+            timings,
+            account_overrides,
+            log_messages_bytes_limit,
+            None,
+            None,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn load_and_execute_transactions_with_tracer(
+        &self,
+        batch: &TransactionBatch,
+        max_age: usize,
+        enable_cpi_recording: bool,
+        enable_log_recording: bool,
+        enable_return_data_recording: bool,
+        timings: &mut ExecuteTimings,
+        account_overrides: Option<&AccountOverrides>,
+        log_messages_bytes_limit: Option<usize>,
+        instruction_tracer: Option<&Rc<RefCell<InstructionTracer>>>,
+        sysvar_cache: Option<&SysvarCache>,
     ) -> LoadAndExecuteTransactionsOutput {
         let sanitized_txs = batch.sanitized_transactions();
         debug!("processing transactions: {}", sanitized_txs.len());
@@ -4345,6 +4869,8 @@ impl Bank {
                         timings,
                         &mut error_counters,
                         log_messages_bytes_limit,
+                        instruction_tracer.cloned(),
+                        sysvar_cache,
                     )
                 }
             })
@@ -6162,6 +6688,16 @@ impl Bank {
             .fetch_add(m.as_us(), Relaxed);
     }
 
+    /// Store `accounts` over the ones of the bank, sysvars included, and
+    /// reload the sysvar cache from them
+    pub fn overwrite_accounts(&self, accounts: &[(Pubkey, AccountSharedData)]) {
+        for (pubkey, account) in accounts {
+            self.store_account(pubkey, account);
+        }
+        self.reset_sysvar_cache();
+        self.fill_missing_sysvar_cache_entries();
+    }
+
     pub fn force_flush_accounts_cache(&self) {
         self.rc
             .accounts
@@ -6576,6 +7112,139 @@ impl Bank {
         self.get_signature_status_slot(signature).is_some()
     }
 
+    /// Hash the accounts written in this slot to state tree leaves, and compute
+    /// the state root with them applied on top of the parent's state
+    fn update_state_root(&self) {
+        let leaves = self.rc.accounts.scan_slot(self.slot(), |account| {
+            Some((
+                state_tree::leaf_key(account.pubkey()),
+                state_tree::leaf_value(&account),
+            ))
+        });
+
+        let ancestors: Vec<_> = self
+            .parents()
+            .iter()
+            .map(|bank| (bank.slot(), bank.state_tree_changes.read().unwrap().clone()))
+            .collect();
+        let (state_root, changes) = retry_state_tree("compute the state root", || {
+            self.state_tree.compute_root(
+                self.parent().map(|parent| parent.state_root()),
+                ancestors
+                    .iter()
+                    .map(|(slot, changes)| (*slot, changes.as_ref())),
+                leaves.clone(),
+            )
+        });
+
+        *self.state_tree_changes.write().unwrap() = Arc::new(changes);
+        *self.state_root.write().unwrap() = state_root;
+    }
+
+    /// Load the state tree of a bank loaded from a snapshot. It is resumed
+    /// from `store` if the store committed the tree of the bank's slot, and
+    /// is otherwise rebuilt from the full accounts state and copied to
+    /// `store`, which a missing or stale store costs. If the copy fails, the
+    /// rebuilt tree is kept in memory.
+    fn load_state_tree(
+        &mut self,
+        store: Option<Arc<dyn StateTreeStore>>,
+    ) -> std::result::Result<(), StateTreeError> {
+        let store = match store {
+            Some(store) => store,
+            None => return self.rebuild_state_tree(),
+        };
+        match StateTree::new_from_store(self.slot(), store.clone()) {
+            Ok(Some(state_tree)) => {
+                info!("state tree resumed from store at slot {}", self.slot());
+                self.state_root = RwLock::new(state_tree.rooted_root());
+                self.state_tree = Arc::new(state_tree);
+                return Ok(());
+            }
+            Ok(None) => info!(
+                "state tree store did not commit slot {}, rebuilding the state tree",
+                self.slot()
+            ),
+            Err(err) => warn!(
+                "failed to read the state tree store: {:?}, rebuilding the state tree",
+                err
+            ),
+        }
+        self.rebuild_state_tree()?;
+        if let Err(err) = self.set_state_tree_store(store) {
+            warn!(
+                "failed to copy the state tree to its store: {:?}, keeping it in memory",
+                err
+            );
+        }
+        Ok(())
+    }
+
+    /// Compute the root of the state tree of the parent with the accounts
+    /// written in this slot so far, except the sysvars
+    fn update_transactions_state_root(&self) {
+        let leaves = self.rc.accounts.scan_slot(self.slot(), |account| {
+            (!sysvar::is_sysvar_id(account.pubkey())).then(|| {
+                (
+                    state_tree::leaf_key(account.pubkey()),
+                    state_tree::leaf_value(&account),
+                )
+            })
+        });
+
+        let ancestors: Vec<_> = self
+            .parents()
+            .iter()
+            .map(|bank| (bank.slot(), bank.state_tree_changes.read().unwrap().clone()))
+            .collect();
+        let (transactions_state_root, _) =
+            retry_state_tree("compute the transactions state root", || {
+                self.state_tree.compute_root(
+                    self.parent().map(|parent| parent.state_root()),
+                    ancestors
+                        .iter()
+                        .map(|(slot, changes)| (*slot, changes.as_ref())),
+                    leaves.clone(),
+                )
+            });
+
+        *self.transactions_state_root.write().unwrap() = transactions_state_root;
+    }
+
+    /// Build the state tree of a bank loaded from a snapshot from its full
+    /// accounts state
+    fn rebuild_state_tree(&mut self) -> std::result::Result<(), StateTreeError> {
+        let leaves = self
+            .rc
+            .accounts
+            .accounts_db
+            .scan_accounts(
+                &self.ancestors,
+                self.bank_id,
+                |leaves: &mut Vec<StateTreeLeaf>, option| {
+                    if let Some((pubkey, account, _slot)) = option {
+                        if account.lamports() != 0 {
+                            leaves.push((
+                                state_tree::leaf_key(pubkey),
+                                state_tree::leaf_value(&account),
+                            ));
+                        }
+                    }
+                },
+                &ScanConfig::default(),
+            )
+            .map_err(|err| {
+                StateTreeError::Store(format!(
+                    "failed to scan the accounts to rebuild the state tree: {}",
+                    err
+                ))
+            })?;
+        let state_tree = StateTree::new_from_leaves(self.slot(), leaves)?;
+        self.state_root = RwLock::new(state_tree.rooted_root());
+        self.state_tree = Arc::new(state_tree);
+        Ok(())
+    }
+
     /// Hash the `accounts` HashMap. This represents a validator's interpretation
     ///  of the delta of the ledger since the last vote and up to now
     fn hash_internal_state(&self) -> Hash {
@@ -7647,6 +8316,24 @@ impl Bank {
     }
 }
 
+/// Retry `update` of the state tree until its store does not fail it. Freezing and squashing a
+/// bank can not fail, so a store error stalls them until the store recovers instead of taking the
+/// node down.
+fn retry_state_tree<T>(
+    action: &str,
+    mut update: impl FnMut() -> std::result::Result<T, StateTreeError>,
+) -> T {
+    loop {
+        match update() {
+            Ok(value) => return value,
+            Err(err) => {
+                error!("failed to {}: {:?}, retrying", action, err);
+                std::thread::sleep(STATE_TREE_RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
 /// Compute how much an account has changed size.  This function is useful when the data size delta
 /// needs to be computed and passed to an `update_accounts_data_size_delta` function.
 fn calculate_data_size_delta(old_data_size: usize, new_data_size: usize) -> i64 {
@@ -11382,6 +12069,126 @@ pub(crate) mod tests {
         assert!(bank2.verify_bank_hash(VerifyBankHash::default_for_test()));
     }
 
+    #[test]
+    fn test_bank_state_root() {
+        // The state root of a bank must be the root of the tree of its full state
+        fn full_state_root(bank: &Bank) -> Hash {
+            let leaves = bank
+                .get_all_accounts_with_modified_slots()
+                .unwrap()
+                .into_iter()
+                .filter(|(_, account, _)| account.lamports() != 0)
+                .map(|(pubkey, account, _)| {
+                    (
+                        state_tree::leaf_key(&pubkey),
+                        state_tree::leaf_value(&account),
+                    )
+                })
+                .collect();
+            StateTree::new_from_leaves(bank.slot(), leaves).unwrap().rooted_root()
+        }
+
+        let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        let amount = genesis_config.rent.minimum_balance(0);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        assert_eq!(bank0.state_root(), Hash::default());
+        bank0.freeze();
+        assert_ne!(bank0.state_root(), Hash::default());
+        assert_eq!(bank0.state_root(), full_state_root(&bank0));
+
+        // Sibling banks do not affect each other's state root
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+        let bank2 = Bank::new_from_parent(&bank0, &Pubkey::default(), 2);
+        bank1
+            .transfer(amount, &mint_keypair, &solana_sdk::pubkey::new_rand())
+            .unwrap();
+        bank2
+            .transfer(amount, &mint_keypair, &solana_sdk::pubkey::new_rand())
+            .unwrap();
+        bank1.freeze();
+        bank2.freeze();
+        assert_ne!(bank1.state_root(), bank2.state_root());
+        assert_eq!(bank1.state_root(), full_state_root(&bank1));
+        assert_eq!(bank2.state_root(), full_state_root(&bank2));
+
+        // Rooting a bank applies its leaves and its parents' to the shared tree
+        bank1.squash();
+        assert_eq!(bank1.state_tree.rooted_slot(), Some(1));
+        assert_eq!(bank1.state_tree.rooted_root(), bank1.state_root());
+        let bank3 = Bank::new_from_parent(&bank1, &Pubkey::default(), 3);
+        bank3
+            .transfer(amount, &mint_keypair, &solana_sdk::pubkey::new_rand())
+            .unwrap();
+        bank3.freeze();
+        assert_eq!(bank3.state_root(), full_state_root(&bank3));
+    }
+
+    #[test]
+    fn test_bank_get_account_proof() {
+        let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let mint = mint_keypair.pubkey();
+        assert!(bank0.get_account_proof(&mint).unwrap().is_none());
+        bank0.freeze();
+        let (value, proof) = bank0.get_account_proof(&mint).unwrap().unwrap();
+        assert_eq!(
+            value,
+            state_tree::root_to_hash(&state_tree::leaf_value(&bank0.get_account(&mint).unwrap()))
+        );
+        assert!(!proof.is_empty());
+
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        bank1
+            .transfer(
+                genesis_config.rent.minimum_balance(0),
+                &mint_keypair,
+                &pubkey,
+            )
+            .unwrap();
+        bank1.freeze();
+        // The account is only in the state of the child bank
+        assert_eq!(
+            bank0.get_account_proof(&pubkey).unwrap().unwrap().0,
+            Hash::default()
+        );
+        assert_ne!(
+            bank1.get_account_proof(&pubkey).unwrap().unwrap().0,
+            Hash::default()
+        );
+        let (values, proof) = bank1.get_accounts_proof(&[mint, pubkey]).unwrap().unwrap();
+        assert_eq!(
+            values,
+            vec![
+                bank1.get_account_proof(&mint).unwrap().unwrap().0,
+                bank1.get_account_proof(&pubkey).unwrap().unwrap().0
+            ]
+        );
+        assert!(!proof.is_empty());
+    }
+
+    #[test]
+    fn test_bank_overwrite_accounts() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        let bank = Bank::new_for_tests(&genesis_config);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let account = AccountSharedData::new(42, 0, &Pubkey::default());
+        let clock = sysvar::clock::Clock {
+            slot: 42,
+            ..sysvar::clock::Clock::default()
+        };
+        let clock_account = solana_sdk::account::create_account_shared_data_for_test(&clock);
+        bank.overwrite_accounts(&[
+            (pubkey, account.clone()),
+            (sysvar::clock::id(), clock_account),
+        ]);
+        assert_eq!(bank.get_account(&pubkey), Some(account));
+        assert_eq!(
+            bank.get_sysvar_cache_for_tests().get_clock().unwrap().slot,
+            42
+        );
+    }
+
     #[test]
     fn test_bank_hash_internal_state_verify() {
         solana_logger::setup();
@@ -18534,6 +19341,130 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_trace_transaction() {
+        let leader = Pubkey::new_unique();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(1_000_000, &leader, 42);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let blockhash = bank0.last_blockhash();
+        let mint = bank0.get_balance(&mint_keypair.pubkey());
+
+        let key1 = Pubkey::new_unique();
+        let key2 = Pubkey::new_unique();
+        let tx1 = system_transaction::transfer(&mint_keypair, &key1, 1_000, blockhash);
+        let tx2 = system_transaction::transfer(&mint_keypair, &key2, 2_000, blockhash);
+        let bank1 = Bank::new_from_parent(&bank0, &leader, 1);
+        assert_eq!(bank1.process_transaction(&tx1), Ok(()));
+        assert_eq!(bank1.process_transaction(&tx2), Ok(()));
+        bank1.freeze();
+        let tx1 = SanitizedTransaction::from_transaction_for_tests(tx1);
+        let tx2 = SanitizedTransaction::from_transaction_for_tests(tx2);
+        let fee = bank0.get_fee_for_message(tx1.message()).unwrap();
+
+        let TransactionTraceResult {
+            result,
+            instructions,
+            ..
+        } = bank0.trace_transaction(&bank1, &[tx1], tx2);
+        assert_eq!(result, Ok(()));
+        assert_eq!(instructions.len(), 1);
+        let instruction = &instructions[0];
+        assert_eq!(instruction.stack_height, 1);
+        assert_eq!(instruction.program_id, system_program::id());
+        assert_eq!(instruction.result, Some(Ok(())));
+        assert!(instruction.compute_units_consumed.is_some());
+
+        // The fee payer is seen after the preceding transfer and both fees, and not with the
+        // state left by the block
+        let payer = &instruction.accounts[0];
+        assert_eq!(payer.pubkey, mint_keypair.pubkey());
+        assert!(payer.is_signer && payer.is_writable);
+        assert_eq!(
+            payer.pre.as_ref().map(|account| account.lamports()),
+            Some(mint - 1_000 - 2 * fee)
+        );
+        assert_eq!(
+            payer.post.as_ref().map(|account| account.lamports()),
+            Some(mint - 3_000 - 2 * fee)
+        );
+        let recipient = &instruction.accounts[1];
+        assert_eq!(recipient.pubkey, key2);
+        assert_eq!(
+            recipient.pre.as_ref().map(|account| account.lamports()),
+            Some(0)
+        );
+        assert_eq!(
+            recipient.post.as_ref().map(|account| account.lamports()),
+            Some(2_000)
+        );
+
+        // Nothing was committed, and the block kept its state
+        assert_eq!(bank0.get_balance(&mint_keypair.pubkey()), mint);
+        assert_eq!(bank0.get_balance(&key1), 0);
+        assert_eq!(bank1.get_balance(&key1), 1_000);
+        assert_eq!(bank1.get_balance(&key2), 2_000);
+    }
+
+    fn mock_clock_process_instruction(
+        _first_instruction_account: usize,
+        invoke_context: &mut InvokeContext,
+    ) -> result::Result<(), InstructionError> {
+        let slot = invoke_context.get_sysvar_cache().get_clock()?.slot;
+        let transaction_context = &mut invoke_context.transaction_context;
+        let program_id = *transaction_context
+            .get_current_instruction_context()?
+            .get_last_program_key(transaction_context)?;
+        transaction_context.set_return_data(program_id, slot.to_le_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_trace_transaction_sysvars() {
+        let leader = Pubkey::new_unique();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(1_000_000, &leader, 42);
+        let mut bank0 = Bank::new_for_tests(&genesis_config);
+        let program_id = Pubkey::new_unique();
+        bank0.add_builtin("mock_clock", &program_id, mock_clock_process_instruction);
+        let bank0 = Arc::new(bank0);
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(program_id, &[], vec![])],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            bank0.last_blockhash(),
+        );
+        let bank1 = Bank::new_from_parent(&bank0, &leader, 1);
+        assert_eq!(bank1.process_transaction(&tx), Ok(()));
+        bank1.freeze();
+        let bank1_clock = bank1.clock();
+
+        // The transaction sees the clock of its block, not the one of the bank it is traced in
+        let TransactionTraceResult {
+            result,
+            return_data,
+            ..
+        } = bank0.trace_transaction(
+            &bank1,
+            &[],
+            SanitizedTransaction::from_transaction_for_tests(tx),
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            return_data.map(|return_data| return_data.data),
+            Some(1u64.to_le_bytes().to_vec())
+        );
+
+        // Neither bank was changed by the trace
+        assert_eq!(bank0.clock().slot, 0);
+        assert_eq!(bank1.clock(), bank1_clock);
+    }
+
     #[derive(Serialize, Deserialize)]
     enum MockReallocInstruction {
         Realloc(usize, u64, Pubkey),
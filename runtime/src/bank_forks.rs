@@ -35,6 +35,7 @@ struct SetRootTimings {
     total_squash_accounts_index_ms: i64,
     total_squash_accounts_cache_ms: i64,
     total_squash_accounts_store_ms: i64,
+    total_squash_state_tree_ms: i64,
     total_snapshot_ms: i64,
     prune_non_rooted_ms: i64,
     drop_parent_banks_ms: i64,
@@ -256,6 +257,7 @@ impl BankForks {
         let mut total_squash_accounts_index_ms = 0;
         let mut total_squash_accounts_cache_ms = 0;
         let mut total_squash_accounts_store_ms = 0;
+        let mut total_squash_state_tree_ms = 0;
         let mut total_squash_cache_ms = 0;
         let mut total_snapshot_ms = 0;
         for bank in banks.iter() {
@@ -269,6 +271,7 @@ impl BankForks {
                 total_squash_accounts_index_ms += squash_timing.squash_accounts_index_ms as i64;
                 total_squash_accounts_cache_ms += squash_timing.squash_accounts_cache_ms as i64;
                 total_squash_accounts_store_ms += squash_timing.squash_accounts_store_ms as i64;
+                total_squash_state_tree_ms += squash_timing.squash_state_tree_ms as i64;
                 total_squash_cache_ms += squash_timing.squash_cache_ms as i64;
                 is_root_bank_squashed = bank_slot == root;
 
@@ -312,6 +315,7 @@ impl BankForks {
             total_squash_accounts_index_ms += squash_timing.squash_accounts_index_ms as i64;
             total_squash_accounts_cache_ms += squash_timing.squash_accounts_cache_ms as i64;
             total_squash_accounts_store_ms += squash_timing.squash_accounts_store_ms as i64;
+            total_squash_state_tree_ms += squash_timing.squash_state_tree_ms as i64;
             total_squash_cache_ms += squash_timing.squash_cache_ms as i64;
         }
         let new_tx_count = root_bank.transaction_count();
@@ -335,6 +339,7 @@ impl BankForks {
                     total_squash_accounts_index_ms,
                     total_squash_accounts_cache_ms,
                     total_squash_accounts_store_ms,
+                    total_squash_state_tree_ms,
                     total_snapshot_ms,
                     prune_non_rooted_ms: prune_time.as_ms() as i64,
                     drop_parent_banks_ms: drop_parent_banks_time.as_ms() as i64,
@@ -400,6 +405,11 @@ impl BankForks {
                 set_root_metrics.timings.total_squash_accounts_store_ms,
                 i64
             ),
+            (
+                "total_squash_state_tree_ms",
+                set_root_metrics.timings.total_squash_state_tree_ms,
+                i64
+            ),
             (
                 "total_snapshot_ms",
                 set_root_metrics.timings.total_snapshot_ms,
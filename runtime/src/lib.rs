@@ -56,6 +56,7 @@ pub mod runtime_config;
 pub mod secondary_index;
 pub mod serde_snapshot;
 mod shared_buffer_reader;
+pub mod slot_header;
 pub mod snapshot_archive_info;
 pub mod snapshot_config;
 pub mod snapshot_hash;
@@ -67,6 +68,7 @@ mod stake_account;
 pub mod stake_history;
 pub mod stake_weighted_timestamp;
 pub mod stakes;
+pub mod state_tree;
 pub mod static_ids;
 pub mod status_cache;
 mod storable_accounts;
@@ -4,6 +4,7 @@ use {
     solana_program_runtime::{
         compute_budget::ComputeBudget,
         executor_cache::Executors,
+        instruction_tracer::InstructionTracer,
         invoke_context::{BuiltinProgram, InvokeContext},
         log_collector::LogCollector,
         sysvar_cache::SysvarCache,
@@ -57,6 +58,7 @@ impl MessageProcessor {
         transaction_context: &mut TransactionContext,
         rent: Rent,
         log_collector: Option<Rc<RefCell<LogCollector>>>,
+        instruction_tracer: Option<Rc<RefCell<InstructionTracer>>>,
         executors: Rc<RefCell<Executors>>,
         feature_set: Arc<FeatureSet>,
         compute_budget: ComputeBudget,
@@ -73,6 +75,7 @@ impl MessageProcessor {
             builtin_programs,
             Cow::Borrowed(sysvar_cache),
             log_collector,
+            instruction_tracer,
             compute_budget,
             executors,
             feature_set,
@@ -295,6 +298,7 @@ mod tests {
             &mut transaction_context,
             rent_collector.rent,
             None,
+            None,
             executors.clone(),
             Arc::new(FeatureSet::all_enabled()),
             ComputeBudget::default(),
@@ -344,6 +348,7 @@ mod tests {
             &mut transaction_context,
             rent_collector.rent,
             None,
+            None,
             executors.clone(),
             Arc::new(FeatureSet::all_enabled()),
             ComputeBudget::default(),
@@ -383,6 +388,7 @@ mod tests {
             &mut transaction_context,
             rent_collector.rent,
             None,
+            None,
             executors,
             Arc::new(FeatureSet::all_enabled()),
             ComputeBudget::default(),
@@ -520,6 +526,7 @@ mod tests {
             &mut transaction_context,
             rent_collector.rent,
             None,
+            None,
             executors.clone(),
             Arc::new(FeatureSet::all_enabled()),
             ComputeBudget::default(),
@@ -554,6 +561,7 @@ mod tests {
             &mut transaction_context,
             rent_collector.rent,
             None,
+            None,
             executors.clone(),
             Arc::new(FeatureSet::all_enabled()),
             ComputeBudget::default(),
@@ -585,6 +593,7 @@ mod tests {
             &mut transaction_context,
             rent_collector.rent,
             None,
+            None,
             executors,
             Arc::new(FeatureSet::all_enabled()),
             ComputeBudget::default(),
@@ -665,6 +674,7 @@ mod tests {
             &mut transaction_context,
             RentCollector::default().rent,
             None,
+            None,
             Rc::new(RefCell::new(Executors::default())),
             Arc::new(FeatureSet::all_enabled()),
             ComputeBudget::default(),
@@ -17,6 +17,7 @@ use {
         serde_snapshot::storage::SerializableAccountStorageEntry,
         snapshot_utils::{self, BANK_SNAPSHOT_PRE_FILENAME_EXTENSION},
         stakes::Stakes,
+        state_tree::StateTreeStore,
     },
     bincode::{self, config::Options, Error},
     log::*,
@@ -296,6 +297,7 @@ pub(crate) fn bank_from_streams<R>(
     verify_index: bool,
     accounts_db_config: Option<AccountsDbConfig>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    state_tree_store: Option<Arc<dyn StateTreeStore>>,
 ) -> std::result::Result<Bank, Error>
 where
     R: Read,
@@ -316,6 +318,7 @@ where
         verify_index,
         accounts_db_config,
         accounts_update_notifier,
+        state_tree_store,
     )
 }
 
@@ -504,6 +507,7 @@ fn reconstruct_bank_from_fields<E>(
     verify_index: bool,
     accounts_db_config: Option<AccountsDbConfig>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    state_tree_store: Option<Arc<dyn StateTreeStore>>,
 ) -> Result<Bank, Error>
 where
     E: SerializableStorage + std::marker::Sync,
@@ -534,7 +538,14 @@ where
         additional_builtins,
         debug_do_not_add_builtins,
         reconstructed_accounts_db_info.accounts_data_len,
-    );
+        state_tree_store,
+    )
+    .map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("failed to load the state tree: {}", err),
+        )
+    })?;
 
     info!("rent_collector: {:?}", bank.rent_collector());
 
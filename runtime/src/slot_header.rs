@@ -0,0 +1,84 @@
+//! The header of a slot, which the sequencer signs when it freezes the bank of
+//! the slot. The chain of signed headers lets light nodes follow the state of
+//! the rollup without replaying it.
+use {
+    crate::bank::Bank,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        clock::Slot,
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotHeader {
+    pub slot: Slot,
+    pub parent_slot: Slot,
+    pub blockhash: Hash,
+    pub bank_hash: Hash,
+    pub state_root: Hash,
+    /// The state root the transactions of the slot are proven against, see
+    /// `Bank::transactions_state_root`
+    pub transactions_state_root: Hash,
+}
+
+impl SlotHeader {
+    /// The header of the frozen `bank`
+    pub fn new(bank: &Bank) -> Self {
+        Self {
+            slot: bank.slot(),
+            parent_slot: bank.parent_slot(),
+            blockhash: bank.last_blockhash(),
+            bank_hash: bank.hash(),
+            state_root: bank.state_root(),
+            transactions_state_root: bank.transactions_state_root(),
+        }
+    }
+
+    /// The data covered by the signature of the sequencer
+    pub fn signable_data(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    pub fn sign(&self, keypair: &Keypair) -> Signature {
+        keypair.sign_message(&self.signable_data())
+    }
+
+    pub fn verify(&self, pubkey: &Pubkey, signature: &Signature) -> bool {
+        signature.verify(pubkey.as_ref(), &self.signable_data())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::genesis_utils::create_genesis_config, std::sync::Arc};
+
+    #[test]
+    fn test_slot_header() {
+        let genesis_config = create_genesis_config(1_000_000).genesis_config;
+        let parent = Arc::new(Bank::new_for_tests(&genesis_config));
+        let bank = Bank::new_from_parent(&parent, &Pubkey::default(), 2);
+        bank.freeze();
+        let header = SlotHeader::new(&bank);
+        assert_eq!(header.slot, 2);
+        assert_eq!(header.parent_slot, 0);
+        assert_eq!(header.bank_hash, bank.hash());
+        assert_eq!(header.state_root, bank.state_root());
+        assert_eq!(
+            header.transactions_state_root,
+            bank.transactions_state_root()
+        );
+
+        let keypair = Keypair::new();
+        let signature = header.sign(&keypair);
+        assert!(header.verify(&keypair.pubkey(), &signature));
+        assert!(!header.verify(&Pubkey::new_unique(), &signature));
+        let other_header = SlotHeader {
+            state_root: Hash::default(),
+            ..header
+        };
+        assert!(!other_header.verify(&keypair.pubkey(), &signature));
+    }
+}
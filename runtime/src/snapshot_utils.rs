@@ -18,6 +18,7 @@ use {
         snapshot_package::{
             AccountsPackage, PendingAccountsPackage, SnapshotPackage, SnapshotType,
         },
+        state_tree::StateTreeStore,
         status_cache,
     },
     bincode::{config::Options, serialize_into},
@@ -939,6 +940,7 @@ pub fn bank_from_snapshot_archives(
     verify_index: bool,
     accounts_db_config: Option<AccountsDbConfig>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    state_tree_store: Option<Arc<dyn StateTreeStore>>,
 ) -> Result<(Bank, BankFromArchiveTimings)> {
     let (unarchived_full_snapshot, mut unarchived_incremental_snapshot) =
         verify_and_unarchive_snapshots(
@@ -975,6 +977,7 @@ pub fn bank_from_snapshot_archives(
         verify_index,
         accounts_db_config,
         accounts_update_notifier,
+        state_tree_store,
     )?;
     measure_rebuild.stop();
     info!("{}", measure_rebuild);
@@ -1022,6 +1025,7 @@ pub fn bank_from_latest_snapshot_archives(
     verify_index: bool,
     accounts_db_config: Option<AccountsDbConfig>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    state_tree_store: Option<Arc<dyn StateTreeStore>>,
 ) -> Result<(
     Bank,
     FullSnapshotArchiveInfo,
@@ -1065,6 +1069,7 @@ pub fn bank_from_latest_snapshot_archives(
         verify_index,
         accounts_db_config,
         accounts_update_notifier,
+        state_tree_store,
     )?;
 
     datapoint_info!(
@@ -1682,6 +1687,7 @@ fn rebuild_bank_from_snapshots(
     verify_index: bool,
     accounts_db_config: Option<AccountsDbConfig>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    state_tree_store: Option<Arc<dyn StateTreeStore>>,
 ) -> Result<Bank> {
     let (full_snapshot_version, full_snapshot_root_paths) =
         verify_unpacked_snapshots_dir_and_version(
@@ -1730,6 +1736,7 @@ fn rebuild_bank_from_snapshots(
                     verify_index,
                     accounts_db_config,
                     accounts_update_notifier,
+                    state_tree_store,
                 ),
             }?,
         )
@@ -2295,7 +2302,11 @@ fn can_submit_accounts_package(
 mod tests {
     use {
         super::*,
-        crate::{accounts_db::ACCOUNTS_DB_CONFIG_FOR_TESTING, status_cache::Status},
+        crate::{
+            accounts_db::ACCOUNTS_DB_CONFIG_FOR_TESTING,
+            state_tree::{self, MemoryStateTreeStore, StateTreeChanges},
+            status_cache::Status,
+        },
         assert_matches::assert_matches,
         bincode::{deserialize_from, serialize_into},
         solana_sdk::{
@@ -2306,6 +2317,7 @@ mod tests {
             system_transaction,
             transaction::SanitizedTransaction,
         },
+        sparse_merkle_tree::H256,
         std::{convert::TryFrom, mem::size_of},
         tempfile::NamedTempFile,
     };
@@ -3230,10 +3242,99 @@ mod tests {
             false,
             Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
             None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(original_bank, roundtrip_bank);
+    }
+
+    /// Test the state tree of a bank loaded from a snapshot is resumed from a store which
+    /// committed the bank's slot, and rebuilt into a store which did not
+    #[test]
+    fn test_bank_from_snapshot_archives_state_tree_store() {
+        solana_logger::setup();
+        let genesis_config = GenesisConfig::default();
+        let original_bank = Bank::new_for_tests(&genesis_config);
+
+        while !original_bank.is_complete() {
+            original_bank.register_tick(&Hash::new_unique());
+        }
+
+        let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+        let full_snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        let incremental_snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+
+        let snapshot_archive_info = bank_to_full_snapshot_archive(
+            &bank_snapshots_dir,
+            &original_bank,
+            None,
+            full_snapshot_archives_dir.path(),
+            incremental_snapshot_archives_dir.path(),
+            ArchiveFormat::Tar,
+            DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
         )
         .unwrap();
 
+        let load_bank = |store: Arc<MemoryStateTreeStore>| {
+            let accounts_dir = tempfile::TempDir::new().unwrap();
+            bank_from_snapshot_archives(
+                &[PathBuf::from(accounts_dir.path())],
+                bank_snapshots_dir.path(),
+                &snapshot_archive_info,
+                None,
+                &genesis_config,
+                None,
+                None,
+                AccountSecondaryIndexes::default(),
+                false,
+                None,
+                AccountShrinkThreshold::default(),
+                false,
+                false,
+                false,
+                Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
+                None,
+                Some(store),
+            )
+            .unwrap()
+            .0
+        };
+
+        // A stale store is rewritten with the rebuilt tree
+        let store = Arc::new(MemoryStateTreeStore::default());
+        store
+            .commit(
+                original_bank.slot() + 1,
+                H256::from([1; 32]),
+                &StateTreeChanges::default(),
+            )
+            .unwrap();
+        let roundtrip_bank = load_bank(store.clone());
         assert_eq!(original_bank, roundtrip_bank);
+        assert_eq!(
+            store.committed().unwrap(),
+            Some((
+                original_bank.slot(),
+                state_tree::hash_to_root(&original_bank.state_root())
+            ))
+        );
+
+        // A store which committed the bank's slot is trusted without scanning the accounts
+        let store = Arc::new(MemoryStateTreeStore::default());
+        store
+            .commit(
+                original_bank.slot(),
+                H256::from([2; 32]),
+                &StateTreeChanges::default(),
+            )
+            .unwrap();
+        let roundtrip_bank = load_bank(store);
+        assert_eq!(
+            roundtrip_bank.state_root(),
+            state_tree::root_to_hash(&H256::from([2; 32]))
+        );
     }
 
     /// Test roundtrip of bank to a full snapshot, then back again.  This test is more involved
@@ -3341,6 +3442,7 @@ mod tests {
             false,
             Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
             None,
+            None,
         )
         .unwrap();
 
@@ -3472,6 +3574,7 @@ mod tests {
             false,
             Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
             None,
+            None,
         )
         .unwrap();
 
@@ -3593,6 +3696,7 @@ mod tests {
             false,
             Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
             None,
+            None,
         )
         .unwrap();
 
@@ -3735,6 +3839,7 @@ mod tests {
             false,
             Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
             None,
+            None,
         )
         .unwrap();
         assert_eq!(
@@ -3798,6 +3903,7 @@ mod tests {
             false,
             Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
             None,
+            None,
         )
         .unwrap();
         assert_eq!(
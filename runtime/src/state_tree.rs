@@ -0,0 +1,774 @@
+//! The sparse merkle tree a bank's state root commits to.
+//!
+//! Every account with lamports is a leaf of the tree, keyed by the blake3 hash
+//! of its pubkey, with the blake3 hash of the account as value. The tree of
+//! the rooted state is shared by all the banks descending from the root. A
+//! bank computes its state root at freeze by applying the accounts written in
+//! its slot on top of the tree nodes changed by its unrooted ancestors, so the
+//! root only depends on the bank's state and not on the order the accounts
+//! were written in. Rooting a bank commits the changes of its slot and of its
+//! ancestors to the `StateTreeStore` of the shared tree, which can persist it
+//! across restarts.
+use {
+    solana_sdk::{account::ReadableAccount, clock::Slot, hash::Hash, pubkey::Pubkey},
+    sparse_merkle_tree::{
+        blake2b::Blake2bHasher,
+        default_store::DefaultStore,
+        error::Error,
+        merge::MergeValue,
+        traits::{StoreReadOps, StoreWriteOps},
+        BranchKey, BranchNode, CompiledMerkleProof, SparseMerkleTree, H256,
+    },
+    std::{
+        collections::HashMap,
+        fmt,
+        sync::{Arc, RwLock},
+    },
+};
+
+pub use sparse_merkle_tree::error::Error as StateTreeError;
+
+type Tree<S> = SparseMerkleTree<Blake2bHasher, H256, S>;
+
+/// A leaf of the state tree, the hash of the account pubkey and the hash of
+/// the account
+pub type StateTreeLeaf = (H256, H256);
+
+/// Hash an account pubkey to the key of its leaf
+pub fn leaf_key(pubkey: &Pubkey) -> H256 {
+    H256::from(*blake3::hash(pubkey.as_ref()).as_bytes())
+}
+
+/// Hash an account to the value of its leaf. Accounts without lamports hash
+/// to zero, which removes them from the tree.
+pub fn leaf_value(account: &impl ReadableAccount) -> H256 {
+    if account.lamports() == 0 {
+        return H256::zero();
+    }
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&account.lamports().to_le_bytes());
+    hasher.update(&account.rent_epoch().to_le_bytes());
+    hasher.update(account.owner().as_ref());
+    hasher.update(&[account.executable() as u8]);
+    hasher.update(account.data());
+    H256::from(*hasher.finalize().as_bytes())
+}
+
+/// Convert a root of the state tree to a `Hash`
+pub fn root_to_hash(root: &H256) -> Hash {
+    Hash::new_from_array((*root).into())
+}
+
+/// Convert a `Hash` to a root of the state tree
+pub fn hash_to_root(hash: &Hash) -> H256 {
+    H256::from(hash.to_bytes())
+}
+
+/// The nodes of the state tree written in a slot, removed nodes are kept as
+/// `None`
+#[derive(Debug, Default)]
+pub struct StateTreeChanges {
+    pub branches: HashMap<BranchKey, Option<BranchNode>>,
+    pub leaves: HashMap<H256, Option<H256>>,
+}
+
+impl StateTreeChanges {
+    /// Apply the newer `changes` on top of these ones
+    fn extend(&mut self, changes: &StateTreeChanges) {
+        self.branches.extend(
+            changes
+                .branches
+                .iter()
+                .map(|(key, branch)| (key.clone(), branch.clone())),
+        );
+        self.leaves.extend(changes.leaves.iter());
+    }
+}
+
+const MERGE_VALUE_TAG: u8 = 0;
+const MERGE_WITH_ZERO_TAG: u8 = 1;
+
+fn serialize_merge_value(value: &MergeValue, bytes: &mut Vec<u8>) {
+    match value {
+        MergeValue::Value(value) => {
+            bytes.push(MERGE_VALUE_TAG);
+            bytes.extend_from_slice(value.as_slice());
+        }
+        MergeValue::MergeWithZero {
+            base_node,
+            zero_bits,
+            zero_count,
+        } => {
+            bytes.push(MERGE_WITH_ZERO_TAG);
+            bytes.extend_from_slice(base_node.as_slice());
+            bytes.extend_from_slice(zero_bits.as_slice());
+            bytes.push(*zero_count);
+        }
+    }
+}
+
+fn deserialize_merge_value(bytes: &[u8]) -> Result<(MergeValue, &[u8]), Error> {
+    let read_h256 = |bytes: &[u8]| -> Result<H256, Error> {
+        let bytes: [u8; 32] = bytes
+            .get(..32)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| Error::Store("truncated state tree branch".to_string()))?;
+        Ok(H256::from(bytes))
+    };
+    match bytes.split_first() {
+        Some((&MERGE_VALUE_TAG, rest)) => Ok((MergeValue::Value(read_h256(rest)?), &rest[32..])),
+        Some((&MERGE_WITH_ZERO_TAG, rest)) if rest.len() >= 65 => Ok((
+            MergeValue::MergeWithZero {
+                base_node: read_h256(rest)?,
+                zero_bits: read_h256(&rest[32..])?,
+                zero_count: rest[64],
+            },
+            &rest[65..],
+        )),
+        _ => Err(Error::Store("invalid state tree branch".to_string())),
+    }
+}
+
+/// Serialize a branch of the state tree, for the stores persisting it as bytes
+pub fn serialize_branch(branch: &BranchNode) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(2 * 66);
+    serialize_merge_value(&branch.left, &mut bytes);
+    serialize_merge_value(&branch.right, &mut bytes);
+    bytes
+}
+
+/// Deserialize a branch serialized by `serialize_branch`
+pub fn deserialize_branch(bytes: &[u8]) -> Result<BranchNode, Error> {
+    let (left, rest) = deserialize_merge_value(bytes)?;
+    let (right, rest) = deserialize_merge_value(rest)?;
+    if !rest.is_empty() {
+        return Err(Error::Store("invalid state tree branch".to_string()));
+    }
+    Ok(BranchNode { left, right })
+}
+
+/// A store of the nodes of the rooted state tree
+pub trait StateTreeStore: StoreReadOps<H256> + fmt::Debug + Send + Sync {
+    /// Return the slot and root of the last committed tree, `None` if the
+    /// store is empty
+    fn committed(&self) -> Result<Option<(Slot, H256)>, Error>;
+
+    /// Atomically apply `changes` and record the tree is rooted at `slot`
+    /// with `root`
+    fn commit(&self, slot: Slot, root: H256, changes: &StateTreeChanges) -> Result<(), Error>;
+
+    /// Return every node of the store, as the changes building the tree from
+    /// an empty store
+    fn nodes(&self) -> Result<StateTreeChanges, Error>;
+
+    /// Remove every node and the committed root
+    fn clear(&self) -> Result<(), Error>;
+}
+
+/// Keeps the rooted state tree in memory, it has to be rebuilt from the
+/// accounts on restart
+#[derive(Debug, Default)]
+pub struct MemoryStateTreeStore {
+    committed: RwLock<Option<(Slot, H256)>>,
+    store: RwLock<DefaultStore<H256>>,
+}
+
+impl StoreReadOps<H256> for MemoryStateTreeStore {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, Error> {
+        self.store.read().unwrap().get_branch(branch_key)
+    }
+
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<H256>, Error> {
+        self.store.read().unwrap().get_leaf(leaf_key)
+    }
+}
+
+impl StateTreeStore for MemoryStateTreeStore {
+    fn committed(&self) -> Result<Option<(Slot, H256)>, Error> {
+        Ok(*self.committed.read().unwrap())
+    }
+
+    fn commit(&self, slot: Slot, root: H256, changes: &StateTreeChanges) -> Result<(), Error> {
+        let mut store = self.store.write().unwrap();
+        for (branch_key, branch) in &changes.branches {
+            match branch {
+                Some(branch) => store.insert_branch(branch_key.clone(), branch.clone())?,
+                None => store.remove_branch(branch_key)?,
+            }
+        }
+        for (leaf_key, leaf) in &changes.leaves {
+            match leaf {
+                Some(leaf) => store.insert_leaf(*leaf_key, *leaf)?,
+                None => store.remove_leaf(leaf_key)?,
+            }
+        }
+        *self.committed.write().unwrap() = Some((slot, root));
+        Ok(())
+    }
+
+    fn nodes(&self) -> Result<StateTreeChanges, Error> {
+        let store = self.store.read().unwrap();
+        Ok(StateTreeChanges {
+            branches: store
+                .branches_map()
+                .iter()
+                .map(|(key, branch)| (key.clone(), Some(branch.clone())))
+                .collect(),
+            leaves: store
+                .leaves_map()
+                .iter()
+                .map(|(key, leaf)| (*key, Some(*leaf)))
+                .collect(),
+        })
+    }
+
+    fn clear(&self) -> Result<(), Error> {
+        self.store.write().unwrap().clear();
+        *self.committed.write().unwrap() = None;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct RootedStateTree {
+    /// The last slot applied to the tree, `None` for the empty tree
+    slot: Option<Slot>,
+    root: H256,
+    store: Arc<dyn StateTreeStore>,
+}
+
+impl Default for RootedStateTree {
+    fn default() -> Self {
+        Self {
+            slot: None,
+            root: H256::zero(),
+            store: Arc::<MemoryStateTreeStore>::default(),
+        }
+    }
+}
+
+impl RootedStateTree {
+    fn is_unrooted(&self, slot: Slot) -> bool {
+        self.slot
+            .map(|rooted_slot| slot > rooted_slot)
+            .unwrap_or(true)
+    }
+}
+
+/// The state tree of the rooted bank
+#[derive(Debug, Default)]
+pub struct StateTree {
+    rooted: RwLock<RootedStateTree>,
+}
+
+impl StateTree {
+    /// Create the tree of the full state of the bank at `slot`, in memory
+    pub fn new_from_leaves(slot: Slot, leaves: Vec<StateTreeLeaf>) -> Result<Self, Error> {
+        let mut tree = Tree::default();
+        tree.update_all(leaves)?;
+        let root = *tree.root();
+        Ok(Self {
+            rooted: RwLock::new(RootedStateTree {
+                slot: Some(slot),
+                root,
+                store: Arc::new(MemoryStateTreeStore {
+                    committed: RwLock::new(Some((slot, root))),
+                    store: RwLock::new(tree.take_store()),
+                }),
+            }),
+        })
+    }
+
+    /// Resume the tree of the rooted bank at `slot` from `store`, without
+    /// reading the accounts state. Returns `None` if `store` did not commit
+    /// the tree of `slot`, and then it has to be rebuilt.
+    pub fn new_from_store(
+        slot: Slot,
+        store: Arc<dyn StateTreeStore>,
+    ) -> Result<Option<Self>, Error> {
+        Ok(match store.committed()? {
+            Some((committed_slot, root)) if committed_slot == slot => Some(Self {
+                rooted: RwLock::new(RootedStateTree {
+                    slot: Some(slot),
+                    root,
+                    store,
+                }),
+            }),
+            _ => None,
+        })
+    }
+
+    pub fn rooted_slot(&self) -> Option<Slot> {
+        self.rooted.read().unwrap().slot
+    }
+
+    pub fn rooted_root(&self) -> Hash {
+        root_to_hash(&self.rooted.read().unwrap().root)
+    }
+
+    /// Keep the rooted tree in `store`. The tree is resumed from `store` if it
+    /// committed the rooted slot and root, otherwise the store is cleared and
+    /// the rooted tree is copied to it. Returns whether the tree was resumed.
+    pub fn set_store(&self, store: Arc<dyn StateTreeStore>) -> Result<bool, Error> {
+        let mut rooted = self.rooted.write().unwrap();
+        let resumed = store.committed()? == rooted.slot.map(|slot| (slot, rooted.root));
+        if !resumed {
+            store.clear()?;
+            if let Some(slot) = rooted.slot {
+                store.commit(slot, rooted.root, &rooted.store.nodes()?)?;
+            }
+        }
+        rooted.store = store;
+        Ok(resumed)
+    }
+
+    /// Compute the root of the tree with `leaves` applied on top of the state
+    /// with root `parent_root`, `None` for the rooted state. `ancestors` are
+    /// the changes of the slots between the rooted state and the parent state,
+    /// newest first, the slots already rooted are skipped. Returns the root and
+    /// the changes `leaves` made to the tree, without modifying the rooted tree.
+    /// Fails if the store failed to read the nodes `leaves` change.
+    pub fn compute_root<'a>(
+        &self,
+        parent_root: Option<Hash>,
+        ancestors: impl IntoIterator<Item = (Slot, &'a StateTreeChanges)>,
+        leaves: Vec<StateTreeLeaf>,
+    ) -> Result<(Hash, StateTreeChanges), Error> {
+        let rooted = self.rooted.read().unwrap();
+        let layers = ancestors
+            .into_iter()
+            .filter(|(slot, _)| rooted.is_unrooted(*slot))
+            .map(|(_, changes)| changes)
+            .collect();
+        let parent_root = parent_root
+            .map(|root| hash_to_root(&root))
+            .unwrap_or(rooted.root);
+        let mut tree = Tree::new(
+            parent_root,
+            OverlayStore::new(layers, rooted.store.as_ref()),
+        );
+        tree.update_all(leaves)?;
+        Ok((root_to_hash(tree.root()), tree.take_store().changes))
+    }
+
+    /// Return the values of the leaves of `keys` in the state of `slot` with
+    /// root `root`, zero if there is no such leaf, and the compiled merkle
+    /// proof of them. `changes` are the changes of `slot` and of its
+    /// ancestors, newest first. Returns `None` if `slot` is older than the
+    /// rooted state.
+    pub fn merkle_proof<'a>(
+        &self,
+        slot: Slot,
+        root: Hash,
+        changes: impl IntoIterator<Item = (Slot, &'a StateTreeChanges)>,
+        keys: Vec<H256>,
+    ) -> Result<Option<(Vec<H256>, CompiledMerkleProof)>, Error> {
+        let rooted = self.rooted.read().unwrap();
+        if rooted.slot.map(|rooted_slot| slot < rooted_slot) == Some(true) {
+            return Ok(None);
+        }
+        let layers = changes
+            .into_iter()
+            .filter(|(slot, _)| rooted.is_unrooted(*slot))
+            .map(|(_, changes)| changes)
+            .collect();
+        let tree = Tree::new(
+            hash_to_root(&root),
+            OverlayStore::new(layers, rooted.store.as_ref()),
+        );
+        let values = keys
+            .iter()
+            .map(|key| tree.get(key))
+            .collect::<Result<_, _>>()?;
+        let proof = tree.merkle_proof(keys.clone())?.compile(keys)?;
+        Ok(Some((values, proof)))
+    }
+
+    /// Commit the changes of the rooted `slots` to the store, in ascending
+    /// order. The slots already rooted are skipped. If the store fails to
+    /// commit them, the rooted tree is left as it was.
+    pub fn root_slots<'a>(
+        &self,
+        slots: impl IntoIterator<Item = (Slot, Hash, &'a StateTreeChanges)>,
+    ) -> Result<(), Error> {
+        let mut rooted = self.rooted.write().unwrap();
+        let mut root = None;
+        let mut changes = StateTreeChanges::default();
+        for (slot, slot_root, slot_changes) in slots {
+            if rooted.is_unrooted(slot) {
+                changes.extend(slot_changes);
+                root = Some((slot, hash_to_root(&slot_root)));
+            }
+        }
+        if let Some((slot, root)) = root {
+            rooted.store.commit(slot, root, &changes)?;
+            rooted.slot = Some(slot);
+            rooted.root = root;
+        }
+        Ok(())
+    }
+}
+
+/// Buffers the changes to a store on top of the changes of the ancestor slots
+struct OverlayStore<'a> {
+    /// The changes of the unrooted ancestors, newest first
+    layers: Vec<&'a StateTreeChanges>,
+    base: &'a dyn StateTreeStore,
+    changes: StateTreeChanges,
+}
+
+impl<'a> OverlayStore<'a> {
+    fn new(layers: Vec<&'a StateTreeChanges>, base: &'a dyn StateTreeStore) -> Self {
+        Self {
+            layers,
+            base,
+            changes: StateTreeChanges::default(),
+        }
+    }
+}
+
+impl<'a> StoreReadOps<H256> for OverlayStore<'a> {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, Error> {
+        match std::iter::once(&self.changes)
+            .chain(self.layers.iter().copied())
+            .find_map(|changes| changes.branches.get(branch_key))
+        {
+            Some(branch) => Ok(branch.clone()),
+            None => self.base.get_branch(branch_key),
+        }
+    }
+
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<H256>, Error> {
+        match std::iter::once(&self.changes)
+            .chain(self.layers.iter().copied())
+            .find_map(|changes| changes.leaves.get(leaf_key))
+        {
+            Some(leaf) => Ok(*leaf),
+            None => self.base.get_leaf(leaf_key),
+        }
+    }
+}
+
+impl<'a> StoreWriteOps<H256> for OverlayStore<'a> {
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<(), Error> {
+        self.changes.branches.insert(branch_key, Some(branch));
+        Ok(())
+    }
+
+    fn insert_leaf(&mut self, leaf_key: H256, leaf: H256) -> Result<(), Error> {
+        self.changes.leaves.insert(leaf_key, Some(leaf));
+        Ok(())
+    }
+
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<(), Error> {
+        self.changes.branches.insert(branch_key.clone(), None);
+        Ok(())
+    }
+
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<(), Error> {
+        self.changes.leaves.insert(*leaf_key, None);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::account::AccountSharedData,
+        std::sync::atomic::{AtomicBool, Ordering},
+    };
+
+    fn new_leaf(lamports: u64) -> StateTreeLeaf {
+        let account = AccountSharedData::new(lamports, 0, &Pubkey::default());
+        (leaf_key(&Pubkey::new_unique()), leaf_value(&account))
+    }
+
+    #[test]
+    fn test_leaf_value_of_zero_lamport_account() {
+        let account = AccountSharedData::new(0, 10, &Pubkey::new_unique());
+        assert_eq!(leaf_value(&account), H256::zero());
+        let account = AccountSharedData::new(1, 10, &Pubkey::new_unique());
+        assert_ne!(leaf_value(&account), H256::zero());
+    }
+
+    #[test]
+    fn test_compute_root_matches_rooted_tree() {
+        let state_tree = StateTree::default();
+        assert_eq!(state_tree.rooted_root(), Hash::default());
+
+        let slot1 = vec![new_leaf(1), new_leaf(2)];
+        // Remove an account written in slot 1
+        let slot2 = vec![new_leaf(3), (slot1[0].0, H256::zero())];
+
+        let (root1, changes1) = state_tree
+            .compute_root(None, vec![], slot1.clone())
+            .unwrap();
+        let (root2, changes2) = state_tree
+            .compute_root(Some(root1), vec![(1, &changes1)], slot2.clone())
+            .unwrap();
+        assert_ne!(root1, root2);
+        // Computing roots does not modify the rooted tree
+        assert_eq!(state_tree.rooted_root(), Hash::default());
+        assert_eq!(state_tree.rooted_slot(), None);
+
+        state_tree.root_slots(vec![(1, root1, &changes1)]).unwrap();
+        assert_eq!(state_tree.rooted_root(), root1);
+        assert_eq!(state_tree.rooted_slot(), Some(1));
+        // Slots already rooted are skipped
+        assert_eq!(
+            state_tree
+                .compute_root(Some(root1), vec![(1, &changes1)], slot2.clone())
+                .unwrap()
+                .0,
+            root2
+        );
+
+        state_tree
+            .root_slots(vec![(1, root1, &changes1), (2, root2, &changes2)])
+            .unwrap();
+        assert_eq!(state_tree.rooted_root(), root2);
+        assert_eq!(state_tree.rooted_slot(), Some(2));
+        state_tree.root_slots(vec![(1, root1, &changes1)]).unwrap();
+        assert_eq!(state_tree.rooted_root(), root2);
+
+        // The root only depends on the state, not on the order of the writes
+        let mut leaves = slot1[1..].to_vec();
+        leaves.push(slot2[0]);
+        leaves.reverse();
+        assert_eq!(
+            StateTree::new_from_leaves(2, leaves).unwrap().rooted_root(),
+            root2
+        );
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        let state_tree = StateTree::default();
+        let slot1 = vec![new_leaf(1), new_leaf(2)];
+        let (root1, changes1) = state_tree
+            .compute_root(None, vec![], slot1.clone())
+            .unwrap();
+        let slot2 = vec![new_leaf(3)];
+        let (root2, changes2) = state_tree
+            .compute_root(Some(root1), vec![(1, &changes1)], slot2.clone())
+            .unwrap();
+        let absent_key = leaf_key(&Pubkey::new_unique());
+
+        let verify = |slot, root, changes: Vec<_>, (key, value)| {
+            let (proven_values, proof) = state_tree
+                .merkle_proof(slot, root, changes, vec![key])
+                .unwrap()
+                .unwrap();
+            assert_eq!(proven_values, vec![value]);
+            proof
+                .verify::<Blake2bHasher>(&hash_to_root(&root), vec![(key, value)])
+                .unwrap()
+        };
+        assert!(verify(1, root1, vec![(1, &changes1)], slot1[0]));
+        assert!(verify(
+            1,
+            root1,
+            vec![(1, &changes1)],
+            (absent_key, H256::zero())
+        ));
+        let slot2_changes = vec![(2, &changes2), (1, &changes1)];
+        assert!(verify(2, root2, slot2_changes.clone(), slot2[0]));
+        assert!(verify(2, root2, slot2_changes.clone(), slot1[1]));
+        let (values, proof) = state_tree
+            .merkle_proof(
+                2,
+                root2,
+                slot2_changes.clone(),
+                vec![absent_key, slot2[0].0],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(values, vec![H256::zero(), slot2[0].1]);
+        assert!(proof
+            .verify::<Blake2bHasher>(
+                &hash_to_root(&root2),
+                vec![(absent_key, H256::zero()), slot2[0]]
+            )
+            .unwrap());
+        // The leaves of slot 2 are not in the state of slot 1
+        let (values, proof) = state_tree
+            .merkle_proof(1, root1, vec![(1, &changes1)], vec![slot2[0].0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(values, vec![H256::zero()]);
+        assert!(!proof
+            .verify::<Blake2bHasher>(&hash_to_root(&root1), vec![slot2[0]])
+            .unwrap());
+
+        // Slots older than the rooted state can not be proven
+        state_tree
+            .root_slots(vec![(1, root1, &changes1), (2, root2, &changes2)])
+            .unwrap();
+        assert!(state_tree
+            .merkle_proof(1, root1, vec![(1, &changes1)], vec![slot1[0].0])
+            .unwrap()
+            .is_none());
+        assert!(verify(2, root2, slot2_changes, slot2[0]));
+    }
+
+    #[test]
+    fn test_serialize_branch() {
+        let branch = BranchNode {
+            left: MergeValue::Value(H256::from([1; 32])),
+            right: MergeValue::MergeWithZero {
+                base_node: H256::from([2; 32]),
+                zero_bits: H256::from([3; 32]),
+                zero_count: 4,
+            },
+        };
+        let bytes = serialize_branch(&branch);
+        assert_eq!(deserialize_branch(&bytes).unwrap(), branch);
+        assert!(deserialize_branch(&bytes[..bytes.len() - 1]).is_err());
+        assert!(deserialize_branch(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(deserialize_branch(&[]).is_err());
+    }
+
+    #[test]
+    fn test_set_store() {
+        let leaves = vec![new_leaf(1), new_leaf(2)];
+        let state_tree = StateTree::new_from_leaves(1, leaves.clone()).unwrap();
+        let slot2 = vec![new_leaf(3)];
+        let (root2, changes2) = state_tree
+            .compute_root(None, vec![], slot2.clone())
+            .unwrap();
+
+        // The rooted tree is copied to a store which did not commit it...
+        let store = Arc::new(MemoryStateTreeStore::default());
+        store
+            .commit(5, H256::from([5; 32]), &StateTreeChanges::default())
+            .unwrap();
+        assert!(!state_tree.set_store(store.clone()).unwrap());
+        assert_eq!(
+            store.committed().unwrap(),
+            Some((1, hash_to_root(&state_tree.rooted_root())))
+        );
+        assert_eq!(
+            state_tree
+                .compute_root(None, vec![], slot2.clone())
+                .unwrap()
+                .0,
+            root2
+        );
+        state_tree.root_slots(vec![(2, root2, &changes2)]).unwrap();
+        assert_eq!(store.committed().unwrap(), Some((2, hash_to_root(&root2))));
+
+        // ...and resumed from a store which did
+        let resumed_tree = StateTree::new_from_leaves(2, [leaves, slot2].concat()).unwrap();
+        assert_eq!(resumed_tree.rooted_root(), root2);
+        assert!(resumed_tree.set_store(store.clone()).unwrap());
+        assert_eq!(resumed_tree.rooted_slot(), Some(2));
+        let slot3 = vec![new_leaf(4)];
+        assert_eq!(
+            resumed_tree
+                .compute_root(None, vec![], slot3.clone())
+                .unwrap()
+                .0,
+            state_tree
+                .compute_root(None, vec![], slot3.clone())
+                .unwrap()
+                .0
+        );
+
+        // The tree is resumed from the store alone at its committed slot
+        assert!(StateTree::new_from_store(1, store.clone())
+            .unwrap()
+            .is_none());
+        let stored_tree = StateTree::new_from_store(2, store).unwrap().unwrap();
+        assert_eq!(stored_tree.rooted_slot(), Some(2));
+        assert_eq!(stored_tree.rooted_root(), root2);
+        assert_eq!(
+            stored_tree
+                .compute_root(None, vec![], slot3.clone())
+                .unwrap()
+                .0,
+            state_tree.compute_root(None, vec![], slot3).unwrap().0
+        );
+    }
+
+    /// A store failing every read and write while `failing` is set
+    #[derive(Debug, Default)]
+    struct FailingStore {
+        failing: AtomicBool,
+        store: MemoryStateTreeStore,
+    }
+
+    impl FailingStore {
+        fn check(&self) -> Result<(), Error> {
+            if self.failing.load(Ordering::Relaxed) {
+                return Err(Error::Store("store failure".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    impl StoreReadOps<H256> for FailingStore {
+        fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, Error> {
+            self.check()?;
+            self.store.get_branch(branch_key)
+        }
+
+        fn get_leaf(&self, leaf_key: &H256) -> Result<Option<H256>, Error> {
+            self.check()?;
+            self.store.get_leaf(leaf_key)
+        }
+    }
+
+    impl StateTreeStore for FailingStore {
+        fn committed(&self) -> Result<Option<(Slot, H256)>, Error> {
+            self.check()?;
+            self.store.committed()
+        }
+
+        fn commit(&self, slot: Slot, root: H256, changes: &StateTreeChanges) -> Result<(), Error> {
+            self.check()?;
+            self.store.commit(slot, root, changes)
+        }
+
+        fn nodes(&self) -> Result<StateTreeChanges, Error> {
+            self.check()?;
+            self.store.nodes()
+        }
+
+        fn clear(&self) -> Result<(), Error> {
+            self.check()?;
+            self.store.clear()
+        }
+    }
+
+    #[test]
+    fn test_store_errors() {
+        let state_tree = StateTree::new_from_leaves(1, vec![new_leaf(1), new_leaf(2)]).unwrap();
+        let root1 = state_tree.rooted_root();
+        let store = Arc::new(FailingStore::default());
+        store.failing.store(true, Ordering::Relaxed);
+        assert!(state_tree.set_store(store.clone()).is_err());
+        store.failing.store(false, Ordering::Relaxed);
+        assert!(!state_tree.set_store(store.clone()).unwrap());
+
+        // The errors of the store are returned, and leave the rooted tree as
+        // it was
+        let slot2 = vec![new_leaf(3)];
+        store.failing.store(true, Ordering::Relaxed);
+        assert!(state_tree
+            .compute_root(None, vec![], slot2.clone())
+            .is_err());
+        store.failing.store(false, Ordering::Relaxed);
+        let (root2, changes2) = state_tree.compute_root(None, vec![], slot2).unwrap();
+        store.failing.store(true, Ordering::Relaxed);
+        assert!(state_tree.root_slots(vec![(2, root2, &changes2)]).is_err());
+        assert_eq!(state_tree.rooted_slot(), Some(1));
+        assert_eq!(state_tree.rooted_root(), root1);
+
+        // ...so the update can be retried once the store recovers
+        store.failing.store(false, Ordering::Relaxed);
+        state_tree.root_slots(vec![(2, root2, &changes2)]).unwrap();
+        assert_eq!(state_tree.rooted_slot(), Some(2));
+        assert_eq!(store.committed().unwrap(), Some((2, hash_to_root(&root2))));
+    }
+}
@@ -1,2 +1,3 @@
 /// shred_replay process lib
+pub mod replay_status;
 pub mod shred_replay;
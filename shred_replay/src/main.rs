@@ -1,17 +1,57 @@
-use log::error;
 use {
-    clap::{value_t_or_exit, App, Arg, SubCommand},
-    // shred_replay::{generate_hash, verify},
-    shred_replay::shred_replay::{
-        run_ledger_tool, Replayer, ReplayerError, ReplayerPostgresConfig,
+    clap::{value_t, value_t_or_exit, App, AppSettings, Arg, ArgMatches, SubCommand},
+    shred_replay::{
+        replay_status::ReplayStatusService,
+        shred_replay::{
+            Replayer, ReplayerError, ReplayerPostgresConfig, DEFAULT_POLL_INTERVAL,
+            DEFAULT_SNAPSHOT_INTERVAL_SLOTS, DEFAULT_VERIFY_INTERVAL_SLOTS,
+        },
     },
-    std::{fs::File, io::Read, path::PathBuf},
+    smt_proof::snapshot_bootstrap::SnapshotSource,
+    solana_clap_utils::input_validators::{is_parsable, is_slot},
+    solana_sdk::clock::Slot,
+    std::{fs::File, io::Read, net::SocketAddr, path::PathBuf, process::exit, time::Duration},
 };
 
+fn is_positive_interval(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(0) => Err("interval must be greater than 0".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("error parsing '{}': {}", value, err)),
+    }
+}
+
+// Slot 0 is the genesis slot, which is in the ledger from the start
+fn is_starting_slot(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(0) => Err("starting slot must be greater than 0".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("error parsing '{}': {}", value, err)),
+    }
+}
+
 fn main() {
+    let default_verify_interval_slots = DEFAULT_VERIFY_INTERVAL_SLOTS.to_string();
+    let default_snapshot_interval_slots = DEFAULT_SNAPSHOT_INTERVAL_SLOTS.to_string();
+    let default_poll_interval_secs = DEFAULT_POLL_INTERVAL.as_secs().to_string();
+
+    let starting_slot_arg = Arg::with_name("starting_slot")
+        .long("starting-slot")
+        .value_name("SLOT")
+        .takes_value(true)
+        .validator(is_starting_slot)
+        .help("Start at this slot");
+    let ending_slot_arg = Arg::with_name("ending_slot")
+        .long("ending-slot")
+        .value_name("SLOT")
+        .takes_value(true)
+        .validator(is_slot)
+        .help("The last slot to iterate to");
+
     let matches = App::new("solana-shred-replayer")
         .about("Replayer")
         .version("0.1")
+        .setting(AppSettings::InferSubcommands)
         .arg(
             Arg::with_name("config_file")
                 .short("c")
@@ -42,22 +82,136 @@ fn main() {
                 .default_value("ledger")
                 .help("Use GENESIS_PATH as genesis path"),
         )
-        // .after_help("The default subcommand is replay")
-        // .subcommand(SubCommand::with_name("replay").about("replay shred and update ledger"))
-        // .subcommand(SubCommand::with_name("verify").about("Replay shred and verify the ledger"))
-        // .subcommand(SubCommand::with_name("hash").about("Replay shred and generate bank hash"))
+        .arg(
+            Arg::with_name("snapshot_archive")
+                .long("snapshot-archive")
+                .value_name("PATH")
+                .takes_value(true)
+                .help(
+                    "Start a new ledger from this full snapshot archive instead of \
+                    replaying it from genesis",
+                ),
+        )
+        .arg(
+            Arg::with_name("incremental_snapshot_archive")
+                .long("incremental-snapshot-archive")
+                .value_name("PATH")
+                .takes_value(true)
+                .requires("snapshot_archive")
+                .help("Start from this incremental snapshot archive on top of --snapshot-archive"),
+        )
+        .arg(
+            Arg::with_name("snapshot_rpc")
+                .long("snapshot-rpc")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .validator(is_parsable::<SocketAddr>)
+                .conflicts_with("snapshot_archive")
+                .help(
+                    "Start a new ledger from the latest snapshot served by the RPC service \
+                    of the execution node at this address",
+                ),
+        )
+        .arg(
+            Arg::with_name("status_addr")
+                .long("status-addr")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .validator(is_parsable::<SocketAddr>)
+                .help(
+                    "Serve the replay status as JSON at http://HOST:PORT/status, and \
+                    whether the ledger diverged at /health",
+                ),
+        )
+        .arg(
+            Arg::with_name("check_schema")
+                .long("check-schema")
+                .takes_value(false)
+                .help(
+                    "Print the schema version of the database and the migrations it lacks, \
+                    and exit without applying them",
+                ),
+        )
+        .after_help("The default subcommand is follow")
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Replay the shreds of a slot range and verify the ledger")
+                .arg(starting_slot_arg.clone().default_value("1"))
+                .arg(ending_slot_arg.clone().required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("follow")
+                .about("Continuously replay new shreds and verify the ledger")
+                .arg(
+                    starting_slot_arg.clone().help(
+                        "Start at this slot [default: the slot after the last verified slot]",
+                    ),
+                )
+                .arg(
+                    Arg::with_name("verify_interval_slots")
+                        .long("verify-interval-slots")
+                        .value_name("NUMBER")
+                        .takes_value(true)
+                        .validator(is_positive_interval)
+                        .default_value(&default_verify_interval_slots)
+                        .help("Verify the ledger every this many slots"),
+                )
+                .arg(
+                    Arg::with_name("snapshot_interval_slots")
+                        .long("snapshot-interval-slots")
+                        .value_name("NUMBER")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .default_value(&default_snapshot_interval_slots)
+                        .help(
+                            "Create a snapshot of the verified ledger every this many slots, \
+                            0 to disable snapshots",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("poll_interval_secs")
+                        .long("poll-interval-secs")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .validator(is_positive_interval)
+                        .default_value(&default_poll_interval_secs)
+                        .help(
+                            "Poll for new shreds every this many seconds. With the postgres \
+                            DA backend new shreds are also notified as soon as they are stored",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bank-hash")
+                .about("Replay the ledger and print the bank hash and state root of each slot")
+                .arg(
+                    ending_slot_arg.clone().help(
+                        "The last slot to replay to [default: the highest slot in the ledger]",
+                    ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("snapshot")
+                .about("Replay the ledger and create a snapshot, without fetching new shreds")
+                .arg(ending_slot_arg.help(
+                    "The slot to create the snapshot at [default: the highest slot in the ledger]",
+                )),
+        )
         .get_matches();
 
     let config_file = value_t_or_exit!(matches, "config_file", PathBuf);
     let ledger_path = value_t_or_exit!(matches, "ledger_path", PathBuf);
     let genesis_path = value_t_or_exit!(matches, "genesis_path", PathBuf);
-    // let config_file = PathBuf::from("./solana-accountsdb-plugin-postgres/scripts/geyser.json");
-    // let ledger_path = PathBuf::from("replay-ledger");
-    // let genesis_path = PathBuf::from("test-ledger");
 
-    let mut file = File::open(config_file.as_path()).unwrap();
+    let mut file = File::open(config_file.as_path()).unwrap_or_else(|err| {
+        eprintln!("Unable to open config file {:?}: {}", config_file, err);
+        exit(1);
+    });
     let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
+    file.read_to_string(&mut contents).unwrap_or_else(|err| {
+        eprintln!("Unable to read config file {:?}: {}", config_file, err);
+        exit(1);
+    });
 
     let config: ReplayerPostgresConfig = serde_json::from_str(&contents)
         .map_err(|err| ReplayerError::ConfigFileReadError {
@@ -66,51 +220,126 @@ fn main() {
                 err
             ),
         })
-        .unwrap();
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            exit(1);
+        });
 
     let mut replayer = Replayer::new()
         .config(&config)
         .ledger_path(&ledger_path)
         .genesis_path(&genesis_path);
+    if let Some(snapshot_source) = snapshot_source_of(&matches) {
+        replayer = replayer.snapshot_source(snapshot_source);
+    }
+    if matches.is_present("check_schema") {
+        match replayer.check_schema() {
+            Ok(status) => println!("{}", status),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        }
+        return;
+    }
+    let _status_service = value_t!(matches, "status_addr", SocketAddr)
+        .ok()
+        .map(|status_addr| {
+            ReplayStatusService::new(&status_addr, replayer.status()).unwrap_or_else(|err| {
+                eprintln!(
+                    "Unable to serve the replay status at {}: {}",
+                    status_addr, err
+                );
+                exit(1);
+            })
+        });
 
-    if let Err(e) = replayer.connect_db() {
-        error!("{}", e);
+    let result = match matches.subcommand() {
+        ("replay", Some(arg_matches)) => {
+            let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+            let ending_slot = value_t_or_exit!(arg_matches, "ending_slot", Slot);
+            setup_replayer(&mut replayer, true)
+                .and_then(|_| replayer.insert_shred_range(starting_slot, ending_slot))
+                .and_then(|_| replayer.verify_to_slot(ending_slot))
+        }
+        ("bank-hash", Some(arg_matches)) => setup_replayer(&mut replayer, false)
+            .and_then(|_| ending_slot_of(&replayer, arg_matches))
+            .and_then(|ending_slot| replayer.verify_to_slot(ending_slot)),
+        ("snapshot", Some(arg_matches)) => setup_replayer(&mut replayer, false)
+            .and_then(|_| ending_slot_of(&replayer, arg_matches))
+            .and_then(|ending_slot| replayer.verify_to_slot(ending_slot))
+            .and_then(|_| replayer.create_snapshot())
+            .map(|slot| println!("create snapshot succed for slot: {}", slot)),
+        (_, arg_matches) => {
+            if let Some(arg_matches) = arg_matches {
+                let verify_interval_slots =
+                    value_t_or_exit!(arg_matches, "verify_interval_slots", u64);
+                let snapshot_interval_slots =
+                    value_t_or_exit!(arg_matches, "snapshot_interval_slots", u64);
+                let poll_interval_secs = value_t_or_exit!(arg_matches, "poll_interval_secs", u64);
+                replayer = replayer
+                    .verify_interval_slots(verify_interval_slots)
+                    .snapshot_interval_slots(snapshot_interval_slots)
+                    .poll_interval(Duration::from_secs(poll_interval_secs));
+            }
+            let starting_slot = arg_matches
+                .and_then(|arg_matches| value_t!(arg_matches, "starting_slot", Slot).ok());
+            replayer
+                .connect_db()
+                .and_then(|_| setup_replayer(&mut replayer, true))
+                .and_then(|_| match starting_slot {
+                    Some(starting_slot) => Ok(starting_slot),
+                    // We query last verified slot from DB, else start after
+                    // the genesis or snapshot bank.
+                    None => replayer
+                        .query_last_verified_slot()
+                        .map(|slot| slot.unwrap_or_else(|| replayer.root_slot()) + 1),
+                })
+                .and_then(|starting_slot| {
+                    println!("Start to verify shred from slot: {}", starting_slot);
+                    replayer.insert_shred_startwith_slot(starting_slot)
+                })
+        }
     };
-    if let Err(e) = replayer.init_ledger() {
-        error!("{}", e);
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        exit(1);
     }
-    if let Err(e) = replayer.setup_blockstore() {
-        error!("{}", e);
-    };
-    // We query last verified slot from DB.
-    let slot = replayer.query_last_verified_slot();
-    let start_slot = match slot {
-        Some(slot) => slot + 1,
-        None => 1,
-    };
+}
 
-    println!("Start to verify shred from slot: {}", start_slot);
-    replayer.insert_shred_startwith_slot(start_slot);
+/// Open the ledger and load its banks. If `fetch_shreds`, also set up the DA
+/// backend the shreds are fetched from.
+fn setup_replayer(replayer: &mut Replayer, fetch_shreds: bool) -> Result<(), ReplayerError> {
+    if fetch_shreds {
+        replayer.setup_da_backend()?;
+    }
+    replayer.init_ledger()?;
+    replayer.setup_blockstore()?;
+    replayer.setup_ledger_verifier()
+}
 
-    // // Execute subcommand.
-    // match matches.subcommand() {
-    //     ("verify", _) => {
-    //         let src_slot_output =
-    //             run_ledger_tool(&["-l", &ledger_path.as_path().display().to_string(), "verify"]);
-    //         assert!(src_slot_output.status.success());
-    //         println!("{}", String::from_utf8(src_slot_output.stdout).unwrap());
-    //         return;
-    //     }
-    //     ("bank-hash", _) => {
-    //         let src_slot_output = run_ledger_tool(&[
-    //             "-l",
-    //             &ledger_path.as_path().display().to_string(),
-    //             "bank-hash",
-    //         ]);
-    //         assert!(src_slot_output.status.success());
-    //         println!("{}", String::from_utf8(src_slot_output.stdout).unwrap());
-    //         return;
-    //     }
-    //     _ => unreachable!(),
-    // };
+fn snapshot_source_of(matches: &ArgMatches<'_>) -> Option<SnapshotSource> {
+    if let Ok(full_snapshot_archive) = value_t!(matches, "snapshot_archive", PathBuf) {
+        Some(SnapshotSource::Archives {
+            full_snapshot_archive,
+            incremental_snapshot_archive: value_t!(
+                matches,
+                "incremental_snapshot_archive",
+                PathBuf
+            )
+            .ok(),
+        })
+    } else {
+        value_t!(matches, "snapshot_rpc", SocketAddr)
+            .ok()
+            .map(SnapshotSource::Rpc)
+    }
+}
+
+fn ending_slot_of(replayer: &Replayer, matches: &ArgMatches<'_>) -> Result<Slot, ReplayerError> {
+    match value_t!(matches, "ending_slot", Slot) {
+        Ok(ending_slot) => Ok(ending_slot),
+        Err(_) => Ok(replayer.highest_inserted_slot()?.unwrap_or_default()),
+    }
 }
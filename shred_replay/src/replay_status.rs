@@ -0,0 +1,233 @@
+//! The progress of the replayer, served as JSON over HTTP and reported as
+//! metrics, so the health of the verification nodes can be monitored.
+use {
+    jsonrpc_http_server::{
+        hyper, jsonrpc_core::IoHandler, RequestMiddleware, RequestMiddlewareAction, Server,
+        ServerBuilder,
+    },
+    log::*,
+    serde_derive::{Deserialize, Serialize},
+    solana_metrics::{datapoint_error, datapoint_info},
+    solana_sdk::clock::Slot,
+    std::{
+        io,
+        net::SocketAddr,
+        sync::{Arc, RwLock},
+    },
+};
+
+pub const STATUS_REQUEST_PATH: &str = "/status";
+pub const HEALTH_REQUEST_PATH: &str = "/health";
+
+/// The first replayed slot whose bank hash differs from the one the execution
+/// node produced.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergentSlot {
+    pub slot: Slot,
+    pub expected_bank_hash: String,
+    pub actual_bank_hash: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayStatus {
+    pub last_inserted_slot: Option<Slot>,
+    pub last_verified_slot: Option<Slot>,
+    /// The highest slot published to the DA backend, when last checked.
+    pub da_highest_slot: Option<Slot>,
+    /// How many slots the last inserted slot is behind the DA backend.
+    pub slots_behind: u64,
+    /// How long the last verification took to replay the new slots.
+    pub last_verify_duration_ms: Option<u64>,
+    pub divergent_slot: Option<DivergentSlot>,
+}
+
+impl ReplayStatus {
+    pub fn health(&self) -> &'static str {
+        if self.divergent_slot.is_some() {
+            "diverged"
+        } else {
+            "ok"
+        }
+    }
+
+    pub fn report_metrics(&self) {
+        datapoint_info!(
+            "shred_replay-status",
+            (
+                "last_inserted_slot",
+                self.last_inserted_slot.unwrap_or_default(),
+                i64
+            ),
+            (
+                "last_verified_slot",
+                self.last_verified_slot.unwrap_or_default(),
+                i64
+            ),
+            (
+                "da_highest_slot",
+                self.da_highest_slot.unwrap_or_default(),
+                i64
+            ),
+            ("slots_behind", self.slots_behind, i64),
+            (
+                "last_verify_duration_ms",
+                self.last_verify_duration_ms.unwrap_or_default(),
+                i64
+            ),
+        );
+    }
+
+    /// Record the first divergent slot; the later ones follow from it.
+    pub fn set_divergent_slot(&mut self, divergent_slot: DivergentSlot) {
+        if self.divergent_slot.is_some() {
+            return;
+        }
+        datapoint_error!(
+            "shred_replay-divergent_slot",
+            ("slot", divergent_slot.slot, i64),
+            (
+                "expected_bank_hash",
+                divergent_slot.expected_bank_hash,
+                String
+            ),
+            ("actual_bank_hash", divergent_slot.actual_bank_hash, String),
+        );
+        self.divergent_slot = Some(divergent_slot);
+    }
+}
+
+struct StatusRequestMiddleware {
+    status: Arc<RwLock<ReplayStatus>>,
+}
+
+impl RequestMiddleware for StatusRequestMiddleware {
+    fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+        trace!("request uri: {}", request.uri());
+        let status = self.status.read().unwrap();
+        let (status_code, content_type, body) = match request.uri().path() {
+            STATUS_REQUEST_PATH => (
+                hyper::StatusCode::OK,
+                "application/json",
+                serde_json::to_string(&*status).unwrap(),
+            ),
+            // Health checks look at the status code, not the body
+            HEALTH_REQUEST_PATH if status.divergent_slot.is_some() => (
+                hyper::StatusCode::SERVICE_UNAVAILABLE,
+                "text/plain",
+                status.health().to_string(),
+            ),
+            HEALTH_REQUEST_PATH => (
+                hyper::StatusCode::OK,
+                "text/plain",
+                status.health().to_string(),
+            ),
+            _ => {
+                return hyper::Response::builder()
+                    .status(hyper::StatusCode::NOT_FOUND)
+                    .body(hyper::Body::empty())
+                    .unwrap()
+                    .into()
+            }
+        };
+        hyper::Response::builder()
+            .status(status_code)
+            .header(hyper::header::CONTENT_TYPE, content_type)
+            .body(hyper::Body::from(body))
+            .unwrap()
+            .into()
+    }
+}
+
+/// Serves the replay status at `STATUS_REQUEST_PATH` and whether the ledger
+/// diverged at `HEALTH_REQUEST_PATH`, which answers 503 once it has.
+pub struct ReplayStatusService {
+    server: Server,
+}
+
+impl ReplayStatusService {
+    pub fn new(status_addr: &SocketAddr, status: Arc<RwLock<ReplayStatus>>) -> io::Result<Self> {
+        let server = ServerBuilder::new(IoHandler::default())
+            .threads(1)
+            .request_middleware(StatusRequestMiddleware { status })
+            .start_http(status_addr)?;
+        info!("Serving the replay status at {}", status_addr);
+        Ok(Self { server })
+    }
+
+    pub fn close(self) {
+        self.server.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            io::{Read, Write},
+            net::{TcpListener, TcpStream},
+        },
+    };
+
+    fn http_get(addr: &SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, addr
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_replay_status_service() {
+        let status = Arc::new(RwLock::new(ReplayStatus {
+            last_inserted_slot: Some(12),
+            last_verified_slot: Some(10),
+            da_highest_slot: Some(15),
+            slots_behind: 3,
+            ..ReplayStatus::default()
+        }));
+        let status_addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let service = ReplayStatusService::new(&status_addr, status.clone()).unwrap();
+
+        let response = http_get(&status_addr, STATUS_REQUEST_PATH);
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ReplayStatus>(body).unwrap(),
+            *status.read().unwrap()
+        );
+        assert!(body.contains("\"lastVerifiedSlot\":10"));
+        let response = http_get(&status_addr, HEALTH_REQUEST_PATH);
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("ok"));
+
+        status.write().unwrap().set_divergent_slot(DivergentSlot {
+            slot: 11,
+            expected_bank_hash: "expected".to_string(),
+            actual_bank_hash: "actual".to_string(),
+        });
+        // Only the first divergent slot is kept
+        status.write().unwrap().set_divergent_slot(DivergentSlot {
+            slot: 12,
+            ..DivergentSlot::default()
+        });
+        assert_eq!(
+            status.read().unwrap().divergent_slot.as_ref().unwrap().slot,
+            11
+        );
+        let response = http_get(&status_addr, HEALTH_REQUEST_PATH);
+        assert!(response.starts_with("HTTP/1.1 503"));
+        assert!(response.ends_with("diverged"));
+        assert!(http_get(&status_addr, "/other").starts_with("HTTP/1.1 404"));
+        service.close();
+    }
+}
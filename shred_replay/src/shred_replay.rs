@@ -1,20 +1,53 @@
-use assert_cmd::prelude::*;
 use {
+    crate::replay_status::{DivergentSlot, ReplayStatus},
+    crossbeam_channel::unbounded,
+    da_backend::{
+        blob::{DaBlob, SlotEntries},
+        postgres::SlotCompleteListener,
+        schema::{self, SchemaStatus},
+        DataAvailabilityBackend, DataAvailabilityBackendConfig, DataAvailabilityError,
+        DataAvailabilityPayload,
+    },
     postgres::{Client, NoTls},
     serde_derive::{Deserialize, Serialize},
+    smt_proof::snapshot_bootstrap::{
+        query_committed_bank_hash, query_committed_state_root, verify_snapshot_bank,
+        SnapshotBootstrapError, SnapshotSource, SnapshotStaging,
+    },
     solana_ledger::{
+        bank_forks_utils,
         blockstore,
         blockstore::Blockstore,
         // genesis_utils::create_genesis_config,
         blockstore_options,
-        shred::Shred,
+        blockstore_processor::{self, ProcessOptions},
+        leader_schedule_cache::LeaderScheduleCache,
+        shred::{ProcessShredsStats, ReedSolomonCache, Shred, Shredder},
+    },
+    solana_runtime::{
+        accounts_background_service::{
+            AbsRequestHandler, AbsRequestSender, AccountsBackgroundService,
+        },
+        bank::Bank,
+        bank_forks::BankForks,
+        hardened_unpack::MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
+        snapshot_config::SnapshotConfig,
+        snapshot_utils,
+    },
+    solana_sdk::{
+        clock::Slot, genesis_config::GenesisConfig, hash::Hash,
+        shred_version::compute_shred_version, signature::Keypair,
     },
-    solana_runtime::hardened_unpack::MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
-    solana_sdk::genesis_config::GenesisConfig,
     std::{
+        collections::HashMap,
         io,
-        path::{Path, PathBuf},
-        process::{Command, Output},
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, RwLock,
+        },
+        thread::sleep,
+        time::{Duration, Instant},
     },
     thiserror::Error,
 };
@@ -29,6 +62,12 @@ pub struct ReplayerPostgresConfig {
     pub dbname: Option<String>,
     /// The port number of the PostgreSQL database, the default is 5432
     pub port: Option<u16>,
+    /// The data-availability backend the shreds are fetched from.
+    #[serde(default)]
+    pub da_backend: DataAvailabilityBackendConfig,
+    /// Whether the shreds of each slot or blobs of the entries are fetched.
+    #[serde(default)]
+    pub da_payload: DataAvailabilityPayload,
 }
 
 #[derive(Error, Debug)]
@@ -52,29 +91,308 @@ pub enum ReplayerError {
     #[error("Error initializing blockstore Error message: ({msg})")]
     InitBlockstoreError { msg: String },
 
-    #[error("Error insert shreds into blockstore")]
-    InsertShredError,
+    #[error("Error inserting shreds of slot {slot} into blockstore Error message: ({msg})")]
+    InsertShredError { slot: Slot, msg: String },
+
+    #[error("Error querying the replay progress Error message: ({msg})")]
+    ReplayProgressError { msg: String },
+
+    #[error("Error accessing the DA backend: ({0})")]
+    DataAvailabilityError(#[from] DataAvailabilityError),
+
+    #[error("Error verifying shreds of slot {slot} Error message: ({msg})")]
+    InvalidShredError { slot: Slot, msg: String },
+
+    #[error("Error verifying the blob of slot {slot} Error message: ({msg})")]
+    InvalidBlobError { slot: Slot, msg: String },
+
+    #[error("Error replaying the ledger up to slot {slot} Error message: ({msg})")]
+    VerifyError { slot: Slot, msg: String },
+
+    #[error("Bank hash mismatch at slot {slot}, expected: {expected}, actual: {actual}")]
+    BankHashMismatch {
+        slot: Slot,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Error creating snapshot for slot {slot} Error message: ({msg})")]
+    CreateSnapshotError { slot: Slot, msg: String },
+
+    #[error("Error bootstrapping from the snapshot: ({0})")]
+    SnapshotBootstrapError(#[from] SnapshotBootstrapError),
 }
 
+/// The banks replayed from the fetched shreds. They are kept warm between
+/// batches, so each verification only replays the slots inserted since the
+/// last one instead of the whole ledger.
+struct LedgerVerifier {
+    bank_forks: Arc<RwLock<BankForks>>,
+    leader_schedule_cache: LeaderScheduleCache,
+    shred_version: u16,
+    exit: Arc<AtomicBool>,
+    accounts_background_service: Option<AccountsBackgroundService>,
+}
+
+impl LedgerVerifier {
+    fn new(
+        bank_forks: Arc<RwLock<BankForks>>,
+        leader_schedule_cache: LeaderScheduleCache,
+        shred_version: u16,
+    ) -> Self {
+        let exit = Arc::new(AtomicBool::new(false));
+        let pruned_banks_receiver =
+            AccountsBackgroundService::setup_bank_drop_callback(bank_forks.clone());
+        let abs_request_handler = AbsRequestHandler {
+            snapshot_request_handler: None,
+            pruned_banks_receiver,
+        };
+        let accounts_background_service = AccountsBackgroundService::new(
+            bank_forks.clone(),
+            &exit,
+            abs_request_handler,
+            true,
+            false,
+            None,
+        );
+        Self {
+            bank_forks,
+            leader_schedule_cache,
+            shred_version,
+            exit,
+            accounts_background_service: Some(accounts_background_service),
+        }
+    }
+
+    fn root_bank(&self) -> Arc<Bank> {
+        self.bank_forks.read().unwrap().root_bank()
+    }
+
+    /// Check the shreds fetched for `slot` were signed by the slot leader, with
+    /// the shred version of the cluster.
+    fn verify_shreds(&self, slot: Slot, shreds: &[Shred]) -> Result<(), ReplayerError> {
+        let invalid_shred = |msg: String| ReplayerError::InvalidShredError { slot, msg };
+        let leader = self
+            .leader_schedule_cache
+            .slot_leader_at(slot, Some(&self.root_bank()))
+            .ok_or_else(|| invalid_shred("unknown slot leader".to_string()))?;
+        for shred in shreds {
+            if shred.slot() != slot {
+                return Err(invalid_shred(format!(
+                    "shred {} belongs to slot {}",
+                    shred.index(),
+                    shred.slot()
+                )));
+            }
+            if shred.version() != self.shred_version {
+                return Err(invalid_shred(format!(
+                    "shred {} has version {}, expected {}",
+                    shred.index(),
+                    shred.version(),
+                    self.shred_version
+                )));
+            }
+            if !shred.verify(&leader) {
+                return Err(invalid_shred(format!(
+                    "shred {} is not signed by the slot leader {}",
+                    shred.index(),
+                    leader
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check `blob` was signed by the leader of each of its `slots`.
+    fn verify_blob(&self, blob: &DaBlob, slots: &[SlotEntries]) -> Result<(), ReplayerError> {
+        let root_bank = self.root_bank();
+        for slot in slots.iter().map(|slot_entries| slot_entries.slot) {
+            let invalid_blob = |msg: String| ReplayerError::InvalidBlobError { slot, msg };
+            let leader = self
+                .leader_schedule_cache
+                .slot_leader_at(slot, Some(&root_bank))
+                .ok_or_else(|| invalid_blob("unknown slot leader".to_string()))?;
+            if !blob.verify(&leader) {
+                return Err(invalid_blob(format!(
+                    "blob {}-{} is not signed by the slot leader {}",
+                    blob.header.start_slot, blob.header.end_slot, leader
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replay the slots of the blockstore from the current root up to `slot`,
+    /// then root the highest replayed bank. Returns the bank hash and state root
+    /// of every replayed slot, or an error if `slot` could not be replayed.
+    fn replay_to_slot(
+        &self,
+        blockstore: &Blockstore,
+        slot: Slot,
+    ) -> Result<Vec<(Slot, Hash, Hash)>, ReplayerError> {
+        let root = self.bank_forks.read().unwrap().root();
+        if slot <= root {
+            return Ok(vec![]);
+        }
+
+        let process_options = ProcessOptions {
+            poh_verify: true,
+            halt_at_slot: Some(slot),
+            accounts_db_caching_enabled: true,
+            ..ProcessOptions::default()
+        };
+        // Every replayed bank is sent once frozen, including the ones pruned
+        // by roots found in the blockstore while replaying.
+        let (frozen_bank_sender, frozen_bank_receiver) = unbounded();
+        blockstore_processor::process_blockstore_from_root(
+            blockstore,
+            &self.bank_forks,
+            &self.leader_schedule_cache,
+            &process_options,
+            None,
+            Some(&frozen_bank_sender),
+            &AbsRequestSender::default(),
+        )
+        .map_err(|err| ReplayerError::VerifyError {
+            slot,
+            msg: err.to_string(),
+        })?;
+
+        let mut bank_hashes: Vec<_> = frozen_bank_receiver
+            .try_iter()
+            .map(|bank| (bank.slot(), bank.hash(), bank.state_root()))
+            .collect();
+        bank_hashes.sort_unstable_by_key(|(slot, _, _)| *slot);
+
+        // Root the highest replayed bank, so the next batch starts from it.
+        let new_root_bank = self
+            .bank_forks
+            .read()
+            .unwrap()
+            .frozen_banks()
+            .into_values()
+            .max_by_key(|bank| bank.slot());
+        if let Some(new_root_bank) = new_root_bank {
+            self.leader_schedule_cache.set_root(&new_root_bank);
+            let _ = self.bank_forks.write().unwrap().set_root(
+                new_root_bank.slot(),
+                &AbsRequestSender::default(),
+                None,
+            );
+        }
+
+        if self.bank_forks.read().unwrap().root() != slot {
+            return Err(ReplayerError::VerifyError {
+                slot,
+                msg: format!(
+                    "replay stopped at slot {}",
+                    self.bank_forks.read().unwrap().root()
+                ),
+            });
+        }
+        Ok(bank_hashes)
+    }
+}
+
+/// Insert the entries of `slots`, decoded from a DA blob, into `blockstore` as
+/// complete slots. A blob carries no shreds signed by the slot leaders, so the
+/// shreds are signed with `keypair` and only meant for the local blockstore:
+/// the entries are authenticated by the signed header of the blob instead.
+/// Returns the number of shreds inserted.
+pub fn insert_slot_entries(
+    blockstore: &Blockstore,
+    slots: &[SlotEntries],
+    keypair: &Keypair,
+    shred_version: u16,
+) -> Result<usize, ReplayerError> {
+    let reed_solomon_cache = ReedSolomonCache::default();
+    let mut num_shreds = 0;
+    for slot_entries in slots {
+        let slot = slot_entries.slot;
+        let insert_shred_error = |msg: String| ReplayerError::InsertShredError { slot, msg };
+        let num_ticks = slot_entries
+            .entries
+            .iter()
+            .filter(|entry| entry.is_tick())
+            .count();
+        let (shreds, _) = Shredder::new(
+            slot,
+            slot_entries.parent_slot,
+            num_ticks.min(u8::MAX as usize) as u8,
+            shred_version,
+        )
+        .map_err(|err| insert_shred_error(format!("{:?}", err)))?
+        .entries_to_shreds(
+            keypair,
+            &slot_entries.entries,
+            true, // is_last_in_slot
+            0,    // next_shred_index
+            0,    // next_code_index
+            true, // merkle_variant
+            &reed_solomon_cache,
+            &mut ProcessShredsStats::default(),
+        );
+        num_shreds += shreds.len();
+        blockstore
+            .insert_shreds(shreds, None, false)
+            .map_err(|err| insert_shred_error(err.to_string()))?;
+    }
+    Ok(num_shreds)
+}
+
+/// Apply the schema migrations the database `client` is connected to lacks.
+fn migrate_schema(client: &mut Client) -> Result<(), DataAvailabilityError> {
+    for migration in schema::migrate_schema(client)? {
+        println!("Applied the schema migration {}", migration);
+    }
+    Ok(())
+}
+
+impl Drop for LedgerVerifier {
+    fn drop(&mut self) {
+        self.exit.store(true, Ordering::Relaxed);
+        if let Some(accounts_background_service) = self.accounts_background_service.take() {
+            accounts_background_service.join().unwrap();
+        }
+    }
+}
+
+pub const DEFAULT_VERIFY_INTERVAL_SLOTS: u64 = 50;
+pub const DEFAULT_SNAPSHOT_INTERVAL_SLOTS: u64 = 40;
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
 pub struct Replayer {
     client: Option<Client>,
+    da_backend: Option<Box<dyn DataAvailabilityBackend>>,
+    slot_listener: Option<SlotCompleteListener>,
     config: Option<ReplayerPostgresConfig>,
     ledger_path: Option<PathBuf>,
     genesis_path: Option<PathBuf>,
+    snapshot_source: Option<SnapshotSource>,
     blockstore: Option<Blockstore>,
+    ledger_verifier: Option<LedgerVerifier>,
+    verify_interval_slots: u64,
+    snapshot_interval_slots: u64,
+    poll_interval: Duration,
+    status: Arc<RwLock<ReplayStatus>>,
 }
 
 impl Replayer {
-    const VERIFY_INTERVAL_SLOTS: i64 = 50;
-    const CREATE_SNAPSHOT_INTERVAL_SLOTS: i64 = 40;
-
     pub fn new() -> Self {
         Self {
             client: None,
+            da_backend: None,
+            slot_listener: None,
             config: None,
             ledger_path: None,
             genesis_path: None,
+            snapshot_source: None,
             blockstore: None,
+            ledger_verifier: None,
+            verify_interval_slots: DEFAULT_VERIFY_INTERVAL_SLOTS,
+            snapshot_interval_slots: DEFAULT_SNAPSHOT_INTERVAL_SLOTS,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            status: Arc::default(),
         }
     }
 
@@ -93,25 +411,92 @@ impl Replayer {
         self
     }
 
-    pub fn connect_db(&mut self) -> Result<(), ReplayerError> {
+    /// Start a new ledger from this snapshot instead of replaying it from
+    /// genesis. Ignored once the ledger directory has a snapshot archive.
+    pub fn snapshot_source(mut self, snapshot_source: SnapshotSource) -> Self {
+        self.snapshot_source = Some(snapshot_source);
+        self
+    }
+
+    /// Replay and verify the followed slots every this many slots.
+    pub fn verify_interval_slots(mut self, verify_interval_slots: u64) -> Self {
+        self.verify_interval_slots = verify_interval_slots;
+        self
+    }
+
+    /// Create a snapshot of the verified slots every this many slots, 0 to disable.
+    pub fn snapshot_interval_slots(mut self, snapshot_interval_slots: u64) -> Self {
+        self.snapshot_interval_slots = snapshot_interval_slots;
+        self
+    }
+
+    /// How long to wait for new slots before polling the DA backend again.
+    /// With the PostgreSQL backend new slots are notified as soon as they are
+    /// complete, the polling only catches up on the notifications missed while
+    /// not connected.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// The progress of the replayer, shared with the `ReplayStatusService`.
+    pub fn status(&self) -> Arc<RwLock<ReplayStatus>> {
+        self.status.clone()
+    }
+
+    fn connection_str(&self) -> String {
         let config = self.config.as_ref().unwrap();
-        let connection_str = format!(
+        format!(
             "host={} user={} password={} dbname={} port={}",
             config.host.as_ref().unwrap(),
             config.user.as_ref().unwrap(),
             config.password.as_ref().unwrap(),
             config.dbname.as_ref().unwrap(),
             config.port.as_ref().unwrap(),
-        );
-        let client =
+        )
+    }
+
+    /// Connect to the database, applying the schema migrations it lacks.
+    pub fn connect_db(&mut self) -> Result<(), ReplayerError> {
+        let connection_str = self.connection_str();
+        let mut client =
             Client::connect(&connection_str, NoTls).map_err(|_| ReplayerError::DbConnectError {
                 msg: format!("the config is {}", connection_str),
             })?;
+        migrate_schema(&mut client)?;
 
         self.client = Some(client);
         Ok(())
     }
 
+    /// The schema version of the database and the migrations it lacks,
+    /// without applying them.
+    pub fn check_schema(&self) -> Result<SchemaStatus, ReplayerError> {
+        let connection_str = self.connection_str();
+        let mut client = Client::connect(&connection_str, NoTls).map_err(|err| {
+            ReplayerError::DbConnectError {
+                msg: format!("the config is {}, error: {}", connection_str, err),
+            }
+        })?;
+        Ok(schema::check_schema(&mut client)?)
+    }
+
+    /// Create the DA backend the shreds are fetched from.
+    pub fn setup_da_backend(&mut self) -> Result<(), ReplayerError> {
+        let connection_str = self.connection_str();
+        let da_backend = self.config.as_ref().unwrap().da_backend.build(|| {
+            let mut client = Client::connect(&connection_str, NoTls).map_err(|err| {
+                DataAvailabilityError::ConnectionError {
+                    msg: format!("the config is {}, error: {}", connection_str, err),
+                }
+            })?;
+            migrate_schema(&mut client)?;
+            Ok(client)
+        })?;
+        self.da_backend = Some(da_backend);
+        Ok(())
+    }
+
     pub fn init_ledger(&mut self) -> Result<(), ReplayerError> {
         if self.ledger_path.as_ref().unwrap().exists() {
         } else {
@@ -129,66 +514,169 @@ impl Replayer {
         Ok(())
     }
 
-    /// load shred from postgres by slot, order by index asc
-    fn load_shred_from_pg(&mut self, slot: u64) -> Vec<Shred> {
+    /// load shred from the DA backend by slot, order by index asc, rejecting
+    /// the slot if any shred was not signed by the slot leader
+    fn load_shreds(&mut self, slot: u64) -> Result<Vec<Shred>, ReplayerError> {
         let mut shreds: Vec<Shred> = Vec::new();
-        let stmt =
-            "SELECT slot, entry_index, entry, is_full_slot FROM entry where slot = $1 ORDER BY entry_index ASC";
-        let client = self.client.as_mut().unwrap();
-        let stmt = client.prepare(stmt).unwrap();
+        let slot_data = self.da_backend.as_mut().unwrap().fetch_slot(slot)?;
 
-        let result = client.query(&stmt, &[&(slot as i64)]);
-        if result.is_err() {
-            println!("query error for slot: {}", slot);
-        }
-
-        for row in result.unwrap() {
-            let s: i64 = row.get(0);
-            let ei: i64 = row.get(1);
-            let full: bool = row.get(3);
-            println!("[queried db] slot: {}, entry_index: {}, is_full_slot: {}", s, ei, full);
-            let payload: Vec<u8> = row.get(2);
-            let result = Shred::new_from_serialized_shred(payload);
-            if result.is_err() {
-                println!("serialize shred error for slot: {}", slot);
+        if let Some(slot_data) = slot_data {
+            println!(
+                "[queried da] slot: {}, num_shreds: {}, is_full_slot: {}",
+                slot,
+                slot_data.shreds.len(),
+                slot_data.is_full_slot
+            );
+            for payload in slot_data.shreds {
+                let shred = Shred::new_from_serialized_shred(payload).map_err(|err| {
+                    ReplayerError::InvalidShredError {
+                        slot,
+                        msg: format!("failed to deserialize shred: {:?}", err),
+                    }
+                })?;
+                shreds.push(shred);
             }
-            shreds.push(result.unwrap());
+            self.ledger_verifier
+                .as_ref()
+                .unwrap()
+                .verify_shreds(slot, &shreds)?;
         }
-        shreds
+        Ok(shreds)
     }
 
-    pub fn query_last_verified_slot(&mut self) -> Option<u64> {
+    pub fn query_last_verified_slot(&mut self) -> Result<Option<u64>, ReplayerError> {
         let stmt = "SELECT slot, entry_index FROM replay ORDER BY slot DESC LIMIT 1";
         let client = self.client.as_mut().unwrap();
-        let stmt = client.prepare(stmt).unwrap();
-        let result = client.query(&stmt, &[]);
-        if result.is_err() {
-            println!("query replay record failed: {:?}", result.err());
-            return None;
-        }
-
-        if result.as_ref().unwrap().is_empty() {
-            return None;
-        }
+        let row =
+            client
+                .query_opt(stmt, &[])
+                .map_err(|err| ReplayerError::ReplayProgressError {
+                    msg: format!("query replay record failed: {:?}", err),
+                })?;
 
-        let row = &result.unwrap()[0];
-        let slot: i64 = row.get(0);
+        Ok(row.map(|row| {
+            let slot: i64 = row.get(0);
+            slot as u64
+        }))
+    }
 
-        Some(slot as u64)
+    fn record_verified_slot(&mut self, slot: u64) -> Result<(), ReplayerError> {
+        let entry_index = 0_i64;
+        let client = self.client.as_mut().unwrap();
+        client
+            .execute(
+                "INSERT INTO replay (slot, entry_index) VALUES ($1, $2)",
+                &[&(slot as i64), &entry_index],
+            )
+            .map_err(|err| ReplayerError::ReplayProgressError {
+                msg: format!("failed to record verified slot {}: {:?}", slot, err),
+            })?;
+        Ok(())
     }
 
-    pub fn query_newer_slot(&mut self, slot: u64) -> u64 {
-        let stmt = "SELECT COUNT(*) FROM entry WHERE slot > $1;";
+    /// The bank hashes the execution node produced for the slots in
+    /// `[first_slot, last_slot]`, from the `block` table.
+    fn query_expected_bank_hashes(
+        &mut self,
+        first_slot: Slot,
+        last_slot: Slot,
+    ) -> Result<HashMap<Slot, String>, ReplayerError> {
         let client = self.client.as_mut().unwrap();
-        let stmt = client.prepare(stmt).unwrap();
-        let result = client.query(&stmt, &[&(slot as i64)]);
-        if result.is_err() {
-            println!("query error for slot: {}", slot);
+        let rows = client
+            .query(
+                "SELECT slot, bank_hash FROM block \
+                WHERE slot >= $1 AND slot <= $2 AND bank_hash IS NOT NULL",
+                &[&(first_slot as i64), &(last_slot as i64)],
+            )
+            .map_err(|err| ReplayerError::ReplayProgressError {
+                msg: format!("query bank hashes failed: {:?}", err),
+            })?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get::<_, i64>(0) as Slot, row.get(1)))
+            .collect())
+    }
+
+    /// Compare the replayed bank hashes with the ones the execution node
+    /// produced, recording the first divergent slot.
+    fn check_bank_hashes(
+        &mut self,
+        bank_hashes: &[(Slot, Hash, Hash)],
+    ) -> Result<(), ReplayerError> {
+        let (first_slot, last_slot) = match (bank_hashes.first(), bank_hashes.last()) {
+            (Some(first), Some(last)) => (first.0, last.0),
+            _ => return Ok(()),
+        };
+        let expected_bank_hashes = self.query_expected_bank_hashes(first_slot, last_slot)?;
+        for (slot, hash, _) in bank_hashes {
+            let actual = hash.to_string();
+            match expected_bank_hashes.get(slot) {
+                Some(expected) if *expected != actual => {
+                    self.status
+                        .write()
+                        .unwrap()
+                        .set_divergent_slot(DivergentSlot {
+                            slot: *slot,
+                            expected_bank_hash: expected.clone(),
+                            actual_bank_hash: actual.clone(),
+                        });
+                    return Err(ReplayerError::BankHashMismatch {
+                        slot: *slot,
+                        expected: expected.clone(),
+                        actual,
+                    });
+                }
+                _ => (),
+            }
         }
+        Ok(())
+    }
+
+    /// Check if the DA backend has data of any slot newer than `slot`.
+    pub fn has_newer_slot(&mut self, slot: u64) -> Result<bool, ReplayerError> {
+        let highest_slot = self.da_backend.as_mut().unwrap().highest_slot()?;
+        Ok(matches!(highest_slot, Some(highest_slot) if highest_slot > slot))
+    }
+
+    /// Listen for the slots completed in the PostgreSQL DA backend.
+    fn connect_slot_listener(&self) -> Result<SlotCompleteListener, ReplayerError> {
+        let connection_str = self.connection_str();
+        let client = Client::connect(&connection_str, NoTls).map_err(|err| {
+            ReplayerError::DbConnectError {
+                msg: format!("the config is {}, error: {}", connection_str, err),
+            }
+        })?;
+        Ok(SlotCompleteListener::new(client)?)
+    }
 
-        let row = &result.unwrap()[0];
-        let num: i64 = row.get(0);
-        num as u64
+    /// Wait for a new slot in the DA backend: until one is notified with the
+    /// PostgreSQL backend, for the poll interval otherwise. The listener is
+    /// reconnected if its connection is lost, returning right away so the
+    /// slots completed in the meantime are polled for.
+    fn wait_for_new_slot(&mut self) {
+        let listen = matches!(
+            self.config.as_ref().unwrap().da_backend,
+            DataAvailabilityBackendConfig::Postgres
+        );
+        if listen && self.slot_listener.is_none() {
+            match self.connect_slot_listener() {
+                Ok(slot_listener) => {
+                    self.slot_listener = Some(slot_listener);
+                    return;
+                }
+                Err(err) => println!("Failed to listen for new slots, polling instead: {}", err),
+            }
+        }
+        match self.slot_listener.as_mut() {
+            Some(slot_listener) => {
+                if let Err(err) = slot_listener.wait(self.poll_interval) {
+                    println!("Lost the connection listening for new slots: {}", err);
+                    self.slot_listener = None;
+                    sleep(self.poll_interval);
+                }
+            }
+            None => sleep(self.poll_interval),
+        }
     }
 
     pub fn setup_blockstore(&mut self) -> Result<(), ReplayerError> {
@@ -201,58 +689,322 @@ impl Replayer {
         Ok(())
     }
 
-    /// Query shred by slot and update blockstore.
-    pub fn insert_shred_endwith_slot(&mut self, slot: u64) -> Result<(), ReplayerError> {
-        let mut cur_slot = 1;
-        loop {
-            let shreds = self.load_shred_from_pg(cur_slot);
-            shreds.into_iter().for_each(|s| {
-                let res = self
-                    .blockstore
-                    .as_mut()
-                    .unwrap()
-                    .insert_shreds(vec![s], None, false);
-                if res.is_err() {
-                    println!("insert failed at slot: {}", cur_slot);
-                }
-            });
+    /// The snapshot archives are written to, and resumed from, the ledger directory.
+    fn snapshot_config(&self) -> SnapshotConfig {
+        let ledger_path = self.ledger_path.as_ref().unwrap();
+        SnapshotConfig {
+            full_snapshot_archives_dir: ledger_path.clone(),
+            incremental_snapshot_archives_dir: ledger_path.clone(),
+            bank_snapshots_dir: ledger_path.join("snapshot.shred-replay"),
+            ..SnapshotConfig::default()
+        }
+    }
 
-            if cur_slot >= slot {
-                break;
-            }
-            cur_slot += 1;
+    /// Fetch the archives of the snapshot source into a staging directory,
+    /// unless the ledger directory already has a snapshot archive to resume
+    /// from. Returns the staging directory and the slot of the fetched
+    /// snapshot.
+    fn fetch_snapshot(&self) -> Result<Option<(SnapshotStaging, Slot)>, ReplayerError> {
+        let snapshot_source = match self.snapshot_source.as_ref() {
+            Some(snapshot_source) => snapshot_source,
+            None => return Ok(None),
+        };
+        let snapshot_config = self.snapshot_config();
+        if snapshot_utils::get_highest_full_snapshot_archive_info(
+            &snapshot_config.full_snapshot_archives_dir,
+        )
+        .is_some()
+        {
+            println!("Resuming from the snapshot in the ledger directory");
+            return Ok(None);
         }
+        let staging = SnapshotStaging::new(self.ledger_path.as_ref().unwrap())?;
+        let slot = snapshot_source.fetch(&staging.snapshot_config("snapshot.shred-replay"))?;
+        println!("Bootstrapping from the snapshot of slot {}", slot);
+        Ok(Some((staging, slot)))
+    }
+
+    /// Check the bank loaded from the snapshot of `slot` against the state
+    /// root and the bank hash the execution node committed for its slot. Only
+    /// then its archives are moved from `staging` to the ledger directory, to
+    /// resume from.
+    fn verify_snapshot(
+        &mut self,
+        bank: &Bank,
+        staging: SnapshotStaging,
+        slot: Slot,
+    ) -> Result<(), ReplayerError> {
+        if self.client.is_none() {
+            self.connect_db()?;
+        }
+        let client = self.client.as_mut().unwrap();
+        let committed_state_root = query_committed_state_root(client, slot)?;
+        let committed_bank_hash = query_committed_bank_hash(client, slot)?;
+        verify_snapshot_bank(bank, slot, committed_state_root, committed_bank_hash)?;
+        staging.commit(&self.snapshot_config().full_snapshot_archives_dir)?;
+        println!(
+            "snapshot of slot {} verified, state root: {}, bank hash: {}",
+            slot,
+            bank.state_root(),
+            bank.hash()
+        );
         Ok(())
     }
 
-    pub fn insert_shred_startwith_slot(&mut self, slot: u64) -> Result<(), ReplayerError> {
-        let mut verified: i64 = (slot - 1) as i64;
-        let mut cur_slot: i64 = slot as i64;
-        let ledger_path = self
+    /// Load the banks from the latest snapshot in the ledger directory, from the
+    /// snapshot source for a new ledger, or from genesis. They provide the
+    /// leader schedule and the shred version the shreds are verified against,
+    /// and are kept warm to replay the inserted slots. Requires the blockstore.
+    pub fn setup_ledger_verifier(&mut self) -> Result<(), ReplayerError> {
+        let snapshot = self.fetch_snapshot()?;
+        let genesis_config = GenesisConfig::load(self.genesis_path.as_ref().unwrap().as_path())
+            .map_err(|e| ReplayerError::InitLedgerError { msg: e.to_string() })?;
+        let account_paths = vec![self
             .ledger_path
             .as_ref()
             .unwrap()
-            .as_path()
-            .display()
-            .to_string();
-        let entry_index = 0_i64;
+            .join("accounts.shred-replay")];
+        let process_options = ProcessOptions {
+            accounts_db_caching_enabled: true,
+            persist_state_tree: true,
+            ..ProcessOptions::default()
+        };
+        let snapshot_config = match snapshot.as_ref() {
+            Some((staging, _)) => staging.snapshot_config("snapshot.shred-replay"),
+            None => self.snapshot_config(),
+        };
+        let (bank_forks, leader_schedule_cache, ..) = bank_forks_utils::load_bank_forks(
+            &genesis_config,
+            self.blockstore.as_ref().unwrap(),
+            account_paths,
+            None,
+            Some(&snapshot_config),
+            &process_options,
+            None,
+            None,
+        );
+        let root_bank = bank_forks.read().unwrap().root_bank();
+        if let Some((staging, slot)) = snapshot {
+            self.verify_snapshot(&root_bank, staging, slot)?;
+        }
+        let shred_version = compute_shred_version(
+            &genesis_config.hash(),
+            Some(&root_bank.hard_forks().read().unwrap()),
+        );
+        self.ledger_verifier = Some(LedgerVerifier::new(
+            bank_forks,
+            leader_schedule_cache,
+            shred_version,
+        ));
+        Ok(())
+    }
+
+    /// Replay the inserted slots up to `slot`, printing the bank hash and state
+    /// root of each. When connected to the database, the bank hashes are also
+    /// checked against the ones of the execution node.
+    pub fn verify_to_slot(&mut self, slot: Slot) -> Result<(), ReplayerError> {
+        let start = Instant::now();
+        let bank_hashes = self
+            .ledger_verifier
+            .as_ref()
+            .unwrap()
+            .replay_to_slot(self.blockstore.as_ref().unwrap(), slot)?;
+        let verify_duration = start.elapsed();
+        for (slot, hash, state_root) in &bank_hashes {
+            println!(
+                "slot: {}, bank hash: {}, state root: {}",
+                slot, hash, state_root
+            );
+        }
+        if self.client.is_some() {
+            self.check_bank_hashes(&bank_hashes)?;
+        }
+
+        let mut status = self.status.write().unwrap();
+        status.last_verified_slot = Some(slot);
+        status.last_verify_duration_ms = Some(verify_duration.as_millis() as u64);
+        status.report_metrics();
+        Ok(())
+    }
+
+    /// Create a full snapshot archive of the last verified bank in the ledger directory.
+    pub fn create_snapshot(&mut self) -> Result<Slot, ReplayerError> {
+        let bank = self.ledger_verifier.as_ref().unwrap().root_bank();
+        let snapshot_config = self.snapshot_config();
+        std::fs::create_dir_all(&snapshot_config.bank_snapshots_dir)?;
+        snapshot_utils::bank_to_full_snapshot_archive(
+            &snapshot_config.bank_snapshots_dir,
+            &bank,
+            Some(snapshot_config.snapshot_version),
+            &snapshot_config.full_snapshot_archives_dir,
+            &snapshot_config.incremental_snapshot_archives_dir,
+            snapshot_config.archive_format,
+            snapshot_config.maximum_full_snapshot_archives_to_retain,
+            snapshot_config.maximum_incremental_snapshot_archives_to_retain,
+        )
+        .map_err(|err| ReplayerError::CreateSnapshotError {
+            slot: bank.slot(),
+            msg: err.to_string(),
+        })?;
+        Ok(bank.slot())
+    }
+
+    /// The slot of the bank the replay starts from.
+    pub fn root_slot(&self) -> Slot {
+        self.ledger_verifier.as_ref().unwrap().root_bank().slot()
+    }
+
+    /// The highest slot with shreds in the blockstore.
+    pub fn highest_inserted_slot(&self) -> Result<Option<Slot>, ReplayerError> {
+        self.blockstore
+            .as_ref()
+            .unwrap()
+            .highest_slot()
+            .map_err(|e| ReplayerError::InitBlockstoreError { msg: e.to_string() })
+    }
+
+    fn insert_shreds(&mut self, slot: u64, shreds: Vec<Shred>) -> Result<(), ReplayerError> {
+        let num_shreds = shreds.len();
+        self.blockstore
+            .as_ref()
+            .unwrap()
+            .insert_shreds(shreds, None, false)
+            .map_err(|err| ReplayerError::InsertShredError {
+                slot,
+                msg: err.to_string(),
+            })?;
+        println!(
+            "insert shred succeed at slot: {}, num_shreds: {}",
+            slot, num_shreds
+        );
+        self.status.write().unwrap().last_inserted_slot = Some(slot);
+        Ok(())
+    }
+
+    /// Insert the slots of a DA blob into the blockstore, once its entries are
+    /// checked against the commitment in its header and the header against
+    /// the signature of the slot leader. Returns the slots inserted.
+    pub fn insert_blob(&mut self, blob: &DaBlob) -> Result<Vec<Slot>, ReplayerError> {
+        let slots = blob.decode()?;
+        self.ledger_verifier
+            .as_ref()
+            .unwrap()
+            .verify_blob(blob, &slots)?;
+        let num_shreds = insert_slot_entries(
+            self.blockstore.as_ref().unwrap(),
+            &slots,
+            &Keypair::new(),
+            self.ledger_verifier.as_ref().unwrap().shred_version,
+        )?;
+        println!(
+            "insert blob succeed at slots: {}-{}, num_entries: {}, num_shreds: {}",
+            blob.header.start_slot, blob.header.end_slot, blob.header.entry_count, num_shreds
+        );
+        self.status.write().unwrap().last_inserted_slot = Some(blob.header.end_slot);
+        Ok(slots.iter().map(|slot_entries| slot_entries.slot).collect())
+    }
+
+    fn da_payload(&self) -> DataAvailabilityPayload {
+        self.config
+            .as_ref()
+            .map(|config| config.da_payload)
+            .unwrap_or_default()
+    }
+
+    /// Insert the slot published to the DA backend at `slot`, its shreds or
+    /// the slots of the blob starting at it, as configured by `da_payload`.
+    /// Returns the last slot inserted, `None` if nothing was published at
+    /// `slot`.
+    fn insert_published_slot(&mut self, slot: Slot) -> Result<Option<Slot>, ReplayerError> {
+        match self.da_payload() {
+            DataAvailabilityPayload::Shreds => {
+                let shreds = self.load_shreds(slot)?;
+                if shreds.is_empty() {
+                    return Ok(None);
+                }
+                self.insert_shreds(slot, shreds)?;
+                Ok(Some(slot))
+            }
+            DataAvailabilityPayload::Blob => {
+                match self.da_backend.as_mut().unwrap().fetch_blob(slot)? {
+                    Some(blob) => Ok(self.insert_blob(&blob)?.last().copied()),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Query the shreds or blobs of the slots in `[starting_slot, ending_slot]`
+    /// and update blockstore.
+    pub fn insert_shred_range(
+        &mut self,
+        starting_slot: u64,
+        ending_slot: u64,
+    ) -> Result<(), ReplayerError> {
+        let mut slot = starting_slot;
+        while slot <= ending_slot {
+            slot = self.insert_published_slot(slot)?.unwrap_or(slot) + 1;
+        }
+        Ok(())
+    }
+
+    /// The first slot after `slot` and up to `highest_slot` published to the DA
+    /// backend, if its parent precedes `slot`: `slot` and the slots up to it are
+    /// then skipped. `None` if the next published slot descends from a slot
+    /// which is not published yet.
+    fn next_published_slot(
+        &mut self,
+        slot: Slot,
+        highest_slot: Slot,
+    ) -> Result<Option<Slot>, ReplayerError> {
+        let da_payload = self.da_payload();
+        let da_backend = self.da_backend.as_mut().unwrap();
+        for next_slot in slot.saturating_add(1)..=highest_slot {
+            let parent_slot = match da_payload {
+                DataAvailabilityPayload::Shreds => da_backend
+                    .fetch_slot(next_slot)?
+                    .map(|slot_data| slot_data.parent_slot),
+                DataAvailabilityPayload::Blob => da_backend
+                    .fetch_blob(next_slot)?
+                    .map(|blob| blob.header.parent_slot),
+            };
+            if let Some(parent_slot) = parent_slot {
+                return Ok((parent_slot < slot).then(|| next_slot));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Follow the DA backend from `slot`, inserting the shreds of every new slot.
+    /// Every `verify_interval_slots` slots the new slots are replayed and the
+    /// verified slot recorded; every `snapshot_interval_slots` slots a snapshot
+    /// is created. Only returns on error.
+    pub fn insert_shred_startwith_slot(&mut self, slot: u64) -> Result<(), ReplayerError> {
+        let mut verified = slot.saturating_sub(1);
+        let mut snapshot_slot = verified;
+        let mut cur_slot = slot;
         loop {
-            let mut flag = true;
-            let more_slots = self.query_newer_slot(cur_slot as u64);
-            if more_slots == 0 {
+            let highest_slot = self.da_backend.as_mut().unwrap().highest_slot()?;
+            let slots_behind = highest_slot.unwrap_or_default().saturating_sub(cur_slot);
+            {
+                let mut status = self.status.write().unwrap();
+                status.da_highest_slot = highest_slot;
+                status.slots_behind = slots_behind;
+            }
+            if slots_behind == 0 {
                 println!(
                     "[{:?}]No more new shred available beyond slot {} ",
                     chrono::offset::Utc::now(),
                     cur_slot
                 );
 
-                std::thread::sleep(std::time::Duration::from_secs(10));
+                self.wait_for_new_slot();
                 continue;
             }
 
-            let shreds = self.load_shred_from_pg(cur_slot as u64);
+            let inserted_slot = self.insert_published_slot(cur_slot)?;
             // This is hardly happen.
-            if shreds.is_empty() {
+            if inserted_slot.is_none() {
                 // no more new shred available
                 println!(
                     "[{:?}]No more shred available at slot {} ",
@@ -260,103 +1012,289 @@ impl Replayer {
                     cur_slot
                 );
 
-                // in case cur_slot is restart point, we try next slot.
-                let shreds = self.load_shred_from_pg((cur_slot + 1) as u64);
-                if !shreds.is_empty() {
-                    cur_slot += 1;
-
+                // cur_slot may have been skipped by its leader, or be the
+                // restart point, then the replay goes on with the next slot
+                // published after it.
+                if let Some(next_slot) =
+                    self.next_published_slot(cur_slot, highest_slot.unwrap_or_default())?
+                {
+                    println!(
+                        "[{:?}]slots {}-{} were skipped, resuming at slot {}",
+                        chrono::offset::Utc::now(),
+                        cur_slot,
+                        next_slot - 1,
+                        next_slot
+                    );
+                    cur_slot = next_slot;
                     continue;
                 }
-                std::thread::sleep(std::time::Duration::from_secs(10));
+                self.wait_for_new_slot();
                 continue;
             }
-            shreds.into_iter().for_each(|s| {
-                let res = self
-                    .blockstore
-                    .as_mut()
-                    .unwrap()
-                    .insert_shreds(vec![s], None, false);
-                if res.is_err() {
-                    println!("insert shred failed at slot: {}", cur_slot);
-                    flag = false;
-                }
-            });
-
-            let (entries, num_shreds, is_full) = self
-                .blockstore
-                .as_ref()
-                .unwrap()
-                .get_slot_entries_with_shred_info(cur_slot as u64, 0, false)
-                .map_err(|err| format!("Failed to load entries for slot {}: {:?}", cur_slot, err))
-                .unwrap();
+            // A blob may hold the slots after cur_slot too
+            cur_slot = inserted_slot.unwrap();
             println!(
-                "insert shred succeed at slot: {}, num_shreds: {}, num_entries: {}, is_full: {}",
+                "[{:?}]slot {} is {} slots behind the DA backend",
+                chrono::offset::Utc::now(),
                 cur_slot,
-                num_shreds,
-                entries.len(),
-                is_full
+                slots_behind
             );
+            self.status.read().unwrap().report_metrics();
 
-            if !flag {
-                break;
-            }
-
-            // Every VERIFY_INTERVAL_SLOTS slot we do a create-snapshot and verify.
-            if cur_slot == verified + Self::VERIFY_INTERVAL_SLOTS {
+            // Every verify_interval_slots slot we replay the new slots and create a snapshot.
+            if cur_slot >= verified + self.verify_interval_slots {
                 // verify first
-                let v_out = run_ledger_tool(&[
-                    "-l",
-                    &ledger_path,
-                    "verify",
-                    "--halt-at-slot",
-                    &cur_slot.to_string(),
-                ]);
-                if !v_out.status.success() {
-                    println!("verify replay ledger failed at slot: {}", cur_slot);
-                    println!("{:?}", v_out);
-                    break;
-                }
-
+                self.verify_to_slot(cur_slot)?;
                 println!("verify replay ledger succed at slot: {}", cur_slot);
+
                 // then create snapshot
-                let cs_slot =
-                    cur_slot - (Self::VERIFY_INTERVAL_SLOTS - Self::CREATE_SNAPSHOT_INTERVAL_SLOTS);
-                let cs_out = run_ledger_tool(&[
-                    "-l",
-                    &ledger_path,
-                    "create-snapshot",
-                    &cs_slot.to_string(),
-                    &ledger_path,
-                ]);
-                if !cs_out.status.success() {
-                    println!("create snapshot failed for slot: {}", cs_slot);
-                    println!("{:?}", cs_out);
-                    break;
+                if self.snapshot_interval_slots > 0
+                    && cur_slot >= snapshot_slot + self.snapshot_interval_slots
+                {
+                    snapshot_slot = self.create_snapshot()?;
+                    println!("create snapshot succed for slot: {}", snapshot_slot);
                 }
-                println!("create snapshot succed for slot: {}", cs_slot);
 
                 // all good? save slot in record db
-                let client = self.client.as_mut().unwrap();
-                client
-                    .execute(
-                        "INSERT INTO replay (slot, entry_index) VALUES ($1, $2)",
-                        &[&cur_slot, &entry_index],
-                    )
-                    .unwrap();
-
+                self.record_verified_slot(cur_slot)?;
                 verified = cur_slot;
             }
             cur_slot += 1;
         }
-
-        Ok(())
     }
 }
 
-pub fn run_ledger_tool(args: &[&str]) -> Output {
-    Command::cargo_bin("solana-ledger-tool")
-        .unwrap()
-        .args(args)
-        .output()
-        .unwrap()
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        da_backend::{filesystem::FilesystemBackend, SlotData},
+        solana_entry::entry,
+        solana_ledger::{
+            blockstore_processor::fill_blockstore_slot_with_ticks,
+            create_new_tmp_ledger_auto_delete,
+        },
+        solana_runtime::genesis_utils::create_genesis_config_with_leader,
+        solana_sdk::{pubkey::Pubkey, signature::Signer},
+    };
+
+    fn make_shreds(keypair: &Keypair, slot: Slot, version: u16) -> Vec<Shred> {
+        let entries = entry::create_ticks(4, 0, Hash::default());
+        Shredder::new(slot, slot - 1, 0, version)
+            .unwrap()
+            .entries_to_shreds(
+                keypair,
+                &entries,
+                true,
+                0,
+                0,
+                true,
+                &ReedSolomonCache::default(),
+                &mut ProcessShredsStats::default(),
+            )
+            .0
+    }
+
+    fn new_ledger_verifier(
+        genesis_config: &GenesisConfig,
+        blockstore: &Blockstore,
+    ) -> LedgerVerifier {
+        let (bank_forks, leader_schedule_cache, ..) = bank_forks_utils::load_bank_forks(
+            genesis_config,
+            blockstore,
+            vec![],
+            None,
+            None,
+            &ProcessOptions::default(),
+            None,
+            None,
+        );
+        let shred_version = compute_shred_version(&genesis_config.hash(), None);
+        LedgerVerifier::new(bank_forks, leader_schedule_cache, shred_version)
+    }
+
+    #[test]
+    fn test_verify_shreds() {
+        let leader_keypair = Keypair::new();
+        let genesis_config =
+            create_genesis_config_with_leader(10_000, &leader_keypair.pubkey(), 100).genesis_config;
+        let (ledger_path, _) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let verifier = new_ledger_verifier(&genesis_config, &blockstore);
+        let shred_version = verifier.shred_version;
+
+        let shreds = make_shreds(&leader_keypair, 1, shred_version);
+        assert!(verifier.verify_shreds(1, &shreds).is_ok());
+
+        // Signed by someone other than the slot leader.
+        let shreds = make_shreds(&Keypair::new(), 1, shred_version);
+        assert!(matches!(
+            verifier.verify_shreds(1, &shreds),
+            Err(ReplayerError::InvalidShredError { slot: 1, .. })
+        ));
+
+        // Produced with the shred version of another cluster.
+        let shreds = make_shreds(&leader_keypair, 1, shred_version.wrapping_add(1));
+        assert!(verifier.verify_shreds(1, &shreds).is_err());
+
+        // Fetched as the data of another slot.
+        let shreds = make_shreds(&leader_keypair, 2, shred_version);
+        assert!(verifier.verify_shreds(1, &shreds).is_err());
+    }
+
+    #[test]
+    fn test_replay_to_slot() {
+        let leader_keypair = Keypair::new();
+        let genesis_config =
+            create_genesis_config_with_leader(10_000, &leader_keypair.pubkey(), 100).genesis_config;
+        let (ledger_path, mut last_entry_hash) =
+            create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        for slot in 1..=4 {
+            last_entry_hash = fill_blockstore_slot_with_ticks(
+                &blockstore,
+                genesis_config.ticks_per_slot,
+                slot,
+                slot - 1,
+                last_entry_hash,
+            );
+        }
+
+        // Replaying in batches only replays the new slots of each batch...
+        let verifier = new_ledger_verifier(&genesis_config, &blockstore);
+        let first_batch = verifier.replay_to_slot(&blockstore, 2).unwrap();
+        assert_eq!(
+            first_batch
+                .iter()
+                .map(|(slot, _, _)| *slot)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(verifier.root_bank().slot(), 2);
+        let second_batch = verifier.replay_to_slot(&blockstore, 4).unwrap();
+        assert_eq!(
+            second_batch
+                .iter()
+                .map(|(slot, _, _)| *slot)
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        assert!(verifier.replay_to_slot(&blockstore, 4).unwrap().is_empty());
+
+        // ...and yields the same bank hashes and state roots as replaying all
+        // of them at once.
+        let all_at_once = new_ledger_verifier(&genesis_config, &blockstore)
+            .replay_to_slot(&blockstore, 4)
+            .unwrap();
+        assert_eq!([first_batch, second_batch].concat(), all_at_once);
+
+        // Slot 5 is not in the blockstore.
+        assert!(matches!(
+            verifier.replay_to_slot(&blockstore, 5),
+            Err(ReplayerError::VerifyError { slot: 5, .. })
+        ));
+        assert_eq!(verifier.root_bank().slot(), 4);
+    }
+
+    #[test]
+    fn test_next_published_slot() {
+        let genesis_config =
+            create_genesis_config_with_leader(10_000, &Pubkey::new_unique(), 100).genesis_config;
+        let (da_path, _) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let mut da_backend = FilesystemBackend::new(&da_path.path().join("da")).unwrap();
+        // Slots 3 and 4 were skipped, slot 7 is not published yet
+        for (slot, parent_slot) in [(1, 0), (2, 1), (5, 2), (8, 7)] {
+            da_backend
+                .publish_slot(&SlotData {
+                    slot,
+                    parent_slot,
+                    is_full_slot: true,
+                    shreds: vec![],
+                })
+                .unwrap();
+        }
+        let mut replayer = Replayer::new();
+        replayer.da_backend = Some(Box::new(da_backend));
+
+        assert_eq!(replayer.next_published_slot(3, 8).unwrap(), Some(5));
+        assert_eq!(replayer.next_published_slot(3, 4).unwrap(), None);
+        assert_eq!(replayer.next_published_slot(6, 8).unwrap(), None);
+        assert_eq!(replayer.next_published_slot(7, 8).unwrap(), None);
+    }
+
+    #[test]
+    fn test_insert_slot_entries() {
+        let leader_keypair = Keypair::new();
+        let genesis_config =
+            create_genesis_config_with_leader(10_000, &leader_keypair.pubkey(), 100).genesis_config;
+        let (ledger_path, mut last_entry_hash) =
+            create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let (blob_ledger_path, _) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let blob_blockstore = Blockstore::open(blob_ledger_path.path()).unwrap();
+
+        // The same slots, from the shreds of the leader and from a blob
+        let mut slots = vec![];
+        for slot in 1..=3 {
+            let entries = entry::create_ticks(genesis_config.ticks_per_slot, 0, last_entry_hash);
+            last_entry_hash = fill_blockstore_slot_with_ticks(
+                &blockstore,
+                genesis_config.ticks_per_slot,
+                slot,
+                slot - 1,
+                last_entry_hash,
+            );
+            assert_eq!(entries.last().unwrap().hash, last_entry_hash);
+            slots.push(SlotEntries {
+                slot,
+                parent_slot: slot - 1,
+                entries,
+            });
+        }
+        let mut da_backend = FilesystemBackend::new(&blob_ledger_path.path().join("da")).unwrap();
+        // Signed by someone other than the slot leader.
+        da_backend
+            .publish_blob(&DaBlob::new(&slots, &Keypair::new()).unwrap())
+            .unwrap();
+        let mut replayer = Replayer::new();
+        replayer.config = Some(serde_json::from_str("{\"da_payload\" : \"blob\"}").unwrap());
+        replayer.da_backend = Some(Box::new(da_backend));
+        replayer.ledger_verifier = Some(new_ledger_verifier(&genesis_config, &blob_blockstore));
+        replayer.blockstore = Some(blob_blockstore);
+        assert!(matches!(
+            replayer.insert_shred_range(1, 3),
+            Err(ReplayerError::InvalidBlobError { slot: 1, .. })
+        ));
+        assert!(!replayer.blockstore.as_ref().unwrap().is_full(1));
+
+        replayer
+            .da_backend
+            .as_mut()
+            .unwrap()
+            .publish_blob(&DaBlob::new(&slots, &leader_keypair).unwrap())
+            .unwrap();
+        replayer.insert_shred_range(1, 3).unwrap();
+        assert_eq!(replayer.status.read().unwrap().last_inserted_slot, Some(3));
+        let blob_blockstore = replayer.blockstore.as_ref().unwrap();
+        for slot in 1..=3 {
+            assert!(blob_blockstore.is_full(slot));
+            assert_eq!(
+                blob_blockstore.get_slot_entries(slot, 0).unwrap(),
+                blockstore.get_slot_entries(slot, 0).unwrap()
+            );
+        }
+
+        // Replaying them yields the same bank hashes and state roots
+        assert_eq!(
+            replayer
+                .ledger_verifier
+                .as_ref()
+                .unwrap()
+                .replay_to_slot(blob_blockstore, 3)
+                .unwrap(),
+            new_ledger_verifier(&genesis_config, &blockstore)
+                .replay_to_slot(&blockstore, 3)
+                .unwrap()
+        );
+    }
 }
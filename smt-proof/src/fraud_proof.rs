@@ -0,0 +1,662 @@
+//! Fraud proofs of the transactions of a slot.
+//!
+//! A fraud proof packs the accounts the transactions load, the active features
+//! and the sysvars the slot inherits from its parent, proven against the state
+//! root of the parent bank. The clock, fees and slot hashes sysvars, which the
+//! slot writes before its transactions, are proven against the state root of
+//! the slot. Both roots are the `state_root` of the `SlotHeader`s the sequencer
+//! signs, which the settlement layer checks the package against. The fee of the
+//! transactions and the blockhashes they may use are the ones of the proven
+//! recent blockhashes sysvar, so nothing the transactions see is taken from the
+//! package on trust.
+//!
+//! The package also holds the transactions and the post-state root claimed for
+//! them. The post-state root is the state root of the parent with the accounts
+//! written by the transactions, the `Bank::transactions_state_root` the
+//! sequencer signs in the `SlotHeader` of the slot. It leaves out the changes
+//! the bank makes outside of transactions, like the sysvar updates and the fee
+//! distribution, which are only in the bank's `state_root`. The slots starting
+//! an epoch are not provable, as the rewards and the feature activations are
+//! written before the transactions.
+//!
+//! The verifier re-executes the transactions in a bank holding only the
+//! accounts of the package, and recomputes the post-state root from the
+//! proof of the pre-state.
+use {
+    serde_derive::{Deserialize, Serialize},
+    solana_program_runtime::timings::ExecuteTimings,
+    solana_runtime::{
+        bank::{Bank, TransactionExecutionResult},
+        state_tree::{hash_to_root, leaf_key, leaf_value, root_to_hash},
+    },
+    solana_sdk::{
+        account::{from_account, Account, AccountSharedData, ReadableAccount},
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        clock::{Slot, MAX_PROCESSING_AGE},
+        epoch_schedule::EpochSchedule,
+        feature::{self, Feature},
+        fee_calculator::FeeRateGovernor,
+        genesis_config::GenesisConfig,
+        hash::Hash,
+        pubkey::Pubkey,
+        rent::Rent,
+        sysvar::{self, Sysvar},
+        transaction::{TransactionError, TransactionVerificationMode, VersionedTransaction},
+    },
+    sparse_merkle_tree::{blake2b::Blake2bHasher, CompiledMerkleProof},
+    std::{
+        collections::{BTreeSet, HashMap},
+        sync::Arc,
+    },
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum FraudProofError {
+    #[error("Bank of slot {slot} is not frozen or its state is rooted past it")]
+    BankNotProvable { slot: Slot },
+
+    #[error("Failed to prove the state of slot {slot} Error message: ({msg})")]
+    StateTree { slot: Slot, msg: String },
+
+    #[error("Invalid transaction {index} Error message: ({msg})")]
+    InvalidTransaction { index: usize, msg: String },
+
+    #[error("Transaction {index} was not executed Error message: ({err})")]
+    TransactionNotExecuted { index: usize, err: TransactionError },
+
+    #[error("Account {pubkey} of transaction {index} is not in the fraud proof")]
+    MissingAccount { index: usize, pubkey: Pubkey },
+
+    #[error("The pre-state accounts are not proven against the pre-state root")]
+    InvalidPreState,
+
+    #[error("The sysvars are not proven against the state root of the slot")]
+    InvalidSysvars,
+
+    #[error("Invalid fraud proof Error message: ({msg})")]
+    InvalidFraudProof { msg: String },
+}
+
+/// Outcome of the re-execution of a fraud proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FraudProofVerdict {
+    /// The re-executed post-state root is the claimed one
+    ClaimValid,
+    /// The re-executed post-state root differs from the claimed one
+    ClaimInvalid { post_state_root: Hash },
+}
+
+/// The verdict of a fraud proof, and the transactions which failed when they
+/// were re-executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FraudProofReport {
+    pub verdict: FraudProofVerdict,
+    /// Transactions which executed with an error, by index. They are
+    /// committed as in the slot, which charges their fee.
+    pub failed_transactions: Vec<(usize, TransactionError)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FraudProof {
+    /// Slot the transactions were executed in
+    pub slot: Slot,
+    /// State root of the parent bank of `slot`
+    pub pre_state_root: Hash,
+    /// Accounts loaded by the transactions, the active features and the
+    /// sysvars `slot` inherits, in the state of the parent bank. Missing
+    /// accounts are default accounts.
+    pub pre_state: Vec<(Pubkey, Account)>,
+    /// Compiled merkle proof of `pre_state` against `pre_state_root`
+    pub pre_state_proof: Vec<u8>,
+    /// State root of the bank of `slot`
+    pub state_root: Hash,
+    /// Sysvars written by `slot` before its transactions
+    pub sysvars: Vec<(Pubkey, Account)>,
+    /// Compiled merkle proof of `sysvars` against `state_root`
+    pub sysvars_proof: Vec<u8>,
+    /// Transactions of `slot`, in execution order
+    pub transactions: Vec<VersionedTransaction>,
+    pub claimed_post_state_root: Hash,
+}
+
+impl FraudProof {
+    /// Pack the `transactions` executed in the frozen `bank`, a child of
+    /// `parent`, with the post-state root claimed for them
+    pub fn new(
+        parent: &Bank,
+        bank: &Bank,
+        transactions: Vec<VersionedTransaction>,
+        claimed_post_state_root: Hash,
+    ) -> Result<Self, FraudProofError> {
+        if bank.parent_slot() != parent.slot() {
+            return Err(FraudProofError::InvalidFraudProof {
+                msg: format!(
+                    "slot {} is not a child of slot {}",
+                    bank.slot(),
+                    parent.slot()
+                ),
+            });
+        }
+        if bank.epoch() != parent.epoch() {
+            return Err(FraudProofError::InvalidFraudProof {
+                msg: format!(
+                    "slot {} starts epoch {}, its accounts are written before its transactions",
+                    bank.slot(),
+                    bank.epoch()
+                ),
+            });
+        }
+        let mut pubkeys: BTreeSet<_> = parent.feature_set.active.keys().copied().collect();
+        pubkeys.extend(inherited_sysvar_ids());
+        for (index, transaction) in transactions.iter().enumerate() {
+            let transaction = bank
+                .verify_transaction(transaction.clone(), TransactionVerificationMode::HashOnly)
+                .map_err(|err| FraudProofError::InvalidTransaction {
+                    index,
+                    msg: err.to_string(),
+                })?;
+            pubkeys.extend(transaction.message().account_keys().iter());
+        }
+        for transaction in &transactions {
+            if let Some(lookups) = transaction.message.address_table_lookups() {
+                pubkeys.extend(lookups.iter().map(|lookup| lookup.account_key));
+            }
+        }
+        // Upgradeable programs are executed from their program data account
+        let programdata: Vec<_> = pubkeys
+            .iter()
+            .filter_map(|pubkey| parent.get_account(pubkey))
+            .filter_map(|account| programdata_address(&account))
+            .collect();
+        pubkeys.extend(programdata);
+        // The sysvars written by the slot are proven against its state root
+        // instead, and the instructions sysvar is not an account
+        let pubkeys: Vec<_> = pubkeys
+            .into_iter()
+            .filter(|pubkey| !is_slot_sysvar_id(pubkey) && !sysvar::instructions::check_id(pubkey))
+            .collect();
+
+        let (_, pre_state_proof) = parent
+            .get_accounts_proof(&pubkeys)
+            .map_err(|err| FraudProofError::StateTree {
+                slot: parent.slot(),
+                msg: format!("{:?}", err),
+            })?
+            .ok_or(FraudProofError::BankNotProvable {
+                slot: parent.slot(),
+            })?;
+        let pre_state = pubkeys
+            .into_iter()
+            .map(|pubkey| {
+                let account = parent.get_account(&pubkey).unwrap_or_default();
+                (pubkey, Account::from(account))
+            })
+            .collect();
+
+        let sysvar_ids = slot_sysvar_ids();
+        let (_, sysvars_proof) = bank
+            .get_accounts_proof(&sysvar_ids)
+            .map_err(|err| FraudProofError::StateTree {
+                slot: bank.slot(),
+                msg: format!("{:?}", err),
+            })?
+            .ok_or(FraudProofError::BankNotProvable { slot: bank.slot() })?;
+        let sysvars = sysvar_ids
+            .into_iter()
+            .map(|pubkey| {
+                let account = bank.get_account(&pubkey).unwrap_or_default();
+                (pubkey, Account::from(account))
+            })
+            .collect();
+
+        Ok(Self {
+            slot: bank.slot(),
+            pre_state_root: parent.state_root(),
+            pre_state,
+            pre_state_proof,
+            state_root: bank.state_root(),
+            sysvars,
+            sysvars_proof,
+            transactions,
+            claimed_post_state_root,
+        })
+    }
+
+    /// Re-execute the transactions over the pre-state and compare the
+    /// post-state root with the claimed one. A transaction which fails to load
+    /// can not be in the slot, so it fails the verification, like a package
+    /// which does not prove the sysvars the transactions see.
+    pub fn verify(&self) -> Result<FraudProofReport, FraudProofError> {
+        if self.slot == 0 {
+            return Err(FraudProofError::InvalidFraudProof {
+                msg: "slot 0 has no parent".to_string(),
+            });
+        }
+        // The sysvars written by the slot are proven against its state root,
+        // all the others against the pre-state root
+        let pre_state_keys: BTreeSet<_> =
+            self.pre_state.iter().map(|(pubkey, _)| *pubkey).collect();
+        let sysvar_keys: Vec<_> = self.sysvars.iter().map(|(pubkey, _)| *pubkey).collect();
+        if sysvar_keys != slot_sysvar_ids()
+            || pre_state_keys.iter().any(is_slot_sysvar_id)
+            || inherited_sysvar_ids().any(|pubkey| !pre_state_keys.contains(&pubkey))
+        {
+            return Err(FraudProofError::InvalidFraudProof {
+                msg: "the sysvars of the slot are not the packed ones".to_string(),
+            });
+        }
+        if !verify_accounts(&self.pre_state_root, &self.pre_state, &self.pre_state_proof) {
+            return Err(FraudProofError::InvalidPreState);
+        }
+        if !verify_accounts(&self.state_root, &self.sysvars, &self.sysvars_proof) {
+            return Err(FraudProofError::InvalidSysvars);
+        }
+
+        let bank = self.new_bank()?;
+        let packed: HashMap<_, _> = self
+            .pre_state
+            .iter()
+            .chain(&self.sysvars)
+            .map(|(pubkey, account)| (*pubkey, account))
+            .collect();
+        let mut failed_transactions = vec![];
+        for (index, transaction) in self.transactions.iter().enumerate() {
+            let transaction = bank
+                .verify_transaction(
+                    transaction.clone(),
+                    TransactionVerificationMode::FullVerification,
+                )
+                .map_err(|err| FraudProofError::InvalidTransaction {
+                    index,
+                    msg: err.to_string(),
+                })?;
+            // Programs need their program data account too
+            if let Some(pubkey) = transaction
+                .message()
+                .account_keys()
+                .iter()
+                .flat_map(|pubkey| {
+                    let programdata = packed
+                        .get(pubkey)
+                        .and_then(|account| programdata_address(*account));
+                    std::iter::once(*pubkey).chain(programdata)
+                })
+                .find(|pubkey| {
+                    !packed.contains_key(pubkey) && !sysvar::instructions::check_id(pubkey)
+                })
+            {
+                return Err(FraudProofError::MissingAccount { index, pubkey });
+            }
+            let batch = bank.prepare_sanitized_batch(std::slice::from_ref(&transaction));
+            let (results, _) = bank.load_execute_and_commit_transactions(
+                &batch,
+                MAX_PROCESSING_AGE,
+                false,
+                false,
+                false,
+                false,
+                &mut ExecuteTimings::default(),
+                None,
+            );
+            match &results.execution_results[0] {
+                TransactionExecutionResult::NotExecuted(err) => {
+                    return Err(FraudProofError::TransactionNotExecuted {
+                        index,
+                        err: err.clone(),
+                    });
+                }
+                TransactionExecutionResult::Executed { details, .. } => {
+                    if let Err(err) = &details.status {
+                        failed_transactions.push((index, err.clone()));
+                    }
+                }
+            }
+        }
+
+        let post_state_leaves = self
+            .pre_state
+            .iter()
+            .map(|(pubkey, _)| {
+                let account = bank.get_account(pubkey).unwrap_or_default();
+                (leaf_key(pubkey), leaf_value(&account))
+            })
+            .collect();
+        let post_state_root = CompiledMerkleProof(self.pre_state_proof.clone())
+            .compute_root::<Blake2bHasher>(post_state_leaves)
+            .map(|root| root_to_hash(&root))
+            .map_err(|err| FraudProofError::InvalidFraudProof {
+                msg: err.to_string(),
+            })?;
+        let verdict = if post_state_root == self.claimed_post_state_root {
+            FraudProofVerdict::ClaimValid
+        } else {
+            FraudProofVerdict::ClaimInvalid { post_state_root }
+        };
+        Ok(FraudProofReport {
+            verdict,
+            failed_transactions,
+        })
+    }
+
+    fn sysvar<S: Sysvar>(&self) -> Option<S> {
+        self.pre_state
+            .iter()
+            .chain(&self.sysvars)
+            .find(|(pubkey, _)| *pubkey == S::id())
+            .and_then(|(_, account)| from_account(account))
+    }
+
+    /// A bank of `slot` holding only the accounts of the package
+    #[allow(deprecated)]
+    fn new_bank(&self) -> Result<Bank, FraudProofError> {
+        // Newest first, the first one is the last blockhash of the parent
+        let recent_blockhashes = self
+            .sysvar::<sysvar::recent_blockhashes::RecentBlockhashes>()
+            .filter(|recent_blockhashes| !recent_blockhashes.is_empty())
+            .ok_or_else(|| FraudProofError::InvalidFraudProof {
+                msg: "the recent blockhashes of the parent are not in the pre-state".to_string(),
+            })?;
+        let mut genesis_config = GenesisConfig {
+            fee_rate_governor: FeeRateGovernor::new(
+                recent_blockhashes[0].fee_calculator.lamports_per_signature,
+                0,
+            ),
+            rent: self.sysvar::<Rent>().unwrap_or_default(),
+            epoch_schedule: self.sysvar::<EpochSchedule>().unwrap_or_default(),
+            ..GenesisConfig::default()
+        };
+        for (pubkey, account) in &self.pre_state {
+            if account.lamports == 0 {
+                continue;
+            }
+            let mut account = account.clone();
+            // Features are activated at genesis, the accounts are restored below
+            if let Some(Feature {
+                activated_at: Some(_),
+            }) = feature::from_account(&account)
+            {
+                let mut feature_account = AccountSharedData::from(account);
+                feature::to_account(
+                    &Feature {
+                        activated_at: Some(0),
+                    },
+                    &mut feature_account,
+                );
+                account = Account::from(feature_account);
+            }
+            genesis_config.accounts.insert(*pubkey, account);
+        }
+
+        let bank0 = Bank::new_with_paths(
+            &genesis_config,
+            Vec::new(),
+            None,
+            None,
+            Default::default(),
+            true,
+            Default::default(),
+            false,
+            None,
+            None,
+        );
+        let bank = Bank::new_from_parent(&Arc::new(bank0), &Pubkey::default(), self.slot);
+        // A transaction using the oldest blockhash the bank accepts, which the
+        // sysvar does not hold anymore, is not provable
+        for entry in recent_blockhashes.iter().rev() {
+            bank.register_recent_blockhash_with_fee(
+                &entry.blockhash,
+                entry.fee_calculator.lamports_per_signature,
+            );
+        }
+        let accounts: Vec<_> = self
+            .pre_state
+            .iter()
+            .chain(&self.sysvars)
+            .map(|(pubkey, account)| (*pubkey, AccountSharedData::from(account.clone())))
+            .collect();
+        bank.overwrite_accounts(&accounts);
+        Ok(bank)
+    }
+}
+
+/// The sysvars a slot writes before its transactions
+#[allow(deprecated)]
+fn slot_sysvar_ids() -> Vec<Pubkey> {
+    vec![
+        sysvar::clock::id(),
+        sysvar::fees::id(),
+        sysvar::slot_hashes::id(),
+    ]
+}
+
+fn is_slot_sysvar_id(pubkey: &Pubkey) -> bool {
+    slot_sysvar_ids().contains(pubkey)
+}
+
+/// The sysvar accounts a slot sees as they are in the state of its parent
+fn inherited_sysvar_ids() -> impl Iterator<Item = Pubkey> {
+    sysvar::ALL_IDS
+        .iter()
+        .copied()
+        .filter(|pubkey| !is_slot_sysvar_id(pubkey) && !sysvar::instructions::check_id(pubkey))
+}
+
+/// Whether `proof` proves `accounts` against `root`
+fn verify_accounts(root: &Hash, accounts: &[(Pubkey, Account)], proof: &[u8]) -> bool {
+    let leaves = accounts
+        .iter()
+        .map(|(pubkey, account)| (leaf_key(pubkey), leaf_value(account)))
+        .collect();
+    CompiledMerkleProof(proof.to_vec())
+        .verify::<Blake2bHasher>(&hash_to_root(root), leaves)
+        .unwrap_or(false)
+}
+
+fn programdata_address(account: &impl ReadableAccount) -> Option<Pubkey> {
+    if !bpf_loader_upgradeable::check_id(account.owner()) {
+        return None;
+    }
+    match bincode::deserialize(account.data()) {
+        Ok(UpgradeableLoaderState::Program {
+            programdata_address,
+        }) => Some(programdata_address),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            clock::Clock, genesis_config::create_genesis_config, native_token::sol_to_lamports,
+            signature::Signer, system_instruction::SystemError, system_transaction,
+        },
+    };
+
+    #[test]
+    fn test_fraud_proof() {
+        let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        bank0.freeze();
+        let amount = genesis_config.rent.minimum_balance(0);
+        let to = Pubkey::new_unique();
+        let transaction =
+            system_transaction::transfer(&mint_keypair, &to, amount, bank0.last_blockhash());
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank1.process_transaction(&transaction).unwrap();
+        bank1.freeze();
+
+        let fraud_proof =
+            FraudProof::new(&bank0, &bank1, vec![transaction.into()], Hash::default()).unwrap();
+        let post_state_root = bank1.transactions_state_root();
+        assert_ne!(post_state_root, fraud_proof.pre_state_root);
+        assert_eq!(
+            fraud_proof.verify().unwrap(),
+            FraudProofReport {
+                verdict: FraudProofVerdict::ClaimInvalid { post_state_root },
+                failed_transactions: vec![],
+            }
+        );
+        let fraud_proof = FraudProof {
+            claimed_post_state_root: post_state_root,
+            ..fraud_proof
+        };
+        assert_eq!(
+            fraud_proof.verify().unwrap().verdict,
+            FraudProofVerdict::ClaimValid
+        );
+        let bytes = bincode::serialize(&fraud_proof).unwrap();
+        assert_eq!(
+            bincode::deserialize::<FraudProof>(&bytes).unwrap(),
+            fraud_proof
+        );
+
+        // The pre-state must be the proven one
+        let mut tampered = fraud_proof.clone();
+        tampered
+            .pre_state
+            .iter_mut()
+            .find(|(pubkey, _)| *pubkey == mint_keypair.pubkey())
+            .unwrap()
+            .1
+            .lamports += 1;
+        assert!(matches!(
+            tampered.verify(),
+            Err(FraudProofError::InvalidPreState)
+        ));
+
+        // ...so must the sysvars of the slot
+        let mut tampered = fraud_proof.clone();
+        let clock_account = &mut tampered
+            .sysvars
+            .iter_mut()
+            .find(|(pubkey, _)| *pubkey == sysvar::clock::id())
+            .unwrap()
+            .1;
+        let mut clock: Clock = from_account(&*clock_account).unwrap();
+        clock.unix_timestamp += 1;
+        clock_account.data = bincode::serialize(&clock).unwrap();
+        assert!(matches!(
+            tampered.verify(),
+            Err(FraudProofError::InvalidSysvars)
+        ));
+
+        // ...and the ones inherited from the parent can not be left out
+        let mut tampered = fraud_proof.clone();
+        tampered
+            .pre_state
+            .retain(|(pubkey, _)| *pubkey != sysvar::rent::id());
+        assert!(matches!(
+            tampered.verify(),
+            Err(FraudProofError::InvalidFraudProof { .. })
+        ));
+
+        // The transactions failing to execute are reported
+        let mut failing = fraud_proof.clone();
+        failing.transactions.push(
+            system_transaction::transfer(
+                &mint_keypair,
+                &to,
+                sol_to_lamports(2.),
+                bank0.last_blockhash(),
+            )
+            .into(),
+        );
+        assert_eq!(
+            failing.verify().unwrap().failed_transactions,
+            vec![(
+                1,
+                TransactionError::InstructionError(
+                    0,
+                    SystemError::ResultWithNegativeLamports.into()
+                )
+            )]
+        );
+
+        // ...and the ones which fail to load can not be in the slot
+        let mut failing = fraud_proof.clone();
+        failing.transactions =
+            vec![
+                system_transaction::transfer(&mint_keypair, &to, amount, Hash::new_unique()).into(),
+            ];
+        assert!(matches!(
+            failing.verify(),
+            Err(FraudProofError::TransactionNotExecuted {
+                index: 0,
+                err: TransactionError::BlockhashNotFound,
+            })
+        ));
+
+        // The package must hold every account of the transactions
+        let missing = Pubkey::new_unique();
+        let mut incomplete = fraud_proof;
+        incomplete.transactions = vec![system_transaction::transfer(
+            &mint_keypair,
+            &missing,
+            amount,
+            bank0.last_blockhash(),
+        )
+        .into()];
+        assert!(matches!(
+            incomplete.verify(),
+            Err(FraudProofError::MissingAccount { index: 0, pubkey }) if pubkey == missing
+        ));
+    }
+
+    #[test]
+    fn test_fraud_proof_committed_root() {
+        let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        bank0.freeze();
+        let amount = genesis_config.rent.minimum_balance(0);
+        let transactions: Vec<_> = (0..3)
+            .map(|_| {
+                system_transaction::transfer(
+                    &mint_keypair,
+                    &Pubkey::new_unique(),
+                    amount,
+                    bank0.last_blockhash(),
+                )
+            })
+            .collect();
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::new_unique(), 1));
+        for transaction in &transactions {
+            bank1.process_transaction(transaction).unwrap();
+        }
+        bank1.freeze();
+
+        let fraud_proof = FraudProof::new(
+            &bank0,
+            &bank1,
+            transactions.into_iter().map(Into::into).collect(),
+            bank1.transactions_state_root(),
+        )
+        .unwrap();
+        assert_eq!(
+            fraud_proof.verify().unwrap().verdict,
+            FraudProofVerdict::ClaimValid
+        );
+        let fraud_proof = FraudProof {
+            claimed_post_state_root: bank1.state_root(),
+            ..fraud_proof
+        };
+        assert_eq!(
+            fraud_proof.verify().unwrap().verdict,
+            FraudProofVerdict::ClaimInvalid {
+                post_state_root: bank1.transactions_state_root()
+            }
+        );
+
+        // The slots starting an epoch are not provable
+        let first_slot = genesis_config
+            .epoch_schedule
+            .get_first_slot_in_epoch(bank1.epoch() + 1);
+        let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), first_slot);
+        assert!(matches!(
+            FraudProof::new(&bank1, &bank2, vec![], Hash::default()),
+            Err(FraudProofError::InvalidFraudProof { .. })
+        ));
+    }
+}
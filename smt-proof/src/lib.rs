@@ -1,20 +1,38 @@
+pub mod fraud_proof;
+pub mod snapshot_bootstrap;
 
-/// SparseMerkleTree api
-/// 1. new
-/// 2. updates
-/// 3. get_proof_with
-/// 4. provide intermediate for conversion
+use {
+    solana_runtime::state_tree::{hash_to_root, leaf_key},
+    solana_sdk::{hash::Hash, pubkey::Pubkey},
+    sparse_merkle_tree::{blake2b::Blake2bHasher, CompiledMerkleProof},
+};
+
+/// Verifies an account proof returned by the `getAccountProof` RPC method.
 ///
-/// type conversion notes
-/// Provide an intermediate type for conversion, instead of directly using solana's type
-/// to avoid circular reference.
+/// `value_hash` is the leaf value of the account, the default hash when the
+/// proof is one of non-membership. Malformed proofs are reported as invalid.
+pub fn verify_account_proof(
+    pubkey: &Pubkey,
+    value_hash: &Hash,
+    proof: &[u8],
+    state_root: &Hash,
+) -> bool {
+    CompiledMerkleProof(proof.to_vec())
+        .verify::<Blake2bHasher>(
+            &hash_to_root(state_root),
+            vec![(leaf_key(pubkey), hash_to_root(value_hash))],
+        )
+        .unwrap_or(false)
+}
 
 #[cfg(test)]
 mod tests {
     use {
+        super::verify_account_proof,
         blake3::{self, Hash},
         hex::decode,
-        solana_sdk::pubkey::Pubkey,
+        solana_runtime::state_tree::{leaf_key, root_to_hash, StateTree},
+        solana_sdk::{hash::Hash as SolanaHash, pubkey::Pubkey},
         sparse_merkle_tree::{
             blake2b::Blake2bHasher, default_store::DefaultStore, SparseMerkleTree, H256,
         },
@@ -29,6 +47,63 @@ mod tests {
         H256::from(data)
     }
 
+    #[test]
+    fn test_verify_account_proof() {
+        let pubkey = Pubkey::new_unique();
+        let missing_pubkey = Pubkey::new_unique();
+        let value = H256::from([1; 32]);
+        let state_tree = StateTree::new_from_leaves(0, vec![(leaf_key(&pubkey), value)]).unwrap();
+        let state_root = state_tree.rooted_root();
+        let value_hash = root_to_hash(&value);
+
+        let (_, proof) = state_tree
+            .merkle_proof(0, state_root, vec![], vec![leaf_key(&pubkey)])
+            .unwrap()
+            .unwrap();
+        assert!(verify_account_proof(
+            &pubkey,
+            &value_hash,
+            &proof.0,
+            &state_root
+        ));
+        assert!(!verify_account_proof(
+            &pubkey,
+            &SolanaHash::default(),
+            &proof.0,
+            &state_root
+        ));
+        assert!(!verify_account_proof(
+            &pubkey,
+            &value_hash,
+            &proof.0,
+            &SolanaHash::default()
+        ));
+        assert!(!verify_account_proof(
+            &pubkey,
+            &value_hash,
+            &[],
+            &state_root
+        ));
+
+        let (missing_values, proof) = state_tree
+            .merkle_proof(0, state_root, vec![], vec![leaf_key(&missing_pubkey)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(missing_values, vec![H256::zero()]);
+        assert!(verify_account_proof(
+            &missing_pubkey,
+            &SolanaHash::default(),
+            &proof.0,
+            &state_root
+        ));
+        assert!(!verify_account_proof(
+            &missing_pubkey,
+            &value_hash,
+            &proof.0,
+            &state_root
+        ));
+    }
+
     #[test]
     fn test_smt_new_empty_tree() {
         let tree = SMT::default();
@@ -8,7 +8,14 @@ use {
     solana_sdk::{
         clock::Slot,
         genesis_config::{GenesisConfig},
+        hash::Hash,
+        signature::Signature,
     },
+    smt_proof::{
+        fraud_proof::{FraudProof, FraudProofVerdict},
+        snapshot_bootstrap::SnapshotSource,
+    },
+    hex::FromHex,
     log::{info, error},
     solana_ledger::{
         bank_forks_utils,
@@ -17,18 +24,24 @@ use {
             AccessType, BlockstoreOptions, BlockstoreRecoveryMode, LedgerColumnOptions,
             ShredStorageType,
         },
-        blockstore_processor::{self, BlockstoreProcessorError, ProcessOptions},
+        blockstore_processor::{
+            self, BlockstoreProcessorError, CacheBlockMetaSender, ConfirmationProgress,
+            ConfirmationTiming, ProcessOptions,
+        },
+        leader_schedule_cache::LeaderScheduleCache,
     },
+    solana_entry::entry::VerifyRecyclers,
     solana_runtime::{
         accounts_background_service::{
             AbsRequestHandler, AbsRequestSender, AccountsBackgroundService,
         },
-        accounts_update_notifier_interface::AccountsUpdateNotifier,
+        bank::Bank,
         accounts_db::{AccountsDbConfig, FillerAccountsConfig},
         accounts_index::{AccountsIndexConfig},
         bank_forks::BankForks,
         hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
         runtime_config::RuntimeConfig,
+        snapshot_config::SnapshotConfig,
     },
     solana_clap_utils::{
         // input_parsers::{cluster_type_of, pubkey_of, pubkeys_of},
@@ -36,8 +49,11 @@ use {
             is_parsable // , is_pow2, is_pubkey, is_pubkey_or_keypair, is_slot, is_valid_percentage,
         },
     },
+    crossbeam_channel::unbounded,
     std::{
-        path::{Path},
+        net::SocketAddr,
+        path::{Path, PathBuf},
+        thread,
         process::{exit},
         sync::{
             atomic::{AtomicBool, Ordering},
@@ -49,7 +65,7 @@ use {
 
 mod ledger_path;
 mod shred_replay;
-mod account_notifier;
+mod root_verifier;
 
 const DEFAULT_LEDGER_TOOL_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES: u64 = u64::MAX;
 
@@ -92,6 +108,25 @@ fn main() {
         .default_value(&default_genesis_archive_unpacked_size)
         .help("maximum total uncompressed size of unpacked genesis archive");
 
+    let snapshot_archive_arg = Arg::with_name("snapshot_archive")
+        .long("snapshot-archive")
+        .value_name("PATH")
+        .takes_value(true)
+        .help("Start from this full snapshot archive instead of replaying from genesis");
+    let incremental_snapshot_archive_arg = Arg::with_name("incremental_snapshot_archive")
+        .long("incremental-snapshot-archive")
+        .value_name("PATH")
+        .takes_value(true)
+        .requires("snapshot_archive")
+        .help("Start from this incremental snapshot archive on top of --snapshot-archive");
+    let snapshot_rpc_arg = Arg::with_name("snapshot_rpc")
+        .long("snapshot-rpc")
+        .value_name("HOST:PORT")
+        .takes_value(true)
+        .validator(is_parsable::<SocketAddr>)
+        .conflicts_with("snapshot_archive")
+        .help("Start from the latest snapshot served by the RPC service of the execution node at this address");
+
     let matches = App::new("solana-smt-replayer")
         .about("Replayer")
         .version("0.1")
@@ -157,6 +192,59 @@ fn main() {
                 .arg(&accounts_filler_count)
                 .arg(&accounts_filler_size)
                 .arg(&max_genesis_archive_unpacked_size_arg)
+                .arg(&snapshot_archive_arg)
+                .arg(&incremental_snapshot_archive_arg)
+                .arg(&snapshot_rpc_arg)
+        )
+        .subcommand(
+            SubCommand::with_name("fraud-proof")
+                .about("Generate the fraud proof of the transactions of a slot and verify it")
+                .arg(
+                    Arg::with_name("slot")
+                        .long("slot")
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Slot of the transactions"),
+                )
+                .arg(
+                    Arg::with_name("signature")
+                        .long("signature")
+                        .value_name("SIGNATURE")
+                        .takes_value(true)
+                        .help("Only pack the transactions of the slot up to this one"),
+                )
+                .arg(
+                    Arg::with_name("claimed_root")
+                        .long("claimed-root")
+                        .value_name("HEX")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Transactions state root claimed in the slot header"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .default_value("fraud-proof.bin")
+                        .help("Write the fraud proof to FILE"),
+                )
+                .arg(&accounts_filler_count)
+                .arg(&accounts_filler_size)
+                .arg(&max_genesis_archive_unpacked_size_arg)
+                .arg(&snapshot_archive_arg)
+                .arg(&incremental_snapshot_archive_arg)
+                .arg(&snapshot_rpc_arg)
+        )
+        .arg(
+            Arg::with_name("check_schema")
+                .long("check-schema")
+                .takes_value(false)
+                .help(
+                    "Print the schema version of the database and the migrations it lacks, \
+                    and exit without applying them",
+                ),
         )
         .after_help("The default subcommand is replay")
         .get_matches();
@@ -180,6 +268,18 @@ fn main() {
         })
         .unwrap();
 
+    if matches.is_present("check_schema") {
+        let replayer = shred_replay::Replayer::new().config(&config);
+        match replayer.check_schema() {
+            Ok(status) => println!("{}", status),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        }
+        return;
+    }
+
     let wal_recovery_mode = matches
         .value_of("wal_recovery_mode")
         .map(BlockstoreRecoveryMode::from);
@@ -199,106 +299,337 @@ fn main() {
 
     match matches.subcommand() {
         ("verify", Some(arg_matches)) => {
-            let mut replayer = shred_replay::Replayer::new().config(&config).ledger_path(&out_ledger_path);
-
-            if let Err(e) = replayer.connect_db() {
-                eprintln!("Failed to connect pg {}", e);
-                exit(1);
-            };
-
-            let mut accounts_index_config = AccountsIndexConfig::default();
-
             let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
             let ending_slot = value_t_or_exit!(arg_matches, "ending_slot", Slot);
             println!("{} {}", starting_slot, ending_slot);
 
-            if let Some(bins) = value_t!(arg_matches, "accounts_index_bins", usize).ok() {
-                accounts_index_config.bins = Some(bins);
-            }
-
-            let filler_accounts_config = FillerAccountsConfig {
-                count: value_t_or_exit!(arg_matches, "accounts_filler_count", usize),
-                size: value_t_or_exit!(arg_matches, "accounts_filler_size", usize),
-            };
+            let (genesis_config, blockstore, mut process_options, snapshot_config) = setup_replay(
+                &config,
+                arg_matches,
+                &ledger_path,
+                &out_ledger_path,
+                wal_recovery_mode,
+                &shred_storage_type,
+                ending_slot,
+            );
+            process_options.halt_at_slot = Some(ending_slot);
 
-            let accounts_db_config = Some(AccountsDbConfig {
-                index: Some(accounts_index_config),
-                accounts_hash_cache_path: Some(ledger_path.clone()),
-                filler_accounts_config,
-                skip_rewrites: arg_matches.is_present("accounts_db_skip_rewrites"),
-                ancient_append_vecs: arg_matches.is_present("accounts_db_ancient_append_vecs"),
-                skip_initial_hash_calc: arg_matches
-                    .is_present("accounts_db_skip_initial_hash_calculation"),
-                ..AccountsDbConfig::default()
+            let mut smt_root_verifier = root_verifier::SmtRootVerifier::new(&config)
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to setup smt root verifier: {}", err);
+                    exit(1);
+                });
+
+            // Verify the state root of each bank as it is frozen, until the first
+            // divergent slot
+            let (cache_block_meta_sender, cache_block_meta_receiver) =
+                unbounded::<Arc<Bank>>();
+            let root_verifier = thread::spawn(move || {
+                let mut result = Ok(());
+                for bank in cache_block_meta_receiver.iter() {
+                    if result.is_err() || bank.slot() < starting_slot {
+                        continue;
+                    }
+                    result = smt_root_verifier.verify_bank(&bank).map(|recorded| {
+                        println!(
+                            "slot: {}, state root: {}{}",
+                            bank.slot(),
+                            hex::encode(bank.state_root()),
+                            if recorded {
+                                ""
+                            } else {
+                                ", no root recorded by the execution node"
+                            }
+                        )
+                    });
+                }
+                result
             });
 
-            let process_options = ProcessOptions {
-                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
-                poh_verify: !arg_matches.is_present("skip_poh_verify"),
-                on_halt_store_hash_raw_data_for_debug: arg_matches
-                    .is_present("halt_at_slot_store_hash_raw_data"),
-                // ledger tool verify always runs the accounts hash calc at the end of processing the blockstore
-                run_final_accounts_hash_calc: true,
-                halt_at_slot: value_t!(arg_matches, "halt_at_slot", u64).ok(),
-                debug_keys: None,
-                accounts_db_caching_enabled: !arg_matches.is_present("no_accounts_db_caching"),
-                limit_load_slot_count_from_snapshot: value_t!(
-                        arg_matches,
-                        "limit_load_slot_count_from_snapshot",
-                        usize
-                    )
-                    .ok(),
-                accounts_db_config,
-                verify_index: arg_matches.is_present("verify_accounts_index"),
-                allow_dead_slots: arg_matches.is_present("allow_dead_slots"),
-                accounts_db_test_hash_calculation: arg_matches
-                    .is_present("accounts_db_test_hash_calculation"),
-                accounts_db_skip_shrink: arg_matches.is_present("accounts_db_skip_shrink"),
-                runtime_config: RuntimeConfig {
-                    bpf_jit: !arg_matches.is_present("no_bpf_jit"),
-                    ..RuntimeConfig::default()
-                },
-                ..ProcessOptions::default()
-            };
-
-            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
-            if let Err(e) = replayer.init_ledger(&genesis_config) {
-                eprintln!("Failed to init new ledger{}", e);
+            let result = load_bank_forks(
+                &genesis_config,
+                &blockstore,
+                process_options,
+                snapshot_config.as_ref(),
+                Some(&cache_block_meta_sender),
+            );
+            drop(cache_block_meta_sender);
+            let verify_result = root_verifier.join().unwrap();
+            if let Err(err) = result {
+                eprintln!("Failed to replay the ledger: {:?}", err);
                 exit(1);
-            };
-
-            let mut blockstore = open_blockstore(
+            }
+            if let Err(err) = verify_result {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        }
+        ("fraud-proof", Some(arg_matches)) => {
+            let slot = value_t_or_exit!(arg_matches, "slot", Slot);
+            let claimed_post_state_root =
+                <[u8; 32]>::from_hex(value_t_or_exit!(arg_matches, "claimed_root", String))
+                    .map(Hash::new_from_array)
+                    .unwrap_or_else(|err| {
+                        eprintln!("Invalid claimed post-state root: {}", err);
+                        exit(1);
+                    });
+            let signature = value_t!(arg_matches, "signature", Signature).ok();
+            let output = PathBuf::from(value_t_or_exit!(arg_matches, "output", String));
+
+            let (genesis_config, blockstore, mut process_options, snapshot_config) = setup_replay(
+                &config,
+                arg_matches,
+                &ledger_path,
                 &out_ledger_path,
-                AccessType::Primary,
                 wal_recovery_mode,
                 &shred_storage_type,
+                slot,
             );
-
-            if let Err(e) = replayer.insert_shred_endwith_slot(ending_slot, &mut blockstore) {
-                eprintln!("Failed to insert shred in pg: {}", e);
-                exit(1);
+            let parent_slot = match blockstore.meta(slot) {
+                Ok(Some(meta)) if meta.parent_slot.is_some() => meta.parent_slot.unwrap(),
+                _ => {
+                    eprintln!("Slot {} or its parent is not in the ledger", slot);
+                    exit(1);
+                }
             };
-
-            // account_notifier
-            let accounts_update_notifier: Option<AccountsUpdateNotifier> = {
-                // init smt and pg client
-                let mut accounts_update_notifier = account_notifier::AccountsUpdateNotifierImpl::new(&config);
-                Some(Arc::new(RwLock::new(accounts_update_notifier)))
+            let mut transactions: Vec<_> = match blockstore.get_slot_entries(slot, 0) {
+                Ok(entries) => entries
+                    .into_iter()
+                    .flat_map(|entry| entry.transactions)
+                    .collect(),
+                Err(err) => {
+                    eprintln!("Failed to read the entries of slot {}: {:?}", slot, err);
+                    exit(1);
+                }
             };
+            if let Some(signature) = signature {
+                match transactions
+                    .iter()
+                    .position(|transaction| transaction.signatures.first() == Some(&signature))
+                {
+                    Some(index) => transactions.truncate(index + 1),
+                    None => {
+                        eprintln!("Transaction {} is not in slot {}", signature, slot);
+                        exit(1);
+                    }
+                }
+            }
 
-            let result = load_bank_forks(
+            // Replay up to the parent, whose state the transactions are
+            // re-executed over
+            process_options.halt_at_slot = Some(parent_slot);
+            let bank_forks = load_bank_forks(
                 &genesis_config,
                 &blockstore,
                 process_options,
-                accounts_update_notifier,
+                snapshot_config.as_ref(),
+                None,
+            )
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to replay the ledger: {:?}", err);
+                    exit(1);
+                });
+            let parent = bank_forks.read().unwrap().get(parent_slot).unwrap_or_else(|| {
+                eprintln!("Bank of slot {} is not replayed", parent_slot);
+                exit(1);
+            });
+            // Replay the slot too, its sysvars are proven against its state
+            // root
+            let leader = LeaderScheduleCache::new_from_bank(&parent)
+                .slot_leader_at(slot, Some(&parent))
+                .unwrap_or_default();
+            let bank = Arc::new(Bank::new_from_parent(&parent, &leader, slot));
+            if let Err(err) = blockstore_processor::confirm_slot(
+                &blockstore,
+                &bank,
+                &mut ConfirmationTiming::default(),
+                &mut ConfirmationProgress::new(parent.last_blockhash()),
+                true,
+                None,
+                None,
+                None,
+                &VerifyRecyclers::default(),
+                false,
+                None,
+            ) {
+                eprintln!("Failed to replay slot {}: {:?}", slot, err);
+                exit(1);
+            }
+            bank.freeze();
+            let fraud_proof =
+                FraudProof::new(&parent, &bank, transactions, claimed_post_state_root)
+                    .unwrap_or_else(|err| {
+                        eprintln!("Failed to generate the fraud proof: {}", err);
+                        exit(1);
+                    });
+            let bytes = bincode::serialize(&fraud_proof).unwrap();
+            if let Err(err) = std::fs::write(&output, bytes) {
+                eprintln!("Failed to write the fraud proof to {:?}: {}", output, err);
+                exit(1);
+            }
+            println!(
+                "fraud proof of {} transactions of slot {} written to {:?}",
+                fraud_proof.transactions.len(),
+                slot,
+                output
             );
 
-            println!("{}", result.is_ok());
+            // Check the package as the settlement layer would, from the file
+            let fraud_proof: FraudProof = std::fs::read(&output)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| bincode::deserialize(&bytes).map_err(|err| err.to_string()))
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to read the fraud proof from {:?}: {}", output, err);
+                    exit(1);
+                });
+            let report = fraud_proof.verify().unwrap_or_else(|err| {
+                eprintln!("Failed to verify the fraud proof: {}", err);
+                exit(1);
+            });
+            for (index, err) in &report.failed_transactions {
+                println!(
+                    "transaction {} {} failed: {}",
+                    index, fraud_proof.transactions[*index].signatures[0], err
+                );
+            }
+            match report.verdict {
+                FraudProofVerdict::ClaimValid => println!(
+                    "claimed post-state root {} is valid",
+                    hex::encode(claimed_post_state_root)
+                ),
+                FraudProofVerdict::ClaimInvalid { post_state_root } => println!(
+                    "claimed post-state root {} is invalid, re-executed post-state root: {}",
+                    hex::encode(claimed_post_state_root),
+                    hex::encode(post_state_root)
+                ),
+            }
         }
         _ => {}
     }
 }
 
+/// Rebuild the output ledger from the shreds in postgres up to `ending_slot`,
+/// and return it with the options to replay it, and the snapshot config to
+/// load the banks with if starting from a snapshot
+fn setup_replay(
+    config: &shred_replay::ReplayerPostgresConfig,
+    arg_matches: &ArgMatches<'_>,
+    ledger_path: &Path,
+    out_ledger_path: &PathBuf,
+    wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+    shred_storage_type: &ShredStorageType,
+    ending_slot: Slot,
+) -> (GenesisConfig, Blockstore, ProcessOptions, Option<SnapshotConfig>) {
+    let mut replayer = shred_replay::Replayer::new().config(config).ledger_path(out_ledger_path);
+    if let Some(snapshot_source) = snapshot_source_of(arg_matches) {
+        replayer = replayer.snapshot_source(snapshot_source);
+    }
+
+    if let Err(e) = replayer.connect_db() {
+        eprintln!("Failed to connect pg {}", e);
+        exit(1);
+    };
+    if let Err(e) = replayer.setup_da_backend() {
+        eprintln!("Failed to setup DA backend {}", e);
+        exit(1);
+    };
+
+    let mut accounts_index_config = AccountsIndexConfig::default();
+
+    if let Some(bins) = value_t!(arg_matches, "accounts_index_bins", usize).ok() {
+        accounts_index_config.bins = Some(bins);
+    }
+
+    let filler_accounts_config = FillerAccountsConfig {
+        count: value_t_or_exit!(arg_matches, "accounts_filler_count", usize),
+        size: value_t_or_exit!(arg_matches, "accounts_filler_size", usize),
+    };
+
+    let accounts_db_config = Some(AccountsDbConfig {
+        index: Some(accounts_index_config),
+        accounts_hash_cache_path: Some(ledger_path.to_path_buf()),
+        filler_accounts_config,
+        skip_rewrites: arg_matches.is_present("accounts_db_skip_rewrites"),
+        ancient_append_vecs: arg_matches.is_present("accounts_db_ancient_append_vecs"),
+        skip_initial_hash_calc: arg_matches
+            .is_present("accounts_db_skip_initial_hash_calculation"),
+        ..AccountsDbConfig::default()
+    });
+
+    let process_options = ProcessOptions {
+        new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+        poh_verify: !arg_matches.is_present("skip_poh_verify"),
+        on_halt_store_hash_raw_data_for_debug: arg_matches
+            .is_present("halt_at_slot_store_hash_raw_data"),
+        // ledger tool verify always runs the accounts hash calc at the end of processing the blockstore
+        run_final_accounts_hash_calc: true,
+        debug_keys: None,
+        accounts_db_caching_enabled: !arg_matches.is_present("no_accounts_db_caching"),
+        limit_load_slot_count_from_snapshot: value_t!(
+                arg_matches,
+                "limit_load_slot_count_from_snapshot",
+                usize
+            )
+            .ok(),
+        accounts_db_config,
+        verify_index: arg_matches.is_present("verify_accounts_index"),
+        allow_dead_slots: arg_matches.is_present("allow_dead_slots"),
+        accounts_db_test_hash_calculation: arg_matches
+            .is_present("accounts_db_test_hash_calculation"),
+        accounts_db_skip_shrink: arg_matches.is_present("accounts_db_skip_shrink"),
+        runtime_config: RuntimeConfig {
+            bpf_jit: !arg_matches.is_present("no_bpf_jit"),
+            ..RuntimeConfig::default()
+        },
+        persist_state_tree: true,
+        ..ProcessOptions::default()
+    };
+
+    let genesis_config = open_genesis_config_by(ledger_path, arg_matches);
+    if let Err(e) = replayer.init_ledger(&genesis_config) {
+        eprintln!("Failed to init new ledger{}", e);
+        exit(1);
+    };
+
+    let mut blockstore = open_blockstore(
+        out_ledger_path,
+        AccessType::Primary,
+        wal_recovery_mode,
+        shred_storage_type,
+    );
+
+    if let Err(e) = replayer.setup_shred_verifier(&genesis_config, &blockstore) {
+        eprintln!("Failed to setup shred verifier: {}", e);
+        exit(1);
+    };
+
+    if let Err(e) = replayer.insert_shred_endwith_slot(ending_slot, &mut blockstore) {
+        eprintln!("Failed to insert shred in pg: {}", e);
+        exit(1);
+    };
+
+    let snapshot_config = replayer.snapshot_config("snapshot.ledger-tool");
+    (genesis_config, blockstore, process_options, snapshot_config)
+}
+
+fn snapshot_source_of(matches: &ArgMatches<'_>) -> Option<SnapshotSource> {
+    if let Ok(full_snapshot_archive) = value_t!(matches, "snapshot_archive", PathBuf) {
+        Some(SnapshotSource::Archives {
+            full_snapshot_archive,
+            incremental_snapshot_archive: value_t!(
+                matches,
+                "incremental_snapshot_archive",
+                PathBuf
+            )
+            .ok(),
+        })
+    } else {
+        value_t!(matches, "snapshot_rpc", SocketAddr)
+            .ok()
+            .map(SnapshotSource::Rpc)
+    }
+}
+
 fn open_blockstore(
     ledger_path: &Path,
     access_type: AccessType,
@@ -334,26 +665,15 @@ fn hardforks_of(matches: &ArgMatches<'_>, name: &str) -> Option<Vec<Slot>> {
     }
 }
 
+/// Replay the ledger from genesis, or from the snapshot in the archive
+/// directories of `snapshot_config`
 fn load_bank_forks(
     genesis_config: &GenesisConfig,
     blockstore: &Blockstore,
     process_options: ProcessOptions,
-    accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    snapshot_config: Option<&SnapshotConfig>,
+    cache_block_meta_sender: Option<&CacheBlockMetaSender>,
 ) -> Result<Arc<RwLock<BankForks>>, BlockstoreProcessorError> {
-    let starting_slot = 0; // default start check with genesis
-
-    if let Some(halt_slot) = process_options.halt_at_slot {
-        // Check if we have the slot data necessary to replay from starting_slot to >= halt_slot.
-        //  - This will not catch the case when loading from genesis without a full slot 0.
-        if !blockstore.slot_range_connected(starting_slot, halt_slot) {
-            eprintln!(
-                "Unable to load bank forks at slot {} due to disconnected blocks.",
-                halt_slot,
-            );
-            exit(1);
-        }
-    }
-
     let account_paths =
         {
             let non_primary_accounts_path = blockstore.ledger_path().join("accounts.ledger-tool");
@@ -376,20 +696,33 @@ fn load_bank_forks(
             vec![non_primary_accounts_path]
         };
 
-    let snapshot_config = None;
-
     let (bank_forks, leader_schedule_cache, ..) =
         bank_forks_utils::load_bank_forks(
             genesis_config,
             blockstore,
             account_paths,
             None,
-            snapshot_config.as_ref(),
+            snapshot_config,
             &process_options,
             None,
-            accounts_update_notifier,
+            None,
         );
 
+    // default start check with genesis, or the snapshot slot
+    let starting_slot = bank_forks.read().unwrap().root();
+
+    if let Some(halt_slot) = process_options.halt_at_slot {
+        // Check if we have the slot data necessary to replay from starting_slot to >= halt_slot.
+        //  - This will not catch the case when loading from genesis without a full slot 0.
+        if !blockstore.slot_range_connected(starting_slot, halt_slot) {
+            eprintln!(
+                "Unable to load bank forks at slot {} due to disconnected blocks.",
+                halt_slot,
+            );
+            exit(1);
+        }
+    }
+
     let pruned_banks_receiver =
         AccountsBackgroundService::setup_bank_drop_callback(bank_forks.clone());
     let abs_request_handler = AbsRequestHandler {
@@ -412,7 +745,7 @@ fn load_bank_forks(
         &leader_schedule_cache,
         &process_options,
         None,
-        None,
+        cache_block_meta_sender,
         &AbsRequestSender::default(),
     )?;
 
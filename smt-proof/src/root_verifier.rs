@@ -0,0 +1,98 @@
+//! Checks the state roots of the replayed banks against the roots the
+//! execution node committed.
+//!
+//! The roots are computed by `Bank` at freeze from the accounts of the slot,
+//! so the execution node and the verifier get the same root whatever order
+//! the geyser stream delivered the accounts in. This replaced the SMT the
+//! verifier used to rebuild from the account notifications of the replay,
+//! whose root depended on that order and on which writes of a slot were
+//! notified.
+use {
+    crate::shred_replay::ReplayerPostgresConfig,
+    postgres::{Client, NoTls},
+    smt_proof::snapshot_bootstrap::{query_committed_state_root, SnapshotBootstrapError},
+    solana_runtime::bank::Bank,
+    solana_sdk::{clock::Slot, hash::Hash, pubkey::Pubkey},
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum ReplayRootError {
+    #[error("Error connecting postgresdb Error message: ({msg})")]
+    DbConnectError { msg: String },
+
+    #[error("Error querying the merkle tree root of slot {slot} Error message: ({msg})")]
+    QueryRootError { slot: Slot, msg: String },
+
+    #[error(
+        "Merkle tree root mismatch at slot {slot}, expected: {}, actual: {}, accounts touched: {accounts:?}",
+        hex::encode(expected),
+        hex::encode(actual)
+    )]
+    RootMismatchError {
+        slot: Slot,
+        expected: Hash,
+        actual: Hash,
+        accounts: Vec<Pubkey>,
+    },
+}
+
+/// Checks the state root of the replayed banks against the `merkle_tree_proof`
+/// rows written by the execution node.
+pub(crate) struct SmtRootVerifier {
+    client: Client,
+}
+
+impl SmtRootVerifier {
+    pub fn new(config: &ReplayerPostgresConfig) -> Result<Self, ReplayRootError> {
+        let connection_str = format!(
+            "host={} user={} password={} dbname={} port={}",
+            config.host.as_ref().unwrap(),
+            config.user.as_ref().unwrap(),
+            config.password.as_ref().unwrap(),
+            config.dbname.as_ref().unwrap(),
+            config.port.as_ref().unwrap(),
+        );
+        let client =
+            Client::connect(&connection_str, NoTls).map_err(|_| ReplayRootError::DbConnectError {
+                msg: format!("the config is {}", connection_str),
+            })?;
+        Ok(Self { client })
+    }
+
+    /// The latest root the execution node wrote for `slot`, if any.
+    fn query_root(&mut self, slot: Slot) -> Result<Option<Hash>, ReplayRootError> {
+        query_committed_state_root(&mut self.client, slot).map_err(|err| {
+            let msg = match err {
+                SnapshotBootstrapError::QueryStateRootError { msg, .. } => msg,
+                err => err.to_string(),
+            };
+            ReplayRootError::QueryRootError { slot, msg }
+        })
+    }
+
+    /// Compare the state root of the frozen `bank` with the one the execution
+    /// node wrote for its slot. Returns whether the execution node had written
+    /// one.
+    pub fn verify_bank(&mut self, bank: &Bank) -> Result<bool, ReplayRootError> {
+        let actual = bank.state_root();
+        match self.query_root(bank.slot())? {
+            Some(expected) if expected != actual => {
+                let mut accounts: Vec<_> = bank
+                    .get_all_accounts_modified_since_parent()
+                    .into_iter()
+                    .map(|(pubkey, _)| pubkey)
+                    .collect();
+                accounts.sort();
+                Err(ReplayRootError::RootMismatchError {
+                    slot: bank.slot(),
+                    expected,
+                    actual,
+                    accounts,
+                })
+            }
+            Some(_) => Ok(true),
+            None => Ok(false),
+        }
+    }
+}
@@ -1,19 +1,38 @@
 use {
+    da_backend::{
+        blob::{DaBlob, SlotEntries},
+        schema::{self, SchemaStatus},
+        DataAvailabilityBackend, DataAvailabilityBackendConfig, DataAvailabilityError,
+        DataAvailabilityPayload,
+    },
     postgres::{Client, NoTls},
     serde_derive::{Deserialize, Serialize},
+    smt_proof::snapshot_bootstrap::{
+        query_committed_bank_hash, query_committed_state_root, verify_snapshot_bank,
+        SnapshotBootstrapError, SnapshotSource, SnapshotStaging,
+    },
     solana_ledger::{
-        blockstore,
+        bank_forks_utils, blockstore,
         blockstore::Blockstore,
         // genesis_utils::create_genesis_config,
         blockstore_options,
-        shred::Shred,
+        blockstore_processor::{self, ProcessOptions},
+        leader_schedule_cache::LeaderScheduleCache,
+        shred::{ProcessShredsStats, ReedSolomonCache, Shred, Shredder},
+    },
+    solana_runtime::{
+        accounts_background_service::AbsRequestSender, bank::Bank, bank_forks::BankForks,
+        hardened_unpack::MAX_GENESIS_ARCHIVE_UNPACKED_SIZE, snapshot_config::SnapshotConfig,
+    },
+    solana_sdk::{
+        clock::Slot, genesis_config::GenesisConfig, shred_version::compute_shred_version,
+        signature::Keypair,
     },
-    solana_runtime::hardened_unpack::MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
-    solana_sdk::genesis_config::GenesisConfig,
     std::{
         io,
         path::{Path, PathBuf},
         process::{Command, Output},
+        sync::{Arc, RwLock},
     },
     thiserror::Error,
 };
@@ -28,6 +47,13 @@ pub struct ReplayerPostgresConfig {
     pub dbname: Option<String>,
     /// The port number of the PostgreSQL database, the default is 5432
     pub port: Option<u16>,
+    /// The data-availability backend the shreds are fetched from.
+    #[serde(default)]
+    pub da_backend: DataAvailabilityBackendConfig,
+    /// Whether the shreds of each slot or blobs of their entries are fetched.
+    /// The default is the shreds.
+    #[serde(default)]
+    pub da_payload: DataAvailabilityPayload,
 }
 
 #[derive(Error, Debug)]
@@ -53,20 +79,204 @@ pub enum ReplayerError {
 
     #[error("Error insert shreds into blockstore")]
     InsertShredError,
+
+    #[error("Error accessing the DA backend: ({0})")]
+    DataAvailabilityError(#[from] DataAvailabilityError),
+
+    #[error("Error verifying shreds of slot {slot} Error message: ({msg})")]
+    InvalidShredError { slot: Slot, msg: String },
+
+    #[error("Error verifying the blob of slot {slot} Error message: ({msg})")]
+    InvalidBlobError { slot: Slot, msg: String },
+
+    #[error("Error replaying the ledger up to slot {slot} Error message: ({msg})")]
+    VerifyError { slot: Slot, msg: String },
+
+    #[error("Error bootstrapping from the snapshot: ({0})")]
+    SnapshotBootstrapError(#[from] SnapshotBootstrapError),
+}
+
+/// Checks the shreds or blobs fetched from the DA backend were signed by the
+/// slot leader, the shreds with the shred version of the cluster. The banks are
+/// replayed from the verified slots as they go past the epochs whose leader
+/// schedule the root bank knows.
+struct ShredVerifier {
+    bank_forks: Arc<RwLock<BankForks>>,
+    leader_schedule_cache: LeaderScheduleCache,
+    shred_version: u16,
+}
+
+impl ShredVerifier {
+    fn root_bank(&self) -> Arc<Bank> {
+        self.bank_forks.read().unwrap().root_bank()
+    }
+
+    fn slot_leader_known(&self, slot: Slot) -> bool {
+        self.leader_schedule_cache
+            .slot_leader_at(slot, Some(&self.root_bank()))
+            .is_some()
+    }
+
+    fn verify(&self, slot: Slot, shreds: &[Shred]) -> Result<(), ReplayerError> {
+        let invalid_shred = |msg: String| ReplayerError::InvalidShredError { slot, msg };
+        let leader = self
+            .leader_schedule_cache
+            .slot_leader_at(slot, Some(&self.root_bank()))
+            .ok_or_else(|| invalid_shred("unknown slot leader".to_string()))?;
+        for shred in shreds {
+            if shred.slot() != slot {
+                return Err(invalid_shred(format!(
+                    "shred {} belongs to slot {}",
+                    shred.index(),
+                    shred.slot()
+                )));
+            }
+            if shred.version() != self.shred_version {
+                return Err(invalid_shred(format!(
+                    "shred {} has version {}, expected {}",
+                    shred.index(),
+                    shred.version(),
+                    self.shred_version
+                )));
+            }
+            if !shred.verify(&leader) {
+                return Err(invalid_shred(format!(
+                    "shred {} is not signed by the slot leader {}",
+                    shred.index(),
+                    leader
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check `blob` was signed by the leader of each of its `slots`.
+    fn verify_blob(&self, blob: &DaBlob, slots: &[SlotEntries]) -> Result<(), ReplayerError> {
+        let root_bank = self.root_bank();
+        for slot in slots.iter().map(|slot_entries| slot_entries.slot) {
+            let invalid_blob = |msg: String| ReplayerError::InvalidBlobError { slot, msg };
+            let leader = self
+                .leader_schedule_cache
+                .slot_leader_at(slot, Some(&root_bank))
+                .ok_or_else(|| invalid_blob("unknown slot leader".to_string()))?;
+            if !blob.verify(&leader) {
+                return Err(invalid_blob(format!(
+                    "blob {}-{} is not signed by the slot leader {}",
+                    blob.header.start_slot, blob.header.end_slot, leader
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert the entries of `slots`, decoded from a verified blob, into
+    /// `blockstore` as complete slots. The shreds are signed with a throwaway
+    /// keypair and only meant for the local blockstore.
+    fn insert_slot_entries(
+        &self,
+        blockstore: &Blockstore,
+        slots: &[SlotEntries],
+    ) -> Result<(), ReplayerError> {
+        let keypair = Keypair::new();
+        let reed_solomon_cache = ReedSolomonCache::default();
+        for slot_entries in slots {
+            let num_ticks = slot_entries
+                .entries
+                .iter()
+                .filter(|entry| entry.is_tick())
+                .count();
+            let (shreds, _) = Shredder::new(
+                slot_entries.slot,
+                slot_entries.parent_slot,
+                num_ticks.min(u8::MAX as usize) as u8,
+                self.shred_version,
+            )
+            .map_err(|_| ReplayerError::InsertShredError)?
+            .entries_to_shreds(
+                &keypair,
+                &slot_entries.entries,
+                true, // is_last_in_slot
+                0,    // next_shred_index
+                0,    // next_code_index
+                true, // merkle_variant
+                &reed_solomon_cache,
+                &mut ProcessShredsStats::default(),
+            );
+            blockstore
+                .insert_shreds(shreds, None, false)
+                .map_err(|_| ReplayerError::InsertShredError)?;
+        }
+        Ok(())
+    }
+
+    /// Replay the slots of the blockstore from the current root up to `slot`,
+    /// and root the highest replayed bank.
+    fn replay_to_slot(&self, blockstore: &Blockstore, slot: Slot) -> Result<(), ReplayerError> {
+        let process_options = ProcessOptions {
+            poh_verify: true,
+            halt_at_slot: Some(slot),
+            accounts_db_caching_enabled: true,
+            ..ProcessOptions::default()
+        };
+        blockstore_processor::process_blockstore_from_root(
+            blockstore,
+            &self.bank_forks,
+            &self.leader_schedule_cache,
+            &process_options,
+            None,
+            None,
+            &AbsRequestSender::default(),
+        )
+        .map_err(|err| ReplayerError::VerifyError {
+            slot,
+            msg: err.to_string(),
+        })?;
+
+        let new_root_bank = self
+            .bank_forks
+            .read()
+            .unwrap()
+            .frozen_banks()
+            .into_values()
+            .max_by_key(|bank| bank.slot());
+        if let Some(new_root_bank) = new_root_bank {
+            self.leader_schedule_cache.set_root(&new_root_bank);
+            let _ = self.bank_forks.write().unwrap().set_root(
+                new_root_bank.slot(),
+                &AbsRequestSender::default(),
+                None,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Apply the schema migrations the database `client` is connected to lacks.
+fn migrate_schema(client: &mut Client) -> Result<(), DataAvailabilityError> {
+    for migration in schema::migrate_schema(client)? {
+        println!("Applied the schema migration {}", migration);
+    }
+    Ok(())
 }
 
 pub struct Replayer {
     client: Option<Client>,
+    da_backend: Option<Box<dyn DataAvailabilityBackend>>,
     config: Option<ReplayerPostgresConfig>,
     ledger_path: Option<PathBuf>,
+    snapshot_source: Option<SnapshotSource>,
+    shred_verifier: Option<ShredVerifier>,
 }
 
 impl Replayer {
     pub fn new() -> Self {
         Self {
             client: None,
+            da_backend: None,
             config: None,
             ledger_path: None,
+            snapshot_source: None,
+            shred_verifier: None,
         }
     }
 
@@ -80,25 +290,79 @@ impl Replayer {
         self
     }
 
-    pub fn connect_db(&mut self) -> Result<(), ReplayerError> {
+    /// Start from this snapshot instead of replaying the ledger from genesis.
+    pub fn snapshot_source(mut self, snapshot_source: SnapshotSource) -> Self {
+        self.snapshot_source = Some(snapshot_source);
+        self
+    }
+
+    /// The verified snapshot archives are kept in the ledger directory. Each
+    /// load of the banks needs its own `bank_snapshots_dir`, it is cleared on
+    /// load.
+    pub fn snapshot_config(&self, bank_snapshots_dir: &str) -> Option<SnapshotConfig> {
+        self.snapshot_source.as_ref()?;
+        let ledger_path = self.ledger_path.as_ref().unwrap();
+        Some(SnapshotConfig {
+            full_snapshot_archives_dir: ledger_path.clone(),
+            incremental_snapshot_archives_dir: ledger_path.clone(),
+            bank_snapshots_dir: ledger_path.join(bank_snapshots_dir),
+            ..SnapshotConfig::default()
+        })
+    }
+
+    fn connection_str(&self) -> String {
         let config = self.config.as_ref().unwrap();
-        let connection_str = format!(
+        format!(
             "host={} user={} password={} dbname={} port={}",
             config.host.as_ref().unwrap(),
             config.user.as_ref().unwrap(),
             config.password.as_ref().unwrap(),
             config.dbname.as_ref().unwrap(),
             config.port.as_ref().unwrap(),
-        );
-        let client =
+        )
+    }
+
+    /// Connect to the database, applying the schema migrations it lacks.
+    pub fn connect_db(&mut self) -> Result<(), ReplayerError> {
+        let connection_str = self.connection_str();
+        let mut client =
             Client::connect(&connection_str, NoTls).map_err(|_| ReplayerError::DbConnectError {
                 msg: format!("the config is {}", connection_str),
             })?;
+        migrate_schema(&mut client)?;
 
         self.client = Some(client);
         Ok(())
     }
 
+    /// The schema version of the database and the migrations it lacks,
+    /// without applying them.
+    pub fn check_schema(&self) -> Result<SchemaStatus, ReplayerError> {
+        let connection_str = self.connection_str();
+        let mut client = Client::connect(&connection_str, NoTls).map_err(|err| {
+            ReplayerError::DbConnectError {
+                msg: format!("the config is {}, error: {}", connection_str, err),
+            }
+        })?;
+        Ok(schema::check_schema(&mut client)?)
+    }
+
+    /// Create the DA backend the shreds are fetched from.
+    pub fn setup_da_backend(&mut self) -> Result<(), ReplayerError> {
+        let connection_str = self.connection_str();
+        let da_backend = self.config.as_ref().unwrap().da_backend.build(|| {
+            let mut client = Client::connect(&connection_str, NoTls).map_err(|err| {
+                DataAvailabilityError::ConnectionError {
+                    msg: format!("the config is {}, error: {}", connection_str, err),
+                }
+            })?;
+            migrate_schema(&mut client)?;
+            Ok(client)
+        })?;
+        self.da_backend = Some(da_backend);
+        Ok(())
+    }
+
     pub fn init_ledger(&mut self, genesis_config: &GenesisConfig) -> Result<(), ReplayerError> {
         if !self.ledger_path.as_ref().unwrap().exists() {
             let _last_hash = blockstore::create_new_ledger(
@@ -111,31 +375,132 @@ impl Replayer {
         Ok(())
     }
 
-    /// load shred from postgres by slot, order by index asc
-    fn load_shred_from_pg(&mut self, slot: u64) -> Vec<Shred> {
-        let mut shreds: Vec<Shred> = Vec::new();
-        let stmt = "SELECT slot, entry_index, entry FROM entry where slot <= $1";
-        let client = self.client.as_mut().unwrap();
-        let stmt = client.prepare(stmt).unwrap();
-
-        let result = client.query(&stmt, &[&(slot as i64)]);
-        if result.is_err() {}
-
-        for row in result.unwrap() {
-            let payload: Vec<u8> = row.get(2);
-            let result = Shred::new_from_serialized_shred(payload);
-            if result.is_err() {}
-            shreds.push(result.unwrap());
+    /// Load the genesis bank, or the snapshot bank if there is a snapshot
+    /// source, which provides the leader schedule and the shred version the
+    /// shreds are verified against. The snapshot bank is checked against the
+    /// state root and the bank hash the execution node committed for its slot.
+    /// Requires the database connection.
+    pub fn setup_shred_verifier(
+        &mut self,
+        genesis_config: &GenesisConfig,
+        blockstore: &Blockstore,
+    ) -> Result<(), ReplayerError> {
+        let snapshot = match self.snapshot_source.as_ref() {
+            Some(snapshot_source) => {
+                let staging = SnapshotStaging::new(self.ledger_path.as_ref().unwrap())?;
+                let snapshot_config = staging.snapshot_config("snapshot.shred-verifier");
+                let slot = snapshot_source.fetch(&snapshot_config)?;
+                Some((staging, snapshot_config, slot))
+            }
+            None => None,
+        };
+        let accounts_path = self
+            .ledger_path
+            .as_ref()
+            .unwrap()
+            .join("accounts.shred-verifier");
+        if snapshot.is_some() && accounts_path.exists() {
+            std::fs::remove_dir_all(&accounts_path)?;
+        }
+        let (bank_forks, leader_schedule_cache, ..) = bank_forks_utils::load_bank_forks(
+            genesis_config,
+            blockstore,
+            vec![accounts_path],
+            None,
+            snapshot
+                .as_ref()
+                .map(|(_, snapshot_config, _)| snapshot_config),
+            &ProcessOptions::default(),
+            None,
+            None,
+        );
+        let bank = bank_forks.read().unwrap().root_bank();
+        if let Some((staging, _, slot)) = snapshot {
+            let client = self.client.as_mut().unwrap();
+            let committed_state_root = query_committed_state_root(client, slot)?;
+            let committed_bank_hash = query_committed_bank_hash(client, slot)?;
+            verify_snapshot_bank(&bank, slot, committed_state_root, committed_bank_hash)?;
+            let ledger_snapshot_config = self.snapshot_config("snapshot.shred-verifier").unwrap();
+            staging.commit(&ledger_snapshot_config.full_snapshot_archives_dir)?;
+            println!(
+                "snapshot of slot {} verified, state root: {}, bank hash: {}",
+                slot,
+                hex::encode(bank.state_root()),
+                bank.hash()
+            );
         }
-        shreds
+        let shred_version = compute_shred_version(
+            &genesis_config.hash(),
+            Some(&bank.hard_forks().read().unwrap()),
+        );
+        self.shred_verifier = Some(ShredVerifier {
+            bank_forks,
+            leader_schedule_cache,
+            shred_version,
+        });
+        Ok(())
     }
 
-    /// Query shred by slot and update blockstore.
-    pub fn insert_shred_endwith_slot(&mut self, slot: u64, blockstore: &mut Blockstore) -> Result<(), ReplayerError> {
-        let shreds = self.load_shred_from_pg(slot);
-        blockstore
-            .insert_shreds(shreds, None, false)
-            .map_err(|_| ReplayerError::InsertShredError)?;
+    /// Insert the shreds or blobs of all slots from the genesis or snapshot bank
+    /// up to `ending_slot` from the DA backend, as configured by `da_payload`,
+    /// rejecting any slot not signed by the slot leader. When a slot is past the
+    /// epochs whose leader schedule is known, the slots inserted so far are
+    /// replayed to learn it.
+    pub fn insert_shred_endwith_slot(
+        &mut self,
+        ending_slot: u64,
+        blockstore: &mut Blockstore,
+    ) -> Result<(), ReplayerError> {
+        let da_payload = self.config.as_ref().unwrap().da_payload;
+        let da_backend = self.da_backend.as_mut().unwrap();
+        let shred_verifier = self.shred_verifier.as_ref().unwrap();
+        let mut last_inserted_slot = None;
+        let mut slot = shred_verifier.root_bank().slot();
+        while slot <= ending_slot {
+            match da_payload {
+                DataAvailabilityPayload::Shreds => {
+                    if let Some(slot_data) = da_backend.fetch_slot(slot)? {
+                        let mut slot_shreds = Vec::with_capacity(slot_data.shreds.len());
+                        for payload in slot_data.shreds {
+                            let shred =
+                                Shred::new_from_serialized_shred(payload).map_err(|err| {
+                                    ReplayerError::InvalidShredError {
+                                        slot,
+                                        msg: format!("failed to deserialize shred: {:?}", err),
+                                    }
+                                })?;
+                            slot_shreds.push(shred);
+                        }
+                        if !shred_verifier.slot_leader_known(slot) {
+                            if let Some(last_inserted_slot) = last_inserted_slot {
+                                shred_verifier.replay_to_slot(blockstore, last_inserted_slot)?;
+                            }
+                        }
+                        shred_verifier.verify(slot, &slot_shreds)?;
+                        blockstore
+                            .insert_shreds(slot_shreds, None, false)
+                            .map_err(|_| ReplayerError::InsertShredError)?;
+                        last_inserted_slot = Some(slot);
+                    }
+                }
+                DataAvailabilityPayload::Blob => {
+                    // A blob holds the slots after `slot` too
+                    if let Some(blob) = da_backend.fetch_blob(slot)? {
+                        let slots = blob.decode()?;
+                        if !shred_verifier.slot_leader_known(blob.header.end_slot) {
+                            if let Some(last_inserted_slot) = last_inserted_slot {
+                                shred_verifier.replay_to_slot(blockstore, last_inserted_slot)?;
+                            }
+                        }
+                        shred_verifier.verify_blob(&blob, &slots)?;
+                        shred_verifier.insert_slot_entries(blockstore, &slots)?;
+                        slot = blob.header.end_slot;
+                        last_inserted_slot = Some(slot);
+                    }
+                }
+            }
+            slot += 1;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,526 @@
+//! Bootstraps a verification node from a snapshot of the execution node, so it
+//! only has to replay the slots after the snapshot instead of the whole ledger.
+//!
+//! The snapshot archives are fetched to a staging directory and the bank is
+//! loaded from there. The snapshot is trusted once that bank has the slot of
+//! the snapshot, and the state root and the bank hash the execution node
+//! committed for that slot, only then the archives are moved to the ledger
+//! directory.
+use {
+    hex::FromHex,
+    postgres::Client,
+    solana_download_utils::{download_snapshot_archive, get_latest_snapshot_archive_name},
+    solana_runtime::{
+        bank::Bank,
+        snapshot_archive_info::{
+            FullSnapshotArchiveInfo, IncrementalSnapshotArchiveInfo, SnapshotArchiveInfoGetter,
+        },
+        snapshot_config::SnapshotConfig,
+        snapshot_package::SnapshotType,
+        snapshot_utils::build_snapshot_archives_remote_dir,
+    },
+    solana_sdk::{clock::Slot, hash::Hash},
+    std::{
+        fs,
+        net::SocketAddr,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum SnapshotBootstrapError {
+    #[error("Invalid snapshot archive {path:?}. Error message: ({msg})")]
+    InvalidArchive { path: PathBuf, msg: String },
+
+    #[error("Error fetching the snapshot archives. Error message: ({msg})")]
+    FetchError { msg: String },
+
+    #[error("Loaded the bank of slot {actual} instead of the snapshot slot {expected}")]
+    SlotMismatch { expected: Slot, actual: Slot },
+
+    #[error("Error staging the snapshot archives in {path:?}. Error message: ({msg})")]
+    StagingError { path: PathBuf, msg: String },
+
+    #[error("Error querying the state root of slot {slot} Error message: ({msg})")]
+    QueryStateRootError { slot: Slot, msg: String },
+
+    #[error("No state root committed by the execution node for snapshot slot {slot}")]
+    MissingStateRoot { slot: Slot },
+
+    #[error(
+        "State root mismatch at snapshot slot {slot}, committed: {}, actual: {}",
+        hex::encode(expected),
+        hex::encode(actual)
+    )]
+    StateRootMismatch {
+        slot: Slot,
+        expected: Hash,
+        actual: Hash,
+    },
+
+    #[error("Error querying the bank hash of slot {slot} Error message: ({msg})")]
+    QueryBankHashError { slot: Slot, msg: String },
+
+    #[error("No bank hash recorded by the execution node for snapshot slot {slot}")]
+    MissingBankHash { slot: Slot },
+
+    #[error("Bank hash mismatch at snapshot slot {slot}, recorded: {expected}, actual: {actual}")]
+    BankHashMismatch {
+        slot: Slot,
+        expected: Hash,
+        actual: Hash,
+    },
+}
+
+/// Where the snapshot to start from is taken from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotSource {
+    /// A local full snapshot archive, and optionally an incremental snapshot
+    /// archive on top of it
+    Archives {
+        full_snapshot_archive: PathBuf,
+        incremental_snapshot_archive: Option<PathBuf>,
+    },
+    /// The latest snapshot archives served by the RPC service of the
+    /// execution node
+    Rpc(SocketAddr),
+}
+
+impl SnapshotSource {
+    /// Put the snapshot archives in the archive directories of
+    /// `snapshot_config`, where the banks are loaded from. Returns the slot
+    /// of the snapshot.
+    pub fn fetch(&self, snapshot_config: &SnapshotConfig) -> Result<Slot, SnapshotBootstrapError> {
+        match self {
+            Self::Archives {
+                full_snapshot_archive,
+                incremental_snapshot_archive,
+            } => {
+                let full = FullSnapshotArchiveInfo::new_from_path(full_snapshot_archive.clone())
+                    .map_err(|err| invalid_archive(full_snapshot_archive, err))?;
+                copy_archive(
+                    full_snapshot_archive,
+                    &snapshot_config.full_snapshot_archives_dir,
+                )?;
+                let incremental_snapshot_archive = match incremental_snapshot_archive {
+                    Some(path) => path,
+                    None => return Ok(full.slot()),
+                };
+                let incremental = IncrementalSnapshotArchiveInfo::new_from_path(
+                    incremental_snapshot_archive.clone(),
+                )
+                .map_err(|err| invalid_archive(incremental_snapshot_archive, err))?;
+                if incremental.base_slot() != full.slot() {
+                    return Err(invalid_archive(
+                        incremental_snapshot_archive,
+                        format!(
+                            "it is not based on the full snapshot of slot {}",
+                            full.slot()
+                        ),
+                    ));
+                }
+                copy_archive(
+                    incremental_snapshot_archive,
+                    &snapshot_config.incremental_snapshot_archives_dir,
+                )?;
+                Ok(incremental.slot())
+            }
+            Self::Rpc(rpc_addr) => {
+                let fetch_error = |msg: String| SnapshotBootstrapError::FetchError { msg };
+                let full_name = get_latest_snapshot_archive_name(rpc_addr, false)
+                    .map_err(fetch_error)?
+                    .ok_or_else(|| fetch_error(format!("{} serves no snapshot", rpc_addr)))?;
+                let full = FullSnapshotArchiveInfo::new_from_path(PathBuf::from(&full_name))
+                    .map_err(|err| invalid_archive(Path::new(&full_name), err))?;
+                download(rpc_addr, snapshot_config, &full, SnapshotType::FullSnapshot)?;
+
+                let incremental_name =
+                    get_latest_snapshot_archive_name(rpc_addr, true).map_err(fetch_error)?;
+                let incremental = incremental_name
+                    .map(|name| {
+                        IncrementalSnapshotArchiveInfo::new_from_path(PathBuf::from(&name))
+                            .map_err(|err| invalid_archive(Path::new(&name), err))
+                    })
+                    .transpose()?
+                    .filter(|incremental| incremental.base_slot() == full.slot());
+                match incremental {
+                    Some(incremental) => {
+                        download(
+                            rpc_addr,
+                            snapshot_config,
+                            &incremental,
+                            SnapshotType::IncrementalSnapshot(full.slot()),
+                        )?;
+                        Ok(incremental.slot())
+                    }
+                    None => Ok(full.slot()),
+                }
+            }
+        }
+    }
+}
+
+/// The directory the snapshot archives are fetched to until the bank loaded
+/// from them is verified. It is removed when dropped, so the archives of a
+/// snapshot which fails verification are not left behind.
+pub struct SnapshotStaging {
+    staging_dir: PathBuf,
+}
+
+impl SnapshotStaging {
+    const STAGING_DIR: &'static str = "snapshot.staging";
+
+    /// Stage the snapshot archives under `ledger_path`, dropping the ones
+    /// left there by a run which did not finish.
+    pub fn new(ledger_path: &Path) -> Result<Self, SnapshotBootstrapError> {
+        let staging_dir = ledger_path.join(Self::STAGING_DIR);
+        if staging_dir.exists() {
+            fs::remove_dir_all(&staging_dir).map_err(|err| staging_error(&staging_dir, err))?;
+        }
+        Ok(Self { staging_dir })
+    }
+
+    /// The snapshot config to fetch the archives with and to load the bank
+    /// to verify from
+    pub fn snapshot_config(&self, bank_snapshots_dir: &str) -> SnapshotConfig {
+        SnapshotConfig {
+            full_snapshot_archives_dir: self.staging_dir.clone(),
+            incremental_snapshot_archives_dir: self.staging_dir.clone(),
+            bank_snapshots_dir: self.staging_dir.join(bank_snapshots_dir),
+            ..SnapshotConfig::default()
+        }
+    }
+
+    /// Move the archives of the verified snapshot to `archives_dir`, the
+    /// downloaded ones to its download directory.
+    pub fn commit(self, archives_dir: &Path) -> Result<(), SnapshotBootstrapError> {
+        move_archives(&self.staging_dir, archives_dir)?;
+        let remote_dir = build_snapshot_archives_remote_dir(&self.staging_dir);
+        if remote_dir.exists() {
+            move_archives(
+                &remote_dir,
+                &build_snapshot_archives_remote_dir(archives_dir),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn move_archives(from_dir: &Path, to_dir: &Path) -> Result<(), SnapshotBootstrapError> {
+    fs::create_dir_all(to_dir).map_err(|err| staging_error(to_dir, err))?;
+    for entry in fs::read_dir(from_dir).map_err(|err| staging_error(from_dir, err))? {
+        let path = entry.map_err(|err| staging_error(from_dir, err))?.path();
+        if path.is_file() {
+            fs::rename(&path, to_dir.join(path.file_name().unwrap()))
+                .map_err(|err| staging_error(&path, err))?;
+        }
+    }
+    Ok(())
+}
+
+impl Drop for SnapshotStaging {
+    fn drop(&mut self) {
+        if self.staging_dir.exists() {
+            if let Err(err) = fs::remove_dir_all(&self.staging_dir) {
+                eprintln!("Failed to remove {:?}: {}", self.staging_dir, err);
+            }
+        }
+    }
+}
+
+fn staging_error(path: &Path, err: impl ToString) -> SnapshotBootstrapError {
+    SnapshotBootstrapError::StagingError {
+        path: path.to_path_buf(),
+        msg: err.to_string(),
+    }
+}
+
+fn invalid_archive(path: &Path, err: impl ToString) -> SnapshotBootstrapError {
+    SnapshotBootstrapError::InvalidArchive {
+        path: path.to_path_buf(),
+        msg: err.to_string(),
+    }
+}
+
+fn copy_archive(path: &Path, archives_dir: &Path) -> Result<(), SnapshotBootstrapError> {
+    let destination = archives_dir.join(path.file_name().unwrap());
+    if destination.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(archives_dir)
+        .and_then(|_| fs::copy(path, &destination))
+        .map(|_| ())
+        .map_err(|err| invalid_archive(path, err))
+}
+
+fn download(
+    rpc_addr: &SocketAddr,
+    snapshot_config: &SnapshotConfig,
+    archive: &impl SnapshotArchiveInfoGetter,
+    snapshot_type: SnapshotType,
+) -> Result<(), SnapshotBootstrapError> {
+    download_snapshot_archive(
+        rpc_addr,
+        &snapshot_config.full_snapshot_archives_dir,
+        &snapshot_config.incremental_snapshot_archives_dir,
+        (archive.slot(), *archive.hash()),
+        snapshot_type,
+        snapshot_config.maximum_full_snapshot_archives_to_retain,
+        snapshot_config.maximum_incremental_snapshot_archives_to_retain,
+        false,
+        &mut None,
+    )
+    .map_err(|msg| SnapshotBootstrapError::FetchError { msg })
+}
+
+/// The latest state root the execution node committed for `slot` in the
+/// `merkle_tree_proof` table, if any.
+pub fn query_committed_state_root(
+    client: &mut Client,
+    slot: Slot,
+) -> Result<Option<Hash>, SnapshotBootstrapError> {
+    let query_error = |msg: String| SnapshotBootstrapError::QueryStateRootError { slot, msg };
+    let row = client
+        .query_opt(
+            "SELECT root_hash FROM merkle_tree_proof WHERE slot = $1 \
+            ORDER BY updated_on DESC LIMIT 1",
+            &[&(slot as i64)],
+        )
+        .map_err(|err| query_error(err.to_string()))?;
+    let root_hash: Option<String> = match row {
+        Some(row) => row.get(0),
+        None => return Ok(None),
+    };
+    match root_hash {
+        Some(root_hash) => <[u8; 32]>::from_hex(&root_hash)
+            .map(|root| Some(Hash::new_from_array(root)))
+            .map_err(|err| query_error(format!("invalid root {}: {}", root_hash, err))),
+        None => Ok(None),
+    }
+}
+
+/// The bank hash the execution node recorded for `slot` in the `block` table,
+/// if any.
+pub fn query_committed_bank_hash(
+    client: &mut Client,
+    slot: Slot,
+) -> Result<Option<Hash>, SnapshotBootstrapError> {
+    let query_error = |msg: String| SnapshotBootstrapError::QueryBankHashError { slot, msg };
+    let row = client
+        .query_opt(
+            "SELECT bank_hash FROM block WHERE slot = $1",
+            &[&(slot as i64)],
+        )
+        .map_err(|err| query_error(err.to_string()))?;
+    let bank_hash: Option<String> = match row {
+        Some(row) => row.get(0),
+        None => return Ok(None),
+    };
+    match bank_hash {
+        Some(bank_hash) => Hash::from_str(&bank_hash)
+            .map(Some)
+            .map_err(|err| query_error(format!("invalid bank hash {}: {}", bank_hash, err))),
+        None => Ok(None),
+    }
+}
+
+/// Check `bank`, loaded from the snapshot of `slot`, against the state root
+/// and the bank hash the execution node committed for that slot. The bank
+/// hash commits to the accounts written in the slot, next to the state root
+/// over all the accounts.
+pub fn verify_snapshot_bank(
+    bank: &Bank,
+    slot: Slot,
+    committed_state_root: Option<Hash>,
+    committed_bank_hash: Option<Hash>,
+) -> Result<(), SnapshotBootstrapError> {
+    if bank.slot() != slot {
+        return Err(SnapshotBootstrapError::SlotMismatch {
+            expected: slot,
+            actual: bank.slot(),
+        });
+    }
+    let committed_state_root =
+        committed_state_root.ok_or(SnapshotBootstrapError::MissingStateRoot { slot })?;
+    if bank.state_root() != committed_state_root {
+        return Err(SnapshotBootstrapError::StateRootMismatch {
+            slot,
+            expected: committed_state_root,
+            actual: bank.state_root(),
+        });
+    }
+    let committed_bank_hash =
+        committed_bank_hash.ok_or(SnapshotBootstrapError::MissingBankHash { slot })?;
+    if bank.hash() != committed_bank_hash {
+        return Err(SnapshotBootstrapError::BankHashMismatch {
+            slot,
+            expected: committed_bank_hash,
+            actual: bank.hash(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_runtime::{genesis_utils::create_genesis_config, snapshot_utils::ArchiveFormat},
+        solana_sdk::hash::hash,
+        std::sync::Arc,
+    };
+
+    #[test]
+    fn test_fetch_snapshot_archives() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let archives_dir = tempfile::tempdir().unwrap();
+        let snapshot_config = SnapshotConfig {
+            full_snapshot_archives_dir: archives_dir.path().join("full"),
+            incremental_snapshot_archives_dir: archives_dir.path().join("incremental"),
+            ..SnapshotConfig::default()
+        };
+        let full_hash = hash(&[1]);
+        let incremental_hash = hash(&[2]);
+        let full_snapshot_archive =
+            solana_runtime::snapshot_utils::build_full_snapshot_archive_path(
+                source_dir.path(),
+                10,
+                &full_hash,
+                ArchiveFormat::TarZstd,
+            );
+        let incremental_snapshot_archive =
+            solana_runtime::snapshot_utils::build_incremental_snapshot_archive_path(
+                source_dir.path(),
+                10,
+                15,
+                &incremental_hash,
+                ArchiveFormat::TarZstd,
+            );
+        fs::write(&full_snapshot_archive, b"full").unwrap();
+        fs::write(&incremental_snapshot_archive, b"incremental").unwrap();
+
+        let source = SnapshotSource::Archives {
+            full_snapshot_archive: full_snapshot_archive.clone(),
+            incremental_snapshot_archive: None,
+        };
+        assert_eq!(source.fetch(&snapshot_config).unwrap(), 10);
+        assert!(snapshot_config
+            .full_snapshot_archives_dir
+            .join(full_snapshot_archive.file_name().unwrap())
+            .is_file());
+
+        let source = SnapshotSource::Archives {
+            full_snapshot_archive: full_snapshot_archive.clone(),
+            incremental_snapshot_archive: Some(incremental_snapshot_archive.clone()),
+        };
+        assert_eq!(source.fetch(&snapshot_config).unwrap(), 15);
+        assert!(snapshot_config
+            .incremental_snapshot_archives_dir
+            .join(incremental_snapshot_archive.file_name().unwrap())
+            .is_file());
+
+        // The incremental snapshot must be based on the full snapshot
+        let other_incremental_snapshot_archive =
+            solana_runtime::snapshot_utils::build_incremental_snapshot_archive_path(
+                source_dir.path(),
+                5,
+                15,
+                &incremental_hash,
+                ArchiveFormat::TarZstd,
+            );
+        fs::write(&other_incremental_snapshot_archive, b"incremental").unwrap();
+        let source = SnapshotSource::Archives {
+            full_snapshot_archive,
+            incremental_snapshot_archive: Some(other_incremental_snapshot_archive),
+        };
+        assert!(matches!(
+            source.fetch(&snapshot_config),
+            Err(SnapshotBootstrapError::InvalidArchive { .. })
+        ));
+
+        // Not a snapshot archive name
+        let source = SnapshotSource::Archives {
+            full_snapshot_archive: source_dir.path().join("snapshot.tar.zst"),
+            incremental_snapshot_archive: None,
+        };
+        assert!(matches!(
+            source.fetch(&snapshot_config),
+            Err(SnapshotBootstrapError::InvalidArchive { .. })
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_staging() {
+        let ledger_dir = tempfile::tempdir().unwrap();
+        let archive_name = "snapshot-10-11111111111111111111111111111111.tar.zst";
+        let stage = || {
+            let staging = SnapshotStaging::new(ledger_dir.path()).unwrap();
+            let snapshot_config = staging.snapshot_config("snapshot.test");
+            fs::create_dir_all(&snapshot_config.bank_snapshots_dir).unwrap();
+            fs::write(
+                snapshot_config
+                    .full_snapshot_archives_dir
+                    .join(archive_name),
+                b"full",
+            )
+            .unwrap();
+            staging
+        };
+
+        // A snapshot which is not committed is dropped
+        drop(stage());
+        assert!(!ledger_dir
+            .path()
+            .join(SnapshotStaging::STAGING_DIR)
+            .exists());
+        assert!(!ledger_dir.path().join(archive_name).exists());
+
+        let staging = stage();
+        let remote_dir = build_snapshot_archives_remote_dir(&staging.staging_dir);
+        fs::create_dir_all(&remote_dir).unwrap();
+        fs::write(remote_dir.join(archive_name), b"full").unwrap();
+        staging.commit(ledger_dir.path()).unwrap();
+        assert!(!ledger_dir
+            .path()
+            .join(SnapshotStaging::STAGING_DIR)
+            .exists());
+        assert!(ledger_dir.path().join(archive_name).is_file());
+        assert!(build_snapshot_archives_remote_dir(ledger_dir.path())
+            .join(archive_name)
+            .is_file());
+    }
+
+    #[test]
+    fn test_verify_snapshot_bank() {
+        let genesis_config = create_genesis_config(1_000_000).genesis_config;
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        bank.freeze();
+        let slot = bank.slot();
+        let state_root = bank.state_root();
+        let bank_hash = bank.hash();
+
+        assert!(verify_snapshot_bank(&bank, slot, Some(state_root), Some(bank_hash)).is_ok());
+        assert!(matches!(
+            verify_snapshot_bank(&bank, 1, Some(state_root), Some(bank_hash)),
+            Err(SnapshotBootstrapError::SlotMismatch { .. })
+        ));
+        assert!(matches!(
+            verify_snapshot_bank(&bank, slot, None, Some(bank_hash)),
+            Err(SnapshotBootstrapError::MissingStateRoot { .. })
+        ));
+        assert!(matches!(
+            verify_snapshot_bank(&bank, slot, Some(Hash::default()), Some(bank_hash)),
+            Err(SnapshotBootstrapError::StateRootMismatch { .. })
+        ));
+        assert!(matches!(
+            verify_snapshot_bank(&bank, slot, Some(state_root), None),
+            Err(SnapshotBootstrapError::MissingBankHash { .. })
+        ));
+        assert!(matches!(
+            verify_snapshot_bank(&bank, slot, Some(state_root), Some(Hash::default())),
+            Err(SnapshotBootstrapError::BankHashMismatch { .. })
+        ));
+    }
+}
@@ -1,5 +1,9 @@
 /**
  * This plugin implementation for PostgreSQL requires the following tables
+ *
+ * The plugin and the replayers create them on start from the migrations in
+ * da-backend/migrations. This script creates the same schema by hand, at the
+ * version recorded in the schema_version table at the end.
  */
 -- The table storing accounts
 
@@ -180,6 +184,16 @@ CREATE TABLE transaction (
     CONSTRAINT transaction_pk PRIMARY KEY (slot, signature)
 );
 
+-- The indexes the RPC ledger storage reads the transaction history through
+CREATE INDEX transaction_signature ON transaction (signature);
+
+CREATE INDEX transaction_account_keys ON transaction USING GIN ((
+    (legacy_message).account_keys
+    || ((v0_loaded_message).message).account_keys
+    || ((v0_loaded_message).loaded_addresses).writable
+    || ((v0_loaded_message).loaded_addresses).readonly
+));
+
 -- The table storing block metadata
 CREATE TABLE block (
     slot BIGINT PRIMARY KEY,
@@ -187,6 +201,9 @@ CREATE TABLE block (
     rewards "Reward"[],
     block_time BIGINT,
     block_height BIGINT,
+    transactions_state_root VARCHAR(44),
+    bank_hash VARCHAR(44),
+    header_signature VARCHAR(88),
     updated_on TIMESTAMP NOT NULL
 );
 
@@ -216,10 +233,28 @@ CREATE TABLE entry (
     entry_index BIGINT NOT NULL,
     entry BYTEA,
     is_full_slot BOOL NOT NULL,
+    updated_on TIMESTAMP NOT NULL,
+    CONSTRAINT entry_pk PRIMARY KEY (slot, entry_index)
+);
+
+-- The slots whose shreds are all in the entry table, written in the same
+-- transaction as the shreds. Readers only consume the slots listed here.
+CREATE TABLE slot_complete (
+    slot BIGINT PRIMARY KEY,
+    parent_slot BIGINT NOT NULL,
+    shred_count BIGINT NOT NULL,
+    checksum BYTEA NOT NULL,
     updated_on TIMESTAMP NOT NULL
 );
 
-CREATE INDEX entry_slot_index ON entry (slot, entry_index);
+-- The DA blobs of the entries, published instead of the shreds with
+-- "da_payload" : "blob". A blob covers the slots from start_slot to end_slot.
+CREATE TABLE da_blob (
+    start_slot BIGINT PRIMARY KEY,
+    end_slot BIGINT NOT NULL,
+    blob BYTEA NOT NULL,
+    updated_on TIMESTAMP NOT NULL
+);
 
 /**
  * The following is for keeping historical data for accounts and is not required for plugin to work.
@@ -260,4 +295,18 @@ CREATE TRIGGER account_update_trigger AFTER UPDATE OR DELETE ON account
 CREATE TABLE IF NOT EXISTS replay (
     slot    BIGINT NOT NULL,
     entry_index  BIGINT NOT NULL
-); 
\ No newline at end of file
+);
+
+-- The migrations applied to the schema, see da-backend/src/schema.rs
+CREATE TABLE schema_version (
+    version INT PRIMARY KEY,
+    description VARCHAR(256) NOT NULL,
+    applied_on TIMESTAMP NOT NULL
+);
+
+INSERT INTO schema_version VALUES
+    (1, 'initial schema', now()),
+    (2, 'block header columns, entry primary key and slot_complete', now()),
+    (3, 'transaction history indexes', now()),
+    (4, 'block transactions state root', now()),
+    (5, 'DA blobs', now());
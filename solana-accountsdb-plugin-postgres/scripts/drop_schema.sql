@@ -12,8 +12,11 @@ DROP TABLE block;
 DROP TABLE spl_token_owner_index;
 DROP TABLE spl_token_mint_index;
 DROP TABLE entry;
+DROP TABLE slot_complete;
+DROP TABLE da_blob;
 DROP TABLE merkle_tree_proof;
 DROP TABLE replay;
+DROP TABLE schema_version;
 
 DROP TYPE "TransactionError" CASCADE;
 DROP TYPE "TransactionErrorCode" CASCADE;
@@ -3,10 +3,11 @@ use {
     crate::{
         accounts_selector::AccountsSelector,
         entry_selector::EntrySelector,
-        postgres_client::{ParallelPostgresClient, PostgresClientBuilder, SequencePostgresClient},
+        postgres_client::{ParallelPostgresClient, PostgresClientBuilder},
         transaction_selector::TransactionSelector,
     },
     bs58,
+    da_backend::{DataAvailabilityBackendConfig, DataAvailabilityPayload},
     log::*,
     serde_derive::{Deserialize, Serialize},
     serde_json,
@@ -24,8 +25,6 @@ use {
 #[derive(Default)]
 pub struct GeyserPluginPostgres {
     client: Option<ParallelPostgresClient>,
-    sequence_client: Option<SequencePostgresClient>,
-    // only worker thread
     accounts_selector: Option<AccountsSelector>,
     transaction_selector: Option<TransactionSelector>,
     entry_selector: Option<EntrySelector>,
@@ -99,6 +98,36 @@ pub struct GeyserPluginPostgresConfig {
     /// and ignore upsetr accounts (at_startup) that should already exist in DB
     #[serde(default)]
     pub skip_upsert_existing_accounts_at_startup: bool,
+
+    /// The data-availability backend the entries are published to.
+    /// The default is the `entry` table of this PostgreSQL database.
+    #[serde(default)]
+    pub da_backend: DataAvailabilityBackendConfig,
+
+    /// Whether the shreds of each slot or a blob of its entries are
+    /// published. The default is the shreds.
+    #[serde(default)]
+    pub da_payload: DataAvailabilityPayload,
+
+    /// The path to the identity keypair of the validator, which the blobs
+    /// are signed with. Required when `da_payload` is blob.
+    pub blob_keypair: Option<String>,
+
+    /// Controls whether to write the accounts and transactions with binary
+    /// `COPY` through asynchronous connections rather than with `INSERT`
+    /// statements from the worker threads. The default is false
+    pub copy_ingestion: Option<bool>,
+
+    /// The number of connections writing with `COPY`. The default is 4.
+    pub copy_connections: Option<usize>,
+
+    /// The most rows of a slot written with one `COPY`. The default is 5000.
+    pub copy_batch_size: Option<usize>,
+
+    /// Controls whether to only check the schema version of the database on
+    /// load instead of applying the missing migrations, refusing to load if
+    /// any is missing. The default is false
+    pub check_schema: Option<bool>,
 }
 
 #[derive(Error, Debug)]
@@ -168,6 +197,27 @@ impl GeyserPlugin for GeyserPluginPostgres {
     /// "transaction_selector" : {
     ///     "mentions" : \["all_votes"\],
     /// }
+    /// * "da_backend", optional, selects where the entries are published to. The default is
+    /// the `entry` table of the PostgreSQL database:
+    /// "da_backend" : {
+    ///     "type" : "postgres",
+    /// }
+    /// To publish one blob file per slot to a local directory, simulating a DA layer:
+    /// "da_backend" : {
+    ///     "type" : "filesystem",
+    ///     "path" : "/tmp/da",
+    /// }
+    /// * "da_payload", optional, set it to "blob" to publish the entries of each slot as a
+    /// compressed blob rather than its shreds. The default is "shreds".
+    /// * "blob_keypair", required when "da_payload" is "blob", the path to the identity keypair
+    /// of the validator. The blobs are signed with it, and rejected by the replayers unless it
+    /// is the leader of their slots.
+    /// * "copy_ingestion", optional, set it to 'true' to write the accounts and transactions with
+    /// binary `COPY` into staging tables merged per slot, through "copy_connections" asynchronous
+    /// connections (default '4') in batches of up to "copy_batch_size" rows (default '5000').
+    /// The token indexes are not supported with it.
+    /// * "check_schema", optional, the missing schema migrations are applied to the database on
+    /// load. Set it to 'true' to only log them and refuse to load if there are any, as a dry run.
     /// # Examples
     ///
     /// {
@@ -212,9 +262,6 @@ impl GeyserPlugin for GeyserPluginPostgres {
         self.batch_starting_slot = batch_optimize_by_skiping_older_slots;
         self.entry_starting_slot = entry_starting_slot;
 
-        let sequence_client = PostgresClientBuilder::build_sequence_postgres_client(&config)?;
-        self.sequence_client = Some(sequence_client);
-
         Ok(())
     }
 
@@ -227,13 +274,6 @@ impl GeyserPlugin for GeyserPluginPostgres {
                 client.join().unwrap();
             }
         }
-
-        match &mut self.sequence_client {
-            None => {}
-            Some(client) => {
-                client.join().unwrap();
-            }
-        }
     }
 
     fn update_account(
@@ -316,26 +356,6 @@ impl GeyserPlugin for GeyserPluginPostgres {
                         }
                     }
                 }
-
-                // match Sequence Client
-                match &mut self.sequence_client {
-                    None => {
-                        return Err(GeyserPluginError::Custom(Box::new(
-                            GeyserPluginPostgresError::DataStoreConnectionError {
-                                msg: "There is no connection to the PostgreSQL database."
-                                    .to_string(),
-                            },
-                        )));
-                    }
-                    Some(client) => {
-                        let result = { client.update_account(account, slot, is_startup) };
-                        if let Err(err) = result {
-                            return Err(GeyserPluginError::AccountsUpdateError {
-                                msg: format!("Failed to persist the update of account to the PostgreSQL database. Error: {:?}", err)
-                            });
-                        }
-                    }
-                }
             }
         }
 
@@ -458,17 +478,15 @@ impl GeyserPlugin for GeyserPluginPostgres {
                     },
                 )));
             }
-            Some(client) => match block_info {
-                ReplicaBlockInfoVersions::V0_0_1(block_info) => {
-                    let result = client.update_block_metadata(block_info);
+            Some(client) => {
+                let result = client.update_block_metadata(&block_info);
 
-                    if let Err(err) = result {
-                        return Err(GeyserPluginError::SlotStatusUpdateError {
-                            msg: format!("Failed to persist the update of block metadata to the PostgreSQL database. Error: {:?}", err)
-                        });
-                    }
+                if let Err(err) = result {
+                    return Err(GeyserPluginError::SlotStatusUpdateError {
+                        msg: format!("Failed to persist the update of block metadata to the PostgreSQL database. Error: {:?}", err)
+                    });
                 }
-            },
+            }
         }
 
         Ok(())
@@ -522,6 +540,17 @@ impl GeyserPlugin for GeyserPluginPostgres {
             .map_or_else(|| false, |selector| selector.is_enabled())
     }
 
+    fn entry_published(&self, slot: u64) -> Result<bool> {
+        match &self.client {
+            None => Err(GeyserPluginError::Custom(Box::new(
+                GeyserPluginPostgresError::DataStoreConnectionError {
+                    msg: "There is no connection to the PostgreSQL database.".to_string(),
+                },
+            ))),
+            Some(client) => Ok(client.entry_published(slot)),
+        }
+    }
+
     fn last_insert_entry(&self) -> u64 {
         self.entry_starting_slot.unwrap()
     }
@@ -630,4 +659,25 @@ pub(crate) mod tests {
         let entry_selector = GeyserPluginPostgres::create_entry_selector_from_config(&config);
         assert_eq!(true, entry_selector.is_enabled());
     }
+
+    #[test]
+    fn test_da_backend_from_config() {
+        let config = "{\"host\" : \"localhost\"}";
+        let config: GeyserPluginPostgresConfig = serde_json::from_str(config).unwrap();
+        assert_eq!(config.da_backend, DataAvailabilityBackendConfig::Postgres);
+        assert_eq!(config.da_payload, DataAvailabilityPayload::Shreds);
+
+        let config = "{\"host\" : \"localhost\", \
+            \"da_backend\" : { \"type\" : \"filesystem\", \"path\" : \"/tmp/da\" }, \
+            \"da_payload\" : \"blob\", \"blob_keypair\" : \"/tmp/id.json\"}";
+        let config: GeyserPluginPostgresConfig = serde_json::from_str(config).unwrap();
+        assert_eq!(
+            config.da_backend,
+            DataAvailabilityBackendConfig::Filesystem {
+                path: std::path::PathBuf::from("/tmp/da")
+            }
+        );
+        assert_eq!(config.da_payload, DataAvailabilityPayload::Blob);
+        assert_eq!(config.blob_keypair.as_deref(), Some("/tmp/id.json"));
+    }
 }
@@ -5,15 +5,20 @@ mod postgres_client_account_index;
 mod postgres_client_block_metadata;
 mod postgres_client_transaction;
 mod postgres_client_account_smt;
+mod postgres_client_copy;
 
 /// A concurrent implementation for writing accounts into the PostgreSQL in parallel.
 use {
     crate::{
         geyser_plugin_postgres::{GeyserPluginPostgresConfig, GeyserPluginPostgresError},
-        postgres_client::postgres_client_account_index::TokenSecondaryIndexEntry,
+        postgres_client::{
+            postgres_client_account_index::TokenSecondaryIndexEntry,
+            postgres_client_copy::{CopyIngestionService, CopyWorkItem},
+        },
     },
     chrono::Utc,
-    crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender},
+    crossbeam_channel::{bounded, Receiver, RecvTimeoutError, SendError, Sender, TrySendError},
+    da_backend::{schema, DataAvailabilityBackend},
     log::*,
     openssl::ssl::{SslConnector, SslFiletype, SslMethod},
     postgres::{Client, NoTls, Statement},
@@ -22,25 +27,21 @@ use {
     postgres_openssl::MakeTlsConnector,
     solana_entry::entry::UntrustedEntry,
     solana_geyser_plugin_interface::geyser_plugin_interface::{
-        GeyserPluginError, ReplicaAccountInfoV2, ReplicaBlockInfo, SlotStatus,
+        GeyserPluginError, ReplicaAccountInfoV2, ReplicaBlockInfoVersions, SlotStatus,
     },
     solana_measure::measure::Measure,
     solana_metrics::*,
-    solana_sdk::timing::AtomicInterval,
+    solana_sdk::{signature::Keypair, timing::AtomicInterval},
     std::{
-        collections::HashSet,
+        collections::{BTreeSet, HashSet},
         sync::{
             atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
-            Arc, Mutex, RwLock,
+            Arc, Mutex,
         },
         thread::{self, sleep, Builder, JoinHandle},
         time::Duration,
     },
     tokio_postgres::types,
-    sparse_merkle_tree::{
-        blake2b::Blake2bHasher, default_store::DefaultStore, SparseMerkleTree, H256,
-    },
-    blake3::{self, Hash},
 };
 
 
@@ -54,6 +55,8 @@ const DEFAULT_ACCOUNTS_INSERT_BATCH_SIZE: usize = 10;
 const ACCOUNT_COLUMN_COUNT: usize = 10;
 const DEFAULT_PANIC_ON_DB_ERROR: bool = false;
 const DEFAULT_STORE_ACCOUNT_HISTORICAL_DATA: bool = false;
+const DEFAULT_COPY_INGESTION: bool = false;
+const DEFAULT_CHECK_SCHEMA: bool = false;
 
 struct PostgresSqlClientWrapper {
     client: Client,
@@ -68,7 +71,6 @@ struct PostgresSqlClientWrapper {
     insert_token_mint_index_stmt: Option<Statement>,
     bulk_insert_token_owner_index_stmt: Option<Statement>,
     bulk_insert_token_mint_index_stmt: Option<Statement>,
-    log_entry_stmt: Statement,
     update_smt_tree_stmt: Statement,
 }
 
@@ -81,6 +83,11 @@ pub struct SimplePostgresClient {
     pending_token_owner_index: Vec<TokenSecondaryIndexEntry>,
     pending_token_mint_index: Vec<TokenSecondaryIndexEntry>,
     client: Mutex<PostgresSqlClientWrapper>,
+    config: GeyserPluginPostgresConfig,
+    /// The backend the entries are published to, connected on first use.
+    da_backend: Option<Box<dyn DataAvailabilityBackend>>,
+    /// The keypair the blobs are signed with, when they are published.
+    blob_keypair: Option<Keypair>,
 }
 
 struct PostgresClientWorker {
@@ -247,11 +254,16 @@ pub trait PostgresClient {
 }
 
 impl SimplePostgresClient {
-    pub fn connect_to_db(config: &GeyserPluginPostgresConfig) -> Result<Client, GeyserPluginError> {
+    /// The connection string of the database, built from the `host`, `user`,
+    /// `password`, `dbname` and `port` of the config unless `connection_str`
+    /// is set.
+    pub(crate) fn connection_str(
+        config: &GeyserPluginPostgresConfig,
+    ) -> Result<String, GeyserPluginError> {
         let port = config.port.unwrap_or(DEFAULT_POSTGRES_PORT);
 
-        let connection_str = if let Some(connection_str) = &config.connection_str {
-            connection_str.clone()
+        if let Some(connection_str) = &config.connection_str {
+            Ok(connection_str.clone())
         } else {
             if config.host.is_none() || config.user.is_none() {
                 let msg = format!(
@@ -262,75 +274,88 @@ impl SimplePostgresClient {
                     GeyserPluginPostgresError::ConfigurationError { msg },
                 )));
             }
-            format!(
+            Ok(format!(
                 "host={} user={} password={} dbname={} port={}",
                 config.host.as_ref().unwrap(),
                 config.user.as_ref().unwrap(),
                 config.password.as_ref().unwrap(),
                 config.dbname.as_ref().unwrap(),
                 port
-            )
-        };
+            ))
+        }
+    }
 
-        let result = if let Some(true) = config.use_ssl {
-            if config.server_ca.is_none() {
-                let msg = "\"server_ca\" must be specified when \"use_ssl\" is set".to_string();
-                return Err(GeyserPluginError::Custom(Box::new(
-                    GeyserPluginPostgresError::ConfigurationError { msg },
-                )));
-            }
-            if config.client_cert.is_none() {
-                let msg = "\"client_cert\" must be specified when \"use_ssl\" is set".to_string();
-                return Err(GeyserPluginError::Custom(Box::new(
-                    GeyserPluginPostgresError::ConfigurationError { msg },
-                )));
-            }
-            if config.client_key.is_none() {
-                let msg = "\"client_key\" must be specified when \"use_ssl\" is set".to_string();
-                return Err(GeyserPluginError::Custom(Box::new(
-                    GeyserPluginPostgresError::ConfigurationError { msg },
-                )));
-            }
-            let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
-            if let Err(err) = builder.set_ca_file(config.server_ca.as_ref().unwrap()) {
-                let msg = format!(
-                    "Failed to set the server certificate specified by \"server_ca\": {}. Error: ({})",
-                    config.server_ca.as_ref().unwrap(), err);
-                return Err(GeyserPluginError::Custom(Box::new(
-                    GeyserPluginPostgresError::ConfigurationError { msg },
-                )));
-            }
-            if let Err(err) =
-                builder.set_certificate_file(config.client_cert.as_ref().unwrap(), SslFiletype::PEM)
-            {
-                let msg = format!(
-                    "Failed to set the client certificate specified by \"client_cert\": {}. Error: ({})",
-                    config.client_cert.as_ref().unwrap(), err);
-                return Err(GeyserPluginError::Custom(Box::new(
-                    GeyserPluginPostgresError::ConfigurationError { msg },
-                )));
-            }
-            if let Err(err) =
-                builder.set_private_key_file(config.client_key.as_ref().unwrap(), SslFiletype::PEM)
-            {
-                let msg = format!(
-                    "Failed to set the client key specified by \"client_key\": {}. Error: ({})",
-                    config.client_key.as_ref().unwrap(),
-                    err
-                );
-                return Err(GeyserPluginError::Custom(Box::new(
-                    GeyserPluginPostgresError::ConfigurationError { msg },
-                )));
-            }
+    /// The TLS connector of the database when `use_ssl` is set, shared by the
+    /// blocking and the asynchronous clients.
+    pub(crate) fn tls_connector(
+        config: &GeyserPluginPostgresConfig,
+    ) -> Result<Option<MakeTlsConnector>, GeyserPluginError> {
+        if config.use_ssl != Some(true) {
+            return Ok(None);
+        }
+        if config.server_ca.is_none() {
+            let msg = "\"server_ca\" must be specified when \"use_ssl\" is set".to_string();
+            return Err(GeyserPluginError::Custom(Box::new(
+                GeyserPluginPostgresError::ConfigurationError { msg },
+            )));
+        }
+        if config.client_cert.is_none() {
+            let msg = "\"client_cert\" must be specified when \"use_ssl\" is set".to_string();
+            return Err(GeyserPluginError::Custom(Box::new(
+                GeyserPluginPostgresError::ConfigurationError { msg },
+            )));
+        }
+        if config.client_key.is_none() {
+            let msg = "\"client_key\" must be specified when \"use_ssl\" is set".to_string();
+            return Err(GeyserPluginError::Custom(Box::new(
+                GeyserPluginPostgresError::ConfigurationError { msg },
+            )));
+        }
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        if let Err(err) = builder.set_ca_file(config.server_ca.as_ref().unwrap()) {
+            let msg = format!(
+                "Failed to set the server certificate specified by \"server_ca\": {}. Error: ({})",
+                config.server_ca.as_ref().unwrap(), err);
+            return Err(GeyserPluginError::Custom(Box::new(
+                GeyserPluginPostgresError::ConfigurationError { msg },
+            )));
+        }
+        if let Err(err) =
+            builder.set_certificate_file(config.client_cert.as_ref().unwrap(), SslFiletype::PEM)
+        {
+            let msg = format!(
+                "Failed to set the client certificate specified by \"client_cert\": {}. Error: ({})",
+                config.client_cert.as_ref().unwrap(), err);
+            return Err(GeyserPluginError::Custom(Box::new(
+                GeyserPluginPostgresError::ConfigurationError { msg },
+            )));
+        }
+        if let Err(err) =
+            builder.set_private_key_file(config.client_key.as_ref().unwrap(), SslFiletype::PEM)
+        {
+            let msg = format!(
+                "Failed to set the client key specified by \"client_key\": {}. Error: ({})",
+                config.client_key.as_ref().unwrap(),
+                err
+            );
+            return Err(GeyserPluginError::Custom(Box::new(
+                GeyserPluginPostgresError::ConfigurationError { msg },
+            )));
+        }
 
-            let mut connector = MakeTlsConnector::new(builder.build());
-            connector.set_callback(|connect_config, _domain| {
-                connect_config.set_verify_hostname(false);
-                Ok(())
-            });
-            Client::connect(&connection_str, connector)
-        } else {
-            Client::connect(&connection_str, NoTls)
+        let mut connector = MakeTlsConnector::new(builder.build());
+        connector.set_callback(|connect_config, _domain| {
+            connect_config.set_verify_hostname(false);
+            Ok(())
+        });
+        Ok(Some(connector))
+    }
+
+    pub fn connect_to_db(config: &GeyserPluginPostgresConfig) -> Result<Client, GeyserPluginError> {
+        let connection_str = Self::connection_str(config)?;
+        let result = match Self::tls_connector(config)? {
+            Some(connector) => Client::connect(&connection_str, connector),
+            None => Client::connect(&connection_str, NoTls),
         };
 
         match result {
@@ -796,7 +821,6 @@ impl SimplePostgresClient {
             Self::build_transaction_info_upsert_statement(&mut client, config)?;
         let update_block_metadata_stmt =
             Self::build_block_metadata_upsert_statement(&mut client, config)?;
-        let log_entry_stmt = Self::build_entry_upsert_statement(&mut client, config)?;
         let update_smt_tree_stmt = Self::build_smt_tree_upsert_statement(&mut client, config)?;
 
         let batch_size = config
@@ -846,6 +870,8 @@ impl SimplePostgresClient {
             None
         };
 
+        let blob_keypair = Self::load_blob_keypair(config)?;
+
         info!("Created SimplePostgresClient.");
         Ok(Self {
             batch_size,
@@ -863,9 +889,11 @@ impl SimplePostgresClient {
                 insert_token_mint_index_stmt,
                 bulk_insert_token_owner_index_stmt,
                 bulk_insert_token_mint_index_stmt,
-                log_entry_stmt,
                 update_smt_tree_stmt,
             }),
+            config: config.clone(),
+            da_backend: None,
+            blob_keypair,
             index_token_owner: config.index_token_owner.unwrap_or_default(),
             index_token_mint: config.index_token_mint.unwrap_or(false),
             pending_token_owner_index: Vec::with_capacity(batch_size),
@@ -899,25 +927,16 @@ impl SimplePostgresClient {
     }
 
     fn get_highest_entry_slot(&mut self) -> Result<u64, GeyserPluginError> {
-        let client = self.client.get_mut().unwrap();
-
-        let last_slot_query = "SELECT slot FROM entry ORDER BY slot DESC LIMIT 1;";
-
-        let result = client.client.query_opt(last_slot_query, &[]);
+        let result = self.da_backend()?.highest_slot();
         match result {
-            Ok(opt_slot) => Ok(opt_slot
-                .map(|row| {
-                    let raw_slot: i64 = row.get(0);
-                    raw_slot as u64
-                })
-                .unwrap_or(0)),
+            Ok(opt_slot) => Ok(opt_slot.unwrap_or(0)),
             Err(err) => {
                 let msg = format!(
-                    "Failed to receive last slot from PostgreSQL database. Error: {:?}",
+                    "Failed to receive last entry slot from the DA backend. Error: {:?}",
                     err
                 );
                 error!("{}", msg);
-                Err(GeyserPluginError::AccountsUpdateError { msg })
+                Err(GeyserPluginError::EntryUpdateError { msg })
             }
         }
     }
@@ -1033,6 +1052,7 @@ impl PostgresClientWorker {
         exit_worker: Arc<AtomicBool>,
         is_startup_done: Arc<AtomicBool>,
         startup_done_count: Arc<AtomicUsize>,
+        published_entry_slots: Arc<Mutex<BTreeSet<u64>>>,
         panic_on_db_errors: bool,
     ) -> Result<(), GeyserPluginError> {
         while !exit_worker.load(Ordering::Relaxed) {
@@ -1087,10 +1107,16 @@ impl PostgresClientWorker {
                         }
                     }
                     DbWorkItem::LogEntry(entry) => {
-                        if let Err(err) = self.client.log_entry(*entry) {
-                            error!("Failed to log entry : ({})", err);
-                            if panic_on_db_errors {
-                                abort();
+                        let slot = entry.entry.slot;
+                        match self.client.log_entry(*entry) {
+                            Ok(()) => {
+                                published_entry_slots.lock().unwrap().insert(slot);
+                            }
+                            Err(err) => {
+                                error!("Failed to log entry : ({})", err);
+                                if panic_on_db_errors {
+                                    abort();
+                                }
                             }
                         }
                     }
@@ -1124,6 +1150,31 @@ impl PostgresClientWorker {
     }
 }
 
+/// Send `item` to the workers, blocking while the channel is full: the
+/// validator is slowed to the pace of the database rather than the requests
+/// being buffered without bound.
+pub(crate) fn send_with_back_pressure<T>(
+    sender: &Sender<T>,
+    item: T,
+) -> Result<(), SendError<T>> {
+    match sender.try_send(item) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(item)) => {
+            let mut measure = Measure::start("geyser-plugin-postgres-back-pressure");
+            let result = sender.send(item);
+            measure.stop();
+            inc_new_counter_info!(
+                "geyser-plugin-postgres-back-pressure-us",
+                measure.as_us() as usize,
+                1000,
+                1000
+            );
+            result
+        }
+        Err(TrySendError::Disconnected(item)) => Err(SendError(item)),
+    }
+}
+
 pub struct ParallelPostgresClient {
     workers: Vec<JoinHandle<Result<(), GeyserPluginError>>>,
     exit_worker: Arc<AtomicBool>,
@@ -1133,6 +1184,13 @@ pub struct ParallelPostgresClient {
     sender: Sender<DbWorkItem>,
     last_report: AtomicInterval,
     transaction_write_version: AtomicU64,
+    /// The accounts and transactions are sent there instead of to the
+    /// workers when `copy_ingestion` is set.
+    copy_sender: Option<Sender<CopyWorkItem>>,
+    copy_ingestion: Option<CopyIngestionService>,
+    /// The slots whose entries the workers published to the DA backend and
+    /// which are not acknowledged yet, see `entry_published`
+    published_entry_slots: Arc<Mutex<BTreeSet<u64>>>,
 }
 
 impl ParallelPostgresClient {
@@ -1140,17 +1198,37 @@ impl ParallelPostgresClient {
         info!("Creating ParallelPostgresClient...");
         let (sender, receiver) = bounded(MAX_ASYNC_REQUESTS);
         let exit_worker = Arc::new(AtomicBool::new(false));
+        let (copy_sender, copy_ingestion) =
+            if config.copy_ingestion.unwrap_or(DEFAULT_COPY_INGESTION) {
+                if config.index_token_owner == Some(true) || config.index_token_mint == Some(true)
+                {
+                    let msg = "\"index_token_owner\" and \"index_token_mint\" are not supported \
+                               with \"copy_ingestion\""
+                        .to_string();
+                    return Err(GeyserPluginError::Custom(Box::new(
+                        GeyserPluginPostgresError::ConfigurationError { msg },
+                    )));
+                }
+                let (copy_sender, copy_receiver) = bounded(MAX_ASYNC_REQUESTS);
+                let copy_ingestion =
+                    CopyIngestionService::new(config, copy_receiver, exit_worker.clone())?;
+                (Some(copy_sender), Some(copy_ingestion))
+            } else {
+                (None, None)
+            };
         let mut workers = Vec::default();
         let is_startup_done = Arc::new(AtomicBool::new(false));
         let startup_done_count = Arc::new(AtomicUsize::new(0));
         let worker_count = config.threads.unwrap_or(DEFAULT_THREADS_COUNT);
         let initialized_worker_count = Arc::new(AtomicUsize::new(0));
+        let published_entry_slots = Arc::new(Mutex::new(BTreeSet::default()));
         for i in 0..worker_count {
             let cloned_receiver = receiver.clone();
             let exit_clone = exit_worker.clone();
             let is_startup_done_clone = is_startup_done.clone();
             let startup_done_count_clone = startup_done_count.clone();
             let initialized_worker_count_clone = initialized_worker_count.clone();
+            let published_entry_slots_clone = published_entry_slots.clone();
             let config = config.clone();
             let worker = Builder::new()
                 .name(format!("worker-{}", i))
@@ -1169,6 +1247,7 @@ impl ParallelPostgresClient {
                                 exit_clone,
                                 is_startup_done_clone,
                                 startup_done_count_clone,
+                                published_entry_slots_clone,
                                 panic_on_db_errors,
                             )?;
                             Ok(())
@@ -1197,6 +1276,9 @@ impl ParallelPostgresClient {
             initialized_worker_count,
             sender,
             transaction_write_version: AtomicU64::default(),
+            copy_sender,
+            copy_ingestion,
+            published_entry_slots,
         })
     }
 
@@ -1213,6 +1295,9 @@ impl ParallelPostgresClient {
                 error!("The worker thread has failed: {:?}", result);
             }
         }
+        if let Some(copy_ingestion) = &mut self.copy_ingestion {
+            copy_ingestion.join();
+        }
 
         Ok(())
     }
@@ -1232,14 +1317,15 @@ impl ParallelPostgresClient {
             datapoint_debug!(
                 "postgres-plugin-stats",
                 ("message-queue-length", self.sender.len() as i64, i64),
+                (
+                    "copy-queue-length",
+                    self.copy_sender.as_ref().map(Sender::len).unwrap_or_default() as i64,
+                    i64
+                ),
             );
         }
         let mut measure = Measure::start("geyser-plugin-posgres-create-work-item");
-        let wrk_item = DbWorkItem::UpdateAccount(Box::new(UpdateAccountRequest {
-            account: DbAccountInfo::new(account, slot),
-            is_startup,
-        }));
-
+        let account_info = DbAccountInfo::new(account, slot);
         measure.stop();
 
         inc_new_counter_debug!(
@@ -1251,7 +1337,25 @@ impl ParallelPostgresClient {
 
         let mut measure = Measure::start("geyser-plugin-posgres-send-msg");
 
-        if let Err(err) = self.sender.send(wrk_item) {
+        let result = match &self.copy_sender {
+            Some(copy_sender) => send_with_back_pressure(
+                copy_sender,
+                CopyWorkItem::Account {
+                    account: Box::new(account_info),
+                    is_startup,
+                },
+            )
+            .map_err(|err| format!("{:?}", err)),
+            None => send_with_back_pressure(
+                &self.sender,
+                DbWorkItem::UpdateAccount(Box::new(UpdateAccountRequest {
+                    account: account_info,
+                    is_startup,
+                })),
+            )
+            .map_err(|err| format!("{:?}", err)),
+        };
+        if let Err(err) = result {
             return Err(GeyserPluginError::AccountsUpdateError {
                 msg: format!(
                     "Failed to update the account {:?}, error: {:?}",
@@ -1278,18 +1382,25 @@ impl ParallelPostgresClient {
         parent: Option<u64>,
         status: SlotStatus,
     ) -> Result<(), GeyserPluginError> {
-        if let Err(err) = self
-            .sender
-            .send(DbWorkItem::UpdateSlot(Box::new(UpdateSlotRequest {
+        if let Err(err) = send_with_back_pressure(
+            &self.sender,
+            DbWorkItem::UpdateSlot(Box::new(UpdateSlotRequest {
                 slot,
                 parent,
                 slot_status: status,
-            })))
-        {
+            })),
+        ) {
             return Err(GeyserPluginError::SlotStatusUpdateError {
                 msg: format!("Failed to update the slot {:?}, error: {:?}", slot, err),
             });
         }
+        if let Some(copy_sender) = &self.copy_sender {
+            if let Err(err) = send_with_back_pressure(copy_sender, CopyWorkItem::SlotStatus(slot)) {
+                return Err(GeyserPluginError::SlotStatusUpdateError {
+                    msg: format!("Failed to update the slot {:?}, error: {:?}", slot, err),
+                });
+            }
+        }
         Ok(())
     }
 
@@ -1299,11 +1410,13 @@ impl ParallelPostgresClient {
             slot: entry.slot,
             parent_slot: entry.parent_slot,
             is_full_slot: entry.is_full_slot,
+            shreds: entry.shreds.clone(),
+            shred_version: entry.shred_version,
         };
-        if let Err(err) = self
-            .sender
-            .send(DbWorkItem::LogEntry(Box::new(LogEntryRequest { entry })))
-        {
+        if let Err(err) = send_with_back_pressure(
+            &self.sender,
+            DbWorkItem::LogEntry(Box::new(LogEntryRequest { entry })),
+        ) {
             return Err(GeyserPluginError::EntryUpdateError {
                 msg: format!("Failed to update the entry , error: {:?}", err),
             });
@@ -1311,19 +1424,36 @@ impl ParallelPostgresClient {
         Ok(())
     }
 
+    /// Whether a worker published the entries of `slot` to the DA backend.
+    /// The slot and the older ones are forgotten once acknowledged, the
+    /// entry publisher checks the slots in the order it notified them.
+    pub fn entry_published(&self, slot: u64) -> bool {
+        let mut published_entry_slots = self.published_entry_slots.lock().unwrap();
+        let published = published_entry_slots.contains(&slot);
+        if published {
+            *published_entry_slots = published_entry_slots.split_off(&(slot + 1));
+        }
+        published
+    }
+
     pub fn update_block_metadata(
         &mut self,
-        block_info: &ReplicaBlockInfo,
+        block_info: &ReplicaBlockInfoVersions,
     ) -> Result<(), GeyserPluginError> {
-        if let Err(err) = self.sender.send(DbWorkItem::UpdateBlockMetadata(Box::new(
-            UpdateBlockMetadataRequest {
-                block_info: DbBlockInfo::from(block_info),
-            },
-        ))) {
+        let block_info = match block_info {
+            ReplicaBlockInfoVersions::V0_0_1(block_info) => DbBlockInfo::from(*block_info),
+            ReplicaBlockInfoVersions::V0_0_2(block_info) => DbBlockInfo::from(*block_info),
+            ReplicaBlockInfoVersions::V0_0_3(block_info) => DbBlockInfo::from(*block_info),
+        };
+        let slot = block_info.slot;
+        if let Err(err) = send_with_back_pressure(
+            &self.sender,
+            DbWorkItem::UpdateBlockMetadata(Box::new(UpdateBlockMetadataRequest { block_info })),
+        ) {
             return Err(GeyserPluginError::SlotStatusUpdateError {
                 msg: format!(
                     "Failed to update the block metadata at slot {:?}, error: {:?}",
-                    block_info.slot, err
+                    slot, err
                 ),
             });
         }
@@ -1350,6 +1480,19 @@ impl ParallelPostgresClient {
             sleep(Duration::from_millis(100));
         }
 
+        if let Some(copy_sender) = &self.copy_sender {
+            let (done_sender, done_receiver) = bounded(1);
+            let result = copy_sender
+                .send(CopyWorkItem::EndOfStartup(done_sender))
+                .map_err(|err| format!("{:?}", err))
+                .and_then(|()| done_receiver.recv().map_err(|err| format!("{:?}", err)));
+            if let Err(err) = result {
+                return Err(GeyserPluginError::AccountsUpdateError {
+                    msg: format!("Failed to flush the COPY ingestion, error: {}", err),
+                });
+            }
+        }
+
         info!("Done with notifying the end of startup");
         Ok(())
     }
@@ -1361,6 +1504,7 @@ impl PostgresClientBuilder {
     pub fn build_pararallel_postgres_client(
         config: &GeyserPluginPostgresConfig,
     ) -> Result<(ParallelPostgresClient, Option<u64>, Option<u64>), GeyserPluginError> {
+        Self::migrate_schema(config)?;
         let mut on_load_client = SimplePostgresClient::new(config)?;
 
         let batch_optimize_by_skiping_older_slots =
@@ -1385,216 +1529,29 @@ impl PostgresClientBuilder {
         ParallelPostgresClient::new(config).map(|v| (v, batch_optimize_by_skiping_older_slots, Some(entry_starting_slot)))
     }
 
-    pub fn build_sequence_postgres_client(
-        config: &GeyserPluginPostgresConfig,
-    ) -> Result<SequencePostgresClient, GeyserPluginError> {
-        SequencePostgresClient::new(config)
-    }
-}
-
-type SMT = SparseMerkleTree<Blake2bHasher, H256, DefaultStore<H256>>;
-
-/// SequenceClient, 1 recv, 1 worker thread
-#[allow(dead_code)]
-pub struct SequencePostgresClient {
-    worker: Option<JoinHandle<Result<(), GeyserPluginError>>>,
-    exit_worker: Arc<AtomicBool>,
-    sender: Sender<DbWorkItem>,
-    slot: Arc<RwLock<i64>>,
-    smt_tree: Arc<RwLock<SMT>>,
-}
-
-impl SequencePostgresClient {
-    pub fn new(config: &GeyserPluginPostgresConfig) -> Result<Self, GeyserPluginError> {
-        info!("Creating SequencePostgresClient...");
-        let (sender, receiver) = bounded(MAX_ASYNC_REQUESTS);
-        let exit_worker = Arc::new(AtomicBool::new(false));
-        let exit_clone = exit_worker.clone();
-        let config = config.clone();
-        // let smt_tree = Arc::new(RwLock::new(SMT::default()));
-        let slot: Arc<RwLock<i64>> = Arc::new(RwLock::new(1));
-        let slot_clone = slot.clone();
-        let smt_tree = Arc::new(RwLock::new(SMT::default()));
-        let smt_clone = smt_tree.clone();
-        let worker = Builder::new()
-            .name(format!("worker-sequence-account"))
-            .spawn(move || -> Result<(), GeyserPluginError> {
-                let panic_on_db_errors = *config
-                    .panic_on_db_errors
-                    .as_ref()
-                    .unwrap_or(&DEFAULT_PANIC_ON_DB_ERROR);
-                let result = SequencePostgresClientWorker::new(config, slot_clone, smt_clone);
-
-                match result {
-                    Ok(mut worker) => {
-                        worker.do_work(receiver, exit_clone, panic_on_db_errors)?;
-                        Ok(())
-                    }
-                    Err(err) => {
-                        error!("Error when making connection to database: ({})", err);
-                        if panic_on_db_errors {
-                            abort();
-                        }
-                        Err(err)
-                    }
-                }
-            })
-            .unwrap();
-
-        info!("Created SequencePostgresClient.");
-        Ok(Self {
-            worker: Some(worker),
-            exit_worker,
-            sender,
-            slot,
-            smt_tree,
-        })
-    }
-
-    pub fn join(&mut self) -> thread::Result<()> {
-        self.exit_worker.store(true, Ordering::Relaxed);
-        if let Some(handle) = self.worker.take() {
-            let result = handle.join();
-            if result.is_err() {
-                error!("The worker thread has failed: {:?}", result);
-            }
-        }
-        Ok(())
-    }
-
-    pub fn update_account(
-        &mut self,
-        account: &ReplicaAccountInfoV2,
-        slot: u64,
-        is_startup: bool,
-    ) -> Result<(), GeyserPluginError> {
-        if !is_startup && account.txn_signature.is_none() {
-            // we are not interested in accountsdb internal bookeeping updates
-            return Ok(());
-        }
-
-        let wrk_item = DbWorkItem::UpdateAccount(Box::new(UpdateAccountRequest {
-            account: DbAccountInfo::new(account, slot),
-            is_startup,
-        }));
-
-        if let Err(err) = self.sender.send(wrk_item) {
-            return Err(GeyserPluginError::AccountsUpdateError {
-                msg: format!(
-                    "Failed to update the account {:?}, error: {:?}",
-                    bs58::encode(account.pubkey()).into_string(),
-                    err
-                ),
-            });
-        }
-
-        Ok(())
-    }
-}
-
-struct SequencePostgresClientWorker {
-    client: SimplePostgresClient,
-    slot: Arc<RwLock<i64>>,
-    smt_tree: Arc<RwLock<SMT>>,
-}
-
-impl SequencePostgresClientWorker {
-    fn new(
-        config: GeyserPluginPostgresConfig,
-        slot: Arc<RwLock<i64>>,
-        smt_tree: Arc<RwLock<SMT>>,
-    ) -> Result<Self, GeyserPluginError> {
-        let result = SimplePostgresClient::new(&config);
-        match result {
-            Ok(client) => Ok(SequencePostgresClientWorker { client, slot, smt_tree }),
-            Err(err) => {
-                error!("Error in creating SequencePostgresClientWorker: {}", err);
-                Err(err)
-            }
-        }
-    }
-
-    fn do_work(
-        &mut self,
-        receiver: Receiver<DbWorkItem>,
-        exit_worker: Arc<AtomicBool>,
-        panic_on_db_errors: bool,
-    ) -> Result<(), GeyserPluginError> {
-        while !exit_worker.load(Ordering::Relaxed) {
-            let work = receiver.recv_timeout(Duration::from_millis(500));
-            match work {
-                Ok(work) => match work {
-                    DbWorkItem::UpdateAccount(request) => {
-                        let mut current_slot = self.slot.write().unwrap();
-                        // info!("do_work_recv {}, {}", *current_slot, request.account.slot);
-                        if *current_slot != request.account.slot {
-                            // info!("do_work_recv slot_changed {}, {}", *current_slot, request.account.slot);
-                            let smt_tree = self.smt_tree.read().unwrap();
-                            if let Err(_err) = self.client.update_merkle_tree_root(*current_slot, smt_tree.root().as_slice()) {
-                                info!("update_merkle_tree_root err");
-                            }
-                            *current_slot = request.account.slot;
-                        } else {
-                            let key_hash: H256 = request.account.key_hash();
-                            let val_hash: H256 = request.account.value_hash();
-                            if let Err(_err) = self.smt_tree.write().unwrap().update(key_hash, val_hash) {
-                                info!("update_merkle_tree_key_value err");
-                            }
-                        }
-                    }
-                    _ => (),
-                },
-                Err(err) => match err {
-                    RecvTimeoutError::Timeout => {
-                        continue;
-                    }
-                    _ => {
-                        error!("Error in receiving the item {:?}", err);
-                        if panic_on_db_errors {
-                            abort();
-                        }
-                        break;
-                    }
-                },
+    /// Apply the schema migrations the database lacks, or with "check_schema"
+    /// only log them and fail if there are any.
+    fn migrate_schema(config: &GeyserPluginPostgresConfig) -> Result<(), GeyserPluginError> {
+        let mut client = SimplePostgresClient::connect_to_db(config)?;
+        let schema_error = |err: da_backend::DataAvailabilityError| {
+            GeyserPluginError::Custom(Box::new(GeyserPluginPostgresError::DataSchemaError {
+                msg: err.to_string(),
+            }))
+        };
+        if config.check_schema.unwrap_or(DEFAULT_CHECK_SCHEMA) {
+            let status = schema::check_schema(&mut client).map_err(schema_error)?;
+            info!("Checked the {}", status);
+            if !status.is_up_to_date() {
+                return Err(GeyserPluginError::Custom(Box::new(
+                    GeyserPluginPostgresError::DataSchemaError {
+                        msg: format!("The schema is not up to date, {}", status),
+                    },
+                )));
             }
+        } else {
+            schema::migrate_schema(&mut client).map_err(schema_error)?;
         }
         Ok(())
     }
-}
-
-trait HashAccount {
-    /// hash DbAccountInfo pubkey to sparse-merkle-tree key
-    /// Todo: pure synchronous jellyfish-merkle-tree data structure with blake3 hash method
-    fn key_hash(&self) -> H256;
 
-    /// hash DbAccountInfo(without slot property) to sparse-merkle-tree value
-    fn value_hash(&self) -> H256;
-}
-
-impl HashAccount for DbAccountInfo {
-    fn key_hash(&self) -> H256 {
-        // Hash
-        let res: Hash = blake3::hash(&self.pubkey);
-        // Hash to H256
-        H256::from(*res.as_bytes())
-    }
-    fn value_hash(&self) -> H256 {
-        let mut hasher = blake3::Hasher::new();
-        if self.lamports == 0 {
-            let res = hasher.finalize();
-            return H256::from(*res.as_bytes());
-        }
-
-        hasher.update(&self.lamports.to_le_bytes());
-        hasher.update(&self.rent_epoch.to_le_bytes());
-        hasher.update(&self.data);
-
-        if self.executable {
-            hasher.update(&[1u8; 1]);
-        } else {
-            hasher.update(&[0u8; 1]);
-        }
-        let res = hasher.finalize();
-        H256::from(*res.as_bytes())
-    }
 }
@@ -1,89 +1,123 @@
-// build_entry_upsert_statement
-
+/// Module responsible for publishing the entries of a slot to the configured
+/// data-availability backend.
 use {
     crate::{
         geyser_plugin_postgres::{GeyserPluginPostgresConfig, GeyserPluginPostgresError},
         postgres_client::{LogEntryRequest, SimplePostgresClient},
     },
-    chrono::Utc,
+    da_backend::{
+        blob::{DaBlob, SlotEntries},
+        DataAvailabilityBackend, DataAvailabilityError, DataAvailabilityPayload, SlotData,
+    },
     log::*,
-    postgres::{Client, Statement},
     solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPluginError,
-    solana_ledger::blockstore,
+    solana_sdk::signature::{read_keypair_file, Keypair},
 };
 
 impl SimplePostgresClient {
-    pub(crate) fn build_entry_upsert_statement(
-        client: &mut Client,
+    /// Load the keypair the blobs are signed with, `None` unless blobs are
+    /// published.
+    pub(crate) fn load_blob_keypair(
         config: &GeyserPluginPostgresConfig,
-    ) -> Result<Statement, GeyserPluginError> {
-        let stmt =
-            "INSERT INTO entry (slot, parent_slot, entry_index, entry, is_full_slot, updated_on) \
-        VALUES ($1, $2, $3, $4, $5, $6)";
-
-        let stmt = client.prepare(stmt);
+    ) -> Result<Option<Keypair>, GeyserPluginError> {
+        if config.da_payload != DataAvailabilityPayload::Blob {
+            return Ok(None);
+        }
+        let configuration_error = |msg| {
+            GeyserPluginError::Custom(Box::new(GeyserPluginPostgresError::ConfigurationError {
+                msg,
+            }))
+        };
+        let path = config.blob_keypair.as_ref().ok_or_else(|| {
+            configuration_error(
+                "\"blob_keypair\" must be set when \"da_payload\" is \"blob\"".to_string(),
+            )
+        })?;
+        read_keypair_file(path).map(Some).map_err(|err| {
+            configuration_error(format!(
+                "Failed to read the blob keypair {}. Error: {}",
+                path, err
+            ))
+        })
+    }
 
-        match stmt {
-            Err(err) => {
-                Err(GeyserPluginError::Custom(Box::new(GeyserPluginPostgresError::DataSchemaError {
-                    msg: format!(
-                        "Error in preparing for the entry update PostgreSQL database: ({}) host: {:?} user: {:?} config: {:?}",
-                        err, config.host, config.user, config
-                    ),
-                })))
-            }
-            Ok(stmt) => Ok(stmt),
+    /// Get the DA backend, connecting to it on first use so only the workers
+    /// which actually publish entries hold a connection to it.
+    pub(crate) fn da_backend(
+        &mut self,
+    ) -> Result<&mut dyn DataAvailabilityBackend, GeyserPluginError> {
+        if self.da_backend.is_none() {
+            let config = &self.config;
+            let da_backend = config
+                .da_backend
+                .build(|| {
+                    Self::connect_to_db(config).map_err(|err| {
+                        DataAvailabilityError::ConnectionError {
+                            msg: err.to_string(),
+                        }
+                    })
+                })
+                .map_err(|err| GeyserPluginError::EntryUpdateError {
+                    msg: format!("Failed to create the DA backend. Error: {}", err),
+                })?;
+            self.da_backend = Some(da_backend);
         }
+        Ok(self.da_backend.as_deref_mut().unwrap())
     }
 
     pub(crate) fn log_entry_impl(
         &mut self,
         log_entry_request: LogEntryRequest,
     ) -> Result<(), GeyserPluginError> {
-        let client = self.client.get_mut().unwrap();
-        let statement = &client.log_entry_stmt;
-        let client = &mut client.client;
-
-        let updated_on = Utc::now().naive_utc();
-
-        // entry to shred, 64 entry ~= 8 shred
-        let entry = &log_entry_request.entry;
-        let entries = &entry.entries;
+        let entry = log_entry_request.entry;
+        if self.config.da_payload == DataAvailabilityPayload::Blob {
+            return self.publish_blob(SlotEntries {
+                slot: entry.slot,
+                parent_slot: entry.parent_slot,
+                entries: entry.entries,
+            });
+        }
 
-        let (slot, parent_slot, is_full_slot) = (entry.slot, entry.parent_slot, entry.is_full_slot);
-        let (version, merkle_variant) = (0, true);
+        // Publish the shreds as signed by the slot leader rather than re-shredding
+        // the entries, so the replayers can verify them against the leader schedule.
+        if entry.shreds.is_empty() {
+            let msg = format!("No shreds received for the entries of slot {}", entry.slot);
+            error!("{}", msg);
+            return Err(GeyserPluginError::EntryUpdateError { msg });
+        }
 
-        let shreds = blockstore::entries_to_test_shreds(
-            &entries,
-            slot,
-            parent_slot,
-            is_full_slot,
-            version,
-            merkle_variant,
-        );
+        let slot_data = SlotData {
+            slot: entry.slot,
+            parent_slot: entry.parent_slot,
+            is_full_slot: entry.is_full_slot,
+            shreds: entry.shreds,
+        };
 
-        for (index, shred) in shreds.iter().enumerate() {
-            let result = client.execute(
-                statement,
-                &[
-                    &(slot as i64),
-                    &(parent_slot as i64),
-                    &(index as i64),
-                    &shred.payload(),
-                    &is_full_slot,
-                    &updated_on,
-                ],
+        if let Err(err) = self.da_backend()?.publish_slot(&slot_data) {
+            let msg = format!(
+                "Failed to publish entry/shred to the DA backend. Error: {:?}",
+                err
             );
-            if let Err(err) = result {
-                let msg = format!(
-                    "Failed to persist entry/shred to the PostgreSQL database. Error: {:?}",
-                    err
-                );
-                error!("{}", msg);
-                return Err(GeyserPluginError::EntryUpdateError { msg });
-            }
+            error!("{}", msg);
+            return Err(GeyserPluginError::EntryUpdateError { msg });
         }
 
         Ok(())
     }
+
+    /// Publish the entries of a slot as a compressed blob.
+    fn publish_blob(&mut self, slot_entries: SlotEntries) -> Result<(), GeyserPluginError> {
+        let slot = slot_entries.slot;
+        let blob_error = |err: DataAvailabilityError| {
+            let msg = format!(
+                "Failed to publish the blob of slot {} to the DA backend. Error: {:?}",
+                slot, err
+            );
+            error!("{}", msg);
+            GeyserPluginError::EntryUpdateError { msg }
+        };
+        let keypair = self.blob_keypair.as_ref().unwrap();
+        let blob = DaBlob::new(&[slot_entries], keypair).map_err(blob_error)?;
+        self.da_backend()?.publish_blob(&blob).map_err(blob_error)
+    }
 }
@@ -9,7 +9,7 @@ use {
     log::*,
     postgres::{Client, Statement},
     solana_geyser_plugin_interface::geyser_plugin_interface::{
-        GeyserPluginError, ReplicaBlockInfo,
+        GeyserPluginError, ReplicaBlockInfo, ReplicaBlockInfoV2, ReplicaBlockInfoV3,
     },
 };
 
@@ -20,6 +20,16 @@ pub struct DbBlockInfo {
     pub rewards: Vec<DbReward>,
     pub block_time: Option<i64>,
     pub block_height: Option<i64>,
+    /// The base58 encoded state root of the bank, not sent by V0_0_1 block info
+    pub state_root: Option<String>,
+    /// The base58 encoded transactions state root of the bank, sent from
+    /// V0_0_3 block info
+    pub transactions_state_root: Option<String>,
+    /// The base58 encoded bank hash, sent from V0_0_3 block info
+    pub bank_hash: Option<String>,
+    /// The base58 encoded signature of the slot header by the node, sent from
+    /// V0_0_3 block info
+    pub header_signature: Option<String>,
 }
 
 impl<'a> From<&ReplicaBlockInfo<'a>> for DbBlockInfo {
@@ -32,6 +42,46 @@ impl<'a> From<&ReplicaBlockInfo<'a>> for DbBlockInfo {
             block_height: block_info
                 .block_height
                 .map(|block_height| block_height as i64),
+            state_root: None,
+            transactions_state_root: None,
+            bank_hash: None,
+            header_signature: None,
+        }
+    }
+}
+
+impl<'a> From<&ReplicaBlockInfoV2<'a>> for DbBlockInfo {
+    fn from(block_info: &ReplicaBlockInfoV2) -> Self {
+        Self {
+            slot: block_info.slot as i64,
+            blockhash: block_info.blockhash.to_string(),
+            rewards: block_info.rewards.iter().map(DbReward::from).collect(),
+            block_time: block_info.block_time,
+            block_height: block_info
+                .block_height
+                .map(|block_height| block_height as i64),
+            state_root: Some(block_info.state_root.to_string()),
+            transactions_state_root: None,
+            bank_hash: None,
+            header_signature: None,
+        }
+    }
+}
+
+impl<'a> From<&ReplicaBlockInfoV3<'a>> for DbBlockInfo {
+    fn from(block_info: &ReplicaBlockInfoV3) -> Self {
+        Self {
+            slot: block_info.slot as i64,
+            blockhash: block_info.blockhash.to_string(),
+            rewards: block_info.rewards.iter().map(DbReward::from).collect(),
+            block_time: block_info.block_time,
+            block_height: block_info
+                .block_height
+                .map(|block_height| block_height as i64),
+            state_root: Some(block_info.state_root.to_string()),
+            transactions_state_root: Some(block_info.transactions_state_root.to_string()),
+            bank_hash: Some(block_info.bank_hash.to_string()),
+            header_signature: Some(block_info.header_signature.to_string()),
         }
     }
 }
@@ -42,10 +92,13 @@ impl SimplePostgresClient {
         config: &GeyserPluginPostgresConfig,
     ) -> Result<Statement, GeyserPluginError> {
         let stmt =
-            "INSERT INTO block (slot, blockhash, rewards, block_time, block_height, updated_on) \
-        VALUES ($1, $2, $3, $4, $5, $6) \
+            "INSERT INTO block (slot, blockhash, rewards, block_time, block_height, transactions_state_root, \
+        bank_hash, header_signature, updated_on) \
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
         ON CONFLICT (slot) DO UPDATE SET blockhash=excluded.blockhash, rewards=excluded.rewards, \
-        block_time=excluded.block_time, block_height=excluded.block_height, updated_on=excluded.updated_on";
+        block_time=excluded.block_time, block_height=excluded.block_height, \
+        transactions_state_root=excluded.transactions_state_root, bank_hash=excluded.bank_hash, \
+        header_signature=excluded.header_signature, updated_on=excluded.updated_on";
 
         let stmt = client.prepare(stmt);
 
@@ -80,6 +133,9 @@ impl SimplePostgresClient {
                 &block_info.rewards,
                 &block_info.block_time,
                 &block_info.block_height,
+                &block_info.transactions_state_root,
+                &block_info.bank_hash,
+                &block_info.header_signature,
                 &updated_on,
             ],
         );
@@ -92,6 +148,18 @@ impl SimplePostgresClient {
             return Err(GeyserPluginError::AccountsUpdateError { msg });
         }
 
+        if let Some(state_root) = block_info.state_root {
+            let state_root = bs58::decode(&state_root).into_vec().map_err(|err| {
+                GeyserPluginError::SMTUpdateError {
+                    msg: format!(
+                        "Invalid state root {} at slot {}: {}",
+                        state_root, block_info.slot, err
+                    ),
+                }
+            })?;
+            self.update_merkle_tree_root(block_info.slot, &state_root)?;
+        }
+
         Ok(())
     }
 }
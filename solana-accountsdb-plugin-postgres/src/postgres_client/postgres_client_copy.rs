@@ -0,0 +1,756 @@
+/// Module responsible for writing the accounts and transactions with binary
+/// `COPY` over asynchronous connections, when `copy_ingestion` is set.
+///
+/// A dispatcher thread buffers the rows per slot. The batch of a slot is handed
+/// to one of the connections once it is full, once the status of the slot is
+/// updated, or at the latest after `MAX_COPY_BATCH_DELAY`. The connection
+/// copies the batch into its temporary staging tables and merges them into the
+/// `account` and `transaction` tables in one database transaction; the staging
+/// tables are emptied on commit. At most one batch per connection waits to be
+/// written, past that the dispatcher stops receiving and the bounded channel
+/// of the plugin fills up, slowing the validator to the pace of the database.
+use {
+    super::{
+        abort, postgres_client_transaction::DbTransaction, DbAccountInfo, SimplePostgresClient,
+    },
+    crate::geyser_plugin_postgres::{GeyserPluginPostgresConfig, GeyserPluginPostgresError},
+    chrono::Utc,
+    crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender},
+    log::*,
+    solana_geyser_plugin_interface::geyser_plugin_interface::{GeyserPluginError, SlotStatus},
+    solana_measure::measure::Measure,
+    solana_metrics::*,
+    std::{
+        collections::{BTreeMap, HashSet},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+            Arc,
+        },
+        thread::{sleep, Builder, JoinHandle},
+        time::{Duration, Instant},
+    },
+    tokio::{
+        runtime,
+        sync::{mpsc, Mutex},
+    },
+    tokio_postgres::{
+        binary_copy::BinaryCopyInWriter,
+        tls::MakeTlsConnect,
+        types::{ToSql, Type},
+        Client, NoTls, Socket,
+    },
+};
+
+pub(crate) const DEFAULT_COPY_CONNECTIONS: usize = 4;
+pub(crate) const DEFAULT_COPY_BATCH_SIZE: usize = 5000;
+/// The longest rows are buffered before they are written.
+const MAX_COPY_BATCH_DELAY: Duration = Duration::from_millis(500);
+/// How often the ingestion throughput is reported.
+const COPY_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+const ACCOUNT_COLUMNS: &str = "pubkey, slot, owner, lamports, executable, rent_epoch, data, \
+    write_version, updated_on, txn_signature";
+const TRANSACTION_COLUMNS: &str = "signature, is_vote, slot, message_type, legacy_message, \
+    v0_loaded_message, signatures, message_hash, meta, write_version, index, updated_on";
+
+const CREATE_STAGING_TABLES: &str = "\
+    CREATE TEMP TABLE account_copy (LIKE account) ON COMMIT DELETE ROWS; \
+    CREATE TEMP TABLE transaction_copy (LIKE transaction) ON COMMIT DELETE ROWS;";
+
+/// Keeps the latest version of each account of the batch, as `ON CONFLICT`
+/// cannot update a row twice in one command. The accounts are merged in the
+/// order of their pubkeys so concurrent batches lock the rows in the same order.
+const MERGE_ACCOUNTS: &str = "INSERT INTO account AS acct (pubkey, slot, owner, lamports, \
+    executable, rent_epoch, data, write_version, updated_on, txn_signature) \
+    SELECT DISTINCT ON (pubkey) pubkey, slot, owner, lamports, executable, rent_epoch, data, \
+    write_version, updated_on, txn_signature FROM account_copy \
+    ORDER BY pubkey, slot DESC, write_version DESC \
+    ON CONFLICT (pubkey) DO UPDATE SET slot=excluded.slot, owner=excluded.owner, \
+    lamports=excluded.lamports, executable=excluded.executable, rent_epoch=excluded.rent_epoch, \
+    data=excluded.data, write_version=excluded.write_version, updated_on=excluded.updated_on, \
+    txn_signature=excluded.txn_signature WHERE acct.slot < excluded.slot OR (\
+    acct.slot = excluded.slot AND acct.write_version < excluded.write_version)";
+
+/// The versions which are not the current account are kept in `account_audit`,
+/// as the single account upsert does; the replaced ones are by the trigger.
+const AUDIT_SUPERSEDED_ACCOUNTS: &str = "INSERT INTO account_audit (pubkey, slot, owner, \
+    lamports, executable, rent_epoch, data, write_version, updated_on, txn_signature) \
+    SELECT c.pubkey, c.slot, c.owner, c.lamports, c.executable, c.rent_epoch, c.data, \
+    c.write_version, c.updated_on, c.txn_signature FROM account_copy c \
+    WHERE NOT EXISTS (SELECT 1 FROM account a WHERE a.pubkey = c.pubkey \
+    AND a.slot = c.slot AND a.write_version = c.write_version)";
+
+const MERGE_TRANSACTIONS: &str = "INSERT INTO transaction AS txn (signature, is_vote, slot, \
+    message_type, legacy_message, v0_loaded_message, signatures, message_hash, meta, \
+    write_version, index, updated_on) \
+    SELECT DISTINCT ON (slot, signature) signature, is_vote, slot, message_type, legacy_message, \
+    v0_loaded_message, signatures, message_hash, meta, write_version, index, updated_on \
+    FROM transaction_copy ORDER BY slot, signature, write_version DESC \
+    ON CONFLICT (slot, signature) DO UPDATE SET is_vote=excluded.is_vote, \
+    message_type=excluded.message_type, \
+    legacy_message=excluded.legacy_message, \
+    v0_loaded_message=excluded.v0_loaded_message, \
+    signatures=excluded.signatures, \
+    message_hash=excluded.message_hash, \
+    meta=excluded.meta, \
+    write_version=excluded.write_version, \
+    index=excluded.index, \
+    updated_on=excluded.updated_on";
+
+const UPSERT_SLOTS: &str = "INSERT INTO slot (slot, status, updated_on) \
+    SELECT unnest($1::BIGINT[]), $2, $3 \
+    ON CONFLICT (slot) DO UPDATE SET status=excluded.status, updated_on=excluded.updated_on";
+
+pub(crate) enum CopyWorkItem {
+    Account {
+        account: Box<DbAccountInfo>,
+        is_startup: bool,
+    },
+    Transaction(Box<DbTransaction>),
+    /// The status of the slot is updated, write its buffered rows.
+    SlotStatus(u64),
+    /// Write all the buffered rows and mark the slots of the accounts
+    /// restored at startup rooted, then acknowledge once committed.
+    EndOfStartup(Sender<()>),
+}
+
+#[derive(Default)]
+struct CopyBatch {
+    accounts: Vec<DbAccountInfo>,
+    transactions: Vec<DbTransaction>,
+    rooted_slots: Vec<i64>,
+}
+
+impl CopyBatch {
+    fn row_count(&self) -> usize {
+        self.accounts.len() + self.transactions.len() + self.rooted_slots.len()
+    }
+}
+
+#[derive(Default)]
+struct CopyStats {
+    in_flight_batches: AtomicUsize,
+    accounts: AtomicU64,
+    transactions: AtomicU64,
+}
+
+struct CopyConnection {
+    client: Client,
+    account_types: Vec<Type>,
+    transaction_types: Vec<Type>,
+    store_account_historical_data: bool,
+}
+
+fn copy_error(msg: String) -> GeyserPluginError {
+    error!("{}", msg);
+    GeyserPluginError::Custom(Box::new(
+        GeyserPluginPostgresError::DataStoreConnectionError { msg },
+    ))
+}
+
+async fn connect_with<T>(connection_str: &str, tls: T) -> Result<Client, tokio_postgres::Error>
+where
+    T: MakeTlsConnect<Socket>,
+    T::Stream: Send + 'static,
+{
+    let (client, connection) = tokio_postgres::connect(connection_str, tls).await?;
+    tokio::spawn(async move {
+        if let Err(err) = connection.await {
+            error!(
+                "The COPY connection to the PostgreSQL database failed: {}",
+                err
+            );
+        }
+    });
+    Ok(client)
+}
+
+impl CopyConnection {
+    async fn connect(config: &GeyserPluginPostgresConfig) -> Result<Self, GeyserPluginError> {
+        let connection_str = SimplePostgresClient::connection_str(config)?;
+        let client = match SimplePostgresClient::tls_connector(config)? {
+            Some(connector) => connect_with(&connection_str, connector).await,
+            None => connect_with(&connection_str, NoTls).await,
+        }
+        .map_err(|err| {
+            copy_error(format!(
+                "Error in connecting to the PostgreSQL database: {:?} connection_str: {:?}",
+                err, connection_str
+            ))
+        })?;
+
+        let (account_types, transaction_types) = async {
+            client.batch_execute(CREATE_STAGING_TABLES).await?;
+            Ok((
+                Self::column_types(&client, "account_copy", ACCOUNT_COLUMNS).await?,
+                Self::column_types(&client, "transaction_copy", TRANSACTION_COLUMNS).await?,
+            ))
+        }
+        .await
+        .map_err(|err: tokio_postgres::Error| {
+            GeyserPluginError::Custom(Box::new(GeyserPluginPostgresError::DataSchemaError {
+                msg: format!(
+                    "Error in preparing the COPY staging tables: ({}) host: {:?} user: {:?} config: {:?}",
+                    err, config.host, config.user, config
+                ),
+            }))
+        })?;
+
+        Ok(Self {
+            client,
+            account_types,
+            transaction_types,
+            store_account_historical_data: config.store_account_historical_data.unwrap_or(false),
+        })
+    }
+
+    /// The types of `columns` of `table`, which the binary `COPY` rows are
+    /// encoded with.
+    async fn column_types(
+        client: &Client,
+        table: &str,
+        columns: &str,
+    ) -> Result<Vec<Type>, tokio_postgres::Error> {
+        let statement = client
+            .prepare(&format!("SELECT {} FROM {}", columns, table))
+            .await?;
+        Ok(statement
+            .columns()
+            .iter()
+            .map(|column| column.type_().clone())
+            .collect())
+    }
+
+    async fn write_batch(&mut self, batch: &CopyBatch) -> Result<(), tokio_postgres::Error> {
+        let updated_on = Utc::now().naive_utc();
+        let transaction = self.client.transaction().await?;
+
+        if !batch.accounts.is_empty() {
+            let sink = transaction
+                .copy_in(&format!(
+                    "COPY account_copy ({}) FROM STDIN BINARY",
+                    ACCOUNT_COLUMNS
+                ))
+                .await?;
+            let writer = BinaryCopyInWriter::new(sink, &self.account_types);
+            tokio::pin!(writer);
+            for account in &batch.accounts {
+                let row: [&(dyn ToSql + Sync); 10] = [
+                    &account.pubkey,
+                    &account.slot,
+                    &account.owner,
+                    &account.lamports,
+                    &account.executable,
+                    &account.rent_epoch,
+                    &account.data,
+                    &account.write_version,
+                    &updated_on,
+                    &account.txn_signature,
+                ];
+                writer.as_mut().write(&row).await?;
+            }
+            writer.finish().await?;
+            transaction.execute(MERGE_ACCOUNTS, &[]).await?;
+            if self.store_account_historical_data {
+                transaction.execute(AUDIT_SUPERSEDED_ACCOUNTS, &[]).await?;
+            }
+        }
+
+        if !batch.transactions.is_empty() {
+            let sink = transaction
+                .copy_in(&format!(
+                    "COPY transaction_copy ({}) FROM STDIN BINARY",
+                    TRANSACTION_COLUMNS
+                ))
+                .await?;
+            let writer = BinaryCopyInWriter::new(sink, &self.transaction_types);
+            tokio::pin!(writer);
+            for transaction_info in &batch.transactions {
+                let row: [&(dyn ToSql + Sync); 12] = [
+                    &transaction_info.signature,
+                    &transaction_info.is_vote,
+                    &transaction_info.slot,
+                    &transaction_info.message_type,
+                    &transaction_info.legacy_message,
+                    &transaction_info.v0_loaded_message,
+                    &transaction_info.signatures,
+                    &transaction_info.message_hash,
+                    &transaction_info.meta,
+                    &transaction_info.write_version,
+                    &transaction_info.index,
+                    &updated_on,
+                ];
+                writer.as_mut().write(&row).await?;
+            }
+            writer.finish().await?;
+            transaction.execute(MERGE_TRANSACTIONS, &[]).await?;
+        }
+
+        if !batch.rooted_slots.is_empty() {
+            transaction
+                .execute(
+                    UPSERT_SLOTS,
+                    &[
+                        &batch.rooted_slots,
+                        &SlotStatus::Rooted.as_str(),
+                        &updated_on,
+                    ],
+                )
+                .await?;
+        }
+
+        transaction.commit().await
+    }
+
+    async fn run(
+        mut self,
+        batches: Arc<Mutex<mpsc::Receiver<CopyBatch>>>,
+        stats: Arc<CopyStats>,
+        panic_on_db_errors: bool,
+    ) {
+        loop {
+            let batch = batches.lock().await.recv().await;
+            let batch = match batch {
+                Some(batch) => batch,
+                None => break,
+            };
+
+            let mut measure = Measure::start("geyser-plugin-postgres-copy-batch");
+            let result = self.write_batch(&batch).await;
+            measure.stop();
+            match result {
+                Ok(()) => {
+                    stats
+                        .accounts
+                        .fetch_add(batch.accounts.len() as u64, Ordering::Relaxed);
+                    stats
+                        .transactions
+                        .fetch_add(batch.transactions.len() as u64, Ordering::Relaxed);
+                    inc_new_counter_debug!(
+                        "geyser-plugin-postgres-copy-batch-us",
+                        measure.as_us() as usize,
+                        10000,
+                        10000
+                    );
+                    inc_new_counter_debug!(
+                        "geyser-plugin-postgres-copy-batch-rows",
+                        batch.row_count(),
+                        10000,
+                        10000
+                    );
+                }
+                Err(err) => {
+                    error!(
+                        "Failed to write {} accounts and {} transactions with COPY to the PostgreSQL database. Error: {:?}",
+                        batch.accounts.len(),
+                        batch.transactions.len(),
+                        err
+                    );
+                    if panic_on_db_errors {
+                        abort();
+                    }
+                }
+            }
+            stats.in_flight_batches.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+struct CopyDispatcher {
+    receiver: Receiver<CopyWorkItem>,
+    batch_sender: mpsc::Sender<CopyBatch>,
+    batch_size: usize,
+    pending_batches: BTreeMap<i64, CopyBatch>,
+    slots_at_startup: HashSet<i64>,
+    last_flush: Instant,
+    stats: Arc<CopyStats>,
+}
+
+impl CopyDispatcher {
+    fn send_batch(&mut self, batch: CopyBatch) {
+        if batch.row_count() == 0 {
+            return;
+        }
+        self.stats.in_flight_batches.fetch_add(1, Ordering::Relaxed);
+        // Blocks while every connection is busy and a batch is already waiting
+        if self.batch_sender.blocking_send(batch).is_err() {
+            error!("The COPY connections are closed, dropping the batch");
+            self.stats.in_flight_batches.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    fn send_slot(&mut self, slot: i64) {
+        if let Some(batch) = self.pending_batches.remove(&slot) {
+            self.send_batch(batch);
+        }
+    }
+
+    fn send_all(&mut self) {
+        for batch in std::mem::take(&mut self.pending_batches).into_values() {
+            self.send_batch(batch);
+        }
+        self.last_flush = Instant::now();
+    }
+
+    fn send_if_full(&mut self, slot: i64) {
+        let is_full = self
+            .pending_batches
+            .get(&slot)
+            .map(|batch| batch.row_count() >= self.batch_size)
+            .unwrap_or(false);
+        if is_full {
+            self.send_slot(slot);
+        }
+    }
+
+    fn wait_for_in_flight_batches(&self) {
+        while self.stats.in_flight_batches.load(Ordering::Relaxed) > 0 {
+            sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn handle(&mut self, work: CopyWorkItem) {
+        match work {
+            CopyWorkItem::Account {
+                account,
+                is_startup,
+            } => {
+                let slot = account.slot;
+                if is_startup {
+                    self.slots_at_startup.insert(slot);
+                }
+                self.pending_batches
+                    .entry(slot)
+                    .or_default()
+                    .accounts
+                    .push(*account);
+                self.send_if_full(slot);
+            }
+            CopyWorkItem::Transaction(transaction_info) => {
+                let slot = transaction_info.slot;
+                self.pending_batches
+                    .entry(slot)
+                    .or_default()
+                    .transactions
+                    .push(*transaction_info);
+                self.send_if_full(slot);
+            }
+            CopyWorkItem::SlotStatus(slot) => self.send_slot(slot as i64),
+            CopyWorkItem::EndOfStartup(done) => {
+                self.send_all();
+                let rooted_slots = self.slots_at_startup.drain().collect();
+                self.send_batch(CopyBatch {
+                    rooted_slots,
+                    ..CopyBatch::default()
+                });
+                self.wait_for_in_flight_batches();
+                let _ = done.send(());
+            }
+        }
+    }
+
+    fn run(mut self, exit: &AtomicBool) {
+        let mut last_report = Instant::now();
+        let (mut reported_accounts, mut reported_transactions) = (0, 0);
+        while !exit.load(Ordering::Relaxed) {
+            match self.receiver.recv_timeout(MAX_COPY_BATCH_DELAY) {
+                Ok(work) => self.handle(work),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if self.last_flush.elapsed() >= MAX_COPY_BATCH_DELAY {
+                self.send_all();
+            }
+
+            let elapsed = last_report.elapsed();
+            if elapsed >= COPY_STATS_INTERVAL {
+                let accounts = self.stats.accounts.load(Ordering::Relaxed);
+                let transactions = self.stats.transactions.load(Ordering::Relaxed);
+                let accounts_per_second =
+                    (accounts - reported_accounts) as f64 / elapsed.as_secs_f64();
+                let transactions_per_second =
+                    (transactions - reported_transactions) as f64 / elapsed.as_secs_f64();
+                info!(
+                    "COPY ingestion: {:.0} accounts/s, {:.0} transactions/s, {} queued requests",
+                    accounts_per_second,
+                    transactions_per_second,
+                    self.receiver.len()
+                );
+                datapoint_info!(
+                    "geyser-plugin-postgres-copy",
+                    ("accounts-per-second", accounts_per_second as i64, i64),
+                    (
+                        "transactions-per-second",
+                        transactions_per_second as i64,
+                        i64
+                    ),
+                    ("queued-requests", self.receiver.len() as i64, i64),
+                    (
+                        "in-flight-batches",
+                        self.stats.in_flight_batches.load(Ordering::Relaxed) as i64,
+                        i64
+                    ),
+                );
+                last_report = Instant::now();
+                reported_accounts = accounts;
+                reported_transactions = transactions;
+            }
+        }
+
+        // Write what was already notified before closing the connections
+        while let Ok(work) = self.receiver.try_recv() {
+            self.handle(work);
+        }
+        self.send_all();
+    }
+}
+
+/// Writes the accounts and transactions received on its channel with `COPY`.
+pub(crate) struct CopyIngestionService {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CopyIngestionService {
+    /// Connect the `copy_connections` connections and start writing the
+    /// work items received from `receiver` until `exit` is set.
+    pub(crate) fn new(
+        config: &GeyserPluginPostgresConfig,
+        receiver: Receiver<CopyWorkItem>,
+        exit: Arc<AtomicBool>,
+    ) -> Result<Self, GeyserPluginError> {
+        let connection_count = config
+            .copy_connections
+            .unwrap_or(DEFAULT_COPY_CONNECTIONS)
+            .max(1);
+        let batch_size = config
+            .copy_batch_size
+            .unwrap_or(DEFAULT_COPY_BATCH_SIZE)
+            .max(1);
+        let panic_on_db_errors = config.panic_on_db_errors.unwrap_or(false);
+        let config = config.clone();
+
+        // The runtime is created on its own thread, as the plugin may be loaded
+        // from within another runtime.
+        let (connected_sender, connected_receiver) = bounded(1);
+        let thread = Builder::new()
+            .name("copy-dispatcher".to_string())
+            .spawn(move || {
+                let runtime = match runtime::Builder::new_multi_thread()
+                    .worker_threads(connection_count)
+                    .thread_name("copy-worker")
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(err) => {
+                        let _ = connected_sender.send(Err(copy_error(format!(
+                            "Failed to create the COPY ingestion runtime. Error: {:?}",
+                            err
+                        ))));
+                        return;
+                    }
+                };
+                let connections = runtime.block_on(async {
+                    let mut connections = Vec::with_capacity(connection_count);
+                    for _ in 0..connection_count {
+                        connections.push(CopyConnection::connect(&config).await?);
+                    }
+                    Ok(connections)
+                });
+                let connections = match connections {
+                    Ok(connections) => {
+                        let _ = connected_sender.send(Ok(()));
+                        connections
+                    }
+                    Err(err) => {
+                        let _ = connected_sender.send(Err(err));
+                        return;
+                    }
+                };
+
+                let (batch_sender, batch_receiver) = mpsc::channel(connection_count);
+                let batch_receiver = Arc::new(Mutex::new(batch_receiver));
+                let stats = Arc::new(CopyStats::default());
+                let writers: Vec<_> = connections
+                    .into_iter()
+                    .map(|connection| {
+                        runtime.spawn(connection.run(
+                            batch_receiver.clone(),
+                            stats.clone(),
+                            panic_on_db_errors,
+                        ))
+                    })
+                    .collect();
+
+                CopyDispatcher {
+                    receiver,
+                    batch_sender,
+                    batch_size,
+                    pending_batches: BTreeMap::default(),
+                    slots_at_startup: HashSet::default(),
+                    last_flush: Instant::now(),
+                    stats,
+                }
+                .run(&exit);
+
+                // The batch channel is closed, the writers stop once it is drained
+                for writer in writers {
+                    if let Err(err) = runtime.block_on(writer) {
+                        error!("The COPY writer has failed: {:?}", err);
+                    }
+                }
+            })
+            .unwrap();
+
+        match connected_receiver.recv() {
+            Ok(Ok(())) => {
+                info!(
+                    "Created the COPY ingestion with {} connections, batches of {} rows",
+                    connection_count, batch_size
+                );
+                Ok(Self {
+                    thread: Some(thread),
+                })
+            }
+            Ok(Err(err)) => {
+                let _ = thread.join();
+                Err(err)
+            }
+            Err(_) => Err(copy_error(
+                "The COPY ingestion thread has exited".to_string(),
+            )),
+        }
+    }
+
+    pub(crate) fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("The COPY ingestion thread has panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::postgres_client::postgres_client_transaction::tests::build_test_db_transaction,
+        solana_sdk::{
+            pubkey::Pubkey,
+            signature::{Keypair, Signer},
+        },
+    };
+
+    fn test_account(pubkey: &Pubkey, slot: i64, write_version: i64) -> Box<DbAccountInfo> {
+        Box::new(DbAccountInfo {
+            pubkey: pubkey.to_bytes().to_vec(),
+            lamports: slot * 100 + write_version,
+            owner: Pubkey::default().to_bytes().to_vec(),
+            executable: false,
+            rent_epoch: 0,
+            data: vec![slot as u8; 16],
+            slot,
+            write_version,
+            txn_signature: None,
+        })
+    }
+
+    #[test]
+    fn test_copy_ingestion() {
+        solana_logger::setup();
+        let config: GeyserPluginPostgresConfig = serde_json::from_str(
+            r#"{"connection_str": "host=localhost user=solana password=solana port=5432",
+                "copy_ingestion": true, "copy_connections": 2, "copy_batch_size": 3}"#,
+        )
+        .unwrap();
+        let mut client = match SimplePostgresClient::connect_to_db(&config) {
+            Ok(client) => client,
+            Err(err) => {
+                info!("Failed to connect to the PostgreSQL database. Please setup the database to run the integration tests. {:?}", err);
+                return;
+            }
+        };
+
+        let (sender, receiver) = bounded(16);
+        let exit = Arc::new(AtomicBool::new(false));
+        let mut service = CopyIngestionService::new(&config, receiver, exit.clone()).unwrap();
+
+        // Unique slots and keys, as the database outlives the test
+        let slot = (Keypair::new().pubkey().to_bytes()[0] as i64 + 1) << 40;
+        let (pubkey, other_pubkey) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+        for (slot, write_version) in [(slot, 1), (slot + 1, 3), (slot + 1, 2), (slot, 4)] {
+            sender
+                .send(CopyWorkItem::Account {
+                    account: test_account(&pubkey, slot, write_version),
+                    is_startup: true,
+                })
+                .unwrap();
+        }
+        sender
+            .send(CopyWorkItem::Account {
+                account: test_account(&other_pubkey, slot + 1, 1),
+                is_startup: false,
+            })
+            .unwrap();
+        let transaction_info = build_test_db_transaction(slot as u64 + 1, 7);
+        let signature = transaction_info.signature.clone();
+        sender
+            .send(CopyWorkItem::Transaction(Box::new(transaction_info)))
+            .unwrap();
+        sender
+            .send(CopyWorkItem::SlotStatus(slot as u64 + 1))
+            .unwrap();
+        let (done_sender, done_receiver) = bounded(1);
+        sender
+            .send(CopyWorkItem::EndOfStartup(done_sender))
+            .unwrap();
+        done_receiver.recv().unwrap();
+
+        // The latest slot and write version wins, whatever the batch
+        let row = client
+            .query_one(
+                "SELECT slot, write_version, lamports FROM account WHERE pubkey = $1",
+                &[&pubkey.to_bytes().to_vec()],
+            )
+            .unwrap();
+        assert_eq!(
+            (
+                row.get::<_, i64>(0),
+                row.get::<_, i64>(1),
+                row.get::<_, i64>(2)
+            ),
+            (slot + 1, 3, (slot + 1) * 100 + 3)
+        );
+        let row = client
+            .query_one(
+                "SELECT slot FROM account WHERE pubkey = $1",
+                &[&other_pubkey.to_bytes().to_vec()],
+            )
+            .unwrap();
+        assert_eq!(row.get::<_, i64>(0), slot + 1);
+
+        let row = client
+            .query_one(
+                "SELECT write_version FROM transaction WHERE slot = $1 AND signature = $2",
+                &[&(slot + 1), &signature],
+            )
+            .unwrap();
+        assert_eq!(row.get::<_, i64>(0), 7);
+
+        // The slots of the accounts restored at startup are marked rooted
+        let rooted_slots: Vec<i64> = client
+            .query(
+                "SELECT slot FROM slot WHERE slot IN ($1, $2) AND status = 'rooted' ORDER BY slot",
+                &[&slot, &(slot + 1)],
+            )
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(rooted_slots, vec![slot, slot + 1]);
+
+        exit.store(true, Ordering::Relaxed);
+        service.join();
+    }
+}
@@ -3,7 +3,10 @@
 use {
     crate::{
         geyser_plugin_postgres::{GeyserPluginPostgresConfig, GeyserPluginPostgresError},
-        postgres_client::{DbWorkItem, ParallelPostgresClient, SimplePostgresClient},
+        postgres_client::{
+            postgres_client_copy::CopyWorkItem, send_with_back_pressure, DbWorkItem,
+            ParallelPostgresClient, SimplePostgresClient,
+        },
     },
     chrono::Utc,
     log::*,
@@ -623,13 +626,24 @@ impl ParallelPostgresClient {
     ) -> Result<(), GeyserPluginError> {
         self.transaction_write_version
             .fetch_add(1, Ordering::Relaxed);
-        let wrk_item = DbWorkItem::LogTransaction(Box::new(Self::build_transaction_request(
+        let request = Self::build_transaction_request(
             slot,
             transaction_info,
             self.transaction_write_version.load(Ordering::Relaxed),
-        )));
+        );
 
-        if let Err(err) = self.sender.send(wrk_item) {
+        let result = match &self.copy_sender {
+            Some(copy_sender) => send_with_back_pressure(
+                copy_sender,
+                CopyWorkItem::Transaction(Box::new(request.transaction_info)),
+            )
+            .map_err(|err| format!("{:?}", err)),
+            None => {
+                send_with_back_pressure(&self.sender, DbWorkItem::LogTransaction(Box::new(request)))
+                    .map_err(|err| format!("{:?}", err))
+            }
+        };
+        if let Err(err) = result {
             return Err(GeyserPluginError::SlotStatusUpdateError {
                 msg: format!("Failed to update the transaction, error: {:?}", err),
             });
@@ -1381,6 +1395,30 @@ pub(crate) mod tests {
         check_transaction(slot, &transaction_info, &db_transaction);
     }
 
+    /// A transaction of `slot` as logged by the plugin, with a unique signature.
+    pub(crate) fn build_test_db_transaction(slot: u64, write_version: u64) -> DbTransaction {
+        let transaction = VersionedTransaction::from(build_test_transaction_legacy());
+        let signature = transaction.signatures[0];
+        let transaction = SanitizedTransaction::try_create(
+            transaction,
+            Hash::new_unique(),
+            Some(false),
+            SimpleAddressLoader::Disabled,
+            false,
+        )
+        .unwrap();
+
+        let transaction_status_meta = build_transaction_status_meta();
+        let transaction_info = ReplicaTransactionInfoV2 {
+            signature: &signature,
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &transaction_status_meta,
+            index: 0,
+        };
+        build_db_transaction(slot, &transaction_info, write_version)
+    }
+
     fn build_test_transaction_v0() -> VersionedTransaction {
         VersionedTransaction {
             signatures: vec![
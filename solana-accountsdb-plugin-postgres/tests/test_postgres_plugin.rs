@@ -21,13 +21,26 @@ use serde_json::json;
 /// PGPASSWORD=solana psql -U solana -p 5432 -h localhost -w -d solana -f scripts/drop_schema.sql
 ///
 /// Before running 'cargo test', please run 'cargo build'
+///
+/// Set POSTGRES_PLUGIN_COPY_INGESTION=1 to run it with "copy_ingestion". The
+/// number of account and transaction rows written per second is logged at the end.
+///
+/// test_postgres_plugin_ingestion_throughput replays synthetic accounts and
+/// transactions into the plugin as fast as it takes them, with and without
+/// "copy_ingestion", and logs the rows written per second of each.
 use {
     libloading::Library,
     log::*,
+    postgres::Client,
     serial_test::serial,
     solana_core::validator::ValidatorConfig,
+    solana_geyser_plugin_interface::geyser_plugin_interface::{
+        GeyserPlugin, ReplicaAccountInfoV2, ReplicaAccountInfoVersions, ReplicaTransactionInfoV2,
+        ReplicaTransactionInfoVersions, SlotStatus,
+    },
     solana_geyser_plugin_postgres::{
-        geyser_plugin_postgres::GeyserPluginPostgresConfig, postgres_client::SimplePostgresClient,
+        geyser_plugin_postgres::{GeyserPluginPostgres, GeyserPluginPostgresConfig},
+        postgres_client::SimplePostgresClient,
     },
     solana_local_cluster::{
         cluster::Cluster,
@@ -39,24 +52,41 @@ use {
         snapshot_utils,
     },
     solana_sdk::{
-        client::SyncClient, clock::Slot, commitment_config::CommitmentConfig,
-        epoch_schedule::MINIMUM_SLOTS_PER_EPOCH, hash::Hash,
+        client::SyncClient,
+        clock::Slot,
+        commitment_config::CommitmentConfig,
+        epoch_schedule::MINIMUM_SLOTS_PER_EPOCH,
+        hash::Hash,
+        message::Message,
+        pubkey::{self, Pubkey},
+        signature::Signature,
+        system_instruction, system_program,
+        transaction::{SanitizedTransaction, Transaction},
     },
     solana_streamer::socket::SocketAddrSpace,
+    solana_transaction_status::TransactionStatusMeta,
     std::{
         fs::{self, File},
         io::Read,
         io::Write,
         path::{Path, PathBuf},
         thread::sleep,
-        time::Duration,
+        time::{Duration, Instant},
     },
     tempfile::TempDir,
 };
 
+const COPY_INGESTION_ENV: &str = "POSTGRES_PLUGIN_COPY_INGESTION";
+
 const RUST_LOG_FILTER: &str =
     "info,solana_core::replay_stage=warn,solana_local_cluster=info,local_cluster=info,solana_ledger=info";
 
+const THROUGHPUT_SLOTS: Slot = 100;
+const THROUGHPUT_ACCOUNTS_PER_SLOT: usize = 1_000;
+const THROUGHPUT_TRANSACTIONS_PER_SLOT: usize = 500;
+const THROUGHPUT_ACCOUNT_DATA_SIZE: usize = 128;
+const THROUGHPUT_TIMEOUT: Duration = Duration::from_secs(1_200);
+
 fn wait_for_next_snapshot(
     cluster: &LocalCluster,
     snapshot_archives_dir: &Path,
@@ -119,7 +149,7 @@ fn generate_account_paths(num_account_paths: usize) -> (Vec<TempDir>, Vec<PathBu
     (account_storage_dirs, account_storage_paths)
 }
 
-fn generate_geyser_plugin_config() -> (TempDir, PathBuf) {
+fn generate_geyser_plugin_config(copy_ingestion: bool) -> (TempDir, PathBuf) {
     let tmp_dir = tempfile::tempdir_in(farf_dir()).unwrap();
     let mut path = tmp_dir.path().to_path_buf();
     path.push("accounts_db_plugin.json");
@@ -150,6 +180,7 @@ fn generate_geyser_plugin_config() -> (TempDir, PathBuf) {
         "threads": 20,
         "batch_size": 20,
         "panic_on_db_errors": true,
+        "copy_ingestion": copy_ingestion,
         "accounts_selector" : {
             "accounts" : ["*"]
         },
@@ -189,7 +220,8 @@ fn setup_snapshot_validator_config(
     // Create the account paths
     let (account_storage_dirs, account_storage_paths) = generate_account_paths(num_account_paths);
 
-    let (plugin_config_dir, path) = generate_geyser_plugin_config();
+    let (plugin_config_dir, path) =
+        generate_geyser_plugin_config(std::env::var(COPY_INGESTION_ENV).is_ok());
 
     let geyser_plugin_config_files = Some(vec![path]);
 
@@ -213,6 +245,17 @@ fn setup_snapshot_validator_config(
     }
 }
 
+/// The number of rows in the `account` and `transaction` tables.
+fn row_counts(client: &mut Client) -> (i64, i64) {
+    let row = client
+        .query_one(
+            "SELECT (SELECT COUNT(*) FROM account), (SELECT COUNT(*) FROM transaction)",
+            &[],
+        )
+        .unwrap();
+    (row.get::<_, i64>(0), row.get::<_, i64>(1))
+}
+
 fn test_local_cluster_start_and_exit_with_config(socket_addr_space: SocketAddrSpace) {
     const NUM_NODES: usize = 1;
     let config = ValidatorConfig {
@@ -278,11 +321,15 @@ fn test_postgres_plugin() {
     file.read_to_string(&mut contents).unwrap();
     let plugin_config: GeyserPluginPostgresConfig = serde_json::from_str(&contents).unwrap();
 
-    let result = SimplePostgresClient::connect_to_db(&plugin_config);
-    if result.is_err() {
-        info!("Failed to connecto the PostgreSQL database. Please setup the database to run the integration tests. {:?}", result.err());
-        return;
-    }
+    let mut client = match SimplePostgresClient::connect_to_db(&plugin_config) {
+        Ok(client) => client,
+        Err(err) => {
+            info!("Failed to connecto the PostgreSQL database. Please setup the database to run the integration tests. {:?}", err);
+            return;
+        }
+    };
+    let (accounts_at_start, transactions_at_start) = row_counts(&mut client);
+    let start = Instant::now();
 
     let stake = 10_000;
     let mut config = ClusterConfig {
@@ -319,4 +366,149 @@ fn test_postgres_plugin() {
     let (archive_filename, archive_snapshot_hash) =
         wait_for_next_snapshot(&cluster, snapshot_archives_dir);
     info!("Found: {:?} {:?}", archive_filename, archive_snapshot_hash);
+
+    let elapsed = start.elapsed().as_secs_f64();
+    let (accounts, transactions) = row_counts(&mut client);
+    info!(
+        "Wrote {:.0} accounts/s and {:.0} transactions/s over {:.0}s, copy_ingestion: {:?}",
+        (accounts - accounts_at_start) as f64 / elapsed,
+        (transactions - transactions_at_start) as f64 / elapsed,
+        elapsed,
+        plugin_config.copy_ingestion,
+    );
+}
+
+/// Synthetic transfers, each with a distinct fee payer and signature.
+fn generate_transactions(num_transactions: usize) -> Vec<SanitizedTransaction> {
+    (0..num_transactions)
+        .map(|_| {
+            let from = Pubkey::new_unique();
+            let instruction = system_instruction::transfer(&from, &Pubkey::new_unique(), 1);
+            let mut transaction =
+                Transaction::new_unsigned(Message::new(&[instruction], Some(&from)));
+            transaction.signatures[0] = Signature::new_unique();
+            SanitizedTransaction::from_transaction_for_tests(transaction)
+        })
+        .collect()
+}
+
+/// Notify the plugin of `THROUGHPUT_SLOTS` slots of synthetic accounts and
+/// transactions, and return how many rows of each it wrote per second.
+fn replay_synthetic_slots(plugin_config_file: &Path, client: &mut Client) -> (f64, f64) {
+    let mut plugin = GeyserPluginPostgres::new();
+    plugin
+        .on_load(plugin_config_file.to_str().unwrap())
+        .unwrap();
+    // The accounts are written as after the startup of a validator
+    plugin.notify_end_of_startup().unwrap();
+
+    // Generated ahead, so the time is spent in the plugin
+    let slots: Vec<(Slot, Vec<Pubkey>, Vec<SanitizedTransaction>)> = (1..=THROUGHPUT_SLOTS)
+        .map(|slot| {
+            // Random, unlike `Pubkey::new_unique`, so each run adds new accounts
+            let pubkeys = (0..THROUGHPUT_ACCOUNTS_PER_SLOT)
+                .map(|_| pubkey::new_rand())
+                .collect();
+            let transactions = generate_transactions(THROUGHPUT_TRANSACTIONS_PER_SLOT);
+            (slot, pubkeys, transactions)
+        })
+        .collect();
+    let owner = system_program::id();
+    let data = vec![1u8; THROUGHPUT_ACCOUNT_DATA_SIZE];
+    let transaction_status_meta = TransactionStatusMeta::default();
+    let expected_accounts = THROUGHPUT_SLOTS as i64 * THROUGHPUT_ACCOUNTS_PER_SLOT as i64;
+    let expected_transactions = THROUGHPUT_SLOTS as i64 * THROUGHPUT_TRANSACTIONS_PER_SLOT as i64;
+
+    let (accounts_at_start, transactions_at_start) = row_counts(client);
+    let start = Instant::now();
+    let mut write_version = 0;
+    for (slot, pubkeys, transactions) in &slots {
+        let txn_signature = transactions[0].signature();
+        for pubkey in pubkeys {
+            write_version += 1;
+            let account = ReplicaAccountInfoV2 {
+                pubkey: pubkey.as_ref(),
+                lamports: 1_000_000,
+                owner: owner.as_ref(),
+                executable: false,
+                rent_epoch: 0,
+                data: &data,
+                write_version,
+                txn_signature: Some(txn_signature),
+            };
+            plugin
+                .update_account(ReplicaAccountInfoVersions::V0_0_2(&account), *slot, false)
+                .unwrap();
+        }
+        for (index, transaction) in transactions.iter().enumerate() {
+            let transaction_info = ReplicaTransactionInfoV2 {
+                signature: transaction.signature(),
+                is_vote: false,
+                transaction,
+                transaction_status_meta: &transaction_status_meta,
+                index,
+            };
+            plugin
+                .notify_transaction(
+                    ReplicaTransactionInfoVersions::V0_0_2(&transaction_info),
+                    *slot,
+                )
+                .unwrap();
+        }
+        plugin
+            .update_slot_status(*slot, Some(slot - 1), SlotStatus::Processed)
+            .unwrap();
+    }
+
+    // Unloading does not wait for the queued rows, so wait for them to land
+    loop {
+        let (accounts, transactions) = row_counts(client);
+        if accounts - accounts_at_start >= expected_accounts
+            && transactions - transactions_at_start >= expected_transactions
+        {
+            break;
+        }
+        assert!(
+            start.elapsed() < THROUGHPUT_TIMEOUT,
+            "Only {} of {} accounts and {} of {} transactions were written",
+            accounts - accounts_at_start,
+            expected_accounts,
+            transactions - transactions_at_start,
+            expected_transactions,
+        );
+        sleep(Duration::from_millis(100));
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    plugin.on_unload();
+
+    (
+        expected_accounts as f64 / elapsed,
+        expected_transactions as f64 / elapsed,
+    )
+}
+
+#[test]
+#[serial]
+fn test_postgres_plugin_ingestion_throughput() {
+    solana_logger::setup_with_default(RUST_LOG_FILTER);
+
+    for copy_ingestion in [false, true] {
+        let (_plugin_config_dir, path) = generate_geyser_plugin_config(copy_ingestion);
+        let plugin_config: GeyserPluginPostgresConfig =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let mut client = match SimplePostgresClient::connect_to_db(&plugin_config) {
+            Ok(client) => client,
+            Err(err) => {
+                info!("Failed to connect to the PostgreSQL database. Please setup the database to run the integration tests. {:?}", err);
+                return;
+            }
+        };
+
+        let (accounts_per_second, transactions_per_second) =
+            replay_synthetic_slots(&path, &mut client);
+        info!(
+            "Wrote {:.0} accounts/s and {:.0} transactions/s of {} slots, copy_ingestion: {:?}",
+            accounts_per_second, transactions_per_second, THROUGHPUT_SLOTS, copy_ingestion,
+        );
+    }
 }
@@ -0,0 +1,877 @@
+#![allow(clippy::integer_arithmetic)]
+//! Long-term ledger storage backed by the PostgreSQL database of the geyser
+//! plugin, an alternative to `solana-storage-bigtable` for the blocks and
+//! transactions the blockstore no longer has.
+//!
+//! It reads the `block`, `slot` and `transaction` tables the plugin fills and
+//! only serves the slots the plugin marked as rooted. The plugin stores less
+//! than BigTable does, so the token balances of transactions are missing and
+//! instruction errors other than custom program errors come back as
+//! `GenericError`, see `types`.
+//!
+//! The past versions of accounts are read from the `account` table and the
+//! `account_audit` table its trigger fills.
+use {
+    crate::types::{
+        build_transaction_with_status_meta, pubkey_from_bytes, signature_from_bytes, DbReward,
+        DbTransactionError, DbTransactionStatusMeta,
+    },
+    log::*,
+    solana_sdk::{
+        account::Account,
+        clock::{Slot, UnixTimestamp},
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::TransactionError,
+    },
+    solana_transaction_status::{
+        extract_and_fmt_memos, ConfirmedBlock, ConfirmedTransactionStatusWithSignature,
+        ConfirmedTransactionWithStatusMeta, Reward, TransactionConfirmationStatus,
+        TransactionStatus, TransactionWithStatusMeta, VersionedTransactionWithStatusMeta,
+    },
+    std::sync::{Arc, RwLock},
+    thiserror::Error,
+    tokio_postgres::{Client, Config, NoTls, Row},
+};
+
+#[macro_use]
+extern crate solana_metrics;
+
+mod types;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("PostgreSQL: {0}")]
+    PostgresError(#[from] tokio_postgres::Error),
+
+    #[error("Block not found: {0}")]
+    BlockNotFound(Slot),
+
+    #[error("Signature not found")]
+    SignatureNotFound,
+
+    #[error("Object is corrupt: {0}")]
+    ObjectCorrupt(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The accounts a transaction loads, matching the `transaction_account_keys`
+/// index.
+const TRANSACTION_ACCOUNT_KEYS: &str = "((t.legacy_message).account_keys \
+    || ((t.v0_loaded_message).message).account_keys \
+    || ((t.v0_loaded_message).loaded_addresses).writable \
+    || ((t.v0_loaded_message).loaded_addresses).readonly)";
+
+/// The columns `transaction_from_row` reads.
+const TRANSACTION_COLUMNS: &str =
+    "t.slot, t.index, t.legacy_message, t.v0_loaded_message, t.signatures, t.meta";
+
+fn transaction_from_row(row: &Row) -> Result<VersionedTransactionWithStatusMeta> {
+    build_transaction_with_status_meta(
+        row.try_get::<_, Option<_>>("legacy_message")?.as_ref(),
+        row.try_get::<_, Option<_>>("v0_loaded_message")?.as_ref(),
+        &row.try_get::<_, Vec<Vec<u8>>>("signatures")?,
+        &row.try_get::<_, DbTransactionStatusMeta>("meta")?,
+    )
+}
+
+/// The account columns shared by the `account` and `account_audit` tables
+const ACCOUNT_COLUMNS: &str =
+    "owner, lamports, slot, executable, rent_epoch, data, write_version, txn_signature";
+
+/// The versions of the account `$1` in rooted slots, from the `account` table
+/// and the `account_audit` table its trigger fills on update.
+fn rooted_account_versions() -> String {
+    format!(
+        "SELECT a.* FROM (SELECT {0} FROM account WHERE pubkey = $1 \
+        UNION ALL SELECT {0} FROM account_audit WHERE pubkey = $1) a \
+        JOIN slot s ON s.slot = a.slot WHERE s.status = 'rooted'",
+        ACCOUNT_COLUMNS
+    )
+}
+
+/// A version of an account stored by the plugin
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountVersion {
+    pub account: Account,
+    pub slot: Slot,
+    pub write_version: u64,
+    /// The transaction which wrote this version, if any
+    pub txn_signature: Option<Signature>,
+}
+
+fn account_version_from_row(row: &Row) -> Result<AccountVersion> {
+    let owner = match row.try_get::<_, Option<Vec<u8>>>("owner")? {
+        Some(owner) => pubkey_from_bytes(&owner)?,
+        None => Pubkey::default(),
+    };
+    Ok(AccountVersion {
+        account: Account {
+            lamports: row.try_get::<_, i64>("lamports")? as u64,
+            data: row
+                .try_get::<_, Option<Vec<u8>>>("data")?
+                .unwrap_or_default(),
+            owner,
+            executable: row.try_get("executable")?,
+            rent_epoch: row.try_get::<_, i64>("rent_epoch")? as u64,
+        },
+        slot: row.try_get::<_, i64>("slot")? as Slot,
+        write_version: row.try_get::<_, i64>("write_version")? as u64,
+        txn_signature: row
+            .try_get::<_, Option<Vec<u8>>>("txn_signature")?
+            .as_deref()
+            .map(signature_from_bytes)
+            .transpose()?,
+    })
+}
+
+async fn connect(config: &Config) -> Result<Client> {
+    let (client, connection) = config.connect(NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(err) = connection.await {
+            error!("PostgreSQL ledger storage connection error: {}", err);
+        }
+    });
+    Ok(client)
+}
+
+#[derive(Clone)]
+pub struct LedgerStorage {
+    config: Arc<Config>,
+    client: Arc<RwLock<Arc<Client>>>,
+}
+
+impl LedgerStorage {
+    /// Connect to the database of the plugin, `connection_str` being e.g.
+    /// "host=localhost user=solana password=solana port=5432". Must be called
+    /// from the runtime the queries are made on, which drives the connection.
+    pub async fn new(connection_str: &str) -> Result<Self> {
+        let config: Config = connection_str.parse()?;
+        let client = connect(&config).await?;
+        Ok(Self {
+            config: Arc::new(config),
+            client: Arc::new(RwLock::new(Arc::new(client))),
+        })
+    }
+
+    /// The client, reconnected if the connection was lost.
+    async fn client(&self) -> Result<Arc<Client>> {
+        let client = self.client.read().unwrap().clone();
+        if !client.is_closed() {
+            return Ok(client);
+        }
+        warn!("Reconnecting to the PostgreSQL ledger storage");
+        let client = Arc::new(connect(&self.config).await?);
+        *self.client.write().unwrap() = client.clone();
+        Ok(client)
+    }
+
+    /// Return the available slot that contains a block
+    pub async fn get_first_available_block(&self) -> Result<Option<Slot>> {
+        debug!("LedgerStorage::get_first_available_block request received");
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        let row = self
+            .client()
+            .await?
+            .query_one(
+                "SELECT MIN(b.slot) FROM block b JOIN slot s ON s.slot = b.slot \
+                WHERE s.status = 'rooted'",
+                &[],
+            )
+            .await?;
+        Ok(row.get::<_, Option<i64>>(0).map(|slot| slot as Slot))
+    }
+
+    /// Fetch the next slots after the provided slot that contains a block
+    ///
+    /// start_slot: slot to start the search from (inclusive)
+    /// limit: stop after this many slots have been found
+    pub async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
+        debug!(
+            "LedgerStorage::get_confirmed_blocks request received: {:?} {:?}",
+            start_slot, limit
+        );
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        let rows = self
+            .client()
+            .await?
+            .query(
+                "SELECT b.slot FROM block b JOIN slot s ON s.slot = b.slot \
+                WHERE s.status = 'rooted' AND b.slot >= $1 ORDER BY b.slot LIMIT $2",
+                &[&(start_slot as i64), &(limit as i64)],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| row.get::<_, i64>(0) as Slot)
+            .collect())
+    }
+
+    /// Fetch the confirmed block from the desired slot, with the transactions
+    /// the plugin was configured to store. The previous blockhash is the
+    /// default hash if the parent block is not in the database.
+    pub async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
+        debug!(
+            "LedgerStorage::get_confirmed_block request received: {:?}",
+            slot
+        );
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        let client = self.client().await?;
+        let block = client
+            .query_opt(
+                "SELECT b.blockhash, b.rewards, b.block_time, b.block_height, s.parent, \
+                p.blockhash AS previous_blockhash \
+                FROM block b JOIN slot s ON s.slot = b.slot LEFT JOIN block p ON p.slot = s.parent \
+                WHERE b.slot = $1 AND s.status = 'rooted'",
+                &[&(slot as i64)],
+            )
+            .await?
+            .ok_or(Error::BlockNotFound(slot))?;
+        let transactions = client
+            .query(
+                &format!(
+                    "SELECT {} FROM transaction t WHERE t.slot = $1 ORDER BY t.index",
+                    TRANSACTION_COLUMNS
+                ),
+                &[&(slot as i64)],
+            )
+            .await?
+            .iter()
+            .map(|row| transaction_from_row(row).map(TransactionWithStatusMeta::Complete))
+            .collect::<Result<_>>()?;
+
+        Ok(ConfirmedBlock {
+            previous_blockhash: block
+                .get::<_, Option<String>>("previous_blockhash")
+                .unwrap_or_else(|| Hash::default().to_string()),
+            blockhash: block
+                .get::<_, Option<String>>("blockhash")
+                .unwrap_or_default(),
+            parent_slot: block.get::<_, Option<i64>>("parent").unwrap_or_default() as Slot,
+            transactions,
+            rewards: block
+                .get::<_, Option<Vec<DbReward>>>("rewards")
+                .unwrap_or_default()
+                .iter()
+                .map(Reward::from)
+                .collect(),
+            block_time: block.get::<_, Option<i64>>("block_time"),
+            block_height: block
+                .get::<_, Option<i64>>("block_height")
+                .map(|block_height| block_height as u64),
+        })
+    }
+
+    pub async fn get_signature_status(&self, signature: &Signature) -> Result<TransactionStatus> {
+        debug!(
+            "LedgerStorage::get_signature_status request received: {:?}",
+            signature
+        );
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                "SELECT t.slot, (t.meta).error FROM transaction t JOIN slot s ON s.slot = t.slot \
+                WHERE t.signature = $1 AND s.status = 'rooted'",
+                &[&signature.as_ref()],
+            )
+            .await?
+            .ok_or(Error::SignatureNotFound)?;
+        let err = row
+            .get::<_, Option<DbTransactionError>>(1)
+            .as_ref()
+            .map(TransactionError::from);
+        Ok(TransactionStatus {
+            slot: row.get::<_, i64>(0) as Slot,
+            confirmations: None,
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+        })
+    }
+
+    /// Fetch a confirmed transaction
+    pub async fn get_confirmed_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>> {
+        debug!(
+            "LedgerStorage::get_confirmed_transaction request received: {:?}",
+            signature
+        );
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                &format!(
+                    "SELECT {}, b.block_time FROM transaction t JOIN slot s ON s.slot = t.slot \
+                    LEFT JOIN block b ON b.slot = t.slot \
+                    WHERE t.signature = $1 AND s.status = 'rooted'",
+                    TRANSACTION_COLUMNS
+                ),
+                &[&signature.as_ref()],
+            )
+            .await?
+            .ok_or(Error::SignatureNotFound)?;
+        Ok(Some(ConfirmedTransactionWithStatusMeta {
+            slot: row.get::<_, i64>("slot") as Slot,
+            tx_with_meta: TransactionWithStatusMeta::Complete(transaction_from_row(&row)?),
+            block_time: row.get::<_, Option<UnixTimestamp>>("block_time"),
+        }))
+    }
+
+    /// The slot and index in the block of a rooted transaction
+    async fn get_transaction_position(
+        &self,
+        client: &Client,
+        signature: &Signature,
+    ) -> Result<(i64, i64)> {
+        let row = client
+            .query_opt(
+                "SELECT t.slot, t.index FROM transaction t JOIN slot s ON s.slot = t.slot \
+                WHERE t.signature = $1 AND s.status = 'rooted'",
+                &[&signature.as_ref()],
+            )
+            .await?
+            .ok_or(Error::SignatureNotFound)?;
+        Ok((row.get(0), row.get(1)))
+    }
+
+    /// Get confirmed signatures for the provided address, in descending ledger order
+    ///
+    /// address: address to search for
+    /// before_signature: start with the first signature older than this one
+    /// until_signature: end with the last signature more recent than this one
+    /// limit: stop after this many signatures; if limit==0, all records in the table will be read
+    pub async fn get_confirmed_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before_signature: Option<&Signature>,
+        until_signature: Option<&Signature>,
+        limit: usize,
+    ) -> Result<
+        Vec<(
+            ConfirmedTransactionStatusWithSignature,
+            u32, /*slot index*/
+        )>,
+    > {
+        debug!(
+            "LedgerStorage::get_confirmed_signatures_for_address request received: {:?}",
+            address
+        );
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        let client = self.client().await?;
+        let before = match before_signature {
+            None => (i64::MAX, 0),
+            Some(signature) => self.get_transaction_position(&client, signature).await?,
+        };
+        let until = match until_signature {
+            None => (-1, 0),
+            Some(signature) => self.get_transaction_position(&client, signature).await?,
+        };
+        let limit = if limit == 0 { None } else { Some(limit as i64) };
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {}, t.signature, b.block_time FROM transaction t \
+                    JOIN slot s ON s.slot = t.slot LEFT JOIN block b ON b.slot = t.slot \
+                    WHERE s.status = 'rooted' AND {} @> ARRAY[$1::BYTEA] \
+                    AND (t.slot, t.index) < ($2, $3) AND (t.slot, t.index) > ($4, $5) \
+                    ORDER BY t.slot DESC, t.index DESC LIMIT $6",
+                    TRANSACTION_COLUMNS, TRANSACTION_ACCOUNT_KEYS
+                ),
+                &[
+                    &address.as_ref(),
+                    &before.0,
+                    &before.1,
+                    &until.0,
+                    &until.1,
+                    &limit,
+                ],
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                let tx_with_meta = transaction_from_row(row)?;
+                Ok((
+                    ConfirmedTransactionStatusWithSignature {
+                        signature: signature_from_bytes(row.get("signature"))?,
+                        slot: row.get::<_, i64>("slot") as Slot,
+                        err: tx_with_meta.meta.status.clone().err(),
+                        memo: extract_and_fmt_memos(&tx_with_meta),
+                        block_time: row.get::<_, Option<UnixTimestamp>>("block_time"),
+                    },
+                    row.get::<_, i64>("index") as u32,
+                ))
+            })
+            .collect()
+    }
+
+    /// Fetch the last version of the account written in or before `slot`
+    pub async fn get_account_at_slot(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+    ) -> Result<Option<AccountVersion>> {
+        debug!(
+            "LedgerStorage::get_account_at_slot request received: {:?} {:?}",
+            pubkey, slot
+        );
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        self.client()
+            .await?
+            .query_opt(
+                &format!(
+                    "{} AND a.slot <= $2 ORDER BY a.slot DESC, a.write_version DESC LIMIT 1",
+                    rooted_account_versions()
+                ),
+                &[&pubkey.as_ref(), &(slot as i64)],
+            )
+            .await?
+            .as_ref()
+            .map(account_version_from_row)
+            .transpose()
+    }
+
+    /// Get the versions of the account, most recent first
+    ///
+    /// before: start with the versions written before this slot
+    /// until: end with the versions written after this slot
+    /// limit: stop after this many versions
+    pub async fn get_account_history(
+        &self,
+        pubkey: &Pubkey,
+        before: Option<Slot>,
+        until: Option<Slot>,
+        limit: usize,
+    ) -> Result<Vec<AccountVersion>> {
+        debug!(
+            "LedgerStorage::get_account_history request received: {:?}",
+            pubkey
+        );
+        inc_new_counter_debug!("storage-postgres-query", 1);
+        let before = before.map_or(i64::MAX, |slot| slot as i64);
+        let until = until.map_or(-1, |slot| slot as i64);
+        self.client()
+            .await?
+            .query(
+                &format!(
+                    "{} AND a.slot < $2 AND a.slot > $3 \
+                    ORDER BY a.slot DESC, a.write_version DESC LIMIT $4",
+                    rooted_account_versions()
+                ),
+                &[&pubkey.as_ref(), &before, &until, &(limit as i64)],
+            )
+            .await?
+            .iter()
+            .map(account_version_from_row)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::types::{
+            DbCompiledInstruction, DbTransactionErrorCode, DbTransactionMessage,
+            DbTransactionMessageHeader,
+        },
+        solana_sdk::{
+            instruction::InstructionError, message::Message, signature::Keypair,
+            system_transaction, transaction::Transaction,
+        },
+        std::time::{SystemTime, UNIX_EPOCH},
+    };
+
+    const CONNECTION_STR: &str = "host=localhost user=solana password=solana port=5432";
+
+    fn db_message(message: &Message) -> DbTransactionMessage {
+        DbTransactionMessage {
+            header: DbTransactionMessageHeader {
+                num_required_signatures: message.header.num_required_signatures as i16,
+                num_readonly_signed_accounts: message.header.num_readonly_signed_accounts as i16,
+                num_readonly_unsigned_accounts: message.header.num_readonly_unsigned_accounts
+                    as i16,
+            },
+            account_keys: message
+                .account_keys
+                .iter()
+                .map(|key| key.as_ref().to_vec())
+                .collect(),
+            recent_blockhash: message.recent_blockhash.as_ref().to_vec(),
+            instructions: message
+                .instructions
+                .iter()
+                .map(|instruction| DbCompiledInstruction {
+                    program_id_index: instruction.program_id_index as i16,
+                    accounts: instruction
+                        .accounts
+                        .iter()
+                        .map(|account_idx| *account_idx as i16)
+                        .collect(),
+                    data: instruction.data.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Store `transaction` the way the plugin does
+    async fn insert_transaction(
+        client: &Client,
+        slot: i64,
+        index: i64,
+        transaction: &Transaction,
+        error: Option<DbTransactionError>,
+    ) {
+        let meta = DbTransactionStatusMeta {
+            error,
+            fee: 5000,
+            pre_balances: vec![10, 0, 1],
+            post_balances: vec![4, 1, 1],
+            inner_instructions: None,
+            log_messages: Some(vec![]),
+            pre_token_balances: None,
+            post_token_balances: None,
+            rewards: None,
+        };
+        let signatures: Vec<_> = transaction
+            .signatures
+            .iter()
+            .map(|signature| signature.as_ref().to_vec())
+            .collect();
+        client
+            .execute(
+                "INSERT INTO transaction (slot, signature, is_vote, message_type, legacy_message, \
+                signatures, message_hash, meta, write_version, updated_on, index) \
+                VALUES ($1, $2, false, 0, $3, $4, $5, $6, 0, now(), $7)",
+                &[
+                    &slot,
+                    &signatures[0],
+                    &db_message(&transaction.message),
+                    &signatures,
+                    &transaction.message.hash().as_ref(),
+                    &meta,
+                    &index,
+                ],
+            )
+            .await
+            .unwrap();
+    }
+
+    /// Connect to the test database and create the plugin schema in a separate
+    /// PostgreSQL schema, as the database outlives the test. `None` if there is
+    /// no database to test against.
+    async fn create_test_schema() -> Option<(Client, String)> {
+        let (client, connection) = match tokio_postgres::connect(CONNECTION_STR, NoTls).await {
+            Ok(client) => client,
+            Err(err) => {
+                info!("Failed to connect to the PostgreSQL database. Please setup the database to run the integration tests. {:?}", err);
+                return None;
+            }
+        };
+        tokio::spawn(connection);
+        let schema = format!(
+            "test_ledger_storage_{}_{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
+        client
+            .batch_execute(&format!(
+                "CREATE SCHEMA {0}; SET search_path TO {0}",
+                schema
+            ))
+            .await
+            .unwrap();
+        client
+            .batch_execute(include_str!(
+                "../../solana-accountsdb-plugin-postgres/scripts/create_schema.sql"
+            ))
+            .await
+            .unwrap();
+        Some((client, schema))
+    }
+
+    async fn connect_test_storage(schema: &str) -> LedgerStorage {
+        LedgerStorage::new(&format!(
+            "{} options='-c search_path={}'",
+            CONNECTION_STR, schema
+        ))
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ledger_storage() {
+        let (client, schema) = match create_test_schema().await {
+            Some(test_schema) => test_schema,
+            None => return,
+        };
+
+        // Slots 1 and 2 are rooted, 3 is not
+        client
+            .batch_execute(
+                "INSERT INTO slot VALUES (1, 0, 'rooted', now()), (2, 1, 'rooted', now()), \
+                (3, 2, 'processed', now()); \
+                INSERT INTO block (slot, blockhash, block_time, block_height, updated_on) VALUES \
+                (1, 'blockhash1', 100, 1, now()), (2, 'blockhash2', 101, 2, now()), \
+                (3, 'blockhash3', 102, 3, now())",
+            )
+            .await
+            .unwrap();
+        let keypair = Keypair::new();
+        let address = Pubkey::new_unique();
+        let transfer =
+            |lamports| system_transaction::transfer(&keypair, &address, lamports, Hash::default());
+        let (tx_a, tx_b, tx_d) = (transfer(1), transfer(2), transfer(4));
+        let tx_c =
+            system_transaction::transfer(&keypair, &Pubkey::new_unique(), 3, Hash::default());
+        let instruction_error = DbTransactionError {
+            error_code: DbTransactionErrorCode::InstructionError,
+            error_detail: Some(format!(
+                "InstructionError: idx ({}), error: ({})",
+                0,
+                InstructionError::Custom(1)
+            )),
+        };
+        insert_transaction(&client, 1, 0, &tx_a, None).await;
+        insert_transaction(&client, 2, 0, &tx_b, Some(instruction_error)).await;
+        insert_transaction(&client, 2, 1, &tx_c, None).await;
+        insert_transaction(&client, 3, 0, &tx_d, None).await;
+
+        let storage = connect_test_storage(&schema).await;
+
+        assert_eq!(storage.get_first_available_block().await.unwrap(), Some(1));
+        assert_eq!(
+            storage.get_confirmed_blocks(0, 10).await.unwrap(),
+            vec![1, 2]
+        );
+        assert_eq!(storage.get_confirmed_blocks(2, 10).await.unwrap(), vec![2]);
+        assert_eq!(storage.get_confirmed_blocks(0, 1).await.unwrap(), vec![1]);
+
+        let block = storage.get_confirmed_block(2).await.unwrap();
+        assert_eq!(block.blockhash, "blockhash2");
+        assert_eq!(block.previous_blockhash, "blockhash1");
+        assert_eq!(block.parent_slot, 1);
+        assert_eq!(block.block_time, Some(101));
+        assert_eq!(block.block_height, Some(2));
+        let signatures: Vec<_> = block
+            .transactions
+            .iter()
+            .map(|tx_with_meta| *tx_with_meta.transaction_signature())
+            .collect();
+        assert_eq!(signatures, vec![tx_b.signatures[0], tx_c.signatures[0]]);
+        assert!(matches!(
+            storage.get_confirmed_block(3).await,
+            Err(Error::BlockNotFound(3))
+        ));
+
+        let transaction = storage
+            .get_confirmed_transaction(&tx_a.signatures[0])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(transaction.slot, 1);
+        assert_eq!(transaction.block_time, Some(100));
+        match transaction.tx_with_meta {
+            TransactionWithStatusMeta::Complete(tx_with_meta) => {
+                assert_eq!(tx_with_meta.transaction, tx_a.clone().into());
+                assert_eq!(tx_with_meta.meta.fee, 5000);
+            }
+            TransactionWithStatusMeta::MissingMetadata(_) => panic!("missing metadata"),
+        }
+        assert!(matches!(
+            storage.get_confirmed_transaction(&tx_d.signatures[0]).await,
+            Err(Error::SignatureNotFound)
+        ));
+
+        let status = storage
+            .get_signature_status(&tx_b.signatures[0])
+            .await
+            .unwrap();
+        assert_eq!(status.slot, 2);
+        assert_eq!(
+            status.err,
+            Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1)
+            ))
+        );
+
+        let signatures_for_address = |before, until, limit| {
+            let storage = storage.clone();
+            async move {
+                storage
+                    .get_confirmed_signatures_for_address(&address, before, until, limit)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|(status, index)| (status.signature, status.slot, index))
+                    .collect::<Vec<_>>()
+            }
+        };
+        let (sig_a, sig_b) = (tx_a.signatures[0], tx_b.signatures[0]);
+        assert_eq!(
+            signatures_for_address(None, None, 0).await,
+            vec![(sig_b, 2, 0), (sig_a, 1, 0)]
+        );
+        assert_eq!(
+            signatures_for_address(None, None, 1).await,
+            vec![(sig_b, 2, 0)]
+        );
+        assert_eq!(
+            signatures_for_address(Some(&sig_b), None, 0).await,
+            vec![(sig_a, 1, 0)]
+        );
+        assert_eq!(
+            signatures_for_address(None, Some(&sig_a), 0).await,
+            vec![(sig_b, 2, 0)]
+        );
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {} CASCADE", schema))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_account_history() {
+        let (client, schema) = match create_test_schema().await {
+            Some(test_schema) => test_schema,
+            None => return,
+        };
+
+        // Slots 1 to 3 are rooted, 4 is not
+        client
+            .batch_execute(
+                "INSERT INTO slot VALUES (1, 0, 'rooted', now()), (2, 1, 'rooted', now()), \
+                (3, 2, 'rooted', now()), (4, 3, 'processed', now())",
+            )
+            .await
+            .unwrap();
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let txn_signature = Signature::new(&[7; 64]);
+        // Updates fill account_audit through the trigger, as the plugin's upserts do
+        let upsert = "INSERT INTO account AS acct (pubkey, owner, lamports, slot, executable, \
+            rent_epoch, data, write_version, updated_on, txn_signature) \
+            VALUES ($1, $2, $3, $4, false, 0, $5, $6, now(), $7) \
+            ON CONFLICT (pubkey) DO UPDATE SET slot=excluded.slot, lamports=excluded.lamports, \
+            data=excluded.data, write_version=excluded.write_version, \
+            txn_signature=excluded.txn_signature";
+        for (lamports, slot, write_version, signature) in [
+            (10i64, 1i64, 1i64, None),
+            (20, 1, 2, Some(txn_signature.as_ref())),
+            (30, 3, 3, None),
+            (40, 4, 4, None),
+        ] {
+            client
+                .execute(
+                    upsert,
+                    &[
+                        &pubkey.as_ref(),
+                        &owner.as_ref(),
+                        &lamports,
+                        &slot,
+                        &vec![lamports as u8],
+                        &write_version,
+                        &signature,
+                    ],
+                )
+                .await
+                .unwrap();
+        }
+
+        let storage = connect_test_storage(&schema).await;
+        let lamports = |versions: Vec<AccountVersion>| {
+            versions
+                .iter()
+                .map(|version| version.account.lamports)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(storage.get_account_at_slot(&pubkey, 0).await.unwrap(), None);
+        let version = storage
+            .get_account_at_slot(&pubkey, 2)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            version,
+            AccountVersion {
+                account: Account {
+                    lamports: 20,
+                    data: vec![20],
+                    owner,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+                slot: 1,
+                write_version: 2,
+                txn_signature: Some(txn_signature),
+            }
+        );
+        // The version of the unrooted slot 4 is not served
+        let version = storage
+            .get_account_at_slot(&pubkey, 10)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(version.account.lamports, 30);
+
+        assert_eq!(
+            lamports(
+                storage
+                    .get_account_history(&pubkey, None, None, 10)
+                    .await
+                    .unwrap()
+            ),
+            vec![30, 20, 10]
+        );
+        assert_eq!(
+            lamports(
+                storage
+                    .get_account_history(&pubkey, None, None, 1)
+                    .await
+                    .unwrap()
+            ),
+            vec![30]
+        );
+        assert_eq!(
+            lamports(
+                storage
+                    .get_account_history(&pubkey, Some(3), None, 10)
+                    .await
+                    .unwrap()
+            ),
+            vec![20, 10]
+        );
+        assert_eq!(
+            lamports(
+                storage
+                    .get_account_history(&pubkey, None, Some(1), 10)
+                    .await
+                    .unwrap()
+            ),
+            vec![30]
+        );
+        assert!(storage
+            .get_account_history(&Pubkey::new_unique(), None, None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {} CASCADE", schema))
+            .await
+            .unwrap();
+    }
+}
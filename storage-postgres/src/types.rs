@@ -0,0 +1,589 @@
+//! The composite types the PostgreSQL geyser plugin stores transactions and
+//! blocks with, as created by its `scripts/create_schema.sql`, and their
+//! conversion back to the ledger types.
+use {
+    crate::{Error, Result},
+    postgres_types::{FromSql, ToSql},
+    solana_sdk::{
+        hash::Hash,
+        instruction::{CompiledInstruction, InstructionError},
+        message::{
+            v0::{self, LoadedAddresses, MessageAddressTableLookup},
+            Message, MessageHeader, VersionedMessage,
+        },
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::{TransactionError, VersionedTransaction},
+    },
+    solana_transaction_status::{
+        InnerInstructions, Reward, RewardType, TransactionStatusMeta,
+        VersionedTransactionWithStatusMeta,
+    },
+};
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "CompiledInstruction")]
+pub struct DbCompiledInstruction {
+    pub program_id_index: i16,
+    pub accounts: Vec<i16>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "InnerInstructions")]
+pub struct DbInnerInstructions {
+    pub index: i16,
+    pub instructions: Vec<DbCompiledInstruction>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "TransactionTokenBalance")]
+pub struct DbTransactionTokenBalance {
+    pub account_index: i16,
+    pub mint: String,
+    pub ui_token_amount: Option<f64>,
+    pub owner: String,
+}
+
+#[derive(Clone, Debug, Eq, FromSql, ToSql, PartialEq)]
+#[postgres(name = "RewardType")]
+pub enum DbRewardType {
+    Fee,
+    Rent,
+    Staking,
+    Voting,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "Reward")]
+pub struct DbReward {
+    pub pubkey: String,
+    pub lamports: i64,
+    pub post_balance: i64,
+    pub reward_type: Option<DbRewardType>,
+    pub commission: Option<i16>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "TransactionStatusMeta")]
+pub struct DbTransactionStatusMeta {
+    pub error: Option<DbTransactionError>,
+    pub fee: i64,
+    pub pre_balances: Vec<i64>,
+    pub post_balances: Vec<i64>,
+    pub inner_instructions: Option<Vec<DbInnerInstructions>>,
+    pub log_messages: Option<Vec<String>>,
+    pub pre_token_balances: Option<Vec<DbTransactionTokenBalance>>,
+    pub post_token_balances: Option<Vec<DbTransactionTokenBalance>>,
+    pub rewards: Option<Vec<DbReward>>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "TransactionMessageHeader")]
+pub struct DbTransactionMessageHeader {
+    pub num_required_signatures: i16,
+    pub num_readonly_signed_accounts: i16,
+    pub num_readonly_unsigned_accounts: i16,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "TransactionMessage")]
+pub struct DbTransactionMessage {
+    pub header: DbTransactionMessageHeader,
+    pub account_keys: Vec<Vec<u8>>,
+    pub recent_blockhash: Vec<u8>,
+    pub instructions: Vec<DbCompiledInstruction>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "TransactionMessageAddressTableLookup")]
+pub struct DbTransactionMessageAddressTableLookup {
+    pub account_key: Vec<u8>,
+    pub writable_indexes: Vec<i16>,
+    pub readonly_indexes: Vec<i16>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "TransactionMessageV0")]
+pub struct DbTransactionMessageV0 {
+    pub header: DbTransactionMessageHeader,
+    pub account_keys: Vec<Vec<u8>>,
+    pub recent_blockhash: Vec<u8>,
+    pub instructions: Vec<DbCompiledInstruction>,
+    pub address_table_lookups: Vec<DbTransactionMessageAddressTableLookup>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "LoadedAddresses")]
+pub struct DbLoadedAddresses {
+    pub writable: Vec<Vec<u8>>,
+    pub readonly: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, FromSql, ToSql)]
+#[postgres(name = "LoadedMessageV0")]
+pub struct DbLoadedMessageV0 {
+    pub message: DbTransactionMessageV0,
+    pub loaded_addresses: DbLoadedAddresses,
+}
+
+#[derive(Clone, Debug, Eq, FromSql, ToSql, PartialEq)]
+#[postgres(name = "TransactionErrorCode")]
+pub enum DbTransactionErrorCode {
+    AccountInUse,
+    AccountLoadedTwice,
+    AccountNotFound,
+    ProgramAccountNotFound,
+    InsufficientFundsForFee,
+    InvalidAccountForFee,
+    AlreadyProcessed,
+    BlockhashNotFound,
+    InstructionError,
+    CallChainTooDeep,
+    MissingSignatureForFee,
+    InvalidAccountIndex,
+    SignatureFailure,
+    InvalidProgramForExecution,
+    SanitizeFailure,
+    ClusterMaintenance,
+    AccountBorrowOutstanding,
+    WouldExceedMaxAccountCostLimit,
+    WouldExceedMaxBlockCostLimit,
+    UnsupportedVersion,
+    InvalidWritableAccount,
+    WouldExceedMaxAccountDataCostLimit,
+    TooManyAccountLocks,
+    AddressLookupTableNotFound,
+    InvalidAddressLookupTableOwner,
+    InvalidAddressLookupTableData,
+    InvalidAddressLookupTableIndex,
+    InvalidRentPayingAccount,
+    WouldExceedMaxVoteCostLimit,
+    WouldExceedAccountDataBlockLimit,
+    WouldExceedAccountDataTotalLimit,
+    DuplicateInstruction,
+    InsufficientFundsForRent,
+}
+
+#[derive(Clone, Debug, Eq, FromSql, ToSql, PartialEq)]
+#[postgres(name = "TransactionError")]
+pub struct DbTransactionError {
+    pub error_code: DbTransactionErrorCode,
+    pub error_detail: Option<String>,
+}
+
+fn corrupt(what: &str, len: usize) -> Error {
+    Error::ObjectCorrupt(format!("{} of {} bytes", what, len))
+}
+
+pub(crate) fn pubkey_from_bytes(bytes: &[u8]) -> Result<Pubkey> {
+    <[u8; 32]>::try_from(bytes)
+        .map(Pubkey::from)
+        .map_err(|_| corrupt("pubkey", bytes.len()))
+}
+
+pub(crate) fn signature_from_bytes(bytes: &[u8]) -> Result<Signature> {
+    <[u8; 64]>::try_from(bytes)
+        .map(|bytes| Signature::new(&bytes))
+        .map_err(|_| corrupt("signature", bytes.len()))
+}
+
+fn hash_from_bytes(bytes: &[u8]) -> Result<Hash> {
+    <[u8; 32]>::try_from(bytes)
+        .map(Hash::new_from_array)
+        .map_err(|_| corrupt("hash", bytes.len()))
+}
+
+fn pubkeys_from_bytes(keys: &[Vec<u8>]) -> Result<Vec<Pubkey>> {
+    keys.iter().map(|key| pubkey_from_bytes(key)).collect()
+}
+
+impl From<&DbTransactionMessageHeader> for MessageHeader {
+    fn from(header: &DbTransactionMessageHeader) -> Self {
+        Self {
+            num_required_signatures: header.num_required_signatures as u8,
+            num_readonly_signed_accounts: header.num_readonly_signed_accounts as u8,
+            num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts as u8,
+        }
+    }
+}
+
+impl From<&DbCompiledInstruction> for CompiledInstruction {
+    fn from(instruction: &DbCompiledInstruction) -> Self {
+        Self {
+            program_id_index: instruction.program_id_index as u8,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account_idx| *account_idx as u8)
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
+}
+
+impl TryFrom<&DbTransactionMessage> for Message {
+    type Error = Error;
+
+    fn try_from(message: &DbTransactionMessage) -> Result<Self> {
+        Ok(Self {
+            header: MessageHeader::from(&message.header),
+            account_keys: pubkeys_from_bytes(&message.account_keys)?,
+            recent_blockhash: hash_from_bytes(&message.recent_blockhash)?,
+            instructions: message
+                .instructions
+                .iter()
+                .map(CompiledInstruction::from)
+                .collect(),
+        })
+    }
+}
+
+impl TryFrom<&DbTransactionMessageAddressTableLookup> for MessageAddressTableLookup {
+    type Error = Error;
+
+    fn try_from(address_table_lookup: &DbTransactionMessageAddressTableLookup) -> Result<Self> {
+        Ok(Self {
+            account_key: pubkey_from_bytes(&address_table_lookup.account_key)?,
+            writable_indexes: address_table_lookup
+                .writable_indexes
+                .iter()
+                .map(|idx| *idx as u8)
+                .collect(),
+            readonly_indexes: address_table_lookup
+                .readonly_indexes
+                .iter()
+                .map(|idx| *idx as u8)
+                .collect(),
+        })
+    }
+}
+
+impl TryFrom<&DbTransactionMessageV0> for v0::Message {
+    type Error = Error;
+
+    fn try_from(message: &DbTransactionMessageV0) -> Result<Self> {
+        Ok(Self {
+            header: MessageHeader::from(&message.header),
+            account_keys: pubkeys_from_bytes(&message.account_keys)?,
+            recent_blockhash: hash_from_bytes(&message.recent_blockhash)?,
+            instructions: message
+                .instructions
+                .iter()
+                .map(CompiledInstruction::from)
+                .collect(),
+            address_table_lookups: message
+                .address_table_lookups
+                .iter()
+                .map(MessageAddressTableLookup::try_from)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<&DbLoadedAddresses> for LoadedAddresses {
+    type Error = Error;
+
+    fn try_from(loaded_addresses: &DbLoadedAddresses) -> Result<Self> {
+        Ok(Self {
+            writable: pubkeys_from_bytes(&loaded_addresses.writable)?,
+            readonly: pubkeys_from_bytes(&loaded_addresses.readonly)?,
+        })
+    }
+}
+
+impl From<&DbInnerInstructions> for InnerInstructions {
+    fn from(instructions: &DbInnerInstructions) -> Self {
+        Self {
+            index: instructions.index as u8,
+            instructions: instructions
+                .instructions
+                .iter()
+                .map(CompiledInstruction::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&DbRewardType> for RewardType {
+    fn from(reward_type: &DbRewardType) -> Self {
+        match reward_type {
+            DbRewardType::Fee => Self::Fee,
+            DbRewardType::Rent => Self::Rent,
+            DbRewardType::Staking => Self::Staking,
+            DbRewardType::Voting => Self::Voting,
+        }
+    }
+}
+
+impl From<&DbReward> for Reward {
+    fn from(reward: &DbReward) -> Self {
+        Self {
+            pubkey: reward.pubkey.clone(),
+            lamports: reward.lamports,
+            post_balance: reward.post_balance as u64,
+            reward_type: reward.reward_type.as_ref().map(RewardType::from),
+            commission: reward.commission.map(|commission| commission as u8),
+        }
+    }
+}
+
+/// The plugin only keeps the text of an instruction error, as
+/// "InstructionError: idx (<index>), error: (<error>)". The index is
+/// recovered, and the error too if it is a custom program error, other
+/// instruction errors come back as `GenericError`.
+fn parse_instruction_error(error_detail: Option<&str>) -> TransactionError {
+    let parse = |error_detail: &str| -> Option<(u8, InstructionError)> {
+        let detail = error_detail.strip_prefix("InstructionError: idx (")?;
+        let (index, detail) = detail.split_once("), error: (")?;
+        let error = detail
+            .strip_suffix(')')?
+            .strip_prefix("custom program error: 0x")
+            .and_then(|code| u32::from_str_radix(code, 16).ok())
+            .map(InstructionError::Custom)
+            .unwrap_or(InstructionError::GenericError);
+        Some((index.parse().ok()?, error))
+    };
+    let (index, error) = error_detail
+        .and_then(parse)
+        .unwrap_or((0, InstructionError::GenericError));
+    TransactionError::InstructionError(index, error)
+}
+
+impl From<&DbTransactionError> for TransactionError {
+    fn from(err: &DbTransactionError) -> Self {
+        match err.error_code {
+            DbTransactionErrorCode::AccountInUse => Self::AccountInUse,
+            DbTransactionErrorCode::AccountLoadedTwice => Self::AccountLoadedTwice,
+            DbTransactionErrorCode::AccountNotFound => Self::AccountNotFound,
+            DbTransactionErrorCode::ProgramAccountNotFound => Self::ProgramAccountNotFound,
+            DbTransactionErrorCode::InsufficientFundsForFee => Self::InsufficientFundsForFee,
+            DbTransactionErrorCode::InvalidAccountForFee => Self::InvalidAccountForFee,
+            DbTransactionErrorCode::AlreadyProcessed => Self::AlreadyProcessed,
+            DbTransactionErrorCode::BlockhashNotFound => Self::BlockhashNotFound,
+            DbTransactionErrorCode::InstructionError => {
+                parse_instruction_error(err.error_detail.as_deref())
+            }
+            DbTransactionErrorCode::CallChainTooDeep => Self::CallChainTooDeep,
+            DbTransactionErrorCode::MissingSignatureForFee => Self::MissingSignatureForFee,
+            DbTransactionErrorCode::InvalidAccountIndex => Self::InvalidAccountIndex,
+            DbTransactionErrorCode::SignatureFailure => Self::SignatureFailure,
+            DbTransactionErrorCode::InvalidProgramForExecution => Self::InvalidProgramForExecution,
+            DbTransactionErrorCode::SanitizeFailure => Self::SanitizeFailure,
+            DbTransactionErrorCode::ClusterMaintenance => Self::ClusterMaintenance,
+            DbTransactionErrorCode::AccountBorrowOutstanding => Self::AccountBorrowOutstanding,
+            DbTransactionErrorCode::WouldExceedMaxAccountCostLimit => {
+                Self::WouldExceedMaxAccountCostLimit
+            }
+            DbTransactionErrorCode::WouldExceedMaxBlockCostLimit => {
+                Self::WouldExceedMaxBlockCostLimit
+            }
+            DbTransactionErrorCode::UnsupportedVersion => Self::UnsupportedVersion,
+            DbTransactionErrorCode::InvalidWritableAccount => Self::InvalidWritableAccount,
+            // Not produced by the runtime of this version, the plugin never
+            // writes it
+            DbTransactionErrorCode::WouldExceedMaxAccountDataCostLimit => {
+                Self::WouldExceedAccountDataBlockLimit
+            }
+            DbTransactionErrorCode::TooManyAccountLocks => Self::TooManyAccountLocks,
+            DbTransactionErrorCode::AddressLookupTableNotFound => Self::AddressLookupTableNotFound,
+            DbTransactionErrorCode::InvalidAddressLookupTableOwner => {
+                Self::InvalidAddressLookupTableOwner
+            }
+            DbTransactionErrorCode::InvalidAddressLookupTableData => {
+                Self::InvalidAddressLookupTableData
+            }
+            DbTransactionErrorCode::InvalidAddressLookupTableIndex => {
+                Self::InvalidAddressLookupTableIndex
+            }
+            DbTransactionErrorCode::InvalidRentPayingAccount => Self::InvalidRentPayingAccount,
+            DbTransactionErrorCode::WouldExceedMaxVoteCostLimit => {
+                Self::WouldExceedMaxVoteCostLimit
+            }
+            DbTransactionErrorCode::WouldExceedAccountDataBlockLimit => {
+                Self::WouldExceedAccountDataBlockLimit
+            }
+            DbTransactionErrorCode::WouldExceedAccountDataTotalLimit => {
+                Self::WouldExceedAccountDataTotalLimit
+            }
+            // The instruction and account indexes are not stored
+            DbTransactionErrorCode::DuplicateInstruction => Self::DuplicateInstruction(0),
+            DbTransactionErrorCode::InsufficientFundsForRent => {
+                Self::InsufficientFundsForRent { account_index: 0 }
+            }
+        }
+    }
+}
+
+/// Rebuild a transaction and its status from the columns of its row in the
+/// `transaction` table. The token balances are left out, the plugin does not
+/// store their amounts and decimals.
+pub(crate) fn build_transaction_with_status_meta(
+    legacy_message: Option<&DbTransactionMessage>,
+    v0_loaded_message: Option<&DbLoadedMessageV0>,
+    signatures: &[Vec<u8>],
+    meta: &DbTransactionStatusMeta,
+) -> Result<VersionedTransactionWithStatusMeta> {
+    let (message, loaded_addresses) = match (legacy_message, v0_loaded_message) {
+        (Some(message), _) => (
+            VersionedMessage::Legacy(Message::try_from(message)?),
+            LoadedAddresses::default(),
+        ),
+        (None, Some(loaded_message)) => (
+            VersionedMessage::V0(v0::Message::try_from(&loaded_message.message)?),
+            LoadedAddresses::try_from(&loaded_message.loaded_addresses)?,
+        ),
+        (None, None) => {
+            return Err(Error::ObjectCorrupt(
+                "transaction without a message".to_string(),
+            ))
+        }
+    };
+    let transaction = VersionedTransaction {
+        signatures: signatures
+            .iter()
+            .map(|signature| signature_from_bytes(signature))
+            .collect::<Result<_>>()?,
+        message,
+    };
+    let meta = TransactionStatusMeta {
+        status: meta
+            .error
+            .as_ref()
+            .map_or(Ok(()), |err| Err(TransactionError::from(err))),
+        fee: meta.fee as u64,
+        pre_balances: meta
+            .pre_balances
+            .iter()
+            .map(|balance| *balance as u64)
+            .collect(),
+        post_balances: meta
+            .post_balances
+            .iter()
+            .map(|balance| *balance as u64)
+            .collect(),
+        inner_instructions: meta
+            .inner_instructions
+            .as_ref()
+            .map(|instructions| instructions.iter().map(InnerInstructions::from).collect()),
+        log_messages: meta.log_messages.clone(),
+        pre_token_balances: None,
+        post_token_balances: None,
+        rewards: meta
+            .rewards
+            .as_ref()
+            .map(|rewards| rewards.iter().map(Reward::from).collect()),
+        loaded_addresses,
+        return_data: None,
+        compute_units_consumed: None,
+    };
+    Ok(VersionedTransactionWithStatusMeta { transaction, meta })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_instruction_error() {
+        let error_detail = format!(
+            "InstructionError: idx ({}), error: ({})",
+            2,
+            InstructionError::Custom(0x1771)
+        );
+        assert_eq!(
+            parse_instruction_error(Some(&error_detail)),
+            TransactionError::InstructionError(2, InstructionError::Custom(0x1771))
+        );
+        let error_detail = format!(
+            "InstructionError: idx ({}), error: ({})",
+            1,
+            InstructionError::InvalidAccountData
+        );
+        assert_eq!(
+            parse_instruction_error(Some(&error_detail)),
+            TransactionError::InstructionError(1, InstructionError::GenericError)
+        );
+        assert_eq!(
+            parse_instruction_error(None),
+            TransactionError::InstructionError(0, InstructionError::GenericError)
+        );
+    }
+
+    #[test]
+    fn test_build_transaction_with_status_meta() {
+        let keys: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let legacy_message = DbTransactionMessage {
+            header: DbTransactionMessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: keys.iter().map(|key| key.to_bytes().to_vec()).collect(),
+            recent_blockhash: Hash::new_unique().to_bytes().to_vec(),
+            instructions: vec![DbCompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1],
+                data: vec![1, 2, 3],
+            }],
+        };
+        let meta = DbTransactionStatusMeta {
+            error: Some(DbTransactionError {
+                error_code: DbTransactionErrorCode::InsufficientFundsForFee,
+                error_detail: None,
+            }),
+            fee: 5000,
+            pre_balances: vec![10, 0, 1],
+            post_balances: vec![5, 5, 1],
+            inner_instructions: None,
+            log_messages: Some(vec!["log".to_string()]),
+            pre_token_balances: None,
+            post_token_balances: None,
+            rewards: Some(vec![DbReward {
+                pubkey: keys[0].to_string(),
+                lamports: -1,
+                post_balance: 4,
+                reward_type: Some(DbRewardType::Rent),
+                commission: None,
+            }]),
+        };
+        let signature = Signature::new(&[7u8; 64]);
+
+        let transaction = build_transaction_with_status_meta(
+            Some(&legacy_message),
+            None,
+            &[signature.as_ref().to_vec()],
+            &meta,
+        )
+        .unwrap();
+        assert_eq!(transaction.transaction.signatures, vec![signature]);
+        assert_eq!(
+            transaction.transaction.message.static_account_keys(),
+            &keys[..]
+        );
+        assert_eq!(
+            transaction.transaction.message.instructions()[0].data,
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            transaction.meta.status,
+            Err(TransactionError::InsufficientFundsForFee)
+        );
+        assert_eq!(transaction.meta.post_balances, vec![5, 5, 1]);
+        assert_eq!(
+            transaction.meta.rewards.unwrap()[0].reward_type,
+            Some(RewardType::Rent)
+        );
+
+        let mut corrupt_message = legacy_message;
+        corrupt_message.account_keys[1].pop();
+        assert!(matches!(
+            build_transaction_with_status_meta(Some(&corrupt_message), None, &[], &meta),
+            Err(Error::ObjectCorrupt(_))
+        ));
+        assert!(matches!(
+            build_transaction_with_status_meta(None, None, &[], &meta),
+            Err(Error::ObjectCorrupt(_))
+        ));
+    }
+}
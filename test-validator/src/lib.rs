@@ -14,6 +14,7 @@ use {
         tower_storage::TowerStorage,
         validator::{Validator, ValidatorConfig, ValidatorStartProgress},
     },
+    solana_geyser_plugin_manager::geyser_plugin_service::GeyserPluginAdmin,
     solana_gossip::{
         cluster_info::{ClusterInfo, Node},
         gossip_service::discover_cluster,
@@ -977,6 +978,10 @@ impl TestValidator {
     pub fn bank_forks(&self) -> Arc<RwLock<BankForks>> {
         self.validator.as_ref().unwrap().bank_forks.clone()
     }
+
+    pub fn geyser_plugin_admin(&self) -> Option<GeyserPluginAdmin> {
+        self.validator.as_ref().unwrap().geyser_plugin_admin()
+    }
 }
 
 impl Drop for TestValidator {
@@ -1002,6 +1002,35 @@ impl EncodableWithMeta for VersionedTransaction {
     }
 }
 
+/// Without the status meta, the addresses a v0 message loads from lookup tables
+/// are unknown, so its `JsonParsed` encoding falls back to `Json`.
+impl Encodable for VersionedTransaction {
+    type Encoded = EncodedTransaction;
+    fn encode(&self, encoding: UiTransactionEncoding) -> Self::Encoded {
+        match encoding {
+            UiTransactionEncoding::Binary => EncodedTransaction::LegacyBinary(
+                bs58::encode(bincode::serialize(self).unwrap()).into_string(),
+            ),
+            UiTransactionEncoding::Base58 => EncodedTransaction::Binary(
+                bs58::encode(bincode::serialize(self).unwrap()).into_string(),
+                TransactionBinaryEncoding::Base58,
+            ),
+            UiTransactionEncoding::Base64 => EncodedTransaction::Binary(
+                base64::encode(bincode::serialize(self).unwrap()),
+                TransactionBinaryEncoding::Base64,
+            ),
+            UiTransactionEncoding::Json => self.json_encode(),
+            UiTransactionEncoding::JsonParsed => match &self.message {
+                VersionedMessage::Legacy(message) => EncodedTransaction::Json(UiTransaction {
+                    signatures: self.signatures.iter().map(ToString::to_string).collect(),
+                    message: message.encode(UiTransactionEncoding::JsonParsed),
+                }),
+                VersionedMessage::V0(_) => self.json_encode(),
+            },
+        }
+    }
+}
+
 impl Encodable for Transaction {
     type Encoded = EncodedTransaction;
     fn encode(&self, encoding: UiTransactionEncoding) -> Self::Encoded {
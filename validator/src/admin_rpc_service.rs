@@ -9,6 +9,9 @@ use {
     solana_core::{
         consensus::Tower, tower_storage::TowerStorage, validator::ValidatorStartProgress,
     },
+    solana_geyser_plugin_manager::geyser_plugin_service::{
+        GeyserPluginAdmin, GeyserPluginServiceError,
+    },
     solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
     solana_runtime::bank_forks::BankForks,
     solana_sdk::{
@@ -31,6 +34,7 @@ pub struct AdminRpcRequestMetadataPostInit {
     pub cluster_info: Arc<ClusterInfo>,
     pub bank_forks: Arc<RwLock<BankForks>>,
     pub vote_account: Pubkey,
+    pub geyser_plugin_admin: Option<GeyserPluginAdmin>,
 }
 
 #[derive(Clone)]
@@ -58,6 +62,16 @@ impl AdminRpcRequestMetadata {
             ))
         }
     }
+
+    fn geyser_plugin_admin(&self) -> Result<GeyserPluginAdmin> {
+        self.with_post_init(|post_init| {
+            post_init.geyser_plugin_admin.clone().ok_or_else(|| {
+                jsonrpc_core::error::Error::invalid_params(
+                    "The validator was not started with any geyser plugin",
+                )
+            })
+        })
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -130,6 +144,18 @@ impl Display for AdminRpcContactInfo {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcGeyserPlugin {
+    pub name: String,
+    pub config_file: String,
+}
+
+impl Display for AdminRpcGeyserPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.config_file)
+    }
+}
+
 #[rpc]
 pub trait AdminRpc {
     type Metadata;
@@ -177,6 +203,23 @@ pub trait AdminRpc {
 
     #[rpc(meta, name = "contactInfo")]
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo>;
+
+    #[rpc(meta, name = "listPlugins")]
+    fn list_plugins(&self, meta: Self::Metadata) -> Result<Vec<AdminRpcGeyserPlugin>>;
+
+    #[rpc(meta, name = "loadPlugin")]
+    fn load_plugin(&self, meta: Self::Metadata, config_file: String) -> Result<String>;
+
+    #[rpc(meta, name = "unloadPlugin")]
+    fn unload_plugin(&self, meta: Self::Metadata, name: String) -> Result<()>;
+
+    #[rpc(meta, name = "reloadPlugin")]
+    fn reload_plugin(
+        &self,
+        meta: Self::Metadata,
+        name: String,
+        config_file: String,
+    ) -> Result<String>;
 }
 
 pub struct AdminRpcImpl;
@@ -300,9 +343,78 @@ impl AdminRpc for AdminRpcImpl {
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo> {
         meta.with_post_init(|post_init| Ok(post_init.cluster_info.my_contact_info().into()))
     }
+
+    fn list_plugins(&self, meta: Self::Metadata) -> Result<Vec<AdminRpcGeyserPlugin>> {
+        debug!("list_plugins request received");
+        Ok(meta
+            .geyser_plugin_admin()?
+            .list_plugins()
+            .into_iter()
+            .map(|(name, config_file)| AdminRpcGeyserPlugin {
+                name,
+                config_file: config_file.display().to_string(),
+            })
+            .collect())
+    }
+
+    fn load_plugin(&self, meta: Self::Metadata, config_file: String) -> Result<String> {
+        debug!("load_plugin request received");
+        meta.geyser_plugin_admin()?
+            .load_plugin(Path::new(&config_file))
+            .map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to load the plugin from {}: {}",
+                    config_file,
+                    AdminRpcImpl::geyser_plugin_error(err)
+                ))
+            })
+    }
+
+    fn unload_plugin(&self, meta: Self::Metadata, name: String) -> Result<()> {
+        debug!("unload_plugin request received");
+        meta.geyser_plugin_admin()?
+            .unload_plugin(&name)
+            .map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to unload the plugin {}: {}",
+                    name,
+                    AdminRpcImpl::geyser_plugin_error(err)
+                ))
+            })
+    }
+
+    fn reload_plugin(
+        &self,
+        meta: Self::Metadata,
+        name: String,
+        config_file: String,
+    ) -> Result<String> {
+        debug!("reload_plugin request received");
+        meta.geyser_plugin_admin()?
+            .reload_plugin(&name, Path::new(&config_file))
+            .map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to reload the plugin {} from {}: {}",
+                    name,
+                    config_file,
+                    AdminRpcImpl::geyser_plugin_error(err)
+                ))
+            })
+    }
 }
 
 impl AdminRpcImpl {
+    fn geyser_plugin_error(err: GeyserPluginServiceError) -> String {
+        match err {
+            // These carry the details their message lacks
+            GeyserPluginServiceError::CannotOpenConfigFile(msg)
+            | GeyserPluginServiceError::CannotReadConfigFile(msg)
+            | GeyserPluginServiceError::InvalidConfigFileFormat(msg)
+            | GeyserPluginServiceError::PluginLoadError(msg) => msg,
+            err => err.to_string(),
+        }
+    }
+
     fn add_authorized_voter_keypair(
         meta: AdminRpcRequestMetadata,
         authorized_voter: Keypair,
@@ -40,7 +40,7 @@ use {
     solana_perf::recycler::enable_recycler_warming,
     solana_poh::poh_service,
     solana_rpc::{
-        rpc::{JsonRpcConfig, RpcBigtableConfig},
+        rpc::{JsonRpcConfig, RpcBigtableConfig, RpcPostgresConfig},
         rpc_pubsub_service::PubSubConfig,
     },
     solana_runtime::{
@@ -679,6 +679,20 @@ pub fn main() {
                 .takes_value(false)
                 .help("Upload new confirmed blocks into a BigTable instance"),
         )
+        .arg(
+            Arg::with_name("rpc_postgres_ledger_storage")
+                .long("rpc-postgres-ledger-storage")
+                .value_name("CONNECTION_STR")
+                .takes_value(true)
+                .requires("enable_rpc_transaction_history")
+                .conflicts_with_all(&[
+                    "enable_rpc_bigtable_ledger_storage",
+                    "enable_bigtable_ledger_upload",
+                ])
+                .help("Fetch historical transaction info from the PostgreSQL database \
+                       of the geyser plugin as a fallback to local ledger data, \
+                       e.g. \"host=localhost user=solana password=solana port=5432\""),
+        )
         .arg(
             Arg::with_name("enable_cpi_and_log_storage")
                 .long("enable-cpi-and-log-storage")
@@ -1352,6 +1366,12 @@ pub fn main() {
                 .takes_value(false)
                 .help("Enable the unstable RPC PubSub `blockSubscribe` subscription"),
         )
+        .arg(
+            Arg::with_name("rpc_pubsub_enable_entry_subscription")
+                .long("rpc-pubsub-enable-entry-subscription")
+                .takes_value(false)
+                .help("Enable the unstable RPC PubSub `entrySubscribe` subscription"),
+        )
         .arg(
             Arg::with_name("rpc_pubsub_enable_vote_subscription")
                 .long("rpc-pubsub-enable-vote-subscription")
@@ -1545,6 +1565,15 @@ pub fn main() {
                 .multiple(true)
                 .help("Specify the configuration file for the Geyser plugin."),
         )
+        .arg(
+            Arg::with_name("publish_confirmed_entries")
+                .long("publish-confirmed-entries")
+                .requires("geyser_plugin_config")
+                .takes_value(false)
+                .help("Publish the entries of a slot to the Geyser plugins once the slot is \
+                       optimistically confirmed, instead of once it is rooted. \
+                       Requires the RPC service to track optimistic confirmation"),
+        )
         .arg(
             Arg::with_name("halt_on_known_validators_accounts_hash_mismatch")
                 .alias("halt-on-trusted-validators-accounts-hash-mismatch")
@@ -1917,6 +1946,63 @@ pub fn main() {
             SubCommand::with_name("monitor")
             .about("Monitor the validator")
         )
+        .subcommand(
+            SubCommand::with_name("plugin")
+            .about("Manage the validator geyser plugins")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .setting(AppSettings::InferSubcommands)
+            .subcommand(
+                SubCommand::with_name("list")
+                .about("List the loaded plugins")
+            )
+            .subcommand(
+                SubCommand::with_name("load")
+                .about("Load a plugin")
+                .arg(
+                    Arg::with_name("config")
+                        .index(1)
+                        .value_name("CONFIG")
+                        .required(true)
+                        .takes_value(true)
+                        .help("Path to the plugin config file")
+                )
+            )
+            .subcommand(
+                SubCommand::with_name("unload")
+                .about("Unload a plugin")
+                .arg(
+                    Arg::with_name("name")
+                        .index(1)
+                        .value_name("NAME")
+                        .required(true)
+                        .takes_value(true)
+                        .help("Name of the plugin to unload")
+                )
+            )
+            .subcommand(
+                SubCommand::with_name("reload")
+                .about("Replace a plugin by the one a config file loads, without missing notifications")
+                .arg(
+                    Arg::with_name("name")
+                        .index(1)
+                        .value_name("NAME")
+                        .required(true)
+                        .takes_value(true)
+                        .help("Name of the plugin to replace")
+                )
+                .arg(
+                    Arg::with_name("config")
+                        .index(2)
+                        .value_name("CONFIG")
+                        .required(true)
+                        .takes_value(true)
+                        .help("Path to the new plugin config file")
+                )
+            )
+            .after_help("Note: plugins loaded at runtime can only receive the kinds of \
+                         notifications the plugins loaded at startup enabled, and the \
+                         changes only apply to the currently running validator instance")
+        )
         .subcommand(
             SubCommand::with_name("run")
             .about("Run the validator")
@@ -2138,6 +2224,70 @@ pub fn main() {
             monitor_validator(&ledger_path);
             return;
         }
+        ("plugin", Some(plugin_subcommand_matches)) => {
+            let canonicalize_config = |subcommand_matches: &ArgMatches| {
+                let config = value_t_or_exit!(subcommand_matches, "config", String);
+                fs::canonicalize(&config)
+                    .unwrap_or_else(|err| {
+                        println!("Unable to access path: {}: {:?}", config, err);
+                        exit(1);
+                    })
+                    .display()
+                    .to_string()
+            };
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            match plugin_subcommand_matches.subcommand() {
+                ("list", _) => {
+                    let plugins = admin_rpc_service::runtime()
+                        .block_on(async move { admin_client.await?.list_plugins().await })
+                        .unwrap_or_else(|err| {
+                            println!("listPlugins request failed: {}", err);
+                            exit(1);
+                        });
+                    if plugins.is_empty() {
+                        println!("No plugin loaded");
+                    }
+                    for plugin in plugins {
+                        println!("{}", plugin);
+                    }
+                }
+                ("load", Some(subcommand_matches)) => {
+                    let config = canonicalize_config(subcommand_matches);
+                    let name = admin_rpc_service::runtime()
+                        .block_on(async move { admin_client.await?.load_plugin(config).await })
+                        .unwrap_or_else(|err| {
+                            println!("loadPlugin request failed: {}", err);
+                            exit(1);
+                        });
+                    println!("Loaded plugin {}", name);
+                }
+                ("unload", Some(subcommand_matches)) => {
+                    let name = value_t_or_exit!(subcommand_matches, "name", String);
+                    println!("Unloading plugin {}", name);
+                    admin_rpc_service::runtime()
+                        .block_on(async move { admin_client.await?.unload_plugin(name).await })
+                        .unwrap_or_else(|err| {
+                            println!("unloadPlugin request failed: {}", err);
+                            exit(1);
+                        });
+                }
+                ("reload", Some(subcommand_matches)) => {
+                    let name = value_t_or_exit!(subcommand_matches, "name", String);
+                    let config = canonicalize_config(subcommand_matches);
+                    let new_name = admin_rpc_service::runtime()
+                        .block_on(
+                            async move { admin_client.await?.reload_plugin(name, config).await },
+                        )
+                        .unwrap_or_else(|err| {
+                            println!("reloadPlugin request failed: {}", err);
+                            exit(1);
+                        });
+                    println!("Reloaded plugin {}", new_name);
+                }
+                _ => unreachable!(),
+            }
+            return;
+        }
         ("set-identity", Some(subcommand_matches)) => {
             let require_tower = subcommand_matches.is_present("require_tower");
 
@@ -2553,6 +2703,13 @@ pub fn main() {
         None
     };
 
+    let rpc_postgres_config =
+        matches
+            .value_of("rpc_postgres_ledger_storage")
+            .map(|connection_str| RpcPostgresConfig {
+                connection_str: connection_str.to_string(),
+            });
+
     if matches.is_present("accounts_db_index_hashing") {
         info!("The accounts hash is only calculated without using the index. --accounts-db-index-hashing is deprecated and can be removed from the command line");
     }
@@ -2602,6 +2759,7 @@ pub fn main() {
             enable_extended_tx_metadata_storage: matches.is_present("enable_cpi_and_log_storage")
                 || matches.is_present("enable_extended_tx_metadata_storage"),
             rpc_bigtable_config,
+            rpc_postgres_config,
             faucet_addr: matches.value_of("rpc_faucet_addr").map(|address| {
                 solana_net_utils::parse_host_port(address).expect("failed to parse faucet address")
             }),
@@ -2623,6 +2781,7 @@ pub fn main() {
             rpc_scan_and_fix_roots: matches.is_present("rpc_scan_and_fix_roots"),
         },
         geyser_plugin_config_files,
+        publish_confirmed_entries: matches.is_present("publish_confirmed_entries"),
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
             (
                 SocketAddr::new(rpc_bind_address, rpc_port),
@@ -2634,6 +2793,7 @@ pub fn main() {
         }),
         pubsub_config: PubSubConfig {
             enable_block_subscription: matches.is_present("rpc_pubsub_enable_block_subscription"),
+            enable_entry_subscription: matches.is_present("rpc_pubsub_enable_entry_subscription"),
             enable_vote_subscription: matches.is_present("rpc_pubsub_enable_vote_subscription"),
             max_active_subscriptions: value_t_or_exit!(
                 matches,
@@ -3144,6 +3304,7 @@ pub fn main() {
             bank_forks: validator.bank_forks.clone(),
             cluster_info: validator.cluster_info.clone(),
             vote_account,
+            geyser_plugin_admin: validator.geyser_plugin_admin(),
         });
 
     if let Some(filename) = init_complete_file {